@@ -0,0 +1,37 @@
+#[allow(unused_imports)]
+use mcscratchy::{
+    scripting::script_builder::{
+        BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, FieldKind, StackBuilder,
+        StackOrValue,
+    },
+    scripting::{menu::*, procedure::ProcedureBuilder},
+    typed_scripting::{arg::*, blocks::*, script_builder::*},
+};
+#[allow(unused_imports)]
+use rs_sb3::block::{BlockInputValue, BlockMutation, BlockMutationEnum, ShadowInputType};
+
+/// Scripts of "Stage"
+pub fn stage_scripts() -> Vec<StackBuilder> {
+    vec![
+        when_broadcast_received(BroadcastHandle::assume_declared("go")).next(add_to_list(GlobalList::assume_declared("log"), global_var(&GlobalVar::assume_declared("score")))).into_untyped().move_head(0.0, 0.0),
+    ]
+}
+
+/// Custom blocks of "Stage"
+pub fn stage_procedures() -> Vec<ProcedureBuilder> {
+    vec![]
+}
+
+/// Scripts of "Cat"
+pub fn cat_scripts() -> Vec<StackBuilder> {
+    vec![
+        when_flag_clicked().next(set_var_to(SpriteVar::assume_declared("hp"), 3)).next(repeat(10, Some(move_steps(random(1, 10)).next(turn_right(15))))).next(if_else(less_than(sprite_var(&SpriteVar::assume_declared("hp")), 1), Some(say("ouch")), Some(change_var_by(GlobalVar::assume_declared("score"), 1)))).next(set_looks_effect_to(LooksEffect::Ghost, 50)).next(call_procedure(&ProcedureBuilder::new("jump").string_number_argument("height").label("if").boolean_argument("ready").handle(), vec![value_arg(20), empty_bool_arg()])).next(call_procedure(&ProcedureBuilder::new("jump").string_number_argument("height").label("if").boolean_argument("ready").handle(), vec![value_arg(sprite_var(&SpriteVar::assume_declared("hp"))), bool_arg(mouse_down())])).next(play_drum_for_beats(Drum::SnareDrum, 0.25)).next(set_instrument_to(Instrument::Cello)).next(wait(0.5)).next(stop(StopOtherScriptsInSprite)).next(broadcast(BroadcastHandle::assume_declared("go"))).next(stop(StopThisScript)).into_untyped().move_head(0.0, 168.0),
+    ]
+}
+
+/// Custom blocks of "Cat"
+pub fn cat_procedures() -> Vec<ProcedureBuilder> {
+    vec![
+        ProcedureBuilder::new("jump").string_number_argument("height").label("if").boolean_argument("ready").warp(true).body(change_y_by(BlockInputBuilder::stack(argument_string_number("height").0.into_untyped())).into_untyped()),
+    ]
+}
@@ -1,3 +1,6 @@
+use mcscratchy::decompile::decompile_target;
+use mcscratchy::import::GlobalIds;
+use mcscratchy::scratchblocks::render::render_target;
 use rs_sb3::target::SpriteOrStage;
 use serde::Deserialize;
 
@@ -11,16 +14,30 @@ struct Config {
     result_path: PathBuf,
     import_path: PathBuf,
     sprite_name: String,
+    #[serde(default)]
+    output: Output,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Output {
+    /// Blocks of the sprite as JSON
+    #[default]
+    Json,
+    /// Rust source that rebuilds the sprite's scripts
+    Rust,
+    /// Scripts of the sprite as scratchblocks text
+    Scratchblocks,
 }
 
 fn main() {
     use notify::Watcher;
     use std::io::Write;
 
-    fn get_blocks<'a>(
+    fn get_sprite<'a>(
         project: &'a rs_sb3::project::Project,
         sprite_name: &str,
-    ) -> &'a rs_sb3::string_hashmap::StringHashMap<rs_sb3::block::Block> {
+    ) -> &'a rs_sb3::target::Target {
         let sprite = project
             .targets
             .iter()
@@ -36,7 +53,7 @@ fn main() {
                 }
             })
             .expect("finding sprite");
-        &sprite.target.blocks
+        &sprite.target
     }
 
     let cfg_path = var("INSPECTOR_CFG_PATH").expect("INSPECTOR_CFG_PATH environment");
@@ -48,6 +65,7 @@ fn main() {
         result_path,
         import_path,
         sprite_name,
+        output,
     } = cfg;
 
     let (tx, rx) = channel();
@@ -73,8 +91,17 @@ fn main() {
                     let scratch_project: rs_sb3::project::Project =
                         serde_json::from_str(&json).unwrap();
 
-                    let to_print = get_blocks(&scratch_project, &sprite_name);
-                    let to_print = serde_json::to_string_pretty(&to_print).unwrap();
+                    let sprite = get_sprite(&scratch_project, &sprite_name);
+                    let to_print = match output {
+                        Output::Json => serde_json::to_string_pretty(&sprite.blocks).unwrap(),
+                        Output::Rust => decompile_target(
+                            sprite,
+                            GlobalIds::from_project(&scratch_project).as_ref(),
+                            "scripts",
+                        )
+                        .unwrap(),
+                        Output::Scratchblocks => render_target(sprite).unwrap(),
+                    };
 
                     let mut file = std::fs::File::options()
                         .write(true)
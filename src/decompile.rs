@@ -0,0 +1,1071 @@
+//! Generates mcscratchy Rust source that rebuilds the scripts of an existing project.
+//!
+//! Blocks are written with [`crate::typed_scripting::blocks`] functions when there's one for the opcode.
+//! Otherwise they're written with the raw [`crate::scripting::script_builder::BlockNormalBuilder`]
+//! and [`crate::scripting::script_builder::BlockInputBuilder`].
+//! Custom blocks are written with [`crate::scripting::procedure::ProcedureBuilder`]
+//! and called with [`crate::typed_scripting::blocks::call_procedure`].
+//!
+//! The output is not formatted, run it through rustfmt.
+
+use std::collections::{HashMap, HashSet};
+
+use rs_sb3::{
+    block::{
+        Block, BlockField, BlockInput, BlockMutation, BlockMutationEnum, BlockNormal,
+        ListOrVariable, UidOrValue,
+    },
+    project::Project,
+    target::{SpriteOrStage, Target},
+};
+
+use crate::{
+    import::{procedure_arguments, GlobalIds, TargetIds},
+    scripting::{menu::StopOption, script_builder::FieldKind},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecompileError {
+    /// Block id that is referenced but doesn't exist in the target
+    MissingBlock(String),
+}
+
+impl std::error::Error for DecompileError {}
+
+impl std::fmt::Display for DecompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecompileError::MissingBlock(uid) => write!(f, "missing block {uid}"),
+        }
+    }
+}
+
+/// Type of the argument in [`crate::typed_scripting::arg`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Number,
+    PositiveNumber,
+    PositiveInteger,
+    Integer,
+    Angle,
+    Color,
+    Text,
+    Bool,
+    Value,
+    Broadcast,
+    Stack,
+    /// Input that only takes an option of its menu or a reporter. Ex. [`crate::scripting::menu::PenColorParam`]
+    MenuOption,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Param {
+    Input(&'static str, Kind),
+    Field(&'static str),
+    /// Field and the name of its enum in [`crate::scripting::menu`]
+    Menu(&'static str, &'static str),
+    Variable(&'static str),
+    List(&'static str),
+    Broadcast(&'static str),
+    Substack(&'static str),
+    /// Option of [`crate::typed_scripting::blocks::stop`], a type that also gives `hasnext`
+    Stop(&'static str),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    Stack,
+    Reporter(Kind),
+}
+
+struct TypedFn {
+    opcode: &'static str,
+    function: &'static str,
+    params: &'static [Param],
+    output: Output,
+}
+
+macro_rules! typed_fns {
+    ( $(
+        $opcode:ident => $function:ident( $($param:ident $param_args:tt),* )
+            -> $output:ident $(($output_kind:ident))?
+    )* ) => {
+        const TYPED_FNS: &[TypedFn] = &[
+            $(
+                TypedFn {
+                    opcode: stringify!($opcode),
+                    function: stringify!($function),
+                    params: &[$(typed_fns!(@param $param $param_args)),*],
+                    output: typed_fns!(@output $output $($output_kind)?),
+                },
+            )*
+        ];
+    };
+
+    (@param In ($name:literal, $kind:ident)) => { Param::Input($name, Kind::$kind) };
+    (@param Field ($name:literal)) => { Param::Field($name) };
+    (@param Menu ($name:literal, $menu:ident)) => { Param::Menu($name, stringify!($menu)) };
+    (@param Var ($name:literal)) => { Param::Variable($name) };
+    (@param List ($name:literal)) => { Param::List($name) };
+    (@param Broadcast ($name:literal)) => { Param::Broadcast($name) };
+    (@param Sub ($name:literal)) => { Param::Substack($name) };
+    (@param Stop ($name:literal)) => { Param::Stop($name) };
+    (@output Stack) => { Output::Stack };
+    (@output Reporter $kind:ident) => { Output::Reporter(Kind::$kind) };
+}
+
+typed_fns! {
+    // Control
+    control_wait => wait(In("DURATION", PositiveNumber)) -> Stack
+    control_repeat => repeat(In("TIMES", PositiveInteger), Sub("SUBSTACK")) -> Stack
+    control_forever => forever(Sub("SUBSTACK")) -> Stack
+    control_if => if_(In("CONDITION", Bool), Sub("SUBSTACK")) -> Stack
+    control_if_else => if_else(In("CONDITION", Bool), Sub("SUBSTACK"), Sub("SUBSTACK2")) -> Stack
+    control_wait_until => wait_until(In("CONDITION", Bool)) -> Stack
+    control_repeat_until => repeat_until(In("CONDITION", Bool), Sub("SUBSTACK")) -> Stack
+    control_stop => stop(Stop("STOP_OPTION")) -> Stack
+    control_start_as_clone => when_i_start_as_a_clone() -> Stack
+    control_create_clone_of => create_clone_of(In("CLONE_OPTION", Text)) -> Stack
+    control_create_clone_of_menu => create_clone_of_menu(Field("CLONE_OPTION")) -> Reporter(Text)
+    control_delete_this_clone => delete_this_clone() -> Stack
+    // Event
+    event_whenflagclicked => when_flag_clicked() -> Stack
+    event_whenkeypressed => when_key_pressed(Menu("KEY_OPTION", Key)) -> Stack
+    event_whenthisspriteclicked => when_this_sprite_clicked() -> Stack
+    event_whenstageclicked => when_stage_clicked() -> Stack
+    event_whenbackdropswitchesto => when_backdrop_switches_to(Field("BACKDROP")) -> Stack
+    event_whengreaterthan => when_greater_than(Menu("WHENGREATERTHANMENU", WhenGreaterThan), In("VALUE", Number)) -> Stack
+    event_whenbroadcastreceived => when_broadcast_received(Broadcast("BROADCAST_OPTION")) -> Stack
+    event_broadcast => broadcast(In("BROADCAST_INPUT", Broadcast)) -> Stack
+    event_broadcastandwait => broadcast_and_wait(In("BROADCAST_INPUT", Broadcast)) -> Stack
+    // Looks
+    looks_say => say(In("MESSAGE", Text)) -> Stack
+    looks_sayforsecs => say_for_secs(In("MESSAGE", Text), In("SECS", Number)) -> Stack
+    looks_think => think(In("MESSAGE", Text)) -> Stack
+    looks_thinkforsecs => think_for_secs(In("MESSAGE", Text), In("SECS", Number)) -> Stack
+    looks_switchcostumeto => switch_costume_to(In("COSTUME", Text)) -> Stack
+    looks_costume => costume_menu(Field("COSTUME")) -> Reporter(Text)
+    looks_nextcostume => next_costume() -> Stack
+    looks_switchbackdropto => switch_backdrop_to(In("BACKDROP", Text)) -> Stack
+    looks_backdrops => backdrop_menu(Field("BACKDROP")) -> Reporter(Text)
+    looks_switchbackdroptoandwait => switch_backdrop_to_and_wait(In("BACKDROP", Text)) -> Stack
+    looks_nextbackdrop => next_backdrop() -> Stack
+    looks_changesizeby => change_size_by(In("CHANGE", Number)) -> Stack
+    looks_setsizeto => set_size_to(In("SIZE", Number)) -> Stack
+    looks_changeeffectby => change_looks_effect_by(Menu("EFFECT", LooksEffect), In("CHANGE", Number)) -> Stack
+    looks_seteffectto => set_looks_effect_to(Menu("EFFECT", LooksEffect), In("VALUE", Number)) -> Stack
+    looks_cleargraphiceffects => clear_graphic_effects() -> Stack
+    looks_show => show() -> Stack
+    looks_hide => hide() -> Stack
+    looks_gotofrontback => go_to_layer(Menu("FRONT_BACK", FrontBack)) -> Stack
+    looks_goforwardbackwardlayers => change_layer(Menu("FORWARD_BACKWARD", ForwardBackward), In("NUM", Integer)) -> Stack
+    looks_costumenumbername => costume(Menu("NUMBER_NAME", NumberName)) -> Reporter(Value)
+    looks_backdropnumbername => backdrop(Menu("NUMBER_NAME", NumberName)) -> Reporter(Value)
+    looks_size => size() -> Reporter(Number)
+    // Motion
+    motion_movesteps => move_steps(In("STEPS", Number)) -> Stack
+    motion_turnright => turn_right(In("DEGREES", Number)) -> Stack
+    motion_turnleft => turn_left(In("DEGREES", Number)) -> Stack
+    motion_goto => go_to(In("TO", Text)) -> Stack
+    motion_goto_menu => go_to_menu(Field("TO")) -> Reporter(Text)
+    motion_gotoxy => goto_xy(In("X", Number), In("Y", Number)) -> Stack
+    motion_glideto => glide_to(In("SECS", Number), In("TO", Text)) -> Stack
+    motion_glideto_menu => glide_to_menu(Field("TO")) -> Reporter(Text)
+    motion_glidesecstoxy => glide_to_xy(In("SECS", Number), In("X", Number), In("Y", Number)) -> Stack
+    motion_pointindirection => point_in_direction(In("DIRECTION", Angle)) -> Stack
+    motion_pointtowards => point_towards(In("TOWARDS", Text)) -> Stack
+    motion_pointtowards_menu => point_towards_menu(Field("TOWARDS")) -> Reporter(Text)
+    motion_setx => set_x(In("X", Number)) -> Stack
+    motion_changexby => change_x_by(In("DX", Number)) -> Stack
+    motion_sety => set_y(In("Y", Number)) -> Stack
+    motion_changeyby => change_y_by(In("DY", Number)) -> Stack
+    motion_ifonedgebounce => if_on_edge_bounce() -> Stack
+    motion_setrotationstyle => set_rotation_style(Menu("STYLE", RotationStyle)) -> Stack
+    motion_direction => direction() -> Reporter(Number)
+    motion_yposition => y_position() -> Reporter(Number)
+    motion_xposition => x_position() -> Reporter(Number)
+    // Operators
+    operator_add => add(In("NUM1", Number), In("NUM2", Number)) -> Reporter(Number)
+    operator_subtract => sub(In("NUM1", Number), In("NUM2", Number)) -> Reporter(Number)
+    operator_multiply => mul(In("NUM1", Number), In("NUM2", Number)) -> Reporter(Number)
+    operator_divide => div(In("NUM1", Number), In("NUM2", Number)) -> Reporter(Number)
+    operator_random => random(In("FROM", Number), In("TO", Number)) -> Reporter(Number)
+    operator_lt => less_than(In("OPERAND1", Value), In("OPERAND2", Value)) -> Reporter(Bool)
+    operator_gt => greater_than(In("OPERAND1", Value), In("OPERAND2", Value)) -> Reporter(Bool)
+    operator_equals => equals(In("OPERAND1", Value), In("OPERAND2", Value)) -> Reporter(Bool)
+    operator_and => and(In("OPERAND1", Bool), In("OPERAND2", Bool)) -> Reporter(Bool)
+    operator_or => or(In("OPERAND1", Bool), In("OPERAND2", Bool)) -> Reporter(Bool)
+    operator_not => not(In("OPERAND", Bool)) -> Reporter(Bool)
+    operator_join => join(In("STRING1", Text), In("STRING2", Text)) -> Reporter(Text)
+    operator_letter_of => letter_of(In("LETTER", PositiveInteger), In("STRING", Text)) -> Reporter(Text)
+    operator_length => length_of(In("STRING", Text)) -> Reporter(PositiveInteger)
+    operator_contains => contains(In("STRING1", Text), In("STRING2", Text)) -> Reporter(Bool)
+    operator_mod => modulo(In("NUM1", Number), In("NUM2", Number)) -> Reporter(Number)
+    operator_round => round(In("NUM", Number)) -> Reporter(Number)
+    operator_mathop => math_op(Menu("OPERATOR", MathOp), In("NUM", Number)) -> Reporter(Number)
+    // Sensing
+    sensing_touchingobject => touching(In("TOUCHINGOBJECTMENU", Text)) -> Reporter(Bool)
+    sensing_touchingobjectmenu => touching_menu(Field("TOUCHINGOBJECTMENU")) -> Reporter(Text)
+    sensing_touchingcolor => touching_color(In("COLOR", Color)) -> Reporter(Bool)
+    sensing_coloristouchingcolor => color_touching_color(In("COLOR", Color), In("COLOR2", Color)) -> Reporter(Bool)
+    sensing_distanceto => distance_to(In("DISTANCETOMENU", Text)) -> Reporter(Number)
+    sensing_distancetomenu => distance_to_menu(Field("DISTANCETOMENU")) -> Reporter(Text)
+    sensing_askandwait => ask_and_wait(In("QUESTION", Text)) -> Stack
+    sensing_answer => answer() -> Reporter(Text)
+    sensing_keypressed => key_pressed(In("KEY_OPTION", MenuOption)) -> Reporter(Bool)
+    sensing_keyoptions => key_menu(Menu("KEY_OPTION", Key)) -> Reporter(Text)
+    sensing_mousedown => mouse_down() -> Reporter(Bool)
+    sensing_mousex => mouse_x() -> Reporter(Number)
+    sensing_mousey => mouse_y() -> Reporter(Number)
+    sensing_setdragmode => set_drag_mode(Menu("DRAG_MODE", DragMode)) -> Stack
+    sensing_loudness => loudness() -> Reporter(Number)
+    sensing_timer => timer() -> Reporter(Number)
+    sensing_resettimer => reset_timer() -> Stack
+    sensing_of => var_of(Field("PROPERTY"), In("OBJECT", Text)) -> Reporter(Value)
+    sensing_of_object_menu => var_of_object_menu(Field("OBJECT")) -> Reporter(Text)
+    sensing_current => current_datetime(Menu("CURRENTMENU", CurrentTime)) -> Reporter(PositiveInteger)
+    sensing_dayssince2000 => days_since_2000() -> Reporter(Number)
+    sensing_username => username() -> Reporter(Text)
+    // Sound
+    sound_playuntildone => play_sound_until_done(In("SOUND_MENU", Text)) -> Stack
+    sound_play => play_sound(In("SOUND_MENU", Text)) -> Stack
+    sound_sounds_menu => sound_menu(Field("SOUND_MENU")) -> Reporter(Text)
+    sound_stopallsounds => stop_all_sound() -> Stack
+    sound_changeeffectby => change_sound_effect_by(Menu("EFFECT", SoundEffect), In("VALUE", Number)) -> Stack
+    sound_seteffectto => set_sound_effect_to(Menu("EFFECT", SoundEffect), In("VALUE", Number)) -> Stack
+    sound_cleareffects => clear_sound_effects() -> Stack
+    sound_setvolumeto => set_volume_to(In("VOLUME", Number)) -> Stack
+    sound_changevolumeby => change_volume_by(In("VOLUME", Number)) -> Stack
+    sound_volume => volume() -> Reporter(Number)
+    // Data
+    data_setvariableto => set_var_to(Var("VARIABLE"), In("VALUE", Value)) -> Stack
+    data_changevariableby => change_var_by(Var("VARIABLE"), In("VALUE", Value)) -> Stack
+    data_showvariable => show_var(Var("VARIABLE")) -> Stack
+    data_hidevariable => hide_var(Var("VARIABLE")) -> Stack
+    data_addtolist => add_to_list(List("LIST"), In("ITEM", Value)) -> Stack
+    data_deleteoflist => delete_in_list(List("LIST"), In("INDEX", Integer)) -> Stack
+    data_deletealloflist => delete_all_in_list(List("LIST")) -> Stack
+    data_insertatlist => insert_in_list(List("LIST"), In("INDEX", Integer), In("ITEM", Value)) -> Stack
+    data_replaceitemoflist => replace_in_list(List("LIST"), In("INDEX", Integer), In("ITEM", Value)) -> Stack
+    data_itemoflist => item_in_list(List("LIST"), In("INDEX", Integer)) -> Reporter(Value)
+    data_lengthoflist => length_of_list(List("LIST")) -> Reporter(Integer)
+    data_listcontainsitem => list_contains(List("LIST"), In("ITEM", Value)) -> Reporter(Bool)
+    data_showlist => show_list(List("LIST")) -> Stack
+    data_hidelist => hide_list(List("LIST")) -> Stack
+    // Procedures
+    argument_reporter_string_number => argument_string_number(Field("VALUE")) -> Reporter(Value)
+    argument_reporter_boolean => argument_boolean(Field("VALUE")) -> Reporter(Bool)
+    // Pen
+    pen_clear => erase_all() -> Stack
+    pen_stamp => stamp() -> Stack
+    pen_penDown => pen_down() -> Stack
+    pen_penUp => pen_up() -> Stack
+    pen_setPenColorToColor => set_pen_color_to_color(In("COLOR", Color)) -> Stack
+    pen_changePenColorParamBy => change_pen_color_param_by(In("COLOR_PARAM", MenuOption), In("VALUE", Number)) -> Stack
+    pen_setPenColorParamTo => set_pen_color_param_to(In("COLOR_PARAM", MenuOption), In("VALUE", Number)) -> Stack
+    pen_menu_colorParam => pen_color_param_menu(Menu("colorParam", PenColorParam)) -> Reporter(Text)
+    pen_changePenSizeBy => change_pen_size_by(In("SIZE", Number)) -> Stack
+    pen_setPenSizeTo => set_pen_size_to(In("SIZE", Number)) -> Stack
+    pen_setPenShadeToNumber => set_pen_shade_to(In("SHADE", Number)) -> Stack
+    pen_changePenShadeBy => change_pen_shade_by(In("SHADE", Number)) -> Stack
+    pen_setPenHueToNumber => set_pen_hue_to(In("HUE", Number)) -> Stack
+    pen_changePenHueBy => change_pen_hue_by(In("HUE", Number)) -> Stack
+    // Music
+    music_playDrumForBeats => play_drum_for_beats(In("DRUM", MenuOption), In("BEATS", Number)) -> Stack
+    music_menu_DRUM => drum_menu(Menu("DRUM", Drum)) -> Reporter(Text)
+    music_restForBeats => rest_for_beats(In("BEATS", Number)) -> Stack
+    music_playNoteForBeats => play_note_for_beats(In("NOTE", Number), In("BEATS", Number)) -> Stack
+    note => note_menu(Field("NOTE")) -> Reporter(Number)
+    music_setInstrument => set_instrument_to(In("INSTRUMENT", MenuOption)) -> Stack
+    music_menu_INSTRUMENT => instrument_menu(Menu("INSTRUMENT", Instrument)) -> Reporter(Text)
+    music_setTempo => set_tempo_to(In("TEMPO", Number)) -> Stack
+    music_changeTempo => change_tempo_by(In("TEMPO", Number)) -> Stack
+    music_getTempo => tempo() -> Reporter(Number)
+}
+
+fn typed_fn(opcode: &str) -> Option<&'static TypedFn> {
+    TYPED_FNS.iter().find(|typed_fn| typed_fn.opcode == opcode)
+}
+
+/// Rust code of the menu option. Ex. `LooksEffect::Color`.
+/// `None` when the value isn't one of the enum's options.
+fn menu_code(menu: &str, value: &str) -> Option<String> {
+    use crate::scripting::menu::*;
+    macro_rules! menu_code {
+        ($($menu:ident),*) => {
+            match menu {
+                $(stringify!($menu) => $menu::from_value(value)
+                    .map(|option| format!("{}::{option:?}", stringify!($menu))),)*
+                _ => None,
+            }
+        };
+    }
+    menu_code!(
+        LooksEffect,
+        SoundEffect,
+        MathOp,
+        RotationStyle,
+        FrontBack,
+        ForwardBackward,
+        StopOption,
+        WhenGreaterThan,
+        DragMode,
+        CurrentTime,
+        NumberName,
+        PenColorParam,
+        Key,
+        Drum,
+        Instrument
+    )
+}
+
+const HEADER: &str = "\
+#[allow(unused_imports)]
+use mcscratchy::{
+    scripting::script_builder::{
+        BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, FieldKind, StackBuilder,
+        StackOrValue,
+    },
+    scripting::{menu::*, procedure::ProcedureBuilder},
+    typed_scripting::{arg::*, blocks::*, script_builder::*},
+};
+#[allow(unused_imports)]
+use rs_sb3::block::{BlockInputValue, BlockMutation, BlockMutationEnum, ShadowInputType};
+";
+
+/// Generates a module with functions returning the scripts and the custom blocks for each target.
+/// Ex. Sprite named "Cat" will have `pub fn cat_scripts() -> Vec<StackBuilder>`
+/// and `pub fn cat_procedures() -> Vec<ProcedureBuilder>`.
+pub fn decompile_project(project: &Project) -> Result<String, DecompileError> {
+    let global_ids = GlobalIds::from_project(project);
+    let mut fn_names: HashSet<String> = HashSet::default();
+    let mut code = HEADER.to_owned();
+    for target in &project.targets {
+        let (target, global_ids) = match target {
+            SpriteOrStage::Stage(stage) => (&stage.target, None),
+            SpriteOrStage::Sprite(sprite) => (&sprite.target, global_ids.as_ref()),
+        };
+        let mut fn_prefix = ident(&target.name);
+        while fn_names.contains(&fn_prefix) {
+            fn_prefix.insert(0, '_');
+        }
+        code.push('\n');
+        code.push_str(&decompile_target(target, global_ids, &fn_prefix)?);
+        fn_names.insert(fn_prefix);
+    }
+    Ok(code)
+}
+
+/// `global_ids` is `None` when decompiling the Stage.
+/// `fn_prefix` is put before `_scripts` and `_procedures` in the functions' name.
+pub fn decompile_target(
+    target: &Target,
+    global_ids: Option<&GlobalIds>,
+    fn_prefix: &str,
+) -> Result<String, DecompileError> {
+    let decompiler = Decompiler {
+        blocks: &target.blocks.0,
+        ids: TargetIds::new(target, global_ids),
+        arguments: procedure_arguments(&target.blocks.0),
+    };
+    let mut top_level: Vec<(&String, &Block)> = target
+        .blocks
+        .0
+        .iter()
+        .filter(|(_, block)| match block {
+            Block::Normal(n) => n.top_level,
+            Block::VarList(_) => true,
+        })
+        .collect();
+    top_level.sort_by_key(|(uid, _)| *uid);
+    let mut procedures = vec![];
+    let mut stacks = vec![];
+    for (uid, block) in top_level {
+        if let Block::Normal(block) = block {
+            if let Some(procedure) = decompiler.procedure(block)? {
+                procedures.push(procedure);
+                continue;
+            }
+        }
+        let pos = match block {
+            Block::Normal(n) => n.x.as_ref().zip(n.y.as_ref()).map(|(x, y)| {
+                (
+                    serde_json::to_value(x).unwrap_or_default(),
+                    serde_json::to_value(y).unwrap_or_default(),
+                )
+            }),
+            Block::VarList(vl) => Some((
+                serde_json::to_value(&vl.x).unwrap_or_default(),
+                serde_json::to_value(&vl.y).unwrap_or_default(),
+            )),
+        };
+        let stack = decompiler.stack(uid)?.untyped();
+        stacks.push(match pos {
+            Some((x, y)) => format!(
+                "{stack}.move_head({}, {})",
+                float_literal(x.as_f64().unwrap_or_default()),
+                float_literal(y.as_f64().unwrap_or_default())
+            ),
+            None => stack,
+        });
+    }
+    let vec = |items: Vec<String>| {
+        if items.is_empty() {
+            return "vec![]".to_owned();
+        }
+        let items: String = items
+            .into_iter()
+            .map(|item| format!("        {item},\n"))
+            .collect();
+        format!("vec![\n{items}    ]")
+    };
+    Ok(format!(
+        "/// Scripts of {name:?}\npub fn {fn_prefix}_scripts() -> Vec<StackBuilder> {{\n    {}\n}}\n\n\
+        /// Custom blocks of {name:?}\npub fn {fn_prefix}_procedures() -> Vec<ProcedureBuilder> {{\n    {}\n}}\n",
+        vec(stacks),
+        vec(procedures),
+        name = target.name,
+    ))
+}
+
+/// Turns a name into a snake case identifier
+fn ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn float_literal(n: f64) -> String {
+    format!("{n:?}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Form {
+    /// [`crate::typed_scripting::script_builder::TypedStackBuilder`]
+    Typed,
+    /// [`crate::typed_scripting::script_builder::Reporter`]
+    Reporter(Kind),
+    /// [`crate::scripting::script_builder::StackBuilder`]
+    Untyped,
+}
+
+struct Expr {
+    code: String,
+    form: Form,
+}
+
+impl Expr {
+    fn untyped(self) -> String {
+        match self.form {
+            Form::Typed => format!("{}.into_untyped()", self.code),
+            Form::Reporter(_) => format!("{}.0.into_untyped()", self.code),
+            Form::Untyped => self.code,
+        }
+    }
+
+    fn typed(self) -> String {
+        match self.form {
+            Form::Typed => self.code,
+            _ => format!("StackBlock::<AnyTarget>::assume_typed({})", self.untyped()),
+        }
+    }
+
+    /// Falls back to a raw input when the expression doesn't have the type that the argument wanted
+    fn input(self, kind: Kind) -> String {
+        match self.form {
+            Form::Reporter(k) if k == kind => self.code,
+            Form::Reporter(Kind::Text | Kind::Value | Kind::Number) if kind == Kind::MenuOption => {
+                self.code
+            }
+            Form::Typed if kind == Kind::Stack => self.code,
+            _ => format!("BlockInputBuilder::stack({})", self.untyped()),
+        }
+    }
+}
+
+struct Decompiler<'a> {
+    blocks: &'a HashMap<String, Block>,
+    ids: TargetIds<'a>,
+    /// Argument ids and names of the target's custom blocks, by proccode
+    arguments: HashMap<String, Vec<(String, String)>>,
+}
+
+impl<'a> Decompiler<'a> {
+    /// [`crate::scripting::procedure::ProcedureBuilder`] of a definition hat with its body.
+    /// `None` when the block isn't a definition that can be written as one.
+    fn procedure(&self, block: &BlockNormal) -> Result<Option<String>, DecompileError> {
+        let Some((proccode, names, warp)) = self.definition_prototype(block) else {
+            return Ok(None);
+        };
+        let Some(mut code) = procedure_code(proccode, names) else {
+            return Ok(None);
+        };
+        if warp {
+            code.push_str(".warp(true)");
+        }
+        if let Some(next) = &block.next {
+            code.push_str(&format!(".body({})", self.stack(next)?.untyped()));
+        }
+        Ok(Some(code))
+    }
+
+    /// Proccode, argument names and warp of a definition hat
+    fn definition_prototype(&self, block: &BlockNormal) -> Option<(&str, &[String], bool)> {
+        if block.opcode != "procedures_definition" {
+            return None;
+        }
+        let prototype = block.inputs.0.get("custom_block").and_then(first_uid)?;
+        let Some(Block::Normal(BlockNormal {
+            mutation: Some(mutation),
+            ..
+        })) = self.blocks.get(prototype)
+        else {
+            return None;
+        };
+        match &mutation.mutation_enum {
+            BlockMutationEnum::ProceduresPrototype {
+                proccode,
+                argumentnames,
+                warp,
+                ..
+            } => Some((proccode.as_str(), argumentnames.as_slice(), *warp)),
+            _ => None,
+        }
+    }
+
+    /// [`crate::typed_scripting::blocks::call_procedure`] of a procedure of this target.
+    /// `None` when the procedure isn't in the target.
+    fn procedure_call(&self, block: &BlockNormal) -> Result<Option<Expr>, DecompileError> {
+        let Some(BlockMutationEnum::ProceduresCall { proccode, .. }) = block
+            .mutation
+            .as_ref()
+            .map(|mutation| &mutation.mutation_enum)
+        else {
+            return Ok(None);
+        };
+        let Some(arguments) = self.arguments.get(proccode) else {
+            return Ok(None);
+        };
+        let names: Vec<String> = arguments.iter().map(|(_, name)| name.clone()).collect();
+        let Some(procedure) = procedure_code(proccode, &names) else {
+            return Ok(None);
+        };
+        let kinds = proccode
+            .split(' ')
+            .filter(|word| matches!(*word, "%s" | "%b"));
+        let args: Vec<String> = arguments
+            .iter()
+            .zip(kinds)
+            .map(|((id, _), kind)| {
+                let input = block.inputs.0.get(id);
+                Ok(match kind {
+                    "%s" => format!("value_arg({})", self.input(input, Kind::Value)?),
+                    _ if input.and_then(first_uid).is_none() => "empty_bool_arg()".to_owned(),
+                    _ => format!("bool_arg({})", self.input(input, Kind::Bool)?),
+                })
+            })
+            .collect::<Result<_, DecompileError>>()?;
+        Ok(Some(Expr {
+            code: format!(
+                "call_procedure(&{procedure}.handle(), vec![{}])",
+                args.join(", ")
+            ),
+            form: Form::Typed,
+        }))
+    }
+
+    fn varlist(&self, kind: &ListOrVariable, name: &str, id: &str) -> Expr {
+        let (function, handle) = match (kind, self.ids.is_global(kind, id)) {
+            (ListOrVariable::Variable, true) => ("global_var", "GlobalVar"),
+            (ListOrVariable::Variable, false) => ("sprite_var", "SpriteVar"),
+            (ListOrVariable::List, true) => ("global_list", "GlobalList"),
+            (ListOrVariable::List, false) => ("sprite_list", "SpriteList"),
+        };
+        Expr {
+            code: format!("{function}(&{handle}::assume_declared({name:?}))"),
+            form: Form::Reporter(Kind::Value),
+        }
+    }
+
+    fn stack(&self, first_uid: &str) -> Result<Expr, DecompileError> {
+        let mut exprs = vec![];
+        let mut current = Some(first_uid.to_owned());
+        while let Some(uid) = current {
+            match self.blocks.get(&uid) {
+                Some(Block::Normal(block)) => {
+                    exprs.push(self.block(block)?);
+                    current = block.next.clone();
+                }
+                Some(Block::VarList(block)) => {
+                    exprs.push(self.varlist(&block.kind, &block.name, &block.id));
+                    current = None;
+                }
+                None => return Err(DecompileError::MissingBlock(uid)),
+            }
+        }
+        if exprs.len() == 1 {
+            return Ok(exprs.pop().unwrap());
+        }
+        let mut exprs = exprs.into_iter();
+        let mut code = exprs.next().unwrap().typed();
+        for expr in exprs {
+            code = format!("{code}.next({})", expr.typed());
+        }
+        Ok(Expr {
+            code,
+            form: Form::Typed,
+        })
+    }
+
+    fn block(&self, block: &BlockNormal) -> Result<Expr, DecompileError> {
+        if let Some(call) = self.procedure_call(block)? {
+            return Ok(call);
+        }
+        match typed_fn(&block.opcode) {
+            // Typed functions don't make shadows, like the argument reporters in a prototype
+            Some(typed_fn) if covers(typed_fn, block) && !block.shadow => {
+                self.typed_block(typed_fn, block)
+            }
+            _ => self.raw_block(block),
+        }
+    }
+
+    fn typed_block(&self, typed_fn: &TypedFn, block: &BlockNormal) -> Result<Expr, DecompileError> {
+        let args: Vec<String> = typed_fn
+            .params
+            .iter()
+            .map(|param| {
+                Ok(match param {
+                    Param::Input(name, kind) => self.input(block.inputs.0.get(*name), *kind)?,
+                    Param::Field(name) => format!(
+                        "{:?}",
+                        block
+                            .fields
+                            .0
+                            .get(*name)
+                            .map(field_value)
+                            .unwrap_or_default()
+                    ),
+                    Param::Variable(name) => {
+                        self.varlist_field(ListOrVariable::Variable, block.fields.0.get(*name))
+                    }
+                    Param::List(name) => {
+                        self.varlist_field(ListOrVariable::List, block.fields.0.get(*name))
+                    }
+                    Param::Menu(name, menu) => {
+                        let value = block
+                            .fields
+                            .0
+                            .get(*name)
+                            .map(field_value)
+                            .unwrap_or_default();
+                        menu_code(menu, &value).unwrap_or_else(|| {
+                            format!("BlockFieldBuilder::new({value:?}.to_owned())")
+                        })
+                    }
+                    Param::Broadcast(name) => format!(
+                        "BroadcastHandle::assume_declared({:?})",
+                        block
+                            .fields
+                            .0
+                            .get(*name)
+                            .map(field_value)
+                            .unwrap_or_default()
+                    ),
+                    Param::Substack(name) => match block.inputs.0.get(*name).and_then(first_uid) {
+                        Some(uid) => format!("Some({})", self.stack(uid)?.typed()),
+                        None => "None::<StackBlock>".to_owned(),
+                    },
+                    Param::Stop(name) => stop_code(block, name).unwrap_or_default(),
+                })
+            })
+            .collect::<Result<_, DecompileError>>()?;
+        Ok(Expr {
+            code: format!("{}({})", typed_fn.function, args.join(", ")),
+            form: match typed_fn.output {
+                Output::Stack => Form::Typed,
+                Output::Reporter(kind) => Form::Reporter(kind),
+            },
+        })
+    }
+
+    fn varlist_field(&self, kind: ListOrVariable, field: Option<&BlockField>) -> String {
+        let (name, id) = match field {
+            Some(BlockField::WithId { value, id }) => (value_to_string(value), id.clone()),
+            Some(BlockField::NoId { value }) => (value_to_string(value), None),
+            None => (String::new(), None),
+        };
+        let is_global = match id {
+            Some(id) => self.ids.is_global(&kind, &id),
+            None => self.ids.is_stage(),
+        };
+        let wrapper = match (kind, is_global) {
+            (ListOrVariable::Variable, true) => "GlobalVar",
+            (ListOrVariable::Variable, false) => "SpriteVar",
+            (ListOrVariable::List, true) => "GlobalList",
+            (ListOrVariable::List, false) => "SpriteList",
+        };
+        format!("{wrapper}::assume_declared({name:?})")
+    }
+
+    fn input(&self, input: Option<&BlockInput>, kind: Kind) -> Result<String, DecompileError> {
+        Ok(match input.and_then(|input| input.inputs.first()) {
+            Some(Some(UidOrValue::Uid(uid))) if kind == Kind::MenuOption => {
+                match self.menu_option(uid) {
+                    Some(option) => option,
+                    None => self.stack(uid)?.input(kind),
+                }
+            }
+            Some(Some(UidOrValue::Uid(uid))) => self.stack(uid)?.input(kind),
+            Some(Some(UidOrValue::Value(value))) => {
+                let json = serde_json::to_value(value).unwrap_or_default();
+                self.value_input(&json, kind)
+            }
+            _ => "BlockInputBuilder::new()".to_owned(),
+        })
+    }
+
+    /// Option of the menu shadow at `uid` as the menu's enum, it plugs the same menu. Ex. `Drum::SnareDrum`
+    fn menu_option(&self, uid: &str) -> Option<String> {
+        let Some(Block::Normal(block)) = self.blocks.get(uid) else {
+            return None;
+        };
+        let typed_fn = typed_fn(&block.opcode)?;
+        let [Param::Menu(name, menu)] = typed_fn.params else {
+            return None;
+        };
+        if !block.shadow || block.next.is_some() || !covers(typed_fn, block) {
+            return None;
+        }
+        menu_code(menu, &field_value(block.fields.0.get(*name)?))
+    }
+
+    /// `json` is the serialized [`rs_sb3::block::BlockInputValue`]. Ex. `[4, "10"]`
+    fn value_input(&self, json: &serde_json::Value, kind: Kind) -> String {
+        let code = json[0].as_u64().unwrap_or_default();
+        let text = json_to_string(&json[1]);
+        match code {
+            12 | 13 => {
+                let var_or_list = if code == 12 {
+                    ListOrVariable::Variable
+                } else {
+                    ListOrVariable::List
+                };
+                let id = json_to_string(&json[2]);
+                return self.varlist(&var_or_list, &text, &id).input(kind);
+            }
+            _ => {}
+        }
+        let is_number = (4..=8).contains(&code);
+        // Number types other than Number keep their type only when written by the typed function
+        let same_type = match kind {
+            Kind::PositiveNumber => code == 5,
+            Kind::PositiveInteger => code == 6,
+            Kind::Integer => code == 7,
+            Kind::Angle => code == 8,
+            _ => false,
+        };
+        match kind {
+            Kind::Number | Kind::Value if is_number || kind == Kind::Number => {
+                if let Ok(n) = text.parse::<i64>() {
+                    return n.to_string();
+                }
+                if let Ok(n) = text.parse::<f64>() {
+                    if n.is_finite() {
+                        return float_literal(n);
+                    }
+                }
+            }
+            Kind::PositiveNumber | Kind::PositiveInteger | Kind::Integer | Kind::Angle
+                if same_type =>
+            {
+                if let Ok(n) = text.parse::<i64>() {
+                    return n.to_string();
+                }
+                if let Ok(n) = text.parse::<f64>() {
+                    if n.is_finite() {
+                        return float_literal(n);
+                    }
+                }
+            }
+            Kind::Value | Kind::Text => return format!("{text:?}"),
+            Kind::Color if code == 9 => return format!("{text:?}"),
+            Kind::Broadcast if code == 11 => {
+                return format!("BroadcastHandle::assume_declared({text:?})")
+            }
+            _ => {}
+        }
+        format!("BlockInputBuilder::value({})", value_code(json))
+    }
+
+    fn raw_block(&self, block: &BlockNormal) -> Result<Expr, DecompileError> {
+        let mut code = format!(
+            "StackBuilder::start(BlockNormalBuilder::new({:?})",
+            block.opcode
+        );
+        let mut inputs: Vec<(&String, &BlockInput)> = block.inputs.0.iter().collect();
+        inputs.sort_by_key(|(key, _)| *key);
+        for (key, input) in inputs {
+            code.push_str(&format!(".add_input({key:?}, {})", self.raw_input(input)?));
+        }
+        let mut fields: Vec<(&String, &BlockField)> = block.fields.0.iter().collect();
+        fields.sort_by_key(|(key, _)| *key);
+        for (key, field) in fields {
+            code.push_str(&format!(".add_field({key:?}, {})", self.raw_field(field)));
+        }
+        if let Some(mutation) = &block.mutation {
+            code.push_str(&format!(".mutation({})", mutation_code(mutation)));
+        }
+        if block.shadow {
+            code.push_str(".shadow(true)");
+        }
+        code.push(')');
+        Ok(Expr {
+            code,
+            form: Form::Untyped,
+        })
+    }
+
+    fn raw_input(&self, input: &BlockInput) -> Result<String, DecompileError> {
+        let shadow = match serde_json::to_value(&input.shadow)
+            .ok()
+            .and_then(|json| json.as_u64())
+        {
+            Some(1) => "Shadow",
+            Some(3) => "ShadowObscured",
+            _ => "NoShadow",
+        };
+        let mut code = format!("BlockInputBuilder::new().shadow(ShadowInputType::{shadow})");
+        for value in &input.inputs {
+            let value = match value {
+                Some(UidOrValue::Uid(uid)) => {
+                    format!("Some(StackOrValue::Stack({}))", self.stack(uid)?.untyped())
+                }
+                Some(UidOrValue::Value(value)) => {
+                    let json = serde_json::to_value(value).unwrap_or_default();
+                    match json[0].as_u64() {
+                        Some(code @ (12 | 13)) => {
+                            let var_or_list = if code == 12 {
+                                ListOrVariable::Variable
+                            } else {
+                                ListOrVariable::List
+                            };
+                            let varlist = self.varlist(
+                                &var_or_list,
+                                &json_to_string(&json[1]),
+                                &json_to_string(&json[2]),
+                            );
+                            format!("Some(StackOrValue::Stack({}))", varlist.untyped())
+                        }
+                        _ => format!("Some(StackOrValue::Value({}))", value_code(&json)),
+                    }
+                }
+                None => "None".to_owned(),
+            };
+            code.push_str(&format!(".input({value})"));
+        }
+        Ok(code)
+    }
+
+    fn raw_field(&self, field: &BlockField) -> String {
+        let (value, kind) = match field {
+            BlockField::NoId { value } => (value_to_string(value), FieldKind::NoRef),
+            BlockField::WithId { value, id: None } => {
+                (value_to_string(value), FieldKind::NoRefMaybe)
+            }
+            BlockField::WithId {
+                value,
+                id: Some(id),
+            } => (value_to_string(value), self.ids.field_kind(id)),
+        };
+        format!("BlockFieldBuilder::new_with_kind({value:?}.to_owned(), FieldKind::{kind:?})")
+    }
+}
+
+/// [`crate::scripting::procedure::ProcedureBuilder`] with the arguments of the proccode, without a body.
+/// `None` when the proccode doesn't start with a label or doesn't have a `%s` or `%b` for each name.
+fn procedure_code(proccode: &str, argument_names: &[String]) -> Option<String> {
+    let mut code = "ProcedureBuilder".to_owned();
+    let mut names = argument_names.iter();
+    let mut label: Option<String> = None;
+    let push_label = |code: &mut String, label: String| {
+        if code == "ProcedureBuilder" {
+            code.push_str(&format!("::new({label:?})"));
+        } else {
+            code.push_str(&format!(".label({label:?})"));
+        }
+    };
+    for word in proccode.split(' ') {
+        let function = match word {
+            "%s" => "string_number_argument",
+            "%b" => "boolean_argument",
+            _ => {
+                label = Some(match label {
+                    Some(label) => format!("{label} {word}"),
+                    None => word.to_owned(),
+                });
+                continue;
+            }
+        };
+        push_label(&mut code, label.take()?);
+        code.push_str(&format!(".{function}({:?})", names.next()?));
+    }
+    if let Some(label) = label {
+        push_label(&mut code, label);
+    }
+    names.next().is_none().then_some(code)
+}
+
+/// Rust code for the mutation, argument ids of a procedure are kept as they are
+fn mutation_code(mutation: &BlockMutation) -> String {
+    let strings = |strings: &[String]| {
+        strings
+            .iter()
+            .map(|string| format!("{string:?}.to_owned()"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mutation_enum = match &mutation.mutation_enum {
+        BlockMutationEnum::ProceduresPrototype {
+            proccode,
+            argumentids,
+            argumentnames,
+            argumentdefaults,
+            warp,
+        } => format!(
+            "BlockMutationEnum::ProceduresPrototype {{ proccode: {proccode:?}.to_owned(), \
+            argumentids: vec![{}], argumentnames: vec![{}], argumentdefaults: vec![{}], warp: {warp} }}",
+            strings(argumentids),
+            strings(argumentnames),
+            argumentdefaults
+                .iter()
+                .map(|value| format!("{:?}.to_owned().into()", value_to_string(value)))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        BlockMutationEnum::ProceduresCall {
+            proccode,
+            argumentids,
+            warp,
+        } => format!(
+            "BlockMutationEnum::ProceduresCall {{ proccode: {proccode:?}.to_owned(), \
+            argumentids: vec![{}], warp: {warp} }}",
+            strings(argumentids),
+        ),
+        BlockMutationEnum::ControlStop { hasnext } => {
+            format!("BlockMutationEnum::ControlStop {{ hasnext: {hasnext} }}")
+        }
+    };
+    format!(
+        "BlockMutation {{ tag_name: {:?}.to_owned(), children: vec![], mutation_enum: {mutation_enum} }}",
+        mutation.tag_name
+    )
+}
+
+/// Whether all inputs and fields of the block will be kept when using the typed function
+fn covers(typed_fn: &TypedFn, block: &BlockNormal) -> bool {
+    let has_param = |key: &str| {
+        typed_fn.params.iter().any(|param| match param {
+            Param::Input(name, _)
+            | Param::Field(name)
+            | Param::Menu(name, _)
+            | Param::Variable(name)
+            | Param::List(name)
+            | Param::Broadcast(name)
+            | Param::Substack(name)
+            | Param::Stop(name) => *name == key,
+        })
+    };
+    let stop_shape_matches = typed_fn.params.iter().all(|param| match param {
+        Param::Stop(name) => stop_code(block, name).is_some(),
+        _ => true,
+    });
+    block.inputs.0.keys().all(|key| has_param(key))
+        && block.fields.0.keys().all(|key| has_param(key))
+        && stop_shape_matches
+}
+
+/// Rust code of the stop option type. Ex. `StopThisScript`.
+/// `None` when the option or `hasnext` doesn't fit any of the types.
+fn stop_code(block: &BlockNormal, name: &str) -> Option<String> {
+    let option = StopOption::from_value(&field_value(block.fields.0.get(name)?))?;
+    let stackable = option == StopOption::OtherScriptsInSprite;
+    (has_next(block) == stackable && (stackable || block.next.is_none()))
+        .then(|| format!("Stop{option:?}"))
+}
+
+fn first_uid(input: &BlockInput) -> Option<&String> {
+    match input.inputs.first() {
+        Some(Some(UidOrValue::Uid(uid))) => Some(uid),
+        _ => None,
+    }
+}
+
+fn has_next(block: &BlockNormal) -> bool {
+    let json = serde_json::to_value(&block.mutation).unwrap_or_default();
+    match &json["hasnext"] {
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::String(s) => s == "true",
+        _ => false,
+    }
+}
+
+fn field_value(field: &BlockField) -> String {
+    match field {
+        BlockField::NoId { value } | BlockField::WithId { value, .. } => value_to_string(value),
+    }
+}
+
+fn value_to_string(value: &rs_sb3::value::Value) -> String {
+    json_to_string(&serde_json::to_value(value).unwrap_or_default())
+}
+
+fn json_to_string(json: &serde_json::Value) -> String {
+    match json {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Rust code for [`rs_sb3::block::BlockInputValue`]
+fn value_code(json: &serde_json::Value) -> String {
+    let value = match &json[1] {
+        serde_json::Value::Number(n) => {
+            format!("{}.into()", float_literal(n.as_f64().unwrap_or_default()))
+        }
+        other => format!("{:?}.to_owned().into()", json_to_string(other)),
+    };
+    let name = json_to_string(&json[1]);
+    let id = json_to_string(&json[2]);
+    match json[0].as_u64().unwrap_or_default() {
+        4 => format!("BlockInputValue::Number {{ value: {value} }}"),
+        5 => format!("BlockInputValue::PositiveNumber {{ value: {value} }}"),
+        6 => format!("BlockInputValue::PositiveInteger {{ value: {value} }}"),
+        7 => format!("BlockInputValue::Integer {{ value: {value} }}"),
+        8 => format!("BlockInputValue::Angle {{ value: {value} }}"),
+        9 => format!("BlockInputValue::Color {{ value: {value} }}"),
+        // Empty id is resolved from the name when building
+        11 => {
+            format!("BlockInputValue::Broadcast {{ name: {name:?}.to_owned(), id: String::new() }}")
+        }
+        12 => format!(
+            "BlockInputValue::Variable {{ name: {name:?}.to_owned(), id: {id:?}.to_owned() }}"
+        ),
+        13 => {
+            format!("BlockInputValue::List {{ name: {name:?}.to_owned(), id: {id:?}.to_owned() }}")
+        }
+        _ => format!("BlockInputValue::String {{ value: {value} }}"),
+    }
+}
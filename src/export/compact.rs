@@ -0,0 +1,207 @@
+//! Passes over a built project that make project.json smaller without changing what it does.
+//! Same as what Scratch does when it saves a project.
+
+use std::collections::{HashMap, HashSet};
+
+use rs_sb3::{
+    block::{
+        Block, BlockField, BlockInputValue, BlockNormal, BlockVarListReporterTop, ListOrVariable,
+        UidOrValue,
+    },
+    project::Project,
+    target::{SpriteOrStage, Target},
+    value::Value,
+};
+
+use crate::uid::Uid;
+
+fn targets_mut(project: &mut Project) -> impl Iterator<Item = &mut Target> {
+    project.targets.iter_mut().map(|target| match target {
+        SpriteOrStage::Stage(stage) => &mut stage.target,
+        SpriteOrStage::Sprite(sprite) => &mut sprite.target,
+    })
+}
+
+// Primitives ==================================================================
+
+/// Writes primitive shadows, broadcast menus and variable/list reporters as compact arrays.
+/// Ex. `[4, "10"]` in the input instead of a `math_number` block, `[12, name, id]` for a variable.
+pub fn compact_primitives(project: &mut Project) {
+    for target in targets_mut(project) {
+        let blocks = &mut target.blocks.0;
+        let primitives: HashMap<String, BlockInputValue> = blocks
+            .iter()
+            .filter_map(|(uid, block)| match block {
+                Block::Normal(block) if !block.top_level => {
+                    Some((uid.clone(), primitive_of(block)?))
+                }
+                _ => None,
+            })
+            .collect();
+        let mut replaced: HashSet<String> = HashSet::new();
+        for block in blocks.values_mut() {
+            let Block::Normal(block) = block else {
+                continue;
+            };
+            for input in block.inputs.0.values_mut() {
+                for value in input.inputs.iter_mut() {
+                    let Some(UidOrValue::Uid(uid)) = value else {
+                        continue;
+                    };
+                    if let Some(primitive) = primitives.get(uid) {
+                        replaced.insert(uid.clone());
+                        *value = Some(UidOrValue::Value(primitive.clone()));
+                    }
+                }
+            }
+        }
+        blocks.retain(|uid, _| !replaced.contains(uid));
+
+        for block in blocks.values_mut() {
+            if let Some(var_list) = top_level_var_list(block) {
+                *block = Block::VarList(var_list);
+            }
+        }
+    }
+}
+
+fn primitive_of(block: &BlockNormal) -> Option<BlockInputValue> {
+    if block.comment.is_some()
+        || block.next.is_some()
+        || !block.inputs.0.is_empty()
+        || block.fields.0.len() != 1
+    {
+        return None;
+    }
+    let (value, id) = match block.fields.0.values().next()? {
+        BlockField::WithId { value, id } => (value.clone(), id.clone()),
+        BlockField::NoId { value } => (value.clone(), None),
+    };
+    let name_id = || match (&value, &id) {
+        (Value::Text(name), Some(id)) => Some((name.clone(), id.clone())),
+        _ => None,
+    };
+    let primitive = match (block.opcode.as_str(), block.shadow) {
+        ("math_number", true) => BlockInputValue::Number { value },
+        ("math_positive_number", true) => BlockInputValue::PositiveNumber { value },
+        ("math_whole_number", true) => BlockInputValue::PositiveInteger { value },
+        ("math_integer", true) => BlockInputValue::Integer { value },
+        ("math_angle", true) => BlockInputValue::Angle { value },
+        ("colour_picker", true) => BlockInputValue::Color { value },
+        ("text", true) => BlockInputValue::String { value },
+        ("event_broadcast_menu", true) => {
+            let (name, id) = name_id()?;
+            BlockInputValue::Broadcast { name, id }
+        }
+        ("data_variable", false) => {
+            let (name, id) = name_id()?;
+            BlockInputValue::Variable { name, id }
+        }
+        ("data_listcontents", false) => {
+            let (name, id) = name_id()?;
+            BlockInputValue::List { name, id }
+        }
+        _ => return None,
+    };
+    Some(primitive)
+}
+
+/// `[12, name, id, x, y]` for a variable reporter on its own
+fn top_level_var_list(block: &Block) -> Option<BlockVarListReporterTop> {
+    let Block::Normal(block) = block else {
+        return None;
+    };
+    if !block.top_level {
+        return None;
+    }
+    let primitive = primitive_of(block)?;
+    let (kind, name, id) = match primitive {
+        BlockInputValue::Variable { name, id } => (ListOrVariable::Variable, name, id),
+        BlockInputValue::List { name, id } => (ListOrVariable::List, name, id),
+        _ => return None,
+    };
+    Some(BlockVarListReporterTop {
+        kind,
+        name,
+        id,
+        x: block.x.clone()?,
+        y: block.y.clone()?,
+    })
+}
+
+// Uids ========================================================================
+
+/// Renames block and comment uids to the shortest ones that aren't used by anything else.
+/// Variable, list and broadcast uids are kept since monitors and other targets refer to them.
+pub fn shorten_uids(project: &mut Project) {
+    let mut kept: HashSet<String> = HashSet::new();
+    for target in targets_mut(project) {
+        let ids = target.variables.0.keys().chain(target.lists.0.keys());
+        kept.extend(ids.chain(target.broadcasts.0.keys()).cloned());
+    }
+    let mut index = 0;
+    let mut next_uid = || loop {
+        let uid = Uid::nth_shortest(index).into_inner();
+        index += 1;
+        if !kept.contains(&uid) {
+            return uid;
+        }
+    };
+    for target in targets_mut(project) {
+        let block_uids = renames(target.blocks.0.keys(), &mut next_uid);
+        let comment_uids = renames(target.comments.0.keys(), &mut next_uid);
+        let rename_block = |uid: &mut String| {
+            if let Some(renamed) = block_uids.get(uid) {
+                uid.clone_from(renamed);
+            }
+        };
+
+        let blocks = std::mem::take(&mut target.blocks.0);
+        for (uid, mut block) in blocks {
+            if let Block::Normal(block) = &mut block {
+                if let Some(next) = &mut block.next {
+                    rename_block(next);
+                }
+                if let Some(parent) = &mut block.parent {
+                    rename_block(parent);
+                }
+                if let Some(comment) = &mut block.comment {
+                    if let Some(renamed) = comment_uids.get(comment) {
+                        comment.clone_from(renamed);
+                    }
+                }
+                for input in block.inputs.0.values_mut() {
+                    for value in input.inputs.iter_mut() {
+                        if let Some(UidOrValue::Uid(uid)) = value {
+                            rename_block(uid);
+                        }
+                    }
+                }
+            }
+            target.blocks.0.insert(block_uids[&uid].clone(), block);
+        }
+
+        let comments = std::mem::take(&mut target.comments.0);
+        for (uid, mut comment) in comments {
+            if let Some(block_id) = &mut comment.block_id {
+                rename_block(block_id);
+            }
+            target
+                .comments
+                .0
+                .insert(comment_uids[&uid].clone(), comment);
+        }
+    }
+}
+
+/// Sorted so the same project always gets the same uids
+fn renames<'a>(
+    uids: impl Iterator<Item = &'a String>,
+    next_uid: &mut impl FnMut() -> String,
+) -> HashMap<String, String> {
+    let mut uids: Vec<&String> = uids.collect();
+    uids.sort();
+    uids.into_iter()
+        .map(|uid| (uid.clone(), next_uid()))
+        .collect()
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::fs::File as FsFile;
+use std::path::{Path, PathBuf};
+
+use crate::project::{validation::BuildError, ProjectBuilder};
+use rs_sb3::project::Project;
+use std::io::{Error as IoError, Seek, Write};
+
+pub mod compact;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(IoError),
+    Zip(zip::result::ZipError),
+    Build(Box<BuildError>),
+}
+
+impl std::error::Error for ExportError {}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(io) => write!(f, "{io}"),
+            ExportError::Zip(zip) => write!(f, "{zip}"),
+            ExportError::Build(build) => write!(f, "{build}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(value: std::io::Error) -> Self {
+        ExportError::Io(value)
+    }
+}
+impl From<zip::result::ZipError> for ExportError {
+    fn from(value: zip::result::ZipError) -> Self {
+        ExportError::Zip(value)
+    }
+}
+impl From<Box<BuildError>> for ExportError {
+    fn from(value: Box<BuildError>) -> Self {
+        ExportError::Build(value)
+    }
+}
+
+/// How project.json is written
+#[rustfmt::skip]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// See [`compact::compact_primitives`]
+    pub compact_primitives: bool,
+    /// See [`compact::shorten_uids`]
+    pub short_uids:         bool,
+    /// Writes project.json without whitespaces instead of pretty-printing it
+    pub minify:             bool,
+}
+
+impl ExportOptions {
+    /// Pretty-printed, same as [`write_zip`]
+    pub fn readable() -> ExportOptions {
+        ExportOptions::default()
+    }
+
+    /// Everything that makes project.json smaller.
+    /// Uses this when the project is close to Scratch's 5 MB limit for project.json.
+    pub fn compact() -> ExportOptions {
+        ExportOptions {
+            compact_primitives: true,
+            short_uids: true,
+            minify: true,
+        }
+    }
+}
+
+/// Size of project.json in bytes after each step of [`ExportOptions`].
+/// Steps that are off have the same size as the step before.
+#[rustfmt::skip]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizeReport {
+    /// Pretty-printed without any step
+    pub readable:           usize,
+    pub compact_primitives: usize,
+    pub short_uids:         usize,
+    pub minify:             usize,
+}
+
+impl SizeReport {
+    /// Size of the project.json that's written
+    pub fn size(&self) -> usize {
+        self.minify
+    }
+
+    pub fn saved(&self) -> usize {
+        self.readable - self.size()
+    }
+}
+
+impl std::fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percent = |saved: usize| match self.readable {
+            0 => 0.0,
+            readable => saved as f64 / readable as f64 * 100.0,
+        };
+        write!(
+            f,
+            "project.json: {} -> {} bytes, saved {} ({:.1}%)",
+            self.readable,
+            self.size(),
+            self.saved(),
+            percent(self.saved())
+        )?;
+        let steps = [
+            ("compact primitives", self.readable, self.compact_primitives),
+            ("short uids", self.compact_primitives, self.short_uids),
+            ("minify", self.short_uids, self.minify),
+        ];
+        for (step, before, after) in steps {
+            let saved = before - after;
+            write!(f, "\n  {step}: saved {saved} ({:.1}%)", percent(saved))?;
+        }
+        Ok(())
+    }
+}
+
+/// Return amount written
+pub fn write_zip<W: Write + Seek>(
+    writer: W,
+    project: ProjectBuilder,
+) -> Result<usize, ExportError> {
+    let report = write_zip_with(writer, project, ExportOptions::readable())?;
+    Ok(report.size())
+}
+
+/// Same as [`write_zip`] but with the options for project.json.
+/// Returns how much each option saved.
+pub fn write_zip_with<W: Write + Seek>(
+    writer: W,
+    project: ProjectBuilder,
+    options: ExportOptions,
+) -> Result<SizeReport, ExportError> {
+    let mut res_buf = vec![];
+    let mut project = project.build(&mut res_buf)?;
+    // Fixed modified time so the same project always gives the same archive
+    let file_options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+    let mut zip = zip::ZipWriter::new(writer);
+    let mut written_files: HashSet<PathBuf> = HashSet::default();
+    for mut res in res_buf {
+        let file_name = res.generate_file_name();
+        if !written_files.insert(file_name.clone()) {
+            continue;
+        }
+        zip.start_file(file_name.to_str().unwrap(), file_options)?;
+        zip.write_all(res.content())?;
+    }
+    zip.start_file(
+        PathBuf::from("project")
+            .with_extension("json")
+            .to_str()
+            .unwrap(),
+        file_options,
+    )?;
+    // Serialized once per step, the last one is what's written
+    let mut project_json = pretty(&project);
+    let mut report = SizeReport {
+        readable: project_json.len(),
+        ..Default::default()
+    };
+    if options.compact_primitives {
+        compact::compact_primitives(&mut project);
+        project_json = pretty(&project);
+    }
+    report.compact_primitives = project_json.len();
+    if options.short_uids {
+        compact::shorten_uids(&mut project);
+        project_json = pretty(&project);
+    }
+    report.short_uids = project_json.len();
+    if options.minify {
+        // Going through `serde_json::Value` sorts the object keys
+        project_json = serde_json::to_string(&serde_json::to_value(&project).unwrap()).unwrap();
+    }
+    let bytes = project_json.as_bytes();
+    zip.write_all(bytes)?;
+    zip.finish()?;
+    report.minify = bytes.len();
+    Ok(report)
+}
+
+fn pretty(project: &Project) -> String {
+    // Going through `serde_json::Value` sorts the object keys
+    serde_json::to_string_pretty(&serde_json::to_value(project).unwrap()).unwrap()
+}
+
+pub fn export<P: AsRef<Path>>(project: ProjectBuilder, path: P) -> Result<(), ExportError> {
+    let _report = export_with(project, path, ExportOptions::readable())?;
+    Ok(())
+}
+
+/// Same as [`export`] but with the options for project.json
+pub fn export_with<P: AsRef<Path>>(
+    project: ProjectBuilder,
+    path: P,
+    options: ExportOptions,
+) -> Result<SizeReport, ExportError> {
+    let zip_file = FsFile::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    write_zip_with(zip_file, project, options)
+}
@@ -0,0 +1,663 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File as FsFile;
+use std::io::{Error as IoError, Read, Seek};
+use std::path::Path;
+
+use rs_sb3::{
+    asset::{Asset, Costume, Sound},
+    block::{
+        Block, BlockField, BlockInput, BlockInputValue, BlockMutationEnum, BlockNormal,
+        BlockVarListReporterTop, ListOrVariable, UidOrValue,
+    },
+    comment::Comment,
+    project::Project,
+    target::{Sprite, SpriteOrStage, Stage, Target},
+    value::{Number, Value},
+};
+
+use crate::{
+    project::{
+        asset::{AssetBuilder, CostumeBuilder, SoundBuilder},
+        script::{CommentBuilder, ListBuilder, VariableBuilder},
+        target::{SpriteBuilder, StageBuilder, TargetBuilder},
+        ProjectBuilder,
+    },
+    resource::{Resource, ResourceError},
+    scripting::{
+        procedure::ProcedureBuilder,
+        script_builder::{
+            BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, BlockVarListBuilder,
+            FieldKind, StackBuilder, StackOrValue,
+        },
+    },
+    uid::Uid,
+};
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(IoError),
+    Zip(zip::result::ZipError),
+    Json(serde_json::Error),
+    Resource(ResourceError),
+    /// Project doesn't have a Stage
+    NoStage,
+    /// Block id that is referenced but doesn't exist in the target
+    MissingBlock(String),
+}
+
+impl std::error::Error for ImportError {}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(io) => write!(f, "{io}"),
+            ImportError::Zip(zip) => write!(f, "{zip}"),
+            ImportError::Json(json) => write!(f, "{json}"),
+            ImportError::Resource(res) => write!(f, "{res}"),
+            ImportError::NoStage => write!(f, "project doesn't have a stage"),
+            ImportError::MissingBlock(uid) => write!(f, "block `{uid}` doesn't exist"),
+        }
+    }
+}
+
+impl From<IoError> for ImportError {
+    fn from(value: IoError) -> Self {
+        ImportError::Io(value)
+    }
+}
+impl From<zip::result::ZipError> for ImportError {
+    fn from(value: zip::result::ZipError) -> Self {
+        ImportError::Zip(value)
+    }
+}
+impl From<serde_json::Error> for ImportError {
+    fn from(value: serde_json::Error) -> Self {
+        ImportError::Json(value)
+    }
+}
+impl From<ResourceError> for ImportError {
+    fn from(value: ResourceError) -> Self {
+        ImportError::Resource(value)
+    }
+}
+
+/// Reads a .sb3 archive back into a [`ProjectBuilder`].
+///
+/// Monitors are not imported since they're referencing variable ids that will be regenerated.
+pub fn read_zip<R: Read + Seek>(reader: R) -> Result<ProjectBuilder, ImportError> {
+    let mut zip = zip::ZipArchive::new(reader)?;
+    let project: Project = {
+        let mut json = String::new();
+        zip.by_name("project.json")?.read_to_string(&mut json)?;
+        serde_json::from_str(&json)?
+    };
+    let global_ids = GlobalIds::from_project(&project).ok_or(ImportError::NoStage)?;
+    let Project { meta, targets, .. } = project;
+
+    let mut stage = None;
+    let mut sprites = vec![];
+    for target in targets {
+        match target {
+            SpriteOrStage::Stage(s) => stage = Some(s),
+            SpriteOrStage::Sprite(s) => sprites.push(s),
+        }
+    }
+    let stage = stage.ok_or(ImportError::NoStage)?;
+
+    let mut project_builder = ProjectBuilder::new().set_stage(import_stage(stage, &mut zip)?);
+    for sprite in sprites {
+        project_builder = project_builder.add_sprite(import_sprite(sprite, &mut zip, &global_ids)?);
+    }
+    project_builder.meta = meta;
+    Ok(project_builder)
+}
+
+pub fn import<P: AsRef<Path>>(path: P) -> Result<ProjectBuilder, ImportError> {
+    let zip_file = FsFile::options().read(true).open(path)?;
+    read_zip(zip_file)
+}
+
+/// Ids of the Stage's variables and lists, and of every broadcasts in the project
+#[derive(Debug, Default, Clone)]
+pub struct GlobalIds {
+    pub vars: HashSet<String>,
+    pub lists: HashSet<String>,
+    pub broadcasts: HashSet<String>,
+}
+
+impl GlobalIds {
+    /// `None` if the project doesn't have a Stage
+    pub fn from_project(project: &Project) -> Option<GlobalIds> {
+        let stage = project.targets.iter().find_map(|target| match target {
+            SpriteOrStage::Stage(stage) => Some(&stage.target),
+            SpriteOrStage::Sprite(_) => None,
+        })?;
+        Some(GlobalIds {
+            vars: stage.variables.0.keys().cloned().collect(),
+            lists: stage.lists.0.keys().cloned().collect(),
+            broadcasts: project
+                .targets
+                .iter()
+                .flat_map(|target| match target {
+                    SpriteOrStage::Stage(stage) => stage.target.broadcasts.0.keys(),
+                    SpriteOrStage::Sprite(sprite) => sprite.target.broadcasts.0.keys(),
+                })
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+/// What the ids in a target's fields and inputs are of
+pub(crate) struct TargetIds<'a> {
+    /// `None` when it's the Stage
+    global_ids: Option<&'a GlobalIds>,
+    vars: HashSet<String>,
+    lists: HashSet<String>,
+    broadcasts: HashSet<String>,
+}
+
+impl<'a> TargetIds<'a> {
+    /// `global_ids` is `None` when it's the Stage.
+    pub(crate) fn new(target: &Target, global_ids: Option<&'a GlobalIds>) -> TargetIds<'a> {
+        TargetIds {
+            global_ids,
+            vars: target.variables.0.keys().cloned().collect(),
+            lists: target.lists.0.keys().cloned().collect(),
+            broadcasts: target
+                .broadcasts
+                .0
+                .keys()
+                .chain(global_ids.into_iter().flat_map(|ids| ids.broadcasts.iter()))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_stage(&self) -> bool {
+        self.global_ids.is_none()
+    }
+
+    /// Stage's variable and list is global.
+    /// The builder will resolve both scope to the same thing when building the Stage.
+    pub(crate) fn is_global(&self, kind: &ListOrVariable, id: &str) -> bool {
+        match (self.global_ids, kind) {
+            (None, _) => true,
+            (Some(global_ids), ListOrVariable::Variable) => global_ids.vars.contains(id),
+            (Some(global_ids), ListOrVariable::List) => global_ids.lists.contains(id),
+        }
+    }
+
+    /// Kind of the field with this id
+    pub(crate) fn field_kind(&self, id: &str) -> FieldKind {
+        if self.vars.contains(id) {
+            if self.is_stage() {
+                FieldKind::GlobalVariable
+            } else {
+                FieldKind::SpriteVariable
+            }
+        } else if self.lists.contains(id) {
+            if self.is_stage() {
+                FieldKind::GlobalList
+            } else {
+                FieldKind::SpriteList
+            }
+        } else if self.broadcasts.contains(id) {
+            FieldKind::Broadcast
+        } else if self.is_global(&ListOrVariable::Variable, id) {
+            FieldKind::GlobalVariable
+        } else if self.is_global(&ListOrVariable::List, id) {
+            FieldKind::GlobalList
+        } else {
+            FieldKind::Broadcast
+        }
+    }
+}
+
+fn import_stage<R: Read + Seek>(
+    stage: Stage,
+    zip: &mut zip::ZipArchive<R>,
+) -> Result<StageBuilder, ImportError> {
+    let Stage {
+        target,
+        tempo,
+        video_state,
+        video_transparency,
+        ..
+    } = stage;
+    let target = import_target(target, zip, None)?;
+    Ok(StageBuilder::new(target)
+        .tempo(number_to_f64(&tempo) as i64)
+        .video_state(video_state)
+        .video_transparency(number_to_f64(&video_transparency) as i64))
+}
+
+fn import_sprite<R: Read + Seek>(
+    sprite: Sprite,
+    zip: &mut zip::ZipArchive<R>,
+    global_ids: &GlobalIds,
+) -> Result<SpriteBuilder, ImportError> {
+    let Sprite {
+        target,
+        visible,
+        x,
+        y,
+        size,
+        direction,
+        draggable,
+        rotation_style,
+        ..
+    } = sprite;
+    let target = import_target(target, zip, Some(global_ids))?;
+    Ok(SpriteBuilder::new(target)
+        .visible(visible)
+        .pos(number_to_f64(&x), number_to_f64(&y))
+        .size(number_to_f64(&size))
+        .direction(number_to_f64(&direction))
+        .draggable(draggable)
+        .rotation_style(rotation_style))
+}
+
+/// `global_ids` is `None` when importing the Stage
+fn import_target<R: Read + Seek>(
+    target: Target,
+    zip: &mut zip::ZipArchive<R>,
+    global_ids: Option<&GlobalIds>,
+) -> Result<TargetBuilder, ImportError> {
+    let ids = TargetIds::new(&target, global_ids);
+    let Target {
+        name,
+        variables,
+        lists,
+        broadcasts,
+        blocks,
+        comments,
+        current_costume,
+        costumes,
+        sounds,
+        layer_order,
+        volume,
+    } = target;
+    let mut target_builder = TargetBuilder::new(name)
+        .current_costume(current_costume as u64)
+        .layer_order(layer_order as u64)
+        .volume(number_to_f64(&volume));
+
+    for (_, variable) in variables.0.iter() {
+        let variable_builder = if variable.is_cloud_variable {
+            VariableBuilder::new_cloud_variable(variable.value.clone())
+        } else {
+            VariableBuilder::new(variable.value.clone())
+        };
+        target_builder = target_builder.add_variable(variable.name.clone(), variable_builder);
+    }
+    for (_, list) in lists.0.iter() {
+        target_builder =
+            target_builder.add_list(list.name.clone(), ListBuilder::new(list.values.clone()));
+    }
+    for (uid, broadcast) in broadcasts.0.iter() {
+        target_builder =
+            target_builder.add_broadcast_with_uid(broadcast.name.clone(), Uid::new(uid.clone()));
+    }
+
+    let mut block_comments: HashMap<String, Comment> = HashMap::default();
+    for (_, comment) in comments.0 {
+        match comment.block_id.clone() {
+            Some(block_id) if blocks.0.contains_key(&block_id) => {
+                block_comments.insert(block_id, comment);
+            }
+            _ => target_builder = target_builder.add_comment(import_comment(comment)),
+        }
+    }
+
+    let ctx = BlockImportContext {
+        blocks: &blocks.0,
+        block_comments: &block_comments,
+        ids,
+        arguments: procedure_arguments(&blocks.0),
+    };
+    for uid in top_level_uids(&ctx) {
+        match import_procedure(uid, &ctx)? {
+            Some(procedure) => target_builder = target_builder.add_procedure(procedure),
+            None => target_builder = target_builder.add_block_stack(import_stack(uid, &ctx)?),
+        }
+    }
+
+    for costume in costumes {
+        let Costume {
+            rotation_center_x,
+            rotation_center_y,
+            asset,
+            ..
+        } = costume;
+        target_builder = target_builder.add_costume(
+            CostumeBuilder::new(import_asset(asset, zip)?).rotation_center(
+                number_to_f64(&rotation_center_x) as i64,
+                number_to_f64(&rotation_center_y) as i64,
+            ),
+        );
+    }
+    for sound in sounds {
+        let Sound {
+            rate,
+            sample_count,
+            format,
+            asset,
+        } = sound;
+        target_builder = target_builder.add_sound(SoundBuilder::with_metadata(
+            import_asset(asset, zip)?,
+            rate,
+            sample_count,
+            format,
+        ));
+    }
+    Ok(target_builder)
+}
+
+/// Scripts of the target without anything else.
+/// Every variables and lists that's not in this target is treated as global.
+pub fn import_scripts(target: &Target) -> Result<Vec<StackBuilder>, ImportError> {
+    let block_comments: HashMap<String, Comment> = target
+        .comments
+        .0
+        .values()
+        .filter_map(|comment| {
+            let block_id = comment.block_id.clone()?;
+            target
+                .blocks
+                .0
+                .contains_key(&block_id)
+                .then(|| (block_id, comment.clone()))
+        })
+        .collect();
+    let ctx = BlockImportContext {
+        blocks: &target.blocks.0,
+        block_comments: &block_comments,
+        ids: TargetIds::new(target, None),
+        // Procedure calls are kept as they are
+        arguments: HashMap::default(),
+    };
+    top_level_uids(&ctx)
+        .into_iter()
+        .map(|uid| import_stack(uid, &ctx))
+        .collect()
+}
+
+fn top_level_uids<'a>(ctx: &BlockImportContext<'a>) -> Vec<&'a String> {
+    let mut top_level_uids: Vec<&String> = ctx
+        .blocks
+        .iter()
+        .filter(|(_, block)| match block {
+            Block::Normal(n) => n.top_level,
+            Block::VarList(_) => true,
+        })
+        .map(|(uid, _)| uid)
+        .collect();
+    top_level_uids.sort();
+    top_level_uids
+}
+
+/// Argument ids and names of every procedure prototype in the target, by proccode
+pub(crate) fn procedure_arguments(
+    blocks: &HashMap<String, Block>,
+) -> HashMap<String, Vec<(String, String)>> {
+    blocks
+        .values()
+        .filter_map(|block| match block {
+            Block::Normal(BlockNormal {
+                mutation: Some(mutation),
+                ..
+            }) => match &mutation.mutation_enum {
+                BlockMutationEnum::ProceduresPrototype {
+                    proccode,
+                    argumentids,
+                    argumentnames,
+                    ..
+                } => Some((
+                    proccode.clone(),
+                    argumentids
+                        .iter()
+                        .cloned()
+                        .zip(argumentnames.iter().cloned())
+                        .collect(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Definition hat at `uid` as a [`ProcedureBuilder`] with its body.
+/// `None` if it isn't a definition or its prototype can't be read, the stack is then imported as blocks.
+fn import_procedure(
+    uid: &str,
+    ctx: &BlockImportContext,
+) -> Result<Option<ProcedureBuilder>, ImportError> {
+    let Some(Block::Normal(definition)) = ctx.blocks.get(uid) else {
+        return Ok(None);
+    };
+    if definition.opcode != "procedures_definition" {
+        return Ok(None);
+    }
+    let prototype = definition
+        .inputs
+        .0
+        .get("custom_block")
+        .and_then(|input| input.inputs.first())
+        .and_then(|value| match value {
+            Some(UidOrValue::Uid(uid)) => ctx.blocks.get(uid),
+            _ => None,
+        });
+    let Some(Block::Normal(BlockNormal {
+        mutation: Some(mutation),
+        ..
+    })) = prototype
+    else {
+        return Ok(None);
+    };
+    let BlockMutationEnum::ProceduresPrototype {
+        proccode,
+        argumentnames,
+        warp,
+        ..
+    } = &mutation.mutation_enum
+    else {
+        return Ok(None);
+    };
+    let Some(procedure) = ProcedureBuilder::from_proccode(proccode, argumentnames, *warp) else {
+        return Ok(None);
+    };
+    Ok(Some(match &definition.next {
+        Some(next) => procedure.body(import_stack(next, ctx)?),
+        None => procedure,
+    }))
+}
+
+fn import_asset<R: Read + Seek>(
+    asset: Asset,
+    zip: &mut zip::ZipArchive<R>,
+) -> Result<AssetBuilder, ImportError> {
+    let Asset {
+        asset_id,
+        name,
+        md5ext,
+        data_format,
+    } = asset;
+    let file_name = md5ext.unwrap_or_else(|| asset_id + "." + &data_format);
+    let mut content = vec![];
+    zip.by_name(&file_name)?.read_to_end(&mut content)?;
+    Ok(AssetBuilder::new(
+        name,
+        Resource::new(data_format, content)?,
+    ))
+}
+
+fn import_comment(comment: Comment) -> CommentBuilder {
+    let Comment {
+        x,
+        y,
+        width,
+        height,
+        minimized,
+        text,
+        ..
+    } = comment;
+    CommentBuilder::new(text)
+        .pos(
+            x.map(|x| number_to_f64(&x)).unwrap_or_default(),
+            y.map(|y| number_to_f64(&y)).unwrap_or_default(),
+        )
+        .size(number_to_f64(&width) as u64, number_to_f64(&height) as u64)
+        .minimized(minimized)
+}
+
+struct BlockImportContext<'a> {
+    blocks: &'a HashMap<String, Block>,
+    block_comments: &'a HashMap<String, Comment>,
+    ids: TargetIds<'a>,
+    /// See [`procedure_arguments`]
+    arguments: HashMap<String, Vec<(String, String)>>,
+}
+
+impl<'a> BlockImportContext<'a> {
+    fn varlist(&self, kind: ListOrVariable, name: String, id: &str) -> BlockVarListBuilder {
+        if self.ids.is_global(&kind, id) {
+            BlockVarListBuilder::global(kind, name)
+        } else {
+            BlockVarListBuilder::sprite(kind, name)
+        }
+    }
+}
+
+fn import_stack(first_uid: &str, ctx: &BlockImportContext) -> Result<StackBuilder, ImportError> {
+    let mut stack = StackBuilder::new();
+    let mut current = Some(first_uid.to_owned());
+    while let Some(uid) = current {
+        match ctx.blocks.get(&uid) {
+            Some(Block::Normal(block)) => {
+                current = block.next.clone();
+                stack = stack.next(StackBuilder::start(import_block(&uid, block, ctx)?));
+            }
+            Some(Block::VarList(block)) => {
+                let BlockVarListReporterTop {
+                    kind,
+                    name,
+                    id,
+                    x,
+                    y,
+                } = block;
+                current = None;
+                stack = stack.next(StackBuilder::start_varlist(
+                    ctx.varlist(kind.clone(), name.clone(), id)
+                        .pos(number_to_f64(x), number_to_f64(y)),
+                ));
+            }
+            None => return Err(ImportError::MissingBlock(uid)),
+        }
+    }
+    Ok(stack)
+}
+
+fn import_block(
+    uid: &str,
+    block: &BlockNormal,
+    ctx: &BlockImportContext,
+) -> Result<BlockNormalBuilder, ImportError> {
+    let mut block_builder = BlockNormalBuilder::new(block.opcode.clone()).shadow(block.shadow);
+    // Calls to an imported procedure are resolved by name since the procedure gets new argument ids
+    let call_arguments = match block
+        .mutation
+        .as_ref()
+        .map(|mutation| &mutation.mutation_enum)
+    {
+        Some(BlockMutationEnum::ProceduresCall { proccode, .. }) => ctx
+            .arguments
+            .get(proccode)
+            .map(|arguments| (proccode, arguments)),
+        _ => None,
+    };
+    for (key, input) in block.inputs.0.iter() {
+        let key = call_arguments
+            .and_then(|(_, arguments)| arguments.iter().find(|(id, _)| id == key))
+            .map(|(_, name)| name)
+            .unwrap_or(key);
+        block_builder = block_builder.add_input(key.clone(), import_input(input, ctx)?);
+    }
+    for (key, field) in block.fields.0.iter() {
+        block_builder = block_builder.add_field(key.clone(), import_field(field, ctx));
+    }
+    if let Some((proccode, _)) = call_arguments {
+        block_builder = block_builder.procedure_call(proccode.clone());
+    } else if let Some(mutation) = &block.mutation {
+        // Argument ids of a procedure that isn't imported are only referenced inside mutations
+        // and inputs' key so the mutations can be kept as is.
+        block_builder = block_builder.mutation(mutation.clone());
+    }
+    if let Some(comment) = ctx.block_comments.get(uid) {
+        block_builder = block_builder.comment(import_comment(comment.clone()));
+    }
+    if block.top_level {
+        if let (Some(x), Some(y)) = (&block.x, &block.y) {
+            block_builder = block_builder.pos(number_to_f64(x), number_to_f64(y));
+        }
+    }
+    Ok(block_builder)
+}
+
+fn import_input(
+    input: &BlockInput,
+    ctx: &BlockImportContext,
+) -> Result<BlockInputBuilder, ImportError> {
+    let mut input_builder = BlockInputBuilder::new().shadow(input.shadow.clone());
+    for value in input.inputs.iter() {
+        let value = match value {
+            Some(UidOrValue::Uid(uid)) => Some(StackOrValue::Stack(import_stack(uid, ctx)?)),
+            Some(UidOrValue::Value(BlockInputValue::Variable { name, id })) => {
+                Some(StackOrValue::Stack(StackBuilder::start_varlist(
+                    ctx.varlist(ListOrVariable::Variable, name.clone(), id),
+                )))
+            }
+            Some(UidOrValue::Value(BlockInputValue::List { name, id })) => {
+                Some(StackOrValue::Stack(StackBuilder::start_varlist(
+                    ctx.varlist(ListOrVariable::List, name.clone(), id),
+                )))
+            }
+            Some(UidOrValue::Value(value)) => Some(StackOrValue::Value(value.clone())),
+            None => None,
+        };
+        input_builder = input_builder.input(value);
+    }
+    Ok(input_builder)
+}
+
+fn import_field(field: &BlockField, ctx: &BlockImportContext) -> BlockFieldBuilder {
+    match field {
+        BlockField::NoId { value } => {
+            BlockFieldBuilder::new_with_kind(value_to_string(value), FieldKind::NoRef)
+        }
+        BlockField::WithId { value, id: None } => {
+            BlockFieldBuilder::new_with_kind(value_to_string(value), FieldKind::NoRefMaybe)
+        }
+        BlockField::WithId {
+            value,
+            id: Some(id),
+        } => BlockFieldBuilder::new_with_kind(value_to_string(value), ctx.ids.field_kind(id)),
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Text(text) => text.clone(),
+        other => serde_json::to_value(other)
+            .map(|json| json.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+fn number_to_f64(number: &Number) -> f64 {
+    serde_json::to_value(number)
+        .ok()
+        .and_then(|json| json.as_f64())
+        .unwrap_or_default()
+}
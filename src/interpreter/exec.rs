@@ -0,0 +1,1431 @@
+//! Stepping threads and running the blocks
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{
+    Block, Code, Field, Frame, Input, Interpreter, LoopKind, Thread, Value, Variable, MAX_CLONES,
+};
+
+/// Blocks a thread can run in a frame before it's forced to yield.
+/// Keeps an endless loop inside "run without screen refresh" from hanging.
+const BLOCK_BUDGET: usize = 100_000;
+/// Frames the threads can step in a frame that doesn't redraw,
+/// stands in for Scratch's time budget of 75% of a frame
+const FRAME_BUDGET: usize = 100_000;
+/// Days from 1970-01-01 to 2000-01-01, the simulated clock starts on 2000-01-01
+const DAYS_TO_2000: i64 = 10957;
+const EPSILON: f64 = 1e-9;
+
+enum Flow {
+    Continue,
+    Yield,
+    /// Stops the thread
+    Stop,
+}
+
+impl Block {
+    pub(super) fn field(&self, name: &str) -> &str {
+        self.fields
+            .get(name)
+            .map(|field| field.value.as_str())
+            .unwrap_or_default()
+    }
+
+    fn substack(&self, name: &str) -> Option<String> {
+        match self.inputs.get(name) {
+            Some(Input::Block(uid)) => Some(uid.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+// Threads =================================================================
+
+impl Interpreter {
+    fn code(&self, target: usize) -> Rc<Code> {
+        Rc::clone(&self.targets[target].code)
+    }
+
+    fn timer(&self) -> f64 {
+        self.time() - self.timer_start
+    }
+
+    /// Sprites from the top layer down then the stage, the order Scratch starts scripts in
+    fn execution_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = self.layers.iter().rev().copied().collect();
+        order.push(0);
+        order
+    }
+
+    /// Starts the hats with the opcode, in all targets or `only` the target.
+    /// Returns the ids of the started threads.
+    pub(super) fn start_hats(
+        &mut self,
+        opcode: &str,
+        only: Option<usize>,
+        restart: bool,
+        filter: impl Fn(&Block) -> bool,
+    ) -> Vec<usize> {
+        let targets = match only {
+            Some(target) => vec![target],
+            None => self.execution_order(),
+        };
+        let mut started = vec![];
+        for target in targets {
+            if self.targets[target].deleted {
+                continue;
+            }
+            let code = self.code(target);
+            for hat in &code.hats {
+                let block = &code.blocks[hat];
+                if block.opcode != opcode || !filter(block) {
+                    continue;
+                }
+                started.extend(self.start_thread(target, hat, restart));
+            }
+        }
+        started
+    }
+
+    /// A hat that's already running is restarted or left alone
+    fn start_thread(&mut self, target: usize, hat: &str, restart: bool) -> Option<usize> {
+        let next = self.targets[target].code.blocks[hat].next.clone();
+        if let Some((current_target, current_hat, id)) = &self.current {
+            if *current_target == target && current_hat == hat {
+                self.restart_current = restart;
+                return restart.then_some(*id);
+            }
+        }
+        let running = self
+            .threads
+            .iter_mut()
+            .find(|thread| !thread.done && thread.target == target && thread.hat == hat);
+        if let Some(thread) = running {
+            if !restart {
+                return None;
+            }
+            thread.stack = vec![Frame::Sequence(next)];
+            return Some(thread.id);
+        }
+        let id = self.next_thread_id;
+        self.next_thread_id += 1;
+        self.threads.push(Thread {
+            id,
+            target,
+            hat: hat.to_owned(),
+            stack: vec![Frame::Sequence(next)],
+            done: false,
+        });
+        Some(id)
+    }
+
+    fn is_running(&self, id: usize) -> bool {
+        self.threads
+            .iter()
+            .any(|thread| thread.id == id && !thread.done)
+            || matches!(&self.current, Some((_, _, current)) if *current == id)
+    }
+
+    pub(super) fn stop_all(&mut self) {
+        for thread in &mut self.threads {
+            thread.done = true;
+        }
+        for target in &mut self.targets {
+            if target.is_clone {
+                target.deleted = true;
+            }
+            target.state.bubble = None;
+            target.state.effects.clear();
+        }
+        let targets = &self.targets;
+        self.layers.retain(|&idx| !targets[idx].deleted);
+        self.relayer();
+        self.asks.clear();
+    }
+
+    /// "When timer/loudness >" starts when the value goes over
+    pub(super) fn check_greater_than(&mut self) {
+        for target in self.execution_order() {
+            let code = self.code(target);
+            for hat in &code.hats {
+                let block = &code.blocks[hat];
+                if block.opcode != "event_whengreaterthan" {
+                    continue;
+                }
+                let thread = Thread {
+                    id: usize::MAX,
+                    target,
+                    hat: hat.to_owned(),
+                    stack: vec![],
+                    done: false,
+                };
+                let value = self.input(&thread, block, "VALUE").to_number();
+                let sensed = match block.field("WHENGREATERTHANMENU").to_uppercase().as_str() {
+                    "TIMER" => self.timer(),
+                    _ => self.loudness,
+                };
+                let over = sensed > value;
+                let was_over = self
+                    .greater_than
+                    .insert((target, hat.to_owned()), over)
+                    .unwrap_or(false);
+                if over && !was_over {
+                    self.start_thread(target, hat, false);
+                }
+            }
+        }
+    }
+
+    /// Steps the threads until a block requests a redraw, no thread can go on
+    /// or the frame's budget is used up, like Scratch's sequencer
+    pub(super) fn step_threads(&mut self) {
+        self.redraw_requested = false;
+        let mut steps = 0;
+        while steps < FRAME_BUDGET {
+            let stepped = self.step_threads_once();
+            if stepped == 0 || self.redraw_requested || self.threads.is_empty() {
+                break;
+            }
+            steps += stepped;
+        }
+    }
+
+    /// Steps every thread once, including the ones started while stepping.
+    /// Returns how many frames ran, not counting waits that yielded again.
+    fn step_threads_once(&mut self) -> usize {
+        let mut steps = 0;
+        let mut idx = 0;
+        while idx < self.threads.len() {
+            if !self.threads[idx].done {
+                let placeholder = Thread {
+                    id: usize::MAX,
+                    target: 0,
+                    hat: String::new(),
+                    stack: vec![],
+                    done: true,
+                };
+                let mut thread = std::mem::replace(&mut self.threads[idx], placeholder);
+                self.current = Some((thread.target, thread.hat.clone(), thread.id));
+                steps += self.step_thread(&mut thread);
+                self.current = None;
+                self.threads[idx] = thread;
+            }
+            idx += 1;
+        }
+        self.threads.retain(|thread| !thread.done);
+        steps
+    }
+
+    /// Runs the thread until it yields or finishes.
+    /// Returns how many frames ran, not counting a wait that yielded again.
+    fn step_thread(&mut self, thread: &mut Thread) -> usize {
+        for steps in 0..BLOCK_BUDGET {
+            let warp = thread.is_warp();
+            let Some(frame) = thread.stack.pop() else {
+                thread.done = true;
+                return steps + 1;
+            };
+            let waiting = matches!(
+                frame,
+                Frame::Loop {
+                    yield_next: true,
+                    ..
+                } | Frame::Wait { .. }
+                    | Frame::WaitUntil(_)
+                    | Frame::Glide { .. }
+                    | Frame::WaitThreads(_)
+                    | Frame::Ask
+            );
+            let flow = self.step_frame(thread, frame, warp);
+            if std::mem::take(&mut self.restart_current) {
+                let next = self.targets[thread.target].code.blocks[&thread.hat]
+                    .next
+                    .clone();
+                thread.stack = vec![Frame::Sequence(next)];
+                return steps + 1;
+            }
+            match flow {
+                Flow::Continue => {}
+                Flow::Yield if waiting => return steps,
+                Flow::Yield => return steps + 1,
+                Flow::Stop => {
+                    thread.done = true;
+                    return steps + 1;
+                }
+            }
+        }
+        BLOCK_BUDGET
+    }
+
+    /// Frame is popped from the thread and is pushed back if it's not done
+    fn step_frame(&mut self, thread: &mut Thread, frame: Frame, warp: bool) -> Flow {
+        match frame {
+            Frame::Sequence(None) => Flow::Continue,
+            Frame::Sequence(Some(uid)) => {
+                let code = self.code(thread.target);
+                let Some(block) = code.blocks.get(&uid) else {
+                    return Flow::Continue;
+                };
+                thread.stack.push(Frame::Sequence(block.next.clone()));
+                self.execute(thread, &uid, block, warp)
+            }
+            Frame::Loop {
+                block,
+                kind,
+                mut counter,
+                yield_next,
+            } => {
+                // Loops yield after each iteration so the other threads and the redraw get a turn
+                if yield_next && !warp {
+                    thread.stack.push(Frame::Loop {
+                        block,
+                        kind,
+                        counter,
+                        yield_next: false,
+                    });
+                    return Flow::Yield;
+                }
+                let code = self.code(thread.target);
+                let Some(looped) = code.blocks.get(&block) else {
+                    return Flow::Continue;
+                };
+                let again = match kind {
+                    LoopKind::Repeat => {
+                        counter -= 1.;
+                        counter >= 0.
+                    }
+                    LoopKind::Forever => true,
+                    LoopKind::Until => !self.input(thread, looped, "CONDITION").to_bool(),
+                    LoopKind::While => self.input(thread, looped, "CONDITION").to_bool(),
+                    LoopKind::ForEach => {
+                        let times = self.input(thread, looped, "VALUE").to_number();
+                        counter += 1.;
+                        if counter <= times {
+                            let variable = looped.fields.get("VARIABLE");
+                            self.set_variable(thread.target, variable, Value::Number(counter));
+                        }
+                        counter <= times
+                    }
+                };
+                if again {
+                    let substack = looped.substack("SUBSTACK");
+                    thread.stack.push(Frame::Loop {
+                        block,
+                        kind,
+                        counter,
+                        yield_next: true,
+                    });
+                    thread.stack.push(Frame::Sequence(substack));
+                }
+                Flow::Continue
+            }
+            Frame::Wait {
+                until,
+                yielded,
+                clear_bubble,
+            } => {
+                // Waits always yield at least once, even "wait 0"
+                if !yielded || self.time() + EPSILON < until {
+                    thread.stack.push(Frame::Wait {
+                        until,
+                        yielded: true,
+                        clear_bubble,
+                    });
+                    return Flow::Yield;
+                }
+                if clear_bubble {
+                    self.targets[thread.target].state.bubble = None;
+                }
+                Flow::Continue
+            }
+            Frame::WaitUntil(block) => {
+                let code = self.code(thread.target);
+                let Some(waiting) = code.blocks.get(&block) else {
+                    return Flow::Continue;
+                };
+                if self.input(thread, waiting, "CONDITION").to_bool() {
+                    return Flow::Continue;
+                }
+                thread.stack.push(Frame::WaitUntil(block));
+                Flow::Yield
+            }
+            Frame::Glide {
+                from,
+                to,
+                start,
+                duration,
+            } => {
+                let progress = (self.time() - start) / duration;
+                if progress + EPSILON >= 1. {
+                    self.set_position(thread.target, to.0, to.1);
+                    return Flow::Continue;
+                }
+                let x = from.0 + (to.0 - from.0) * progress;
+                let y = from.1 + (to.1 - from.1) * progress;
+                self.set_position(thread.target, x, y);
+                thread.stack.push(Frame::Glide {
+                    from,
+                    to,
+                    start,
+                    duration,
+                });
+                Flow::Yield
+            }
+            Frame::WaitThreads(ids) => {
+                if ids.iter().any(|&id| self.is_running(id)) {
+                    thread.stack.push(Frame::WaitThreads(ids));
+                    return Flow::Yield;
+                }
+                Flow::Continue
+            }
+            Frame::Ask => {
+                if self.asks.contains(&thread.id) {
+                    thread.stack.push(Frame::Ask);
+                    return Flow::Yield;
+                }
+                Flow::Continue
+            }
+            // The procedure's body has finished
+            Frame::Procedure { .. } => Flow::Continue,
+        }
+    }
+
+    /// Returns from the procedure or stops the thread
+    fn stop_script(thread: &mut Thread) -> Flow {
+        while let Some(frame) = thread.stack.pop() {
+            if matches!(frame, Frame::Procedure { .. }) {
+                return Flow::Continue;
+            }
+        }
+        Flow::Stop
+    }
+
+    fn call_procedure(&mut self, thread: &mut Thread, block: &Block, warp: bool) -> Flow {
+        let Some(proccode) = &block.proccode else {
+            return Flow::Continue;
+        };
+        let code = self.code(thread.target);
+        let Some(procedure) = code.procedures.get(proccode) else {
+            return Flow::Continue;
+        };
+        let mut args = HashMap::new();
+        for (id, name) in procedure.argument_ids.iter().zip(&procedure.argument_names) {
+            args.insert(name.to_owned(), self.input(thread, block, id));
+        }
+        let recursive = thread.stack.iter().any(
+            |frame| matches!(frame, Frame::Procedure { proccode: called, .. } if called == proccode),
+        );
+        thread.stack.push(Frame::Procedure {
+            proccode: proccode.to_owned(),
+            args,
+            warp: warp || procedure.warp,
+        });
+        thread.stack.push(Frame::Sequence(procedure.body.clone()));
+        // Recursion yields so it doesn't freeze the project
+        if recursive && !warp {
+            Flow::Yield
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+// Stack blocks ============================================================
+
+impl Interpreter {
+    fn execute(&mut self, thread: &mut Thread, uid: &str, block: &Block, warp: bool) -> Flow {
+        let t = thread.target;
+        let start_loop = |kind, counter| Frame::Loop {
+            block: uid.to_owned(),
+            kind,
+            counter,
+            yield_next: false,
+        };
+        match block.opcode.as_str() {
+            // Motion
+            "motion_movesteps" => {
+                let steps = self.number(thread, block, "STEPS");
+                let state = &mut self.targets[t].state;
+                let radians = (90. - state.direction).to_radians();
+                let (x, y) = (
+                    state.x + steps * radians.cos(),
+                    state.y + steps * radians.sin(),
+                );
+                self.set_position(t, x, y);
+            }
+            "motion_turnright" | "motion_turnleft" => {
+                let mut degrees = self.number(thread, block, "DEGREES");
+                if block.opcode == "motion_turnleft" {
+                    degrees = -degrees;
+                }
+                let direction = self.targets[t].state.direction + degrees;
+                self.set_direction(t, direction);
+            }
+            "motion_goto" => {
+                let to = self.text(thread, block, "TO");
+                if let Some((x, y)) = self.position_of(&to) {
+                    self.set_position(t, x, y);
+                }
+            }
+            "motion_gotoxy" => {
+                let x = self.number(thread, block, "X");
+                let y = self.number(thread, block, "Y");
+                self.set_position(t, x, y);
+            }
+            "motion_glideto" | "motion_glidesecstoxy" => {
+                let duration = self.number(thread, block, "SECS");
+                let to = if block.opcode == "motion_glideto" {
+                    let to = self.text(thread, block, "TO");
+                    self.position_of(&to)
+                } else {
+                    let x = self.number(thread, block, "X");
+                    let y = self.number(thread, block, "Y");
+                    Some((x, y))
+                };
+                let Some(to) = to else {
+                    return Flow::Continue;
+                };
+                if duration <= 0. {
+                    self.set_position(t, to.0, to.1);
+                    return Flow::Continue;
+                }
+                let state = &self.targets[t].state;
+                thread.stack.push(Frame::Glide {
+                    from: (state.x, state.y),
+                    to,
+                    start: self.time(),
+                    duration,
+                });
+                return Flow::Yield;
+            }
+            "motion_pointindirection" => {
+                let direction = self.number(thread, block, "DIRECTION");
+                self.set_direction(t, direction);
+            }
+            "motion_pointtowards" => {
+                let towards = self.text(thread, block, "TOWARDS");
+                if let Some((x, y)) = self.position_of(&towards) {
+                    let state = &self.targets[t].state;
+                    let direction = 90. - (y - state.y).atan2(x - state.x).to_degrees();
+                    self.set_direction(t, direction);
+                }
+            }
+            "motion_changexby" | "motion_setx" | "motion_changeyby" | "motion_sety" => {
+                let (input, relative) = match block.opcode.as_str() {
+                    "motion_changexby" => ("DX", true),
+                    "motion_setx" => ("X", false),
+                    "motion_changeyby" => ("DY", true),
+                    _ => ("Y", false),
+                };
+                let value = self.number(thread, block, input);
+                let state = &self.targets[t].state;
+                let (mut x, mut y) = (state.x, state.y);
+                let axis = if input.ends_with('X') { &mut x } else { &mut y };
+                *axis = if relative { *axis + value } else { value };
+                self.set_position(t, x, y);
+            }
+            "motion_ifonedgebounce" => {
+                // Without costume sizes the sprite is treated as a point
+                let state = &mut self.targets[t].state;
+                if state.x.abs() > 240. {
+                    state.x = state.x.clamp(-240., 240.);
+                    let direction = -state.direction;
+                    self.set_direction(t, direction);
+                }
+                let state = &mut self.targets[t].state;
+                if state.y.abs() > 180. {
+                    state.y = state.y.clamp(-180., 180.);
+                    let direction = 180. - state.direction;
+                    self.set_direction(t, direction);
+                }
+            }
+            "motion_setrotationstyle" => {
+                self.targets[t].state.rotation_style = block.field("STYLE").to_owned();
+            }
+
+            // Looks
+            "looks_sayforsecs" | "looks_thinkforsecs" => {
+                let message = self.text(thread, block, "MESSAGE");
+                let secs = self.number(thread, block, "SECS");
+                self.targets[t].state.bubble = Some(message).filter(|message| !message.is_empty());
+                self.request_redraw(t);
+                thread.stack.push(Frame::Wait {
+                    until: self.time() + secs,
+                    yielded: false,
+                    clear_bubble: true,
+                });
+            }
+            "looks_say" | "looks_think" => {
+                let message = self.text(thread, block, "MESSAGE");
+                self.targets[t].state.bubble = Some(message).filter(|message| !message.is_empty());
+                self.request_redraw(t);
+            }
+            "looks_switchcostumeto" => {
+                let costume = self.input(thread, block, "COSTUME");
+                self.switch_costume(t, &costume);
+            }
+            "looks_nextcostume" => {
+                let next = Value::Number(self.targets[t].state.costume as f64 + 2.);
+                self.switch_costume(t, &next);
+            }
+            "looks_switchbackdropto" | "looks_nextbackdrop" | "looks_switchbackdroptoandwait" => {
+                let backdrop = match block.opcode.as_str() {
+                    "looks_nextbackdrop" => Value::Text("next backdrop".to_owned()),
+                    _ => self.input(thread, block, "BACKDROP"),
+                };
+                let started = self.switch_backdrop(&backdrop);
+                if block.opcode == "looks_switchbackdroptoandwait" {
+                    thread.stack.push(Frame::WaitThreads(started));
+                }
+            }
+            "looks_changesizeby" | "looks_setsizeto" => {
+                let (input, relative) = match block.opcode.as_str() {
+                    "looks_changesizeby" => ("CHANGE", true),
+                    _ => ("SIZE", false),
+                };
+                let value = self.number(thread, block, input);
+                let state = &mut self.targets[t].state;
+                state.size = if relative { state.size + value } else { value }.max(0.);
+                self.request_redraw(t);
+            }
+            "looks_changeeffectby" | "looks_seteffectto" => {
+                let (input, relative) = match block.opcode.as_str() {
+                    "looks_changeeffectby" => ("CHANGE", true),
+                    _ => ("VALUE", false),
+                };
+                let value = self.number(thread, block, input);
+                let effect = block.field("EFFECT").to_lowercase();
+                let state = &mut self.targets[t].state;
+                let current = state.effects.get(&effect).copied().unwrap_or(0.);
+                let value = if relative { current + value } else { value };
+                let value = match effect.as_str() {
+                    "ghost" => value.clamp(0., 100.),
+                    "brightness" => value.clamp(-100., 100.),
+                    _ => value,
+                };
+                state.effects.insert(effect, value);
+                self.request_redraw(t);
+            }
+            "looks_cleargraphiceffects" => {
+                self.targets[t].state.effects.clear();
+                self.request_redraw(t);
+            }
+            "looks_show" => {
+                self.targets[t].state.visible = true;
+                self.request_redraw(t);
+            }
+            "looks_hide" => {
+                self.request_redraw(t);
+                self.targets[t].state.visible = false;
+            }
+            "looks_gotofrontback" => {
+                if let Some(position) = self.layers.iter().position(|&idx| idx == t) {
+                    self.layers.remove(position);
+                    match block.field("FRONT_BACK") {
+                        "back" => self.layers.insert(0, t),
+                        _ => self.layers.push(t),
+                    }
+                    self.relayer();
+                    self.request_redraw(t);
+                }
+            }
+            "looks_goforwardbackwardlayers" => {
+                let mut layers = self.number(thread, block, "NUM") as i64;
+                if block.field("FORWARD_BACKWARD") == "backward" {
+                    layers = -layers;
+                }
+                if let Some(position) = self.layers.iter().position(|&idx| idx == t) {
+                    self.layers.remove(position);
+                    let position = (position as i64 + layers).clamp(0, self.layers.len() as i64);
+                    self.layers.insert(position as usize, t);
+                    self.relayer();
+                    self.request_redraw(t);
+                }
+            }
+
+            // Sound
+            "sound_playuntildone" => {
+                let sound = self.input(thread, block, "SOUND_MENU");
+                let duration = self.sound_duration(t, &sound);
+                thread.stack.push(Frame::Wait {
+                    until: self.time() + duration,
+                    yielded: false,
+                    clear_bubble: false,
+                });
+            }
+            "sound_changevolumeby" | "sound_setvolumeto" => {
+                let value = self.number(thread, block, "VOLUME");
+                let state = &mut self.targets[t].state;
+                let volume = match block.opcode.as_str() {
+                    "sound_changevolumeby" => state.volume + value,
+                    _ => value,
+                };
+                state.volume = volume.clamp(0., 100.);
+            }
+            "music_playDrumForBeats" | "music_restForBeats" | "music_playNoteForBeats" => {
+                let beats = self.number(thread, block, "BEATS");
+                thread.stack.push(Frame::Wait {
+                    until: self.time() + beats * 60. / self.tempo,
+                    yielded: false,
+                    clear_bubble: false,
+                });
+            }
+            "music_setTempo" | "music_changeTempo" => {
+                let tempo = self.number(thread, block, "TEMPO");
+                let tempo = match block.opcode.as_str() {
+                    "music_changeTempo" => self.tempo + tempo,
+                    _ => tempo,
+                };
+                self.tempo = tempo.clamp(20., 500.);
+            }
+
+            // Events
+            "event_broadcast" | "event_broadcastandwait" => {
+                let message = self.text(thread, block, "BROADCAST_INPUT");
+                let started = self.broadcast(&message);
+                if block.opcode == "event_broadcastandwait" {
+                    thread.stack.push(Frame::WaitThreads(started));
+                }
+            }
+
+            // Control
+            "control_wait" => {
+                let duration = self.number(thread, block, "DURATION");
+                thread.stack.push(Frame::Wait {
+                    until: self.time() + duration,
+                    yielded: false,
+                    clear_bubble: false,
+                });
+            }
+            "control_repeat" => {
+                let times = self.number(thread, block, "TIMES");
+                thread
+                    .stack
+                    .push(start_loop(LoopKind::Repeat, round(times)));
+            }
+            "control_forever" => thread.stack.push(start_loop(LoopKind::Forever, 0.)),
+            "control_repeat_until" => thread.stack.push(start_loop(LoopKind::Until, 0.)),
+            "control_while" => thread.stack.push(start_loop(LoopKind::While, 0.)),
+            "control_for_each" => thread.stack.push(start_loop(LoopKind::ForEach, 0.)),
+            "control_if" | "control_if_else" => {
+                let substack = match self.boolean(thread, block, "CONDITION") {
+                    true => "SUBSTACK",
+                    false if block.opcode == "control_if_else" => "SUBSTACK2",
+                    false => return Flow::Continue,
+                };
+                thread.stack.push(Frame::Sequence(block.substack(substack)));
+            }
+            "control_all_at_once" => thread
+                .stack
+                .push(Frame::Sequence(block.substack("SUBSTACK"))),
+            "control_wait_until" => thread.stack.push(Frame::WaitUntil(uid.to_owned())),
+            "control_stop" => match block.field("STOP_OPTION") {
+                "all" => {
+                    self.stop_all();
+                    return Flow::Stop;
+                }
+                "this script" => return Interpreter::stop_script(thread),
+                // "other scripts in sprite" and "other scripts in stage"
+                _ => {
+                    for other in &mut self.threads {
+                        if other.target == t {
+                            other.done = true;
+                        }
+                    }
+                }
+            },
+            "control_create_clone_of" => {
+                let option = self.text(thread, block, "CLONE_OPTION");
+                let source = match option.as_str() {
+                    "_myself_" => Some(t),
+                    name => self.original(name),
+                };
+                if let Some(source) = source {
+                    self.create_clone(source);
+                }
+            }
+            "control_delete_this_clone" if self.targets[t].is_clone => {
+                self.delete_clone(t);
+                return Flow::Stop;
+            }
+            "control_incr_counter" => self.counter += 1.,
+            "control_clear_counter" => self.counter = 0.,
+
+            // Data
+            "data_setvariableto" | "data_changevariableby" => {
+                let value = self.input(thread, block, "VALUE");
+                let variable = block.fields.get("VARIABLE");
+                let value = match block.opcode.as_str() {
+                    "data_changevariableby" => Value::Number(
+                        self.get_variable(t, variable).to_number() + value.to_number(),
+                    ),
+                    _ => value,
+                };
+                self.set_variable(t, variable, value);
+            }
+            "data_addtolist" => {
+                let item = self.input(thread, block, "ITEM");
+                self.list_mut(t, block.fields.get("LIST")).push(item);
+            }
+            "data_deleteoflist" => {
+                let index = self.input(thread, block, "INDEX");
+                let length = self.list_mut(t, block.fields.get("LIST")).len();
+                if index.to_string() == "all" {
+                    self.list_mut(t, block.fields.get("LIST")).clear();
+                } else if let Some(index) = self.list_index(&index, length) {
+                    self.list_mut(t, block.fields.get("LIST")).remove(index);
+                }
+            }
+            "data_deletealloflist" => self.list_mut(t, block.fields.get("LIST")).clear(),
+            "data_insertatlist" => {
+                let item = self.input(thread, block, "ITEM");
+                let index = self.input(thread, block, "INDEX");
+                let length = self.list_mut(t, block.fields.get("LIST")).len();
+                // Inserting after the last item is allowed
+                if let Some(index) = self.list_index(&index, length + 1) {
+                    self.list_mut(t, block.fields.get("LIST"))
+                        .insert(index, item);
+                }
+            }
+            "data_replaceitemoflist" => {
+                let index = self.input(thread, block, "INDEX");
+                let item = self.input(thread, block, "ITEM");
+                let length = self.list_mut(t, block.fields.get("LIST")).len();
+                if let Some(index) = self.list_index(&index, length) {
+                    self.list_mut(t, block.fields.get("LIST"))[index] = item;
+                }
+            }
+
+            // Sensing
+            "sensing_askandwait" => {
+                self.asks.push_back(thread.id);
+                thread.stack.push(Frame::Ask);
+            }
+            "sensing_resettimer" => self.timer_start = self.time(),
+            "sensing_setdragmode" => {
+                self.targets[t].state.draggable = block.field("DRAG_MODE") == "draggable";
+            }
+
+            "procedures_call" => return self.call_procedure(thread, block, warp),
+
+            // Drawing with the pen isn't simulated but it's still a redraw
+            "pen_clear" | "pen_stamp" | "pen_penDown" => self.redraw_requested = true,
+
+            // Pen, sound playback and everything else that can't be seen here
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn broadcast(&mut self, message: &str) -> Vec<usize> {
+        self.start_hats("event_whenbroadcastreceived", None, true, |hat| {
+            hat.field("BROADCAST_OPTION").eq_ignore_ascii_case(message)
+        })
+    }
+
+    /// Ends the frame's stepping if the target can be seen
+    fn request_redraw(&mut self, target: usize) {
+        if self.targets[target].state.visible {
+            self.redraw_requested = true;
+        }
+    }
+
+    fn set_position(&mut self, target: usize, x: f64, y: f64) {
+        let state = &mut self.targets[target].state;
+        if x.is_finite() && y.is_finite() {
+            (state.x, state.y) = (x, y);
+        }
+        self.request_redraw(target);
+    }
+
+    /// Wraps to (-180, 180]
+    fn set_direction(&mut self, target: usize, direction: f64) {
+        if !direction.is_finite() {
+            return;
+        }
+        let direction = (direction + 180.).rem_euclid(360.) - 180.;
+        self.targets[target].state.direction = if direction == -180. { 180. } else { direction };
+        self.request_redraw(target);
+    }
+
+    /// Position of "_mouse_", "_random_" or a sprite
+    fn position_of(&mut self, name: &str) -> Option<(f64, f64)> {
+        match name {
+            "_mouse_" => Some(self.mouse),
+            "_random_" => {
+                let x = (self.random() * 480. - 240.).round();
+                let y = (self.random() * 360. - 180.).round();
+                Some((x, y))
+            }
+            name => {
+                let state = &self.targets[self.original(name)?].state;
+                Some((state.x, state.y))
+            }
+        }
+    }
+
+    /// Costume by name, number or "next/previous/random costume"
+    fn switch_costume(&mut self, target: usize, costume: &Value) {
+        let target = &mut self.targets[target];
+        let count = target.costumes.len() as f64;
+        if count == 0. {
+            return;
+        }
+        let current = target.state.costume as f64;
+        let number = match costume {
+            Value::Text(name) => match target.costumes.iter().position(|costume| costume == name) {
+                Some(idx) => Some(idx as f64 + 1.),
+                None => match name.as_str() {
+                    "next costume" | "next backdrop" => Some(current + 2.),
+                    "previous costume" | "previous backdrop" => Some(current),
+                    _ => costume.strict_number(),
+                },
+            },
+            _ => Some(costume.to_number()),
+        };
+        if let Some(number) = number.filter(|number| number.is_finite()) {
+            target.state.costume = (round(number) - 1.).rem_euclid(count) as usize;
+            self.redraw_requested |= target.state.visible;
+        }
+    }
+
+    /// Returns threads started by "when backdrop switches to"
+    fn switch_backdrop(&mut self, backdrop: &Value) -> Vec<usize> {
+        let random = matches!(backdrop, Value::Text(name) if name == "random backdrop");
+        if random {
+            let count = self.targets[0].costumes.len();
+            if count > 1 {
+                // Never the same backdrop
+                let offset = (self.random() * (count - 1) as f64) as usize + 1;
+                let number = (self.targets[0].state.costume + offset) % count + 1;
+                self.switch_costume(0, &Value::Number(number as f64));
+            }
+        } else {
+            self.switch_costume(0, backdrop);
+        }
+        let name = self.backdrop().to_owned();
+        self.start_hats("event_whenbackdropswitchesto", None, true, |hat| {
+            hat.field("BACKDROP").eq_ignore_ascii_case(&name)
+        })
+    }
+
+    fn sound_duration(&self, target: usize, sound: &Value) -> f64 {
+        let sounds = &self.targets[target].sounds;
+        let by_name = sounds.iter().find(|(name, _)| *name == sound.to_string());
+        let by_number = || {
+            let number = sound.strict_number()?;
+            let idx = (round(number) - 1.).rem_euclid(sounds.len() as f64);
+            sounds.get(idx as usize)
+        };
+        by_name
+            .or_else(by_number)
+            .map(|(_, duration)| *duration)
+            .unwrap_or(0.)
+    }
+
+    fn create_clone(&mut self, source: usize) {
+        let clones = self
+            .targets
+            .iter()
+            .filter(|target| target.is_clone && !target.deleted);
+        if self.targets[source].is_stage
+            || self.targets[source].deleted
+            || clones.count() >= MAX_CLONES
+        {
+            return;
+        }
+        let mut clone = self.targets[source].clone();
+        clone.is_clone = true;
+        clone.state.bubble = None;
+        let idx = self.targets.len();
+        self.targets.push(clone);
+        // Clones go behind their source
+        let position = self
+            .layers
+            .iter()
+            .position(|&layer| layer == source)
+            .unwrap_or(0);
+        self.layers.insert(position, idx);
+        self.relayer();
+        self.start_hats("control_start_as_clone", Some(idx), false, |_| true);
+    }
+
+    fn delete_clone(&mut self, target: usize) {
+        self.targets[target].deleted = true;
+        self.layers.retain(|&idx| idx != target);
+        self.relayer();
+        for thread in &mut self.threads {
+            if thread.target == target {
+                thread.done = true;
+            }
+        }
+    }
+
+    /// Between 0 and 1
+    fn random(&mut self) -> f64 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Variables and lists =====================================================
+
+impl Interpreter {
+    /// Variable by id then by name, in the target then the stage.
+    /// Created in the target if it doesn't exist, same as Scratch.
+    fn variable_key(&mut self, target: usize, field: Option<&Field>) -> (usize, String) {
+        let name = field.map(|field| field.value.clone()).unwrap_or_default();
+        let id = field
+            .and_then(|field| field.id.clone())
+            .unwrap_or_else(|| name.clone());
+        for idx in [target, 0] {
+            if self.targets[idx].variables.contains_key(&id) {
+                return (idx, id);
+            }
+        }
+        for idx in [target, 0] {
+            let variables = &self.targets[idx].variables;
+            if let Some((id, _)) = variables.iter().find(|(_, variable)| variable.name == name) {
+                return (idx, id.to_owned());
+            }
+        }
+        let variable = Variable {
+            name,
+            value: Value::default(),
+        };
+        self.targets[target].variables.insert(id.clone(), variable);
+        (target, id)
+    }
+
+    fn get_variable(&mut self, target: usize, field: Option<&Field>) -> Value {
+        let (idx, id) = self.variable_key(target, field);
+        self.targets[idx].variables[&id].value.clone()
+    }
+
+    fn set_variable(&mut self, target: usize, field: Option<&Field>, value: Value) {
+        let (idx, id) = self.variable_key(target, field);
+        if let Some(variable) = self.targets[idx].variables.get_mut(&id) {
+            variable.value = value;
+        }
+    }
+
+    /// Same lookup as [`Interpreter::variable_key`]
+    fn list_mut(&mut self, target: usize, field: Option<&Field>) -> &mut Vec<Value> {
+        let name = field.map(|field| field.value.clone()).unwrap_or_default();
+        let mut id = field
+            .and_then(|field| field.id.clone())
+            .unwrap_or_else(|| name.clone());
+        let mut found = [target, 0]
+            .into_iter()
+            .find(|&idx| self.targets[idx].lists.contains_key(&id));
+        if found.is_none() {
+            for idx in [target, 0] {
+                let lists = &self.targets[idx].lists;
+                if let Some((list_id, _)) = lists.iter().find(|(_, list)| list.name == name) {
+                    id = list_id.to_owned();
+                    found = Some(idx);
+                    break;
+                }
+            }
+        }
+        let idx = found.unwrap_or(target);
+        let list = self.targets[idx]
+            .lists
+            .entry(id)
+            .or_insert_with(|| super::List {
+                name,
+                values: vec![],
+            });
+        &mut list.values
+    }
+
+    /// 1 based index, "last" or "random" to a 0 based index
+    fn list_index(&mut self, index: &Value, length: usize) -> Option<usize> {
+        if length == 0 {
+            return None;
+        }
+        let index = match index {
+            Value::Text(text) if text == "last" => length,
+            Value::Text(text) if text == "random" || text == "any" => {
+                (self.random() * length as f64) as usize + 1
+            }
+            index => {
+                let index = index.to_number().floor();
+                if index < 1. || index > length as f64 {
+                    return None;
+                }
+                index as usize
+            }
+        };
+        Some(index - 1)
+    }
+
+    fn list_contents(values: &[Value]) -> Value {
+        let texts: Vec<String> = values.iter().map(Value::to_string).collect();
+        let separator = match texts.iter().all(|text| text.chars().count() == 1) {
+            true => "",
+            false => " ",
+        };
+        Value::Text(texts.join(separator))
+    }
+}
+
+// Reporters ===============================================================
+
+impl Interpreter {
+    fn input(&mut self, thread: &Thread, block: &Block, name: &str) -> Value {
+        match block.inputs.get(name) {
+            Some(Input::Block(uid)) => self.eval(thread, uid),
+            Some(Input::Value(value)) => value.clone(),
+            Some(Input::Variable { id, name }) => {
+                let field = Field {
+                    value: name.to_owned(),
+                    id: Some(id.to_owned()),
+                };
+                self.get_variable(thread.target, Some(&field))
+            }
+            Some(Input::List { id, name }) => {
+                let field = Field {
+                    value: name.to_owned(),
+                    id: Some(id.to_owned()),
+                };
+                Interpreter::list_contents(self.list_mut(thread.target, Some(&field)))
+            }
+            None => Value::Text(String::new()),
+        }
+    }
+
+    fn number(&mut self, thread: &Thread, block: &Block, name: &str) -> f64 {
+        self.input(thread, block, name).to_number()
+    }
+
+    fn text(&mut self, thread: &Thread, block: &Block, name: &str) -> String {
+        self.input(thread, block, name).to_string()
+    }
+
+    fn boolean(&mut self, thread: &Thread, block: &Block, name: &str) -> bool {
+        self.input(thread, block, name).to_bool()
+    }
+
+    fn eval(&mut self, thread: &Thread, uid: &str) -> Value {
+        let code = self.code(thread.target);
+        let Some(block) = code.blocks.get(uid) else {
+            return Value::Text(String::new());
+        };
+        let t = thread.target;
+        let state = &self.targets[t].state;
+        let value = match block.opcode.as_str() {
+            // Motion
+            "motion_xposition" => limit_precision(state.x).into(),
+            "motion_yposition" => limit_precision(state.y).into(),
+            "motion_direction" => state.direction.into(),
+
+            // Looks
+            "looks_size" => round(state.size).into(),
+            "looks_costumenumbername" => self.costume_number_name(t, block.field("NUMBER_NAME")),
+            "looks_backdropnumbername" => self.costume_number_name(0, block.field("NUMBER_NAME")),
+
+            // Sound
+            "sound_volume" => state.volume.into(),
+            "music_getTempo" => self.tempo.into(),
+
+            // Control
+            "control_get_counter" => self.counter.into(),
+
+            // Data
+            "data_variable" => self.get_variable(t, block.fields.get("VARIABLE")),
+            "data_listcontents" => {
+                Interpreter::list_contents(self.list_mut(t, block.fields.get("LIST")))
+            }
+            "data_itemoflist" => {
+                let index = self.input(thread, block, "INDEX");
+                let length = self.list_mut(t, block.fields.get("LIST")).len();
+                match self.list_index(&index, length) {
+                    Some(index) => self.list_mut(t, block.fields.get("LIST"))[index].clone(),
+                    None => Value::Text(String::new()),
+                }
+            }
+            "data_itemnumoflist" => {
+                let item = self.input(thread, block, "ITEM");
+                let list = self.list_mut(t, block.fields.get("LIST"));
+                let position = list.iter().position(|value| value.equals(&item));
+                position.map_or(0., |position| position as f64 + 1.).into()
+            }
+            "data_lengthoflist" => (self.list_mut(t, block.fields.get("LIST")).len() as f64).into(),
+            "data_listcontainsitem" => {
+                let item = self.input(thread, block, "ITEM");
+                let list = self.list_mut(t, block.fields.get("LIST"));
+                list.iter().any(|value| value.equals(&item)).into()
+            }
+
+            // Sensing
+            "sensing_touchingobject" | "sensing_touchingcolor" | "sensing_coloristouchingcolor" => {
+                false.into()
+            }
+            "sensing_distanceto" => {
+                let (x, y) = (state.x, state.y);
+                let to = self.text(thread, block, "DISTANCETOMENU");
+                match self.position_of(&to) {
+                    Some((to_x, to_y)) => ((to_x - x).hypot(to_y - y)).into(),
+                    None => 10000.0.into(),
+                }
+            }
+            "sensing_answer" => self.answer.clone().into(),
+            "sensing_keypressed" => {
+                let key = self.text(thread, block, "KEY_OPTION").to_lowercase();
+                match key.as_str() {
+                    "any" => !self.keys_down.is_empty(),
+                    key => self.keys_down.contains(key),
+                }
+                .into()
+            }
+            "sensing_mousedown" => self.mouse_down.into(),
+            "sensing_mousex" => self.mouse.0.into(),
+            "sensing_mousey" => self.mouse.1.into(),
+            "sensing_loudness" => self.loudness.into(),
+            "sensing_timer" => self.timer().into(),
+            "sensing_of" => {
+                let object = self.text(thread, block, "OBJECT");
+                self.attribute_of(&object, block.field("PROPERTY"))
+            }
+            "sensing_current" => self.current_time(block.field("CURRENTMENU")).into(),
+            "sensing_dayssince2000" => (self.time() / 86400.).into(),
+            "sensing_username" => Value::Text(String::new()),
+
+            // Operators
+            "operator_add" | "operator_subtract" | "operator_multiply" | "operator_divide"
+            | "operator_mod" => {
+                let a = self.number(thread, block, "NUM1");
+                let b = self.number(thread, block, "NUM2");
+                match block.opcode.as_str() {
+                    "operator_add" => a + b,
+                    "operator_subtract" => a - b,
+                    "operator_multiply" => a * b,
+                    "operator_divide" => a / b,
+                    // Result has the same sign as the divisor
+                    _ => {
+                        let result = a % b;
+                        if result / b < 0. {
+                            result + b
+                        } else {
+                            result
+                        }
+                    }
+                }
+                .into()
+            }
+            "operator_random" => {
+                let from = self.input(thread, block, "FROM");
+                let to = self.input(thread, block, "TO");
+                let (low, high) = match from.to_number() <= to.to_number() {
+                    true => (from.to_number(), to.to_number()),
+                    false => (to.to_number(), from.to_number()),
+                };
+                let random = self.random();
+                if low == high {
+                    low.into()
+                } else if from.is_int() && to.is_int() {
+                    (low + (random * (high - low + 1.)).floor()).into()
+                } else {
+                    (low + random * (high - low)).into()
+                }
+            }
+            "operator_gt" | "operator_lt" | "operator_equals" => {
+                let a = self.input(thread, block, "OPERAND1");
+                let b = self.input(thread, block, "OPERAND2");
+                let expected = match block.opcode.as_str() {
+                    "operator_gt" => Ordering::Greater,
+                    "operator_lt" => Ordering::Less,
+                    _ => Ordering::Equal,
+                };
+                (a.compare(&b) == expected).into()
+            }
+            "operator_and" => (self.boolean(thread, block, "OPERAND1")
+                && self.boolean(thread, block, "OPERAND2"))
+            .into(),
+            "operator_or" => (self.boolean(thread, block, "OPERAND1")
+                || self.boolean(thread, block, "OPERAND2"))
+            .into(),
+            "operator_not" => (!self.boolean(thread, block, "OPERAND")).into(),
+            "operator_join" => {
+                let a = self.text(thread, block, "STRING1");
+                let b = self.text(thread, block, "STRING2");
+                (a + &b).into()
+            }
+            "operator_letter_of" => {
+                let letter = self.number(thread, block, "LETTER").floor();
+                let string = self.text(thread, block, "STRING");
+                match letter >= 1. {
+                    true => string.chars().nth(letter as usize - 1),
+                    false => None,
+                }
+                .map(String::from)
+                .unwrap_or_default()
+                .into()
+            }
+            "operator_length" => (self.text(thread, block, "STRING").chars().count() as f64).into(),
+            "operator_contains" => {
+                let a = self.text(thread, block, "STRING1").to_lowercase();
+                let b = self.text(thread, block, "STRING2").to_lowercase();
+                a.contains(&b).into()
+            }
+            "operator_round" => round(self.number(thread, block, "NUM")).into(),
+            "operator_mathop" => {
+                let number = self.number(thread, block, "NUM");
+                mathop(block.field("OPERATOR"), number).into()
+            }
+
+            // Custom blocks
+            "argument_reporter_string_number" | "argument_reporter_boolean" => {
+                let name = block.field("VALUE");
+                let args = thread.stack.iter().rev().find_map(|frame| match frame {
+                    Frame::Procedure { args, .. } => Some(args),
+                    _ => None,
+                });
+                args.and_then(|args| args.get(name))
+                    .cloned()
+                    .unwrap_or(Value::Text(String::new()))
+            }
+
+            // Menus report their only field
+            _ if block.inputs.is_empty() && block.fields.len() == 1 => {
+                let field = block.fields.values().next();
+                Value::Text(field.map(|field| field.value.clone()).unwrap_or_default())
+            }
+            _ => Value::Text(String::new()),
+        };
+        value
+    }
+
+    fn costume_number_name(&self, target: usize, number_name: &str) -> Value {
+        let target = &self.targets[target];
+        match number_name {
+            "name" => target
+                .costumes
+                .get(target.state.costume)
+                .cloned()
+                .unwrap_or_default()
+                .into(),
+            _ => (target.state.costume as f64 + 1.).into(),
+        }
+    }
+
+    /// "of" block, `object` is "_stage_" or a sprite name
+    fn attribute_of(&mut self, object: &str, property: &str) -> Value {
+        let idx = match object {
+            "_stage_" => Some(0),
+            name => self.original(name),
+        };
+        let Some(idx) = idx else {
+            return 0.0.into();
+        };
+        let target = &self.targets[idx];
+        let state = &target.state;
+        match (target.is_stage, property) {
+            (true, "backdrop #") | (false, "costume #") => (state.costume as f64 + 1.).into(),
+            (true, "backdrop name") | (false, "costume name") => target
+                .costumes
+                .get(state.costume)
+                .cloned()
+                .unwrap_or_default()
+                .into(),
+            (false, "x position") => limit_precision(state.x).into(),
+            (false, "y position") => limit_precision(state.y).into(),
+            (false, "direction") => state.direction.into(),
+            (false, "size") => round(state.size).into(),
+            (_, "volume") => state.volume.into(),
+            // Only the target's own variables
+            (_, name) => target
+                .variables
+                .values()
+                .find(|variable| variable.name == name)
+                .map(|variable| variable.value.clone())
+                .unwrap_or(0.0.into()),
+        }
+    }
+
+    /// Simulated clock starting at 2000-01-01 00:00:00
+    fn current_time(&self, menu: &str) -> f64 {
+        let seconds = self.time();
+        let days = (seconds / 86400.).floor() as i64;
+        let second_of_day = seconds - days as f64 * 86400.;
+        let (year, month, date) = civil_from_days(DAYS_TO_2000 + days);
+        match menu.to_uppercase().as_str() {
+            "YEAR" => year as f64,
+            "MONTH" => month as f64,
+            "DATE" => date as f64,
+            // 2000-01-01 is a Saturday, Sunday is 1
+            "DAYOFWEEK" => ((days + 6).rem_euclid(7) + 1) as f64,
+            "HOUR" => (second_of_day / 3600.).floor(),
+            "MINUTE" => (second_of_day / 60.).floor() % 60.,
+            "SECOND" => second_of_day.floor() % 60.,
+            _ => 0.,
+        }
+    }
+}
+
+/// Same as javascript's `Math.round`
+fn round(number: f64) -> f64 {
+    (number + 0.5).floor()
+}
+
+/// Removes floating point error from moving. Ex. 0.000000000000001 to 0
+fn limit_precision(coordinate: f64) -> f64 {
+    let rounded = coordinate.round();
+    if (coordinate - rounded).abs() < EPSILON {
+        rounded
+    } else {
+        coordinate
+    }
+}
+
+/// Trigonometry is in degrees and rounded to 10 decimals like Scratch
+fn mathop(operator: &str, number: f64) -> f64 {
+    let round10 = |number: f64| (number * 1e10).round() / 1e10;
+    match operator {
+        "abs" => number.abs(),
+        "floor" => number.floor(),
+        "ceiling" => number.ceil(),
+        "sqrt" => number.sqrt(),
+        "sin" => round10(number.to_radians().sin()),
+        "cos" => round10(number.to_radians().cos()),
+        "tan" => match number % 360. {
+            degrees if degrees == 90. || degrees == -270. => f64::INFINITY,
+            degrees if degrees == -90. || degrees == 270. => f64::NEG_INFINITY,
+            _ => round10(number.to_radians().tan()),
+        },
+        "asin" => number.asin().to_degrees(),
+        "acos" => number.acos().to_degrees(),
+        "atan" => number.atan().to_degrees(),
+        "ln" => number.ln(),
+        "log" => number.log10(),
+        "e ^" => number.exp(),
+        "10 ^" => 10f64.powf(number),
+        _ => 0.,
+    }
+}
+
+/// Year, month and day from days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
@@ -0,0 +1,724 @@
+//! Headless interpreter for running a built project without rendering.
+//!
+//! Like in Scratch, every frame the running scripts are stepped until one of them requests a redraw
+//! (a visible sprite moves, changes its looks or the pen draws) or the frame's budget runs out,
+//! loops and waits yield between the steps unless inside a "run without screen refresh" custom block.
+//! So a loop that doesn't draw can run many iterations per frame while a loop that does runs once.
+//! Time is simulated, one frame is `1 / FRAMES_PER_SECOND` seconds,
+//! and inputs are fed with [`InputEvent`]s.
+//!
+//! ```ignore
+//! let mut interpreter = Interpreter::from_builder(project)?;
+//! interpreter.green_flag();
+//! interpreter.run(30);
+//! assert_eq!(interpreter.variable("Cat", "score"), Some(&Value::Number(10.)));
+//! ```
+//!
+//! Not simulated: collision (touching is always false), pen, rendering and audio playback.
+
+mod exec;
+mod value;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+use rs_sb3::project::Project;
+use serde_json::Value as Json;
+
+use crate::project::{validation::BuildError, ProjectBuilder};
+
+pub use value::Value;
+
+pub const FRAMES_PER_SECOND: u32 = 30;
+/// Limit of clones alive at the same time, same as Scratch
+pub const MAX_CLONES: usize = 300;
+
+/// Scripted input to the project
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    GreenFlag,
+    StopAll,
+    /// Key name as Scratch calls it. Ex. "space", "left arrow", "a"
+    KeyDown(String),
+    KeyUp(String),
+    MouseMove {
+        x: f64,
+        y: f64,
+    },
+    MouseDown,
+    MouseUp,
+    /// Clicks the original sprite with the name
+    ClickSprite(String),
+    ClickStage,
+    /// Answers the oldest "ask and wait"
+    Answer(String),
+    Loudness(f64),
+}
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// Project doesn't have a Stage
+    NoStage,
+    Build(Box<BuildError>),
+    Json(serde_json::Error),
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl std::fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpreterError::NoStage => write!(f, "project doesn't have a stage"),
+            InterpreterError::Build(build) => write!(f, "{build}"),
+            InterpreterError::Json(json) => write!(f, "{json}"),
+        }
+    }
+}
+
+impl From<Box<BuildError>> for InterpreterError {
+    fn from(value: Box<BuildError>) -> Self {
+        InterpreterError::Build(value)
+    }
+}
+impl From<serde_json::Error> for InterpreterError {
+    fn from(value: serde_json::Error) -> Self {
+        InterpreterError::Json(value)
+    }
+}
+
+/// What a sprite currently looks like. The stage only uses the costume, volume and effects.
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteState {
+    pub x:              f64,
+    pub y:              f64,
+    pub direction:      f64,
+    pub size:           f64,
+    pub visible:        bool,
+    /// Index into the costumes
+    pub costume:        usize,
+    /// 1 is the bottom most sprite
+    pub layer:          usize,
+    pub volume:         f64,
+    /// Text of "say" or "think"
+    pub bubble:         Option<String>,
+    pub draggable:      bool,
+    pub rotation_style: String,
+    /// Graphic effects by lowercase name. Ex. "ghost"
+    pub effects:        HashMap<String, f64>,
+}
+
+// Loaded project ==========================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+    Block(String),
+    Value(Value),
+    Variable { id: String, name: String },
+    List { id: String, name: String },
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    value: String,
+    id:    Option<String>,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    opcode:   String,
+    next:     Option<String>,
+    inputs:   HashMap<String, Input>,
+    fields:   HashMap<String, Field>,
+    /// Procedure code of a call or a prototype
+    proccode: Option<String>,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+struct Procedure {
+    body:           Option<String>,
+    argument_ids:   Vec<String>,
+    argument_names: Vec<String>,
+    warp:           bool,
+}
+
+/// Scripts of a sprite, shared with its clones
+#[rustfmt::skip]
+#[derive(Debug, Default)]
+struct Code {
+    blocks:     HashMap<String, Block>,
+    procedures: HashMap<String, Procedure>,
+    /// Top level blocks in order of their id so hats start deterministically
+    hats:       Vec<String>,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
+struct Variable {
+    name:  String,
+    value: Value,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
+struct List {
+    name:   String,
+    values: Vec<Value>,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
+struct Target {
+    code:      Rc<Code>,
+    name:      String,
+    is_stage:  bool,
+    is_clone:  bool,
+    deleted:   bool,
+    variables: HashMap<String, Variable>,
+    lists:     HashMap<String, List>,
+    costumes:  Vec<String>,
+    /// Name and duration in seconds
+    sounds:    Vec<(String, f64)>,
+    state:     SpriteState,
+}
+
+// Threads =================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LoopKind {
+    Repeat,
+    Forever,
+    Until,
+    While,
+    ForEach,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+enum Frame {
+    /// Block to run next in a stack
+    Sequence(Option<String>),
+    Loop {
+        block:      String,
+        kind:       LoopKind,
+        /// Iterations left for repeat, current index for "for each"
+        counter:    f64,
+        yield_next: bool,
+    },
+    Wait {
+        until:        f64,
+        yielded:      bool,
+        clear_bubble: bool,
+    },
+    WaitUntil(String),
+    Glide {
+        from:     (f64, f64),
+        to:       (f64, f64),
+        start:    f64,
+        duration: f64,
+    },
+    /// Broadcast and wait, switch backdrop and wait
+    WaitThreads(Vec<usize>),
+    Ask,
+    Procedure {
+        proccode: String,
+        args:     HashMap<String, Value>,
+        warp:     bool,
+    },
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
+struct Thread {
+    id:     usize,
+    target: usize,
+    hat:    String,
+    stack:  Vec<Frame>,
+    done:   bool,
+}
+
+impl Thread {
+    fn is_warp(&self) -> bool {
+        self.stack
+            .iter()
+            .any(|frame| matches!(frame, Frame::Procedure { warp: true, .. }))
+    }
+}
+
+// Interpreter =============================================================
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
+pub struct Interpreter {
+    targets:         Vec<Target>,
+    /// Sprite target indices from the bottom layer to the top
+    layers:          Vec<usize>,
+    threads:         Vec<Thread>,
+    next_thread_id:  usize,
+    /// Thread being stepped, taken out of `threads`
+    current:         Option<(usize, String, usize)>,
+    restart_current: bool,
+    /// A block changed what's on screen, ends stepping the frame
+    redraw_requested: bool,
+    scheduled:       Vec<(u64, InputEvent)>,
+    frame:           u64,
+    timer_start:     f64,
+    keys_down:       HashSet<String>,
+    mouse:           (f64, f64),
+    mouse_down:      bool,
+    loudness:        f64,
+    answer:          String,
+    /// Thread ids waiting for an answer
+    asks:            VecDeque<usize>,
+    tempo:           f64,
+    counter:         f64,
+    /// Last value of "when timer/loudness >" hats by target and hat id
+    greater_than:    HashMap<(usize, String), bool>,
+    rng:             u64,
+}
+
+impl Interpreter {
+    pub fn new(project: &Project) -> Result<Interpreter, InterpreterError> {
+        let json = serde_json::to_value(project)?;
+        let targets = json
+            .get("targets")
+            .and_then(Json::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut loaded: Vec<Target> = targets.iter().map(load_target).collect();
+        // Stage is always the first target
+        let stage_idx = loaded
+            .iter()
+            .position(|target| target.is_stage)
+            .ok_or(InterpreterError::NoStage)?;
+        let stage = loaded.remove(stage_idx);
+        loaded.insert(0, stage);
+        let tempo = targets
+            .iter()
+            .find(|target| target.get("isStage") == Some(&Json::Bool(true)))
+            .and_then(|stage| stage.get("tempo"))
+            .and_then(Json::as_f64)
+            .unwrap_or(60.);
+
+        let mut layers: Vec<usize> = (1..loaded.len()).collect();
+        layers.sort_by_key(|&idx| loaded[idx].state.layer);
+        let mut interpreter = Interpreter {
+            targets: loaded,
+            layers,
+            threads: vec![],
+            next_thread_id: 0,
+            current: None,
+            restart_current: false,
+            redraw_requested: false,
+            scheduled: vec![],
+            frame: 0,
+            timer_start: 0.,
+            keys_down: HashSet::new(),
+            mouse: (0., 0.),
+            mouse_down: false,
+            loudness: 0.,
+            answer: String::new(),
+            asks: VecDeque::new(),
+            tempo,
+            counter: 0.,
+            greater_than: HashMap::new(),
+            rng: 0x2545_f491_4f6c_dd1d,
+        };
+        interpreter.relayer();
+        Ok(interpreter)
+    }
+
+    pub fn from_builder(project: ProjectBuilder) -> Result<Interpreter, InterpreterError> {
+        let project = project.build(&mut vec![])?;
+        Interpreter::new(&project)
+    }
+
+    /// Seed of "pick random"
+    pub fn seed(mut self, seed: u64) -> Self {
+        // Xorshift gets stuck at 0
+        self.rng = seed.max(1);
+        self
+    }
+}
+
+impl Interpreter {
+    /// Sends the event at the start of the frame
+    pub fn schedule(&mut self, frame: u64, event: InputEvent) {
+        self.scheduled.push((frame, event));
+    }
+
+    /// Sends the event now, scripts started by it run on the next step
+    pub fn send(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::GreenFlag => {
+                self.stop_all();
+                self.timer_start = self.time();
+                self.start_hats("event_whenflagclicked", None, true, |_| true);
+            }
+            InputEvent::StopAll => self.stop_all(),
+            InputEvent::KeyDown(key) => {
+                let key = key.to_lowercase();
+                self.keys_down.insert(key.clone());
+                self.start_hats("event_whenkeypressed", None, false, |block| {
+                    let option = block.field("KEY_OPTION").to_lowercase();
+                    option == "any" || option == key
+                });
+            }
+            InputEvent::KeyUp(key) => {
+                self.keys_down.remove(&key.to_lowercase());
+            }
+            InputEvent::MouseMove { x, y } => self.mouse = (x, y),
+            InputEvent::MouseDown => self.mouse_down = true,
+            InputEvent::MouseUp => self.mouse_down = false,
+            InputEvent::ClickSprite(name) => {
+                if let Some(idx) = self.original(&name) {
+                    self.start_hats("event_whenthisspriteclicked", Some(idx), true, |_| true);
+                }
+            }
+            InputEvent::ClickStage => {
+                self.start_hats("event_whenstageclicked", Some(0), true, |_| true);
+            }
+            InputEvent::Answer(answer) => {
+                self.answer = answer;
+                self.asks.pop_front();
+            }
+            InputEvent::Loudness(loudness) => self.loudness = loudness,
+        }
+    }
+
+    pub fn green_flag(&mut self) {
+        self.send(InputEvent::GreenFlag);
+    }
+
+    /// Runs a frame
+    pub fn step(&mut self) {
+        let (now, later): (Vec<_>, Vec<_>) = std::mem::take(&mut self.scheduled)
+            .into_iter()
+            .partition(|(frame, _)| *frame <= self.frame);
+        self.scheduled = later;
+        for (_, event) in now {
+            self.send(event);
+        }
+        self.check_greater_than();
+        self.step_threads();
+        self.frame += 1;
+    }
+
+    pub fn run(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Runs until no scripts are running and no events are scheduled.
+    /// Returns false if it's still running after `max_frames`.
+    pub fn run_until_idle(&mut self, max_frames: u64) -> bool {
+        for _ in 0..max_frames {
+            if self.is_idle() {
+                return true;
+            }
+            self.step();
+        }
+        self.is_idle()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.scheduled.is_empty() && self.threads.iter().all(|thread| thread.done)
+    }
+
+    /// Frames that have been run
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Simulated seconds since the interpreter started
+    pub fn time(&self) -> f64 {
+        self.frame as f64 / FRAMES_PER_SECOND as f64
+    }
+
+    /// Variable as seen from the target, either its own or a global one.
+    /// `target` is the original sprite's name or "Stage".
+    pub fn variable(&self, target: &str, name: &str) -> Option<&Value> {
+        let idx = self.find_target(target)?;
+        [idx, 0].into_iter().find_map(|idx| {
+            self.targets[idx]
+                .variables
+                .values()
+                .find(|variable| variable.name == name)
+                .map(|variable| &variable.value)
+        })
+    }
+
+    /// List as seen from the target, either its own or a global one.
+    pub fn list(&self, target: &str, name: &str) -> Option<&[Value]> {
+        let idx = self.find_target(target)?;
+        [idx, 0].into_iter().find_map(|idx| {
+            self.targets[idx]
+                .lists
+                .values()
+                .find(|list| list.name == name)
+                .map(|list| list.values.as_slice())
+        })
+    }
+
+    /// State of the original sprite
+    pub fn sprite(&self, name: &str) -> Option<&SpriteState> {
+        self.original(name).map(|idx| &self.targets[idx].state)
+    }
+
+    /// Clones of the sprite that are alive
+    pub fn clone_count(&self, name: &str) -> usize {
+        self.targets
+            .iter()
+            .filter(|target| target.is_clone && !target.deleted && target.name == name)
+            .count()
+    }
+
+    /// Name of the current backdrop
+    pub fn backdrop(&self) -> &str {
+        let stage = &self.targets[0];
+        stage
+            .costumes
+            .get(stage.state.costume)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+impl Interpreter {
+    fn find_target(&self, name: &str) -> Option<usize> {
+        if self.targets[0].name == name {
+            Some(0)
+        } else {
+            self.original(name)
+        }
+    }
+
+    fn original(&self, name: &str) -> Option<usize> {
+        self.targets
+            .iter()
+            .position(|target| !target.is_stage && !target.is_clone && target.name == name)
+    }
+
+    /// Updates each sprite's layer after `layers` changed
+    fn relayer(&mut self) {
+        for (layer, &idx) in self.layers.iter().enumerate() {
+            self.targets[idx].state.layer = layer + 1;
+        }
+    }
+}
+
+// Loading =================================================================
+
+fn load_target(json: &Json) -> Target {
+    let is_stage = json.get("isStage").and_then(Json::as_bool).unwrap_or(false);
+    let number = |key: &str, default: f64| json.get(key).and_then(Json::as_f64).unwrap_or(default);
+    let variables = entries(json, "variables")
+        .map(|(id, variable)| {
+            let variable = Variable {
+                name: variable.get(0).map(json_string).unwrap_or_default(),
+                value: variable.get(1).map(Value::from).unwrap_or_default(),
+            };
+            (id.to_owned(), variable)
+        })
+        .collect();
+    let lists = entries(json, "lists")
+        .map(|(id, list)| {
+            let list = List {
+                name: list.get(0).map(json_string).unwrap_or_default(),
+                values: list
+                    .get(1)
+                    .and_then(Json::as_array)
+                    .map(|values| values.iter().map(Value::from).collect())
+                    .unwrap_or_default(),
+            };
+            (id.to_owned(), list)
+        })
+        .collect();
+    let costumes = array(json, "costumes")
+        .map(|costume| costume.get("name").map(json_string).unwrap_or_default())
+        .collect();
+    let sounds = array(json, "sounds")
+        .map(|sound| {
+            let name = sound.get("name").map(json_string).unwrap_or_default();
+            let rate = sound.get("rate").and_then(Json::as_f64).unwrap_or(0.);
+            let samples = sound
+                .get("sampleCount")
+                .and_then(Json::as_f64)
+                .unwrap_or(0.);
+            let duration = if rate > 0. { samples / rate } else { 0. };
+            (name, duration)
+        })
+        .collect();
+    let state = SpriteState {
+        x: number("x", 0.),
+        y: number("y", 0.),
+        direction: number("direction", 90.),
+        size: number("size", 100.),
+        visible: json.get("visible").and_then(Json::as_bool).unwrap_or(true),
+        costume: number("currentCostume", 0.) as usize,
+        layer: number("layerOrder", 0.) as usize,
+        volume: number("volume", 100.),
+        bubble: None,
+        draggable: json
+            .get("draggable")
+            .and_then(Json::as_bool)
+            .unwrap_or(false),
+        rotation_style: json
+            .get("rotationStyle")
+            .map(json_string)
+            .unwrap_or_else(|| "all around".to_owned()),
+        effects: HashMap::new(),
+    };
+    Target {
+        code: Rc::new(load_code(json.get("blocks"))),
+        name: json.get("name").map(json_string).unwrap_or_default(),
+        is_stage,
+        is_clone: false,
+        deleted: false,
+        variables,
+        lists,
+        costumes,
+        sounds,
+        state,
+    }
+}
+
+fn load_code(json: Option<&Json>) -> Code {
+    let mut code = Code::default();
+    let Some(Json::Object(blocks)) = json else {
+        return code;
+    };
+    for (uid, block) in blocks {
+        // Top level variable and list reporters are arrays and can't run
+        if !block.is_object() {
+            continue;
+        }
+        let inputs = block
+            .get("inputs")
+            .and_then(Json::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, input)| Some((name.to_owned(), load_input(input)?)))
+            .collect();
+        let fields = block
+            .get("fields")
+            .and_then(Json::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(name, field)| {
+                let field = Field {
+                    value: field.get(0).map(json_string).unwrap_or_default(),
+                    id: field.get(1).and_then(Json::as_str).map(str::to_owned),
+                };
+                (name.to_owned(), field)
+            })
+            .collect();
+        let mutation = block.get("mutation");
+        let loaded = Block {
+            opcode: block.get("opcode").map(json_string).unwrap_or_default(),
+            next: block.get("next").and_then(Json::as_str).map(str::to_owned),
+            inputs,
+            fields,
+            proccode: mutation
+                .and_then(|mutation| mutation.get("proccode"))
+                .map(json_string),
+        };
+        if loaded.opcode == "procedures_prototype" {
+            let mutation = mutation.unwrap_or(&Json::Null);
+            let procedure = Procedure {
+                body: None,
+                argument_ids: string_array(mutation.get("argumentids")),
+                argument_names: string_array(mutation.get("argumentnames")),
+                warp: matches!(mutation.get("warp"), Some(Json::Bool(true)))
+                    || matches!(mutation.get("warp"), Some(Json::String(warp)) if warp == "true"),
+            };
+            code.procedures
+                .insert(loaded.proccode.clone().unwrap_or_default(), procedure);
+        }
+        if block
+            .get("topLevel")
+            .and_then(Json::as_bool)
+            .unwrap_or(false)
+        {
+            code.hats.push(uid.to_owned());
+        }
+        code.blocks.insert(uid.to_owned(), loaded);
+    }
+    code.hats.sort();
+    // Definition hat's next block is the procedure's body
+    let definitions: Vec<(String, Option<String>)> = code
+        .blocks
+        .values()
+        .filter(|block| block.opcode == "procedures_definition")
+        .filter_map(|definition| {
+            let Some(Input::Block(prototype)) = definition.inputs.get("custom_block") else {
+                return None;
+            };
+            let proccode = code.blocks.get(prototype)?.proccode.clone()?;
+            Some((proccode, definition.next.clone()))
+        })
+        .collect();
+    for (proccode, body) in definitions {
+        if let Some(procedure) = code.procedures.get_mut(&proccode) {
+            procedure.body = body;
+        }
+    }
+    code
+}
+
+/// `[shadow type, input, obscured shadow]`
+fn load_input(json: &Json) -> Option<Input> {
+    match json.get(1)? {
+        Json::String(uid) => Some(Input::Block(uid.to_owned())),
+        Json::Array(value) => {
+            let code = value.first().and_then(Json::as_i64)?;
+            let text = value.get(1).map(json_string).unwrap_or_default();
+            let id = value.get(2).map(json_string).unwrap_or_default();
+            Some(match code {
+                12 => Input::Variable { id, name: text },
+                13 => Input::List { id, name: text },
+                // Broadcast's value is its name
+                11 => Input::Value(Value::Text(text)),
+                _ => Input::Value(value.get(1).map(Value::from).unwrap_or_default()),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn entries<'a>(json: &'a Json, key: &str) -> impl Iterator<Item = (&'a String, &'a Json)> {
+    json.get(key)
+        .and_then(Json::as_object)
+        .into_iter()
+        .flatten()
+}
+
+fn array<'a>(json: &'a Json, key: &str) -> impl Iterator<Item = &'a Json> {
+    json.get(key).and_then(Json::as_array).into_iter().flatten()
+}
+
+/// Mutation's arrays are written as JSON inside a string in project.json
+fn string_array(json: Option<&Json>) -> Vec<String> {
+    let parsed;
+    let json = match json {
+        Some(Json::String(text)) => {
+            parsed = serde_json::from_str::<Json>(text).unwrap_or(Json::Null);
+            &parsed
+        }
+        Some(json) => json,
+        None => return vec![],
+    };
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .map(json_string)
+        .collect()
+}
+
+fn json_string(json: &Json) -> String {
+    Value::from(json).to_string()
+}
@@ -0,0 +1,196 @@
+//! Scratch's loosely typed values and how they're casted between each other.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    /// Text that's not a number is 0
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Value::Number(number) if number.is_nan() => 0.,
+            Value::Number(number) => *number,
+            Value::Bool(b) => *b as u8 as f64,
+            Value::Text(text) => parse_number(text).unwrap_or(0.),
+        }
+    }
+
+    /// Only `false`, 0, "", "0" and "false" are false
+    pub fn to_bool(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Number(number) => *number != 0. && !number.is_nan(),
+            Value::Text(text) => {
+                !(text.is_empty() || text == "0" || text.eq_ignore_ascii_case("false"))
+            }
+        }
+    }
+
+    /// Whole number when casted. Ex. used by "pick random" to decide to pick an integer
+    pub fn is_int(&self) -> bool {
+        match self {
+            Value::Number(number) => number.fract() == 0.,
+            Value::Bool(_) => true,
+            Value::Text(text) => !text.contains('.'),
+        }
+    }
+
+    /// Compares numerically when both are numbers, otherwise as case insensitive text
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self.strict_number(), other.strict_number()) {
+            (Some(a), Some(b)) => {
+                if a == b {
+                    Ordering::Equal
+                } else {
+                    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+                }
+            }
+            _ => {
+                let a = self.to_string().to_lowercase();
+                let b = other.to_string().to_lowercase();
+                a.cmp(&b)
+            }
+        }
+    }
+
+    pub fn equals(&self, other: &Value) -> bool {
+        self.compare(other) == Ordering::Equal
+    }
+
+    /// Number when comparing. Blank text isn't a number here.
+    pub(super) fn strict_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(number) if number.is_nan() => None,
+            Value::Number(number) => Some(*number),
+            Value::Bool(b) => Some(*b as u8 as f64),
+            Value::Text(text) if text.trim().is_empty() => None,
+            Value::Text(text) => parse_number(text),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number_to_string(*number)),
+            Value::Text(text) => write!(f, "{text}"),
+            Value::Bool(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Number(0.)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&serde_json::Value> for Value {
+    fn from(json: &serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Number(number) => Value::Number(number.as_f64().unwrap_or_default()),
+            serde_json::Value::String(text) => Value::Text(text.clone()),
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Null => Value::Text(String::new()),
+            other => Value::Text(other.to_string()),
+        }
+    }
+}
+
+/// Same as javascript's `Number(text)` but `None` instead of NaN
+fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(0.);
+    }
+    let (sign, unsigned) = match text.as_bytes()[0] {
+        b'-' => (-1., &text[1..]),
+        b'+' => (1., &text[1..]),
+        _ => (1., text),
+    };
+    if unsigned == "Infinity" {
+        return Some(sign * f64::INFINITY);
+    }
+    let radix = match unsigned.get(..2) {
+        Some("0x" | "0X") => Some(16),
+        Some("0o" | "0O") => Some(8),
+        Some("0b" | "0B") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        // Javascript doesn't allow a sign before these
+        if unsigned.len() != text.len() {
+            return None;
+        }
+        return u64::from_str_radix(&unsigned[2..], radix)
+            .ok()
+            .map(|n| n as f64);
+    }
+    // Rust also parses "inf" and "nan" which javascript doesn't
+    if !unsigned
+        .bytes()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+    {
+        return None;
+    }
+    unsigned.parse::<f64>().ok().map(|n| sign * n)
+}
+
+/// Same as javascript's number to string
+fn number_to_string(number: f64) -> String {
+    if number.is_nan() {
+        return "NaN".to_owned();
+    }
+    if number.is_infinite() {
+        return if number > 0. { "Infinity" } else { "-Infinity" }.to_owned();
+    }
+    if number == 0. {
+        return "0".to_owned();
+    }
+    let abs = number.abs();
+    if (1e-6..1e21).contains(&abs) {
+        return format!("{number}");
+    }
+    let exponential = format!("{number:e}");
+    match exponential.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+            format!("{mantissa}e+{exponent}")
+        }
+        _ => exponential,
+    }
+}
@@ -1,11 +1,19 @@
+pub mod decompile;
 pub mod export;
+pub mod import;
+pub mod interpreter;
 pub mod opcode;
 pub mod project;
 pub mod resource;
+pub mod scratchblocks;
 pub mod scripting;
 pub mod typed_scripting;
 pub mod uid;
 
+// Decompiled source refers to this crate by name
+#[cfg(test)]
+extern crate self as mcscratchy;
+
 #[cfg(test)]
 mod test {
     #[allow(unused_imports)]
@@ -15,7 +23,7 @@ mod test {
         if_else_chain_builder::if_,
     };
     use crate::{
-        export::export,
+        export::{export, write_zip},
         project::{
             asset::{AssetBuilder, CostumeBuilder},
             script::{CommentBuilder, VariableBuilder},
@@ -23,9 +31,14 @@ mod test {
             ProjectBuilder,
         },
         resource::Resource,
+        scripting::menu::LooksEffect,
     };
     use std::env::var as envvar;
 
+    /// Brands of the sprites in the tests
+    struct Cat;
+    struct Dog;
+
     #[test]
     fn test_creating_project() {
         let export_path = envvar("EXPORT_NAME").expect("EXPORT_NAME env");
@@ -38,30 +51,1885 @@ mod test {
             .add_comment(CommentBuilder::new("hi"));
         let stage = StageBuilder::new(stage_inner);
 
+        let sprite_inner = TargetBuilder::new("Cat")
+            .add_costume(CostumeBuilder::new(AssetBuilder::new(
+                "costume1",
+                Resource::load("cat.svg").unwrap(),
+            )))
+            .layer_order(1);
+        let mut sprite = SpriteBuilder::<Cat>::new(sprite_inner);
+        let num = sprite.declare_sprite_var("num", VariableBuilder::new(1.into()));
+
         #[rustfmt::skip]
         let start = when_flag_clicked().next(forever(Some(
             if_(
-                equals(sprite_var("num"), 1),
-                Some(change_looks_effect_by("COLOR", 1).next(set_var_to(SpriteVar("num"), 2))),
+                equals(sprite_var(&num), 1),
+                Some(change_looks_effect_by(LooksEffect::Color, 1).next(set_var_to(&num, 2))),
             )
             .else_if(
-                equals(sprite_var("num"), 2),
-                Some(change_looks_effect_by("GHOST", 1).next(set_var_to(SpriteVar("num"), 1))),
+                equals(sprite_var(&num), 2),
+                Some(change_looks_effect_by(LooksEffect::Ghost, 1).next(set_var_to(&num, 1))),
             )
             .end(),
         )));
 
-        let sprite_inner = TargetBuilder::new("Cat")
-            .add_block_stack(start.into_untyped())
-            .add_variable("num", VariableBuilder::new(1.into()))
+        let sprite = sprite.add_script(start);
+
+        let project = ProjectBuilder::new().set_stage(stage).add_sprite(sprite);
+        export(project, export_path).unwrap();
+    }
+
+    #[test]
+    fn test_unresolved_names() {
+        use crate::project::validation::UnresolvedName;
+        use crate::scripting::script_builder::ReferenceKind;
+
+        let project = || {
+            let mut sprite = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+            let lives = sprite.declare_sprite_var("lives", VariableBuilder::new(3.into()));
+            let script = when_flag_clicked()
+                .next(set_var_to(GlobalVar::assume_declared("score"), 1))
+                .next(change_var_by(SpriteVar::assume_declared("hp"), 1))
+                .next(set_var_to(&lives, 3));
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(sprite.add_script(script))
+        };
+
+        let err = project().build(&mut vec![]).unwrap_err();
+        let unresolved = |name: &str, kind, opcode: &str| UnresolvedName {
+            name: name.to_owned(),
+            kind,
+            target: "Cat".to_owned(),
+            opcode: opcode.to_owned(),
+            owner: None,
+        };
+        assert_eq!(
+            err.unresolved,
+            vec![
+                unresolved("score", ReferenceKind::GlobalVariable, "data_setvariableto"),
+                unresolved("hp", ReferenceKind::SpriteVariable, "data_changevariableby"),
+            ]
+        );
+
+        let built = project().lenient(true).build(&mut vec![]).unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        let variable_names = |target: usize| {
+            let mut names: Vec<String> = built["targets"][target]["variables"]
+                .as_object()
+                .unwrap()
+                .values()
+                .map(|variable| variable[0].as_str().unwrap().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(variable_names(0), ["score"]);
+        assert_eq!(variable_names(1), ["hp", "lives"]);
+    }
+
+    #[test]
+    fn test_deterministic_export() {
+        fn project() -> ProjectBuilder {
+            let mut target = TargetBuilder::new("Cat").add_comment(CommentBuilder::new("hi"));
+            let hello = target.declare_broadcast("hello");
+            let mut sprite = SpriteBuilder::<Cat>::new(target);
+            let num = sprite.declare_sprite_var("num", VariableBuilder::new(1.into()));
+            let script = when_flag_clicked()
+                .next(set_var_to(&num, add(sprite_var(&num), 1)))
+                .next(say(join("num is ", "?")))
+                .next(broadcast(&hello));
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(sprite.add_script(script))
+        }
+
+        let mut first = std::io::Cursor::new(vec![]);
+        let mut second = std::io::Cursor::new(vec![]);
+        write_zip(&mut first, project()).unwrap();
+        write_zip(&mut second, project()).unwrap();
+        assert_eq!(first.into_inner(), second.into_inner());
+    }
+
+    #[test]
+    fn test_music_blocks() {
+        use crate::scripting::{
+            blocks as b,
+            menu::{Drum, Instrument},
+            script_builder::{BlockFieldBuilder, BlockInputBuilder},
+        };
+
+        let menu = BlockInputBuilder::stack;
+        let script = b::when_flag_clicked()
+            .next(b::play_drum_for_beats(
+                menu(b::drum_menu(BlockFieldBuilder::new(Drum::Cowbell.into()))),
+                BlockInputBuilder::value(rs_sb3::block::BlockInputValue::Number {
+                    value: 0.25.into(),
+                }),
+            ))
+            .next(b::set_instrument_to(menu(b::instrument_menu(
+                BlockFieldBuilder::new(Instrument::Organ.into()),
+            ))));
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(
+                TargetBuilder::new("Cat").add_block_stack(script),
+            ))
+            .build(&mut vec![])
+            .unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        assert_eq!(built["extensions"], serde_json::json!(["music"]));
+
+        let blocks = built["targets"][1]["blocks"].as_object().unwrap();
+        let block = |opcode: &str| {
+            blocks
+                .values()
+                .find(|block| block["opcode"] == opcode)
+                .unwrap_or_else(|| panic!("no `{opcode}` block"))
+        };
+        let menu_value = |block: &serde_json::Value, input: &str| {
+            let menu = block["inputs"][input][1].as_str().unwrap();
+            blocks[menu]["fields"][input][0].clone()
+        };
+        // Opcodes from scratch3_music's `getPrimitives`
+        let drum = block("music_playDrumForBeats");
+        // Menus are shadow inputs, `[1, menu]`
+        assert_eq!(drum["inputs"]["DRUM"][0], 1);
+        assert_eq!(menu_value(drum, "DRUM"), "11");
+        assert_eq!(block("music_menu_DRUM")["shadow"], true);
+        let instrument = block("music_setInstrument");
+        assert_eq!(menu_value(instrument, "INSTRUMENT"), "3");
+        assert_eq!(block("music_menu_INSTRUMENT")["shadow"], true);
+    }
+
+    #[test]
+    fn test_compact_export() {
+        use crate::export::{write_zip_with, ExportOptions};
+        use crate::scripting::script_builder::{
+            BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, StackBuilder,
+        };
+        use crate::typed_scripting::script_builder::{AnyTarget, StackBlock};
+        use std::io::{Cursor, Read};
+
+        let mut sprite = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+        let num = sprite.declare_sprite_var("num", VariableBuilder::new(1.into()));
+        let script = when_flag_clicked().next(set_var_to(&num, add(sprite_var(&num), 1)));
+        // Same as how Scratch writes a number shadow when it's not compact
+        let ten = BlockNormalBuilder::new("math_number")
+            .add_field("NUM", BlockFieldBuilder::new("10".into()))
+            .shadow(true);
+        let move_steps = BlockNormalBuilder::new("motion_movesteps")
+            .add_input("STEPS", BlockInputBuilder::stack(StackBuilder::start(ten)));
+        let sprite = sprite
+            .add_script(script)
+            .add_script(StackBlock::<AnyTarget>::assume_typed(StackBuilder::start(
+                move_steps,
+            )));
+        let project = || {
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(sprite.clone())
+        };
+
+        let mut zip = Cursor::new(vec![]);
+        let report = write_zip_with(&mut zip, project(), ExportOptions::compact()).unwrap();
+        let readable = write_zip(Cursor::new(vec![]), project()).unwrap();
+        assert_eq!(report.readable, readable);
+        assert!(report.compact_primitives < report.readable);
+        assert!(report.short_uids < report.compact_primitives);
+        assert!(report.size() < report.short_uids);
+
+        let mut project_json = String::new();
+        zip::ZipArchive::new(Cursor::new(zip.get_ref()))
+            .unwrap()
+            .by_name("project.json")
+            .unwrap()
+            .read_to_string(&mut project_json)
+            .unwrap();
+        assert_eq!(project_json.len(), report.size());
+        assert!(!project_json.contains('\n'));
+        assert!(!project_json.contains("math_number"));
+        assert!(project_json.contains(r#""STEPS":[1,[4,"10"]]"#));
+        let json: serde_json::Value = serde_json::from_str(&project_json).unwrap();
+        let blocks = json["targets"][1]["blocks"].as_object().unwrap();
+        assert_eq!(blocks.len(), 4);
+        assert!(blocks.keys().all(|uid| uid.len() == 1));
+
+        zip.set_position(0);
+        let imported = ProjectBuilder::from_sb3(zip).unwrap();
+        assert!(imported.build(&mut vec![]).is_ok());
+    }
+
+    #[test]
+    fn test_large_export() {
+        use crate::export::{write_zip_with, ExportOptions};
+        use std::io::{Cursor, Read};
+
+        let mut sprite = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+        sprite.declare_costume(CostumeBuilder::new(AssetBuilder::new(
+            "cat",
+            Resource::load("cat.svg").unwrap(),
+        )));
+        let mut script = when_flag_clicked().next(move_steps(0));
+        for i in 1..5000 {
+            script = script.next(move_steps(i));
+        }
+        let project = || {
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(sprite.clone().add_script(script.clone()))
+        };
+
+        for options in [ExportOptions::readable(), ExportOptions::compact()] {
+            let mut zip = Cursor::new(vec![]);
+            let report = write_zip_with(&mut zip, project(), options).unwrap();
+            let mut archive = zip::ZipArchive::new(zip).unwrap();
+            let mut project_json = String::new();
+            archive
+                .by_name("project.json")
+                .unwrap()
+                .read_to_string(&mut project_json)
+                .unwrap();
+            assert_eq!(project_json.len(), report.size());
+            let json: serde_json::Value = serde_json::from_str(&project_json).unwrap();
+            assert_eq!(
+                json["targets"][1]["blocks"].as_object().unwrap().len(),
+                5001
+            );
+
+            let md5ext = json["targets"][1]["costumes"][0]["md5ext"]
+                .as_str()
+                .unwrap();
+            let mut costume = vec![];
+            archive
+                .by_name(md5ext)
+                .unwrap()
+                .read_to_end(&mut costume)
+                .unwrap();
+            assert_eq!(costume, std::fs::read("cat.svg").unwrap());
+        }
+    }
+
+    /// 50 samples of silence, mono 16 bit at 22050Hz
+    fn pop_wav() -> Vec<u8> {
+        let mut wav: Vec<u8> = vec![];
+        wav.extend(b"RIFF");
+        wav.extend(136u32.to_le_bytes());
+        wav.extend(b"WAVE");
+        wav.extend(b"fmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes()); // PCM
+        wav.extend(1u16.to_le_bytes()); // mono
+        wav.extend(22050u32.to_le_bytes());
+        wav.extend((22050u32 * 2).to_le_bytes());
+        wav.extend(2u16.to_le_bytes()); // block align
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(100u32.to_le_bytes());
+        wav.extend([0u8; 100]);
+        wav
+    }
+
+    #[test]
+    fn test_sound_metadata() {
+        use crate::project::asset::{SoundBuilder, SoundError};
+
+        let asset = AssetBuilder::new("pop", Resource::new("wav".to_owned(), pop_wav()).unwrap());
+        let sound = SoundBuilder::new(asset).unwrap().build(&mut vec![]);
+        assert_eq!(sound.rate, 22050);
+        assert_eq!(sound.sample_count, 50);
+
+        let ogg = AssetBuilder::new("pop", Resource::new("ogg".to_owned(), vec![]).unwrap());
+        assert_eq!(
+            SoundBuilder::new(ogg),
+            Err(SoundError::UnsupportedFormat("ogg".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_list_and_broadcast_inputs() {
+        use crate::{project::script::ListBuilder, typed_scripting::arg::BroadcastHandle};
+
+        let mut sprite = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+        let log = sprite.declare_sprite_list("log", ListBuilder::new(vec![]));
+        let script = when_flag_clicked()
+            .next(add_to_list(&log, "hi"))
+            .next(broadcast(BroadcastHandle::assume_declared("go")));
+        let sprite = sprite.add_script(script);
+        let project = || {
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(sprite.clone())
+        };
+        let err = project().build(&mut vec![]).unwrap_err();
+        assert_eq!(err.unresolved.len(), 1);
+        assert_eq!(err.unresolved[0].name, "go");
+
+        let built = project().lenient(true).build(&mut vec![]).unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        let (broadcast_id, _) = built["targets"][0]["broadcasts"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap();
+        let (list_id, _) = built["targets"][1]["lists"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap();
+        let blocks = built["targets"][1]["blocks"].as_object().unwrap();
+        let block = |opcode: &str| blocks.values().find(|block| block["opcode"] == opcode);
+        assert_eq!(
+            block("data_addtolist").unwrap()["fields"]["LIST"],
+            serde_json::json!(["log", list_id])
+        );
+        assert_eq!(
+            block("event_broadcast").unwrap()["inputs"]["BROADCAST_INPUT"],
+            serde_json::json!([1, [11, "go", broadcast_id]])
+        );
+    }
+
+    #[test]
+    fn test_scratchblocks_parse() {
+        use crate::scratchblocks::parse::{parse, ParseErrorKind, Scope};
+        use crate::scratchblocks::render::render_stack;
+        use crate::scripting::script_builder::ReferenceKind;
+
+        let source = "\
+when flag clicked
+forever
+    if <(score) > [5]> then
+        add (score) to [scores v] // keep it
+    else
+        change [score v] by (1)
+    end
+end
+
+when this sprite clicked
+say (join [hi ] (username))
+";
+        let scope = Scope::new().sprite_variable("score");
+        let scripts = parse(source, &scope).unwrap();
+        assert_eq!(scripts.len(), 2);
+        let references = scripts[0].references();
+        assert!(references
+            .iter()
+            .any(|r| r.name == "score" && r.kind == ReferenceKind::SpriteVariable));
+        assert!(references
+            .iter()
+            .any(|r| r.name == "scores" && r.kind == ReferenceKind::GlobalList));
+
+        let rendered: Vec<String> = scripts.iter().map(render_stack).collect();
+        assert_eq!(rendered.join("\n"), source);
+
+        let error = parse("when flag clicked\nmove (10) step", &scope).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnknownBlock("move _ step".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_reporter_operators() {
+        use crate::scripting::script_builder::{BlockBuilder, StackBuilder, StackOrValue};
+
+        fn opcode_of(stack: &StackBuilder) -> &str {
+            match &stack.blocks()[0] {
+                BlockBuilder::Normal(block) => block.opcode(),
+                BlockBuilder::VarList(_) => "",
+            }
+        }
+        fn input_of<'a>(stack: &'a StackBuilder, key: &str) -> &'a StackBuilder {
+            let BlockBuilder::Normal(block) = &stack.blocks()[0] else {
+                panic!("not a block");
+            };
+            match block.inputs()[key].values()[0].as_ref() {
+                Some(StackOrValue::Stack(stack)) => stack,
+                _ => panic!("not a reporter"),
+            }
+        }
+
+        let expr = (x_position() * 2 + sprite_var(&SpriteVar::assume_declared("speed")) % 3)
+            .0
+            .into_untyped();
+        assert_eq!(opcode_of(&expr), "operator_add");
+        assert_eq!(opcode_of(input_of(&expr, "NUM1")), "operator_multiply");
+        assert_eq!(opcode_of(input_of(&expr, "NUM2")), "operator_mod");
+
+        let neg = (-y_position()).0.into_untyped();
+        assert_eq!(opcode_of(&neg), "operator_subtract");
+
+        let cond = (!(mouse_down() & x_position().gt(0)) | answer().eq_("yes"))
+            .0
+            .into_untyped();
+        assert_eq!(opcode_of(&cond), "operator_or");
+        assert_eq!(opcode_of(input_of(&cond, "OPERAND1")), "operator_not");
+        assert_eq!(opcode_of(input_of(&cond, "OPERAND2")), "operator_equals");
+    }
+
+    #[test]
+    fn test_lint() {
+        use crate::project::{
+            lint::{LintConfig, PathSegment, Rule, Severity},
+            monitor::MonitorBuilder,
+        };
+        use crate::scripting::{blocks as untyped, script_builder::BlockFieldBuilder};
+        use crate::typed_scripting::script_builder::{AnyTarget, StackBlock};
+
+        let mut stage_inner = TargetBuilder::new("Stage");
+        let ping = stage_inner.declare_broadcast("ping");
+        let mut stage = StageBuilder::new(stage_inner);
+        let score = stage.declare_global_var("score", VariableBuilder::new(0.into()));
+        let flag = when_flag_clicked()
+            .next(set_var_to(&score, 1))
+            .next(broadcast(&ping))
+            .into_untyped()
+            .next(untyped::forever(None))
+            .next(move_steps(10).into_untyped());
+        let stop_all = when_stage_clicked()
+            .into_untyped()
+            .next(untyped::stop(BlockFieldBuilder::new("all".into()), true));
+        // Stacks that the typed API refuses get in with `assume_typed`
+        let stage = stage
+            .add_script(StackBlock::<AnyTarget>::assume_typed(flag))
+            .add_script(StackBlock::<AnyTarget>::assume_typed(
+                y_position().0.into_untyped(),
+            ))
+            .add_script(StackBlock::<AnyTarget>::assume_typed(stop_all));
+        let project = ProjectBuilder::new().set_stage(stage);
+
+        let report = project.lint(&LintConfig::default());
+        let rules: Vec<Rule> = report.findings.iter().map(|finding| finding.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                Rule::BlockAfterCap,
+                Rule::MotionOnStage,
+                Rule::OrphanReporter,
+                Rule::MotionOnStage,
+                Rule::StopHasNext,
+                Rule::UnreceivedBroadcast,
+                Rule::UnreadVariable,
+            ]
+        );
+        assert_eq!(
+            report.findings[0].path.0,
+            vec![PathSegment::Stack(0), PathSegment::Block(3)]
+        );
+        assert_eq!(
+            report.findings[4].path.0,
+            vec![PathSegment::Stack(2), PathSegment::Block(1)]
+        );
+        assert!(report.has_errors());
+
+        let relaxed = LintConfig::default()
+            .severity(Rule::BlockAfterCap, Severity::Warning)
+            .severity(Rule::StopHasNext, Severity::Warning)
+            .severity(Rule::MotionOnStage, Severity::Off);
+        assert!(!project.lint(&relaxed).has_errors());
+
+        // A monitored variable is read by the monitor
+        let project = project.add_monitor(MonitorBuilder::global_variable("score"));
+        let report = project.lint(&LintConfig::default());
+        assert!(report
+            .findings
+            .iter()
+            .all(|finding| finding.rule != Rule::UnreadVariable));
+    }
+
+    #[test]
+    fn test_target_scope() {
+        let mut cat = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+        let num = cat.declare_sprite_var("num", VariableBuilder::new(0.into()));
+        // `StageBuilder::add_script` doesn't accept these, only the untyped path does
+        let walk = when_flag_clicked().next(forever(Some(move_steps(10))));
+        let count = when_stage_clicked()
+            .into_untyped()
+            .next(set_var_to(&num, 1).into_untyped());
+        let project = || {
+            let stage = TargetBuilder::new("Stage")
+                .add_block_stack(walk.clone().into_untyped())
+                .add_block_stack(count.clone());
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(stage))
+                .add_sprite(cat.clone().add_script(walk.clone()))
+        };
+
+        let err = project().build(&mut vec![]).unwrap_err();
+        let out_of_scope: Vec<_> = err
+            .out_of_scope
+            .iter()
+            .map(|o| (o.opcode.as_str(), o.target.as_str()))
+            .collect();
+        assert_eq!(
+            out_of_scope,
+            vec![
+                ("motion_movesteps", "Stage"),
+                ("data_setvariableto", "Stage")
+            ]
+        );
+        let err = project().lenient(true).build(&mut vec![]).unwrap_err();
+        assert_eq!(err.out_of_scope.len(), 2);
+    }
+
+    #[test]
+    fn test_asset_sprite_handles() {
+        use crate::scripting::{menu::GoToTarget, script_builder::ReferenceKind};
+
+        let svg = || Resource::load("cat.svg").unwrap();
+        let mut stage = StageBuilder::new(TargetBuilder::new("Stage"));
+        let night = stage.declare_backdrop(CostumeBuilder::new(AssetBuilder::new("night", svg())));
+        let mut dog = SpriteBuilder::<Dog>::new(TargetBuilder::new("Dog"));
+        let bark = dog.declare_costume(CostumeBuilder::new(AssetBuilder::new("bark", svg())));
+        dog.declare_sprite_var("hp", VariableBuilder::new(10.into()));
+
+        let project = |hp: &str, costume| {
+            let mut project = ProjectBuilder::new();
+            let dog = project.declare_sprite(dog.clone());
+            let mut cat = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+            let meow = cat.declare_costume(CostumeBuilder::new(AssetBuilder::new("meow", svg())));
+            let seen = cat.declare_sprite_var("seen", VariableBuilder::new(0.into()));
+            let script = when_flag_clicked()
+                .next(switch_costume_to(if costume { &meow } else { &bark }))
+                .next(switch_costume_to(sprite_var(&seen)))
+                .next(switch_backdrop_to(&night))
+                .next(switch_backdrop_to(if costume { "night" } else { "day" }))
+                .next(go_to(&dog))
+                .next(go_to(GoToTarget::Mouse))
+                .next(set_var_to(&seen, var_of(hp, &dog)))
+                .next(set_var_to(&seen, var_of("x position", &dog)));
+            project.declare_sprite(cat.add_script(script));
+            project.set_stage(stage.clone())
+        };
+        assert!(project("hp", true).build(&mut vec![]).is_ok());
+
+        let err = project("mp", false).build(&mut vec![]).unwrap_err();
+        let unresolved: Vec<_> = err
+            .unresolved
+            .iter()
+            .map(|u| {
+                (
+                    u.kind,
+                    u.name.as_str(),
+                    u.target.as_str(),
+                    u.owner.as_deref(),
+                )
+            })
+            .collect();
+        assert!(unresolved.contains(&(ReferenceKind::Costume, "bark", "Cat", None)));
+        assert!(unresolved.contains(&(ReferenceKind::SpriteVariable, "mp", "Cat", Some("Dog"))));
+        assert!(unresolved.contains(&(ReferenceKind::Backdrop, "day", "Cat", None)));
+        assert_eq!(unresolved.len(), 3);
+    }
+
+    #[test]
+    fn test_interpreter() {
+        use crate::interpreter::{Interpreter, Value};
+        use crate::project::script::ListBuilder;
+
+        let mut target = TargetBuilder::new("Cat");
+        let done = target.declare_broadcast("done");
+        let mut sprite = SpriteBuilder::<Cat>::new(target);
+        let count = sprite.declare_sprite_var("count", VariableBuilder::new(0.into()));
+        let log = sprite.declare_sprite_list("log", ListBuilder::new(vec![]));
+        let main = when_flag_clicked()
+            .next(repeat(10, Some(change_var_by(&count, 1))))
+            .next(repeat(5, Some(change_x_by(10))))
+            .next(add_to_list(&log, sprite_var(&count)))
+            .next(broadcast_and_wait(&done))
+            .next(add_to_list(&log, "after"));
+        let receiver = when_broadcast_received(&done)
+            .next(wait(1))
+            .next(add_to_list(&log, "received"));
+        let project = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(sprite.add_script(main).add_script(receiver));
+
+        let mut interpreter = Interpreter::from_builder(project).unwrap();
+        interpreter.green_flag();
+        // A loop that doesn't redraw runs to the end in a frame, one that moves a sprite runs an iteration
+        interpreter.run(1);
+        assert_eq!(
+            interpreter.variable("Cat", "count"),
+            Some(&Value::Number(10.))
+        );
+        assert_eq!(interpreter.sprite("Cat").unwrap().x, 10.);
+        interpreter.run(3);
+        assert_eq!(interpreter.sprite("Cat").unwrap().x, 40.);
+        assert_eq!(interpreter.list("Cat", "log"), Some(&[][..]));
+        interpreter.run(20);
+        assert_eq!(
+            interpreter.list("Cat", "log"),
+            Some(&[Value::Number(10.)][..])
+        );
+        assert!(interpreter.run_until_idle(60));
+        assert_eq!(
+            interpreter.list("Cat", "log"),
+            Some(&[Value::Number(10.), "received".into(), "after".into()][..])
+        );
+    }
+
+    /// A stack for every builder in [`crate::scripting::blocks`].
+    /// Menus are in the input of the block they're for.
+    #[rustfmt::skip]
+    fn every_builder() -> Vec<crate::scripting::script_builder::StackBuilder> {
+        use crate::scripting::{
+            blocks as b,
+            procedure::ProcedureBuilder,
+            script_builder::{BlockFieldBuilder as BFB, BlockInputBuilder as BIB},
+        };
+        use crate::uid::UidGenerator;
+        use rs_sb3::block::BlockInputValue;
+
+        let f = |value: &str| BFB::new(value.to_owned());
+        let n = || BIB::value(BlockInputValue::Number { value: 1.into() });
+        let t = || {
+            BIB::value(BlockInputValue::String {
+                value: "a".to_owned().into(),
+            })
+        };
+        let c = || {
+            BIB::value(BlockInputValue::Color {
+                value: "#ff0000".to_owned().into(),
+            })
+        };
+        let cond = || BIB::stack(b::mouse_down());
+        let m = BIB::stack;
+        let jump = ProcedureBuilder::new("jump")
+            .string_number_argument("height")
+            .boolean_argument("fast");
+        let jump_context = jump.context(&mut UidGenerator::new(0), "jump");
+
+        vec![
+            b::wait(n()), b::repeat(n(), Some(m(b::show()))), b::forever(None), b::if_(cond(), None),
+            b::if_else(cond(), None, None), b::wait_until(cond()), b::repeat_until(cond(), None),
+            b::stop(f("all"), false), b::when_i_start_as_a_clone(), b::delete_this_clone(),
+            b::create_clone_of(m(b::create_clone_of_menu(f("_myself_")))),
+            b::when_flag_clicked(), b::when_key_pressed(f("space")), b::when_this_sprite_clicked(),
+            b::when_backdrop_switches_to(f("backdrop1")), b::when_greater_than(f("TIMER"), n()),
+            b::when_broadcast_received(f("go")), b::broadcast(BIB::broadcast("go")),
+            b::broadcast_and_wait(BIB::broadcast("go")),
+            b::think(t()), b::think_for_secs(t(), n()), b::say(t()), b::say_for_secs(t(), n()),
+            b::switch_costume_to(m(b::costume_menu(f("costume1")))), b::next_costume(),
+            b::switch_backdrop_to(m(b::backdrop_menu(f("backdrop1")))), b::next_backdrop(),
+            b::change_size_by(n()), b::set_size_to(n()), b::clear_graphic_effects(), b::show(), b::hide(),
+            b::change_looks_effect_by(f("COLOR"), n()), b::set_looks_effect_to(f("GHOST"), n()),
+            b::go_to_layer(f("front")), b::change_layer(f("forward"), n()),
+            b::costume(f("number")), b::backdrop(f("name")), b::size(),
+            b::move_steps(n()), b::turn_right(n()), b::turn_left(n()), b::goto_xy(n(), n()),
+            b::go_to(m(b::go_to_menu(f("_random_")))), b::glide_to(n(), m(b::glide_to_menu(f("_mouse_")))),
+            b::glide_to_xy(n(), n(), n()), b::point_in_direction(n()),
+            b::point_towards(m(b::point_towards_menu(f("_mouse_")))), b::set_x(n()), b::set_y(n()),
+            b::change_x_by(n()), b::change_y_by(n()), b::if_on_edge_bounce(),
+            b::set_rotation_style(f("all around")), b::direction(), b::x_position(), b::y_position(),
+            b::add(n(), n()), b::sub(n(), n()), b::mul(n(), n()), b::div(n(), n()), b::random(n(), n()),
+            b::less_than(t(), t()), b::greater_than(t(), t()), b::equals(t(), t()), b::and(cond(), cond()),
+            b::or(cond(), cond()), b::not(cond()), b::join(t(), t()), b::letter_of(n(), t()),
+            b::length_of(t()), b::contains(t(), t()), b::modulo(n(), n()), b::round(n()),
+            b::math_op(f("abs"), n()),
+            b::touching(m(b::touching_menu(f("_edge_")))), b::touching_color(c()),
+            b::color_touching_color(c(), c()), b::distance_to(m(b::distance_to_menu(f("_mouse_")))),
+            b::ask_and_wait(t()), b::answer(), b::key_pressed(m(b::key_menu(f("a")))), b::mouse_down(),
+            b::mouse_x(), b::set_drag_mode(f("draggable")), b::loudness(), b::timer(), b::reset_timer(),
+            b::var_of(f("x position"), m(b::var_of_object_menu(f("Cat")))),
+            b::current_datetime(f("YEAR")), b::days_since_2000(), b::username(),
+            b::play_sound_until_done(m(b::sound_menu(f("pop")))), b::play_sound(m(b::sound_menu(f("pop")))),
+            b::stop_all_sound(), b::change_sound_effect_by(f("PITCH"), n()),
+            b::set_sound_effect_to(f("PAN"), n()), b::clear_sound_effects(), b::set_volume_to(n()),
+            b::change_volume_by(n()), b::volume(),
+            b::set_var_to(f("v"), t()), b::change_var_by(f("v"), n()), b::show_var(f("v")), b::hide_var(f("v")),
+            b::add_to_list(f("l"), t()), b::delete_in_list(f("l"), n()), b::delete_all_in_list(f("l")),
+            b::insert_in_list(f("l"), n(), t()), b::replace_in_list(f("l"), n(), t()),
+            b::item_in_list(f("l"), n()), b::count_of_item_in_list(f("l"), t()), b::length_of_list(f("l")),
+            b::list_contains(f("l"), t()), b::show_list(f("l")), b::hide_list(f("l")),
+            b::argument_string_number(f("a")), b::argument_boolean(f("b")),
+            b::erase_all(), b::stamp(), b::pen_down(), b::pen_up(), b::set_pen_color_to_color(c()),
+            b::change_pen_color_param_by(m(b::pen_color_param_menu(f("color"))), n()),
+            b::set_pen_color_param_to(m(b::pen_color_param_menu(f("saturation"))), n()),
+            b::change_pen_size_by(n()), b::set_pen_size_to(n()), b::set_pen_shade_to(n()),
+            b::change_pen_shade_by(n()), b::set_pen_hue_to(n()), b::change_pen_hue_by(n()),
+            b::play_drum_for_beats(m(b::drum_menu(f("1"))), n()), b::rest_for_beats(n()),
+            b::play_note_for_beats(m(b::note_menu(f("60"))), n()),
+            b::set_instrument_to(m(b::instrument_menu(f("1")))), b::set_tempo_to(n()),
+            b::change_tempo_by(n()), b::tempo(),
+            b::while_(cond(), None), b::for_each(f("i"), n(), Some(m(b::show()))), b::counter(),
+            b::incr_counter(), b::clear_counter(), b::all_at_once(None), b::hide_all_sprites(),
+            b::change_stretch_by(n()), b::set_stretch_to(n()), b::scroll_right(n()), b::scroll_up(n()),
+            b::align_scene(f("middle")), b::x_scroll(), b::y_scroll(), b::loud(), b::user_id(),
+            b::when_stage_clicked(), b::when_touching(m(b::when_touching_menu(f("_edge_")))),
+            b::broadcast(m(b::broadcast_menu(f("go")))), b::mouse_y(),
+            b::switch_backdrop_to_and_wait(m(b::backdrop_menu(f("backdrop1")))),
+            b::sprite_var("v"), b::sprite_list("l"), b::global_var("v"), b::global_list("l"),
+            b::call_procedure(&jump.handle(), vec![t(), cond()]), jump.build(&jump_context),
+        ]
+    }
+
+    #[test]
+    fn test_block_spec() {
+        use crate::scripting::{
+            blocks as b,
+            script_builder::BlockInputBuilder as BIB,
+            spec::{spec_of, validate, validate_stack, InputKind, Mismatch, Shape},
+        };
+        use crate::{opcode::PrimaryOpCode, scripting::script_builder::BlockBuilder};
+        use rs_sb3::block::BlockInputValue;
+
+        let n = || BIB::value(BlockInputValue::Number { value: 1.into() });
+
+        for stack in &every_builder() {
+            if let Err(errors) = validate_stack(stack) {
+                panic!("{}", errors[0]);
+            }
+        }
+
+        let repeat_until = spec_of(PrimaryOpCode::control_repeat_until).unwrap();
+        assert_eq!(repeat_until.shape, Shape::Stack);
+        assert!(repeat_until.palette);
+        assert!(!spec_of(PrimaryOpCode::control_for_each).unwrap().palette);
+        assert_eq!(
+            spec_of(PrimaryOpCode::control_forever).unwrap().shape,
+            Shape::Cap
+        );
+
+        let wrong = b::if_else(n(), Some(n()), None);
+        let BlockBuilder::Normal(block) = &wrong.blocks()[0] else {
+            unreachable!()
+        };
+        assert_eq!(
+            validate(block).unwrap_err().mismatches,
+            vec![
+                Mismatch::WrongInput {
+                    input: "CONDITION".to_owned(),
+                    expected: InputKind::Boolean,
+                },
+                Mismatch::WrongInput {
+                    input: "SUBSTACK".to_owned(),
+                    expected: InputKind::Substack,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_palette_coverage() {
+        use crate::{
+            opcode::PrimaryOpCode,
+            scripting::{
+                script_builder::{BlockBuilder, StackBuilder, StackOrValue},
+                spec::spec_of,
+            },
+        };
+        use rs_sb3::block::ListOrVariable;
+        use std::collections::HashSet;
+
+        fn collect_opcodes(stack: &StackBuilder, opcodes: &mut HashSet<String>) {
+            for block in stack.blocks() {
+                match block {
+                    BlockBuilder::Normal(block) => {
+                        opcodes.insert(block.opcode().to_owned());
+                        for input in block.inputs().values() {
+                            for value in input.values() {
+                                if let Some(StackOrValue::Stack(stack)) = value {
+                                    collect_opcodes(stack, opcodes);
+                                }
+                            }
+                        }
+                    }
+                    BlockBuilder::VarList(var_list) => {
+                        let opcode = match var_list.kind() {
+                            ListOrVariable::Variable => "data_variable",
+                            ListOrVariable::List => "data_listcontents",
+                        };
+                        opcodes.insert(opcode.to_owned());
+                    }
+                }
+            }
+        }
+
+        let mut built = HashSet::new();
+        for stack in &every_builder() {
+            collect_opcodes(stack, &mut built);
+        }
+        let missing: Vec<_> = PrimaryOpCode::ALL
+            .iter()
+            .filter(|opcode| spec_of(**opcode).is_some_and(|spec| spec.palette))
+            .filter(|opcode| !built.contains(&opcode.to_string()))
+            .collect();
+        assert!(missing.is_empty(), "no builder for {missing:?}");
+    }
+
+    /// Scripts of `target` in a `project.json` without ids and positions, sorted.
+    /// Argument ids of custom blocks are replaced by the argument's name.
+    fn canonical_scripts(project: &serde_json::Value, target: &str) -> Vec<String> {
+        let target = project["targets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["name"] == target)
+            .unwrap();
+        let blocks = target["blocks"].as_object().unwrap();
+        let mut argument_names = std::collections::HashMap::new();
+        for block in blocks.values() {
+            if block["opcode"] == "procedures_prototype" {
+                let mutation = &block["mutation"];
+                let ids = json_list(&mutation["argumentids"]);
+                let names = json_list(&mutation["argumentnames"]);
+                for (id, name) in ids.iter().zip(names.iter()) {
+                    argument_names.insert(json_text(id), json_text(name));
+                }
+            }
+        }
+        let mut scripts: Vec<String> = blocks
+            .values()
+            .filter(|block| block.is_array() || block["topLevel"] == true)
+            .map(|block| canonical_block(block, blocks, &argument_names).to_string())
+            .collect();
+        scripts.sort();
+        scripts
+    }
+
+    fn canonical_block(
+        block: &serde_json::Value,
+        blocks: &serde_json::Map<String, serde_json::Value>,
+        argument_names: &std::collections::HashMap<String, String>,
+    ) -> serde_json::Value {
+        use serde_json::{json, Value as Json};
+
+        if block.is_array() {
+            return canonical_primitive(block);
+        }
+        let rename = |key: &str| argument_names.get(key).cloned().unwrap_or(key.to_owned());
+        let inputs: serde_json::Map<String, Json> = block["inputs"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, input)| {
+                let input = input.as_array().unwrap();
+                let mut canonical = vec![input[0].clone()];
+                canonical.extend(input[1..].iter().map(|value| match value {
+                    Json::String(uid) => canonical_block(&blocks[uid], blocks, argument_names),
+                    Json::Null => Json::Null,
+                    primitive => canonical_primitive(primitive),
+                }));
+                (rename(key), Json::Array(canonical))
+            })
+            .collect();
+        let fields: serde_json::Map<String, Json> = block["fields"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, field)| (key.clone(), Json::String(json_text(&field[0]))))
+            .collect();
+        let mutation: serde_json::Map<String, Json> = block["mutation"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| !matches!(key.as_str(), "tagName" | "children"))
+            .map(|(key, value)| {
+                let value = match key.as_str() {
+                    "argumentids" => json_list(value)
+                        .iter()
+                        .map(|id| Json::String(rename(&json_text(id))))
+                        .collect(),
+                    "argumentnames" | "argumentdefaults" => json_list(value)
+                        .iter()
+                        .map(|value| Json::String(json_text(value)))
+                        .collect(),
+                    _ => Json::String(json_text(value)),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        let next = match &block["next"] {
+            Json::String(uid) => canonical_block(&blocks[uid], blocks, argument_names),
+            _ => Json::Null,
+        };
+        json!({
+            "opcode": block["opcode"],
+            "shadow": block["shadow"],
+            "inputs": inputs,
+            "fields": fields,
+            "mutation": mutation,
+            "next": next,
+        })
+    }
+
+    /// `[4, 10]` is `[4, "10"]`. Ids and positions of broadcasts, variables and lists are dropped.
+    fn canonical_primitive(primitive: &serde_json::Value) -> serde_json::Value {
+        let primitive = primitive.as_array().unwrap();
+        serde_json::json!([primitive[0], json_text(&primitive[1])])
+    }
+
+    /// Mutations store lists as json in a string
+    fn json_list(json: &serde_json::Value) -> Vec<serde_json::Value> {
+        match json {
+            serde_json::Value::String(text) => serde_json::from_str(text).unwrap(),
+            json => json.as_array().cloned().unwrap_or_default(),
+        }
+    }
+
+    fn json_text(json: &serde_json::Value) -> String {
+        match json {
+            serde_json::Value::String(text) => text.clone(),
+            serde_json::Value::Number(number) => match number.as_f64() {
+                Some(float) if float.fract() == 0. => format!("{}", float as i64),
+                _ => number.to_string(),
+            },
+            serde_json::Value::Null => String::new(),
+            json => json.to_string(),
+        }
+    }
+
+    /// Every blocks in `scripting::blocks` against `fixtures/conformance`,
+    /// which are laid out like the `project.json` the Scratch 3 editor saves.
+    /// To cover a new block, make it in the editor with its default inputs and add it to both.
+    /// Blocks hidden from the palette are in `hidden.json`, as the editor makes them when it loads a Scratch 2 project.
+    #[test]
+    fn test_conformance() {
+        use crate::project::script::ListBuilder;
+        use crate::scripting::{
+            blocks as b,
+            procedure::ProcedureBuilder,
+            script_builder::{
+                BlockFieldBuilder as BFB, BlockInputBuilder as BIB, FieldKind, StackBuilder,
+            },
+        };
+        use rs_sb3::block::BlockInputValue;
+
+        let f = |value: &str| BFB::new(value.to_owned());
+        let var = || BFB::new_with_kind("my variable".to_owned(), FieldKind::GlobalVariable);
+        let list = || BFB::new_with_kind("my list".to_owned(), FieldKind::GlobalList);
+        let message = || BFB::new_with_kind("message1".to_owned(), FieldKind::Broadcast);
+        let num = |value: &str| {
+            BIB::value(BlockInputValue::Number {
+                value: value.into(),
+            })
+        };
+        let pos = |value: &str| {
+            BIB::value(BlockInputValue::PositiveNumber {
+                value: value.into(),
+            })
+        };
+        let whole = |value: &str| {
+            BIB::value(BlockInputValue::PositiveInteger {
+                value: value.into(),
+            })
+        };
+        let int = |value: &str| {
+            BIB::value(BlockInputValue::Integer {
+                value: value.into(),
+            })
+        };
+        let angle = |value: &str| {
+            BIB::value(BlockInputValue::Angle {
+                value: value.into(),
+            })
+        };
+        let color = |value: &str| {
+            BIB::value(BlockInputValue::Color {
+                value: value.into(),
+            })
+        };
+        let text = |value: &str| {
+            BIB::value(BlockInputValue::String {
+                value: value.into(),
+            })
+        };
+        let m = BIB::stack;
+        let wait = || b::wait(pos("1"));
+        let mouse_down = || m(b::mouse_down());
+
+        let jump = ProcedureBuilder::new("jump")
+            .string_number_argument("height")
+            .label("times?")
+            .boolean_argument("fast");
+        let jump_handle = jump.handle();
+        let jump = jump.body(b::if_(
+            m(b::argument_boolean(f("fast"))),
+            Some(m(b::stop(f("this script"), false))),
+        ));
+
+        #[rustfmt::skip]
+        let fixtures: Vec<(&str, Vec<StackBuilder>, Vec<ProcedureBuilder>)> = vec![
+            ("control", vec![
+                b::when_i_start_as_a_clone()
+                    .next(wait())
+                    .next(b::repeat(whole("10"), Some(m(wait()))))
+                    .next(b::forever(Some(m(b::if_(
+                        mouse_down(),
+                        Some(m(b::wait_until(mouse_down()))),
+                    ))))),
+                b::if_else(
+                    mouse_down(),
+                    Some(m(b::repeat_until(
+                        mouse_down(),
+                        Some(m(b::create_clone_of(m(b::create_clone_of_menu(f("_myself_")))))),
+                    ))),
+                    Some(m(b::stop(f("other scripts in sprite"), true))),
+                )
+                .next(b::delete_this_clone()),
+                b::stop(f("all"), false),
+            ], vec![]),
+            ("events", vec![
+                b::when_flag_clicked()
+                    .next(b::broadcast(BIB::broadcast("message1")))
+                    .next(b::broadcast_and_wait(BIB::broadcast("message1"))),
+                b::when_key_pressed(f("space")),
+                b::when_this_sprite_clicked(),
+                b::when_backdrop_switches_to(f("backdrop1")),
+                b::when_greater_than(f("LOUDNESS"), num("10")),
+                b::when_broadcast_received(message()),
+                b::broadcast(m(b::broadcast_menu(message()))),
+            ], vec![]),
+            ("looks", vec![
+                b::think(text("Hmm..."))
+                    .next(b::think_for_secs(text("Hmm..."), num("2")))
+                    .next(b::say(text("Hello!")))
+                    .next(b::say_for_secs(text("Hello!"), num("2")))
+                    .next(b::switch_costume_to(m(b::costume_menu(f("costume2")))))
+                    .next(b::next_costume())
+                    .next(b::switch_backdrop_to(m(b::backdrop_menu(f("backdrop1")))))
+                    .next(b::next_backdrop())
+                    .next(b::change_size_by(num("10")))
+                    .next(b::set_size_to(num("100")))
+                    .next(b::change_looks_effect_by(f("COLOR"), num("25")))
+                    .next(b::set_looks_effect_to(f("COLOR"), num("0")))
+                    .next(b::clear_graphic_effects())
+                    .next(b::show())
+                    .next(b::hide())
+                    .next(b::go_to_layer(f("front")))
+                    .next(b::change_layer(f("forward"), int("1"))),
+                b::say(m(b::global_var("my variable"))),
+                b::costume(f("number")),
+                b::backdrop(f("number")),
+                b::size(),
+            ], vec![]),
+            ("motion", vec![
+                b::move_steps(num("10"))
+                    .next(b::turn_right(num("15")))
+                    .next(b::turn_left(num("15")))
+                    .next(b::go_to(m(b::go_to_menu(f("_random_")))))
+                    .next(b::goto_xy(num("0"), num("0")))
+                    .next(b::glide_to(num("1"), m(b::glide_to_menu(f("_random_")))))
+                    .next(b::glide_to_xy(num("1"), num("0"), num("0")))
+                    .next(b::point_in_direction(angle("90")))
+                    .next(b::point_towards(m(b::point_towards_menu(f("_mouse_")))))
+                    .next(b::change_x_by(num("10")))
+                    .next(b::set_x(num("0")))
+                    .next(b::change_y_by(num("10")))
+                    .next(b::set_y(num("0")))
+                    .next(b::if_on_edge_bounce())
+                    .next(b::set_rotation_style(f("left-right"))),
+                b::move_steps(m(b::x_position())).next(b::go_to(m(b::username()))),
+                b::x_position(),
+                b::y_position(),
+                b::direction(),
+            ], vec![]),
+            ("operators", vec![
+                b::add(num(""), num("")),
+                b::sub(num(""), num("")),
+                b::mul(num(""), num("")),
+                b::div(num(""), num("")),
+                b::random(num("1"), num("10")),
+                b::and(
+                    m(b::less_than(text(""), text("50"))),
+                    m(b::not(m(b::greater_than(text(""), text("50"))))),
+                ),
+                b::or(m(b::equals(text(""), text("50"))), mouse_down()),
+                b::join(text("apple "), text("banana")),
+                b::letter_of(whole("1"), text("apple")),
+                b::length_of(text("apple")),
+                b::contains(text("apple"), text("a")),
+                b::modulo(num(""), num("")),
+                b::round(num("")),
+                b::math_op(f("abs"), num("")),
+            ], vec![]),
+            ("sensing", vec![
+                b::ask_and_wait(text("What's your name?"))
+                    .next(b::set_drag_mode(f("draggable")))
+                    .next(b::reset_timer()),
+                b::touching(m(b::touching_menu(f("_mouse_")))),
+                b::touching_color(color("#d5c21a")),
+                b::color_touching_color(color("#3eb6d2"), color("#9f5ce6")),
+                b::distance_to(m(b::distance_to_menu(f("_mouse_")))),
+                b::answer(),
+                b::key_pressed(m(b::key_menu(f("space")))),
+                b::mouse_down(),
+                b::mouse_x(),
+                b::loudness(),
+                b::timer(),
+                b::var_of(f("backdrop #"), m(b::var_of_object_menu(f("_stage_")))),
+                b::current_datetime(f("YEAR")),
+                b::days_since_2000(),
+                b::username(),
+                b::mouse_y(),
+            ], vec![]),
+            ("sound", vec![
+                b::when_flag_clicked()
+                    .next(b::play_sound_until_done(m(b::sound_menu(f("Meow")))))
+                    .next(b::play_sound(m(b::sound_menu(f("Meow")))))
+                    .next(b::stop_all_sound())
+                    .next(b::change_sound_effect_by(f("PITCH"), num("10")))
+                    .next(b::set_sound_effect_to(f("PITCH"), num("100")))
+                    .next(b::clear_sound_effects())
+                    .next(b::change_volume_by(num("-10")))
+                    .next(b::set_volume_to(num("100"))),
+                b::volume(),
+            ], vec![]),
+            ("data", vec![
+                b::set_var_to(var(), text("0"))
+                    .next(b::change_var_by(var(), num("1")))
+                    .next(b::show_var(var()))
+                    .next(b::hide_var(var()))
+                    .next(b::add_to_list(list(), text("thing")))
+                    .next(b::delete_in_list(list(), int("1")))
+                    .next(b::delete_all_in_list(list()))
+                    .next(b::insert_in_list(list(), int("1"), text("thing")))
+                    .next(b::replace_in_list(list(), int("1"), text("thing")))
+                    .next(b::show_list(list()))
+                    .next(b::hide_list(list())),
+                b::global_var("my variable"),
+                b::global_list("my list"),
+                b::sprite_var("my sprite variable"),
+                b::sprite_list("my sprite list"),
+                b::item_in_list(list(), int("1")),
+                b::count_of_item_in_list(list(), text("thing")),
+                b::length_of_list(list()),
+                b::list_contains(list(), text("thing")),
+            ], vec![]),
+            ("procedures", vec![
+                b::argument_string_number(f("height")),
+                b::when_flag_clicked()
+                    .next(b::call_procedure(&jump_handle, vec![text("10"), mouse_down()]))
+                    .next(b::call_procedure(&jump_handle, vec![m(b::answer()), BIB::new()])),
+            ], vec![jump]),
+            ("pen", vec![
+                b::when_flag_clicked()
+                    .next(b::erase_all())
+                    .next(b::stamp())
+                    .next(b::pen_down())
+                    .next(b::pen_up())
+                    .next(b::set_pen_color_to_color(color("#c6338a")))
+                    .next(b::change_pen_color_param_by(m(b::pen_color_param_menu(f("color"))), num("10")))
+                    .next(b::set_pen_color_param_to(m(b::pen_color_param_menu(f("color"))), num("50")))
+                    .next(b::change_pen_size_by(num("1")))
+                    .next(b::set_pen_size_to(num("1")))
+                    .next(b::set_pen_shade_to(num("50")))
+                    .next(b::change_pen_shade_by(num("10")))
+                    .next(b::set_pen_hue_to(num("0")))
+                    .next(b::change_pen_hue_by(num("10"))),
+            ], vec![]),
+            ("music", vec![
+                b::when_flag_clicked()
+                    .next(b::play_drum_for_beats(m(b::drum_menu(f("1"))), num("0.25")))
+                    .next(b::rest_for_beats(num("0.25")))
+                    .next(b::play_note_for_beats(m(b::note_menu(f("60"))), num("0.25")))
+                    .next(b::set_instrument_to(m(b::instrument_menu(f("1")))))
+                    .next(b::set_tempo_to(num("60")))
+                    .next(b::change_tempo_by(num("20"))),
+                b::tempo(),
+            ], vec![]),
+            ("hidden", vec![
+                b::when_touching(m(b::when_touching_menu(f("_mouse_"))))
+                    .next(b::while_(mouse_down(), Some(m(b::incr_counter()))))
+                    .next(b::for_each(var(), text("10"), Some(m(b::clear_counter()))))
+                    .next(b::all_at_once(Some(m(b::hide_all_sprites()))))
+                    .next(b::change_stretch_by(num("10")))
+                    .next(b::set_stretch_to(num("100")))
+                    .next(b::scroll_right(num("10")))
+                    .next(b::scroll_up(num("10")))
+                    .next(b::align_scene(f("bottom-left"))),
+                b::counter(),
+                b::x_scroll(),
+                b::y_scroll(),
+                b::loud(),
+                b::user_id(),
+            ], vec![]),
+        ];
+        let stage_stacks = |name: &str| match name {
+            "events" => vec![b::when_stage_clicked()],
+            "looks" => vec![b::switch_backdrop_to_and_wait(m(b::backdrop_menu(f(
+                "backdrop1",
+            ))))],
+            _ => vec![],
+        };
+
+        for (name, stacks, procedures) in fixtures {
+            let stage = stage_stacks(name)
+                .into_iter()
+                .fold(TargetBuilder::new("Stage"), |stage, stack| {
+                    stage.add_block_stack(stack)
+                });
+            let mut stage = StageBuilder::new(stage.add_broadcast("message1"));
+            stage.declare_global_var("my variable", VariableBuilder::new(0.into()));
+            stage.declare_global_list("my list", ListBuilder::new(vec![]));
+            let sprite = stacks
+                .into_iter()
+                .fold(TargetBuilder::new("Sprite1"), |sprite, stack| {
+                    sprite.add_block_stack(stack)
+                });
+            let sprite = procedures
+                .into_iter()
+                .fold(sprite, |sprite, procedure| sprite.add_procedure(procedure));
+            let mut sprite = SpriteBuilder::<Cat>::new(sprite);
+            sprite.declare_sprite_var("my sprite variable", VariableBuilder::new(0.into()));
+            sprite.declare_sprite_list("my sprite list", ListBuilder::new(vec![]));
+            let mut built = ProjectBuilder::new()
+                .set_stage(stage)
+                .add_sprite(sprite)
+                .build(&mut vec![])
+                .unwrap();
+            // The fixtures are saved by the editor, which writes broadcast menus as primitives
+            crate::export::compact::compact_primitives(&mut built);
+            let built = serde_json::to_value(built).unwrap();
+
+            let path = format!("fixtures/conformance/{name}.json");
+            let fixture: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            for target in ["Stage", "Sprite1"] {
+                let expected = canonical_scripts(&fixture, target);
+                let actual = canonical_scripts(&built, target);
+                let missing: Vec<_> = expected.iter().filter(|s| !actual.contains(s)).collect();
+                let unexpected: Vec<_> = actual.iter().filter(|s| !expected.contains(s)).collect();
+                assert!(
+                    missing.is_empty() && unexpected.is_empty(),
+                    "{path} {target}\nnot built: {missing:#?}\nnot in fixture: {unexpected:#?}"
+                );
+                assert_eq!(expected.len(), actual.len(), "{path} {target}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_procedure_calls() {
+        use crate::project::validation::{
+            DuplicateArgument, DuplicateProcedure, WrongArgumentCount,
+        };
+        use crate::scripting::{
+            blocks as b, procedure::ProcedureBuilder, script_builder::BlockInputBuilder as BIB,
+        };
+        use rs_sb3::block::BlockInputValue;
+
+        let m = BIB::stack;
+        let jump = || {
+            ProcedureBuilder::new("jump")
+                .string_number_argument("height")
+                .boolean_argument("fast")
+        };
+        let height = || BIB::value(BlockInputValue::Number { value: 10.into() });
+        let build = |procedure: ProcedureBuilder, arguments: Vec<BIB>| {
+            let call = b::call_procedure(&procedure.handle(), arguments);
+            let sprite = TargetBuilder::new("Cat")
+                .add_procedure(procedure)
+                .add_block_stack(b::when_flag_clicked().next(call));
+            ProjectBuilder::new()
+                .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+                .add_sprite(SpriteBuilder::<Cat>::new(sprite))
+                .build(&mut vec![])
+        };
+        assert!(build(jump(), vec![height(), BIB::new()]).is_ok());
+        let wrong_count = |given| {
+            vec![WrongArgumentCount {
+                proccode: "jump %s %b".to_owned(),
+                expected: 2,
+                given,
+                target: "Cat".to_owned(),
+            }]
+        };
+        let err = build(jump(), vec![height()]).unwrap_err();
+        assert_eq!(err.wrong_argument_counts, wrong_count(1));
+        let err = build(jump(), vec![height(), BIB::new(), height()]).unwrap_err();
+        assert_eq!(err.wrong_argument_counts, wrong_count(3));
+
+        // A `%s` in a label isn't an argument
+        let percent = ProcedureBuilder::new("costs %s")
+            .boolean_argument("fast")
+            .string_number_argument("height");
+        let handle = percent.handle();
+        assert!(build(percent, vec![m(b::mouse_down()), m(b::answer())]).is_ok());
+        let call = b::call_procedure(&handle, vec![m(b::mouse_down()), m(b::answer())]);
+        let crate::scripting::script_builder::BlockBuilder::Normal(call) = &call.blocks()[0] else {
+            unreachable!()
+        };
+        // The string argument covers an empty text and the boolean one doesn't
+        assert_eq!(call.inputs()["height"].values().len(), 2);
+        assert_eq!(call.inputs()["fast"].values().len(), 1);
+
+        let twice = ProcedureBuilder::new("jump")
+            .string_number_argument("height")
+            .string_number_argument("height");
+        let err = build(twice, vec![height(), height()]).unwrap_err();
+        assert_eq!(
+            err.duplicate_arguments,
+            vec![DuplicateArgument {
+                proccode: "jump %s %s".to_owned(),
+                argument: "height".to_owned(),
+                target: "Cat".to_owned(),
+            }]
+        );
+
+        let sprite = TargetBuilder::new("Cat")
+            .add_procedure(jump())
+            .add_procedure(jump().warp(true))
+            .add_block_stack(b::call_procedure(
+                &jump().handle(),
+                vec![height(), BIB::new()],
+            ));
+        let err = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(sprite))
+            .build(&mut vec![])
+            .unwrap_err();
+        assert_eq!(
+            err.duplicate_procedures,
+            vec![DuplicateProcedure {
+                proccode: "jump %s %b".to_owned(),
+                target: "Cat".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        use crate::project::{asset::SoundBuilder, script::ListBuilder};
+        use crate::scripting::procedure::ProcedureBuilder;
+        use std::io::Cursor;
+
+        let mut stage = TargetBuilder::new("Stage")
+            .add_costume(CostumeBuilder::new(AssetBuilder::new(
+                "backdrop1",
+                Resource::load("blank.svg").unwrap(),
+            )))
+            .add_comment(CommentBuilder::new("hi").pos(10., 20.));
+        let go = stage.declare_broadcast("go");
+        let mut stage = StageBuilder::new(stage);
+        let score = stage.declare_global_var("score", VariableBuilder::new(0.into()));
+        let log = stage.declare_global_list("log", ListBuilder::new(vec![]));
+        let stage_script = when_broadcast_received(&go)
+            .next(set_var_to(&score, 0))
+            .next(add_to_list(&log, "go"));
+        let stage = stage.add_script(stage_script);
+        let jump = ProcedureBuilder::new("jump")
+            .string_number_argument("height")
+            .label("if")
+            .boolean_argument("ready")
+            .warp(true)
+            .body(change_y_by(argument_string_number("height")).into_untyped());
+        let pop = AssetBuilder::new("pop", Resource::new("wav".to_owned(), pop_wav()).unwrap());
+        let cat = TargetBuilder::new("Cat")
             .add_costume(CostumeBuilder::new(AssetBuilder::new(
                 "costume1",
                 Resource::load("cat.svg").unwrap(),
             )))
-            .layer_order(1);
-        let sprite = SpriteBuilder::new(sprite_inner);
+            .add_sound(SoundBuilder::new(pop).unwrap())
+            .add_procedure(jump.clone());
+        let mut sprite = SpriteBuilder::<Cat>::new(cat);
+        let hp = sprite.declare_sprite_var("hp", VariableBuilder::new(3.into()));
+        // Declaring again keeps the first variable
+        assert_eq!(
+            sprite.declare_sprite_var("hp", VariableBuilder::new(0.into())),
+            hp
+        );
+        let hits = sprite.declare_sprite_list("hits", ListBuilder::new(vec![]));
+        let sprite_script = when_flag_clicked()
+            .next(change_var_by(&hp, -1))
+            .next(add_to_list(&hits, sprite_var(&hp)))
+            .next(change_var_by(&score, 1))
+            .next(call_procedure(
+                &jump.handle(),
+                vec![value_arg(20), bool_arg(less_than(sprite_var(&hp), 1))],
+            ))
+            .next(broadcast(&go));
+        let project = ProjectBuilder::new()
+            .set_stage(stage)
+            .add_sprite(sprite.add_script(sprite_script));
 
-        let project = ProjectBuilder::new().set_stage(stage).add_sprite(sprite);
-        export(project, export_path).unwrap();
+        let mut zip = Cursor::new(vec![]);
+        write_zip(&mut zip, project.clone()).unwrap();
+        zip.set_position(0);
+        let imported = ProjectBuilder::from_sb3(zip).unwrap();
+
+        // Imported custom blocks are procedures so new calls to them resolve
+        let mut called = imported.clone();
+        let cat = called
+            .sprite_builders
+            .remove(0)
+            .add_script(when_this_sprite_clicked().next(call_procedure(
+                &jump.handle(),
+                vec![value_arg(5), empty_bool_arg()],
+            )));
+        called.sprite_builders.push(cat);
+        called.build(&mut vec![]).unwrap();
+
+        let original = serde_json::to_value(project.build(&mut vec![]).unwrap()).unwrap();
+        let imported = serde_json::to_value(imported.build(&mut vec![]).unwrap()).unwrap();
+        // Comments get new ids
+        let comments = |project: &serde_json::Value, i: usize| {
+            let mut comments: Vec<String> = project["targets"][i]["comments"]
+                .as_object()
+                .unwrap()
+                .values()
+                .map(|comment| comment.to_string())
+                .collect();
+            comments.sort();
+            comments
+        };
+        for (i, target) in ["Stage", "Cat"].into_iter().enumerate() {
+            assert_eq!(
+                canonical_scripts(&imported, target),
+                canonical_scripts(&original, target)
+            );
+            for key in ["variables", "lists", "broadcasts", "costumes", "sounds"] {
+                assert_eq!(imported["targets"][i][key], original["targets"][i][key]);
+            }
+            assert_eq!(comments(&imported, i), comments(&original, i));
+        }
+        assert_eq!(
+            original["targets"][0]["comments"]
+                .as_object()
+                .unwrap()
+                .len(),
+            1
+        );
+        let cat_variables = original["targets"][1]["variables"].as_object().unwrap();
+        assert_eq!(cat_variables.values().next().unwrap()[1], 3);
+        assert_eq!(
+            original["targets"][1]["sounds"].as_array().unwrap().len(),
+            1
+        );
+        assert!(canonical_scripts(&imported, "Cat")
+            .iter()
+            .any(|script| script.contains("procedures_definition")));
+        let broadcast_id = original["targets"][0]["broadcasts"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .next()
+            .unwrap()
+            .clone();
+        let hat = imported["targets"][0]["blocks"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|block| block["opcode"] == "event_whenbroadcastreceived")
+            .unwrap();
+        assert_eq!(hat["fields"]["BROADCAST_OPTION"][1], broadcast_id.as_str());
+    }
+
+    #[test]
+    fn test_decompile() {
+        use crate::decompile::{decompile_project, DecompileError};
+        use crate::project::script::ListBuilder;
+        use crate::scripting::menu::{Drum, Instrument};
+        use crate::scripting::procedure::ProcedureBuilder;
+        use crate::typed_scripting::arg::{StopOtherScriptsInSprite, StopThisScript};
+        use rs_sb3::{block::Block, target::SpriteOrStage};
+
+        mod decompiled {
+            include!("../fixtures/decompile/project.rs");
+        }
+
+        let stage = |mut target: TargetBuilder| {
+            let go = target.declare_broadcast("go");
+            let mut stage = StageBuilder::new(target);
+            let score = stage.declare_global_var("score", VariableBuilder::new(0.into()));
+            let log = stage.declare_global_list("log", ListBuilder::new(vec![]));
+            (stage, score, log, go)
+        };
+        let sprite = |target: TargetBuilder| {
+            let mut sprite = SpriteBuilder::<Cat>::new(target);
+            let hp = sprite.declare_sprite_var("hp", VariableBuilder::new(3.into()));
+            (sprite, hp)
+        };
+        let (stage_builder, score, log, go) = stage(TargetBuilder::new("Stage"));
+        let stage_builder = stage_builder
+            .add_script(when_broadcast_received(&go).next(add_to_list(&log, global_var(&score))));
+        let jump = ProcedureBuilder::new("jump")
+            .string_number_argument("height")
+            .label("if")
+            .boolean_argument("ready")
+            .warp(true)
+            .body(change_y_by(argument_string_number("height")).into_untyped());
+        let (sprite_builder, hp) = sprite(TargetBuilder::new("Cat").add_procedure(jump.clone()));
+        let sprite_builder = sprite_builder.add_script(
+            when_flag_clicked()
+                .next(set_var_to(&hp, 3))
+                .next(repeat(
+                    10,
+                    Some(move_steps(random(1, 10)).next(turn_right(15))),
+                ))
+                .next(if_else(
+                    less_than(sprite_var(&hp), 1),
+                    Some(say("ouch")),
+                    Some(change_var_by(&score, 1)),
+                ))
+                .next(set_looks_effect_to(LooksEffect::Ghost, 50))
+                .next(call_procedure(
+                    &jump.handle(),
+                    vec![value_arg(20), empty_bool_arg()],
+                ))
+                .next(call_procedure(
+                    &jump.handle(),
+                    vec![value_arg(sprite_var(&hp)), bool_arg(mouse_down())],
+                ))
+                .next(play_drum_for_beats(Drum::SnareDrum, 0.25))
+                .next(set_instrument_to(Instrument::Cello))
+                .next(wait(0.5))
+                .next(stop(StopOtherScriptsInSprite))
+                .next(broadcast(&go))
+                .next(stop(StopThisScript)),
+        );
+        let project = ProjectBuilder::new()
+            .set_stage(stage_builder)
+            .add_sprite(sprite_builder);
+        let original = project.build(&mut vec![]).unwrap();
+
+        let code = decompile_project(&original).unwrap();
+        assert_eq!(code, include_str!("../fixtures/decompile/project.rs"));
+        for expected in [
+            "repeat(10, ",
+            "wait(0.5)",
+            "play_drum_for_beats(Drum::SnareDrum, 0.25)",
+            "set_instrument_to(Instrument::Cello)",
+            "add_to_list(",
+            "broadcast(BroadcastHandle::assume_declared(\"go\"))",
+            "ProcedureBuilder::new(\"jump\").string_number_argument(\"height\").label(\"if\")",
+        ] {
+            assert!(code.contains(expected), "{expected}");
+        }
+        assert!(!code.contains("serde_json"));
+
+        let mut broken = original.clone();
+        let SpriteOrStage::Sprite(cat) = &mut broken.targets[1] else {
+            unreachable!()
+        };
+        let turn_right = cat
+            .target
+            .blocks
+            .0
+            .iter()
+            .find(|(_, block)| matches!(block, Block::Normal(block) if block.opcode == "motion_turnright"))
+            .map(|(uid, _)| uid.clone())
+            .unwrap();
+        cat.target.blocks.0.remove(&turn_right);
+        assert_eq!(
+            decompile_project(&broken),
+            Err(DecompileError::MissingBlock(turn_right))
+        );
+
+        let stage_target = decompiled::stage_scripts()
+            .into_iter()
+            .fold(TargetBuilder::new("Stage"), |target, stack| {
+                target.add_block_stack(stack)
+            });
+        let stage_target = decompiled::stage_procedures()
+            .into_iter()
+            .fold(stage_target, |target, procedure| {
+                target.add_procedure(procedure)
+            });
+        let sprite_target = decompiled::cat_scripts()
+            .into_iter()
+            .fold(TargetBuilder::new("Cat"), |target, stack| {
+                target.add_block_stack(stack)
+            });
+        let sprite_target = decompiled::cat_procedures()
+            .into_iter()
+            .fold(sprite_target, |target, procedure| {
+                target.add_procedure(procedure)
+            });
+        let decompiled = ProjectBuilder::new()
+            .set_stage(stage(stage_target).0)
+            .add_sprite(sprite(sprite_target).0)
+            .build(&mut vec![])
+            .unwrap();
+        let original = serde_json::to_value(original).unwrap();
+        let decompiled = serde_json::to_value(decompiled).unwrap();
+        for target in ["Stage", "Cat"] {
+            assert_eq!(
+                canonical_scripts(&decompiled, target),
+                canonical_scripts(&original, target)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pen_color_param() {
+        use crate::decompile::decompile_project;
+        use crate::scripting::{menu::PenColorParam, script_builder::BlockInputBuilder};
+        use rs_sb3::block::BlockInputValue;
+
+        let script = when_flag_clicked()
+            .next(change_pen_color_param_by(PenColorParam::Saturation, 10))
+            .next(set_pen_color_param_to(join("bright", "ness"), 50));
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(
+                TargetBuilder::new("Cat").add_block_stack(script.into_untyped()),
+            ))
+            .build(&mut vec![])
+            .unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        assert_eq!(built["extensions"], serde_json::json!(["pen"]));
+
+        let blocks = built["targets"][1]["blocks"].as_object().unwrap();
+        let block = |opcode: &str| {
+            blocks
+                .values()
+                .find(|block| block["opcode"] == opcode)
+                .unwrap()
+        };
+        let color_param = |block: &serde_json::Value, i: usize| {
+            let menu = &blocks[block["inputs"]["COLOR_PARAM"][i].as_str().unwrap()];
+            assert_eq!(menu["opcode"], "pen_menu_colorParam");
+            assert_eq!(menu["shadow"], true);
+            menu["fields"]["colorParam"][0].clone()
+        };
+        let change = block("pen_changePenColorParamBy");
+        assert_eq!(change["inputs"]["COLOR_PARAM"][0], 1);
+        assert_eq!(color_param(change, 1), "saturation");
+        // The reporter covers the menu's default option
+        let set = block("pen_setPenColorParamTo");
+        assert_eq!(set["inputs"]["COLOR_PARAM"][0], 3);
+        let join = &blocks[set["inputs"]["COLOR_PARAM"][1].as_str().unwrap()];
+        assert_eq!(join["opcode"], "operator_join");
+        assert_eq!(color_param(set, 2), "color");
+
+        // A text in the input decompiles to the escape hatch
+        let text = BlockInputBuilder::value(BlockInputValue::String {
+            value: "transparency".into(),
+        });
+        let script = when_flag_clicked().next(set_pen_color_param_to(text, 0));
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat")).add_script(script))
+            .build(&mut vec![])
+            .unwrap();
+        let code = decompile_project(&built).unwrap();
+        assert!(code.contains("set_pen_color_param_to(BlockInputBuilder::value("));
+    }
+
+    #[test]
+    fn test_monitors() {
+        use crate::project::{
+            monitor::{BuiltinReporter, MonitorBuilder, MonitorError, MonitorMode},
+            script::ListBuilder,
+        };
+        use rs_sb3::value::Value;
+
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(
+                TargetBuilder::new("Stage").add_variable("score", VariableBuilder::new(5.into())),
+            ))
+            .add_sprite(SpriteBuilder::<Cat>::new(
+                TargetBuilder::new("Cat").add_list(
+                    "inventory",
+                    ListBuilder::new(vec![Value::Text("sword".to_owned())]),
+                ),
+            ))
+            .add_monitor(
+                MonitorBuilder::global_variable("score")
+                    .slider(-10., 10.)
+                    .discrete(false)
+                    .pos(5., 10.),
+            )
+            .add_monitor(
+                MonitorBuilder::sprite_list("Cat", "inventory")
+                    .mode(MonitorMode::Large)
+                    .size(100, 200),
+            )
+            .add_monitor(MonitorBuilder::builtin(BuiltinReporter::Timer).mode(MonitorMode::Large))
+            .build(&mut vec![])
+            .unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        let id_of = |target: usize, key: &str| {
+            built["targets"][target][key]
+                .as_object()
+                .unwrap()
+                .keys()
+                .next()
+                .unwrap()
+                .clone()
+        };
+        let monitors = built["monitors"].as_array().unwrap();
+        assert_eq!(monitors.len(), 3);
+
+        let score = &monitors[0];
+        assert_eq!(score["id"], id_of(0, "variables").as_str());
+        assert_eq!(score["mode"], "slider");
+        assert_eq!(score["opcode"], "data_variable");
+        assert_eq!(score["params"], serde_json::json!({ "VARIABLE": "score" }));
+        assert_eq!(score["spriteName"], serde_json::Value::Null);
+        assert_eq!(score["value"], 5);
+        assert_eq!(score["sliderMin"], -10.);
+        assert_eq!(score["sliderMax"], 10.);
+        assert_eq!(score["isDiscrete"], false);
+        assert_eq!((&score["x"], &score["y"]), (&5.0.into(), &10.0.into()));
+
+        let inventory = &monitors[1];
+        assert_eq!(inventory["id"], id_of(1, "lists").as_str());
+        assert_eq!(inventory["mode"], "list");
+        assert_eq!(inventory["opcode"], "data_listcontents");
+        assert_eq!(
+            inventory["params"],
+            serde_json::json!({ "LIST": "inventory" })
+        );
+        assert_eq!(inventory["spriteName"], "Cat");
+        assert_eq!(inventory["value"], serde_json::json!(["sword"]));
+        assert_eq!(
+            (&inventory["width"], &inventory["height"]),
+            (&100.into(), &200.into())
+        );
+
+        let timer = &monitors[2];
+        assert_eq!(timer["id"], "timer");
+        assert_eq!(timer["mode"], "large");
+        assert_eq!(timer["opcode"], "sensing_timer");
+
+        let targets = ProjectBuilder::new().build(&mut vec![]).unwrap().targets;
+        assert_eq!(
+            MonitorBuilder::sprite_variable("Dog", "lives")
+                .build(&targets)
+                .unwrap_err(),
+            MonitorError::Missing {
+                sprite_name: Some("Dog".to_owned()),
+                name: "lives".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        use crate::scripting::script_builder::{BlockNormalBuilder, StackBuilder};
+
+        // Hat and a block, 120 high
+        let script = || when_this_sprite_clicked().next(hide()).into_untyped();
+        let commented = StackBuilder::start(BlockNormalBuilder::new("event_whenflagclicked")).next(
+            StackBuilder::start(
+                BlockNormalBuilder::new("looks_show").comment(CommentBuilder::new("shows up")),
+            ),
+        );
+        let sprite = (0..20).fold(
+            TargetBuilder::new("Cat")
+                .add_block_stack(script().move_head(0., 0.))
+                .add_block_stack(commented),
+            |sprite, _| sprite.add_block_stack(script()),
+        );
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(sprite))
+            .build(&mut vec![])
+            .unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        let blocks = built["targets"][1]["blocks"].as_object().unwrap();
+        let position = |block: &serde_json::Value| {
+            (block["x"].as_f64().unwrap(), block["y"].as_f64().unwrap())
+        };
+        let mut heads: Vec<(f64, f64)> = blocks
+            .values()
+            .filter(|block| block["topLevel"] == true)
+            .map(position)
+            .collect();
+        heads.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(heads.len(), 22);
+        assert_eq!(heads[0], (0., 0.));
+        // Stacks without position go around the one with position
+        assert_eq!(heads[1], (0., 168.));
+        for pair in heads.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+        // Columns wrap before they get higher than 1600
+        assert!(heads.iter().all(|(_, y)| y + 120. <= 1600.));
+        let mut columns: Vec<f64> = heads.iter().map(|(x, _)| *x).collect();
+        columns.dedup();
+        assert!(columns.len() > 1);
+
+        // Comment is on the row of its block, at the right of the stack
+        let comment = built["targets"][1]["comments"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        let show = &blocks[comment["blockId"].as_str().unwrap()];
+        let hat = &blocks[show["parent"].as_str().unwrap()];
+        assert_eq!(position(hat), (0., 168.));
+        assert_eq!(comment["y"].as_f64().unwrap(), 168. + 72.);
+        assert!(comment["x"].as_f64().unwrap() > 0.);
+    }
+
+    #[test]
+    fn test_menu_options() {
+        use crate::scripting::menu::{Drum, Instrument, Key};
+        use crate::typed_scripting::arg::{StopAll, StopOtherScriptsInSprite};
+
+        let script = when_flag_clicked()
+            .next(wait_until(key_pressed(Key::Space)))
+            .next(play_drum_for_beats(Drum::Cowbell, 0.25))
+            .next(set_instrument_to(Instrument::Organ))
+            // Blocks can go after stopping other scripts, but not after stopping all
+            .next(stop(StopOtherScriptsInSprite))
+            .next(stop(StopAll));
+        let built = ProjectBuilder::new()
+            .set_stage(StageBuilder::new(TargetBuilder::new("Stage")))
+            .add_sprite(SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat")).add_script(script))
+            .build(&mut vec![])
+            .unwrap();
+        let built = serde_json::to_value(built).unwrap();
+        let blocks = built["targets"][1]["blocks"].as_object().unwrap();
+        let block = |opcode: &str| {
+            blocks
+                .values()
+                .find(|block| block["opcode"] == opcode)
+                .unwrap()
+        };
+        let menu_value = |block: &serde_json::Value, input: &str, menu_opcode: &str| {
+            let menu = &blocks[block["inputs"][input][1].as_str().unwrap()];
+            assert_eq!(menu["opcode"], menu_opcode);
+            assert_eq!(menu["shadow"], true);
+            menu["fields"][input][0].clone()
+        };
+        assert_eq!(
+            menu_value(
+                block("sensing_keypressed"),
+                "KEY_OPTION",
+                "sensing_keyoptions"
+            ),
+            "space"
+        );
+        assert_eq!(
+            menu_value(block("music_playDrumForBeats"), "DRUM", "music_menu_DRUM"),
+            String::from(Drum::Cowbell)
+        );
+        assert_eq!(
+            menu_value(
+                block("music_setInstrument"),
+                "INSTRUMENT",
+                "music_menu_INSTRUMENT"
+            ),
+            String::from(Instrument::Organ)
+        );
+
+        let mut stops: Vec<_> = blocks
+            .values()
+            .filter(|block| block["opcode"] == "control_stop")
+            .map(|block| {
+                (
+                    block["fields"]["STOP_OPTION"][0].clone(),
+                    block["mutation"]["hasnext"].clone(),
+                    block["next"].is_string(),
+                )
+            })
+            .collect();
+        stops.sort_by_key(|(option, ..)| option.to_string());
+        assert_eq!(
+            stops,
+            vec![
+                ("all".into(), "false".into(), false),
+                ("other scripts in sprite".into(), "true".into(), true),
+            ]
+        );
     }
 }
@@ -24,6 +24,10 @@ impl CostumeBuilder {
         self
     }
 
+    pub(crate) fn name(&self) -> &str {
+        &self.asset.name
+    }
+
     pub fn build(self, file_buff: &mut Vec<Resource>) -> Costume {
         let CostumeBuilder {
             rotation_center_x,
@@ -39,7 +43,35 @@ impl CostumeBuilder {
     }
 }
 
-/// Not really sure what to do here yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SoundError {
+    /// Scratch only plays wav and mp3. Contains the file extension
+    UnsupportedFormat(String),
+    /// Wav with encoding that Scratch can't decode. Contains the wav format code
+    UnsupportedWavEncoding(u16),
+    /// The file content doesn't match its extension or is cut off
+    Malformed(&'static str),
+}
+
+impl std::error::Error for SoundError {}
+
+impl std::fmt::Display for SoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoundError::UnsupportedFormat(ext) => {
+                write!(
+                    f,
+                    "unsupported sound format `{ext}`, only wav and mp3 are supported"
+                )
+            }
+            SoundError::UnsupportedWavEncoding(code) => {
+                write!(f, "unsupported wav encoding (format code {code:#06x})")
+            }
+            SoundError::Malformed(reason) => write!(f, "malformed sound file: {reason}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SoundBuilder {
     rate: u64,
@@ -49,6 +81,53 @@ pub struct SoundBuilder {
 }
 
 impl SoundBuilder {
+    /// Reads the sample rate and sample count from the wav header or the mp3 frames.
+    pub fn new(asset_builder: AssetBuilder) -> Result<SoundBuilder, SoundError> {
+        let resource = &asset_builder.resource;
+        let metadata = match resource.extension().to_lowercase().as_str() {
+            "wav" => wav_metadata(resource.content())?,
+            "mp3" => mp3_metadata(resource.content())?,
+            ext => return Err(SoundError::UnsupportedFormat(ext.to_owned())),
+        };
+        Ok(SoundBuilder {
+            rate: metadata.rate,
+            sample_count: metadata.sample_count,
+            format: Some(metadata.format.to_owned()),
+            asset: asset_builder,
+        })
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.asset.name
+    }
+
+    /// Overrides the detected sample rate
+    pub fn rate(mut self, rate: u64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Overrides the detected sample count
+    pub fn sample_count(mut self, sample_count: u64) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// For sound that we already know its metadata. Ex. Sound from an imported project.
+    pub(crate) fn with_metadata(
+        asset_builder: AssetBuilder,
+        rate: u64,
+        sample_count: u64,
+        format: Option<String>,
+    ) -> SoundBuilder {
+        SoundBuilder {
+            rate,
+            sample_count,
+            format,
+            asset: asset_builder,
+        }
+    }
+
     pub fn build(self, file_buff: &mut Vec<Resource>) -> Sound {
         let SoundBuilder {
             rate,
@@ -94,3 +173,147 @@ impl AssetBuilder {
         asset
     }
 }
+
+struct SoundMetadata {
+    rate: u64,
+    sample_count: u64,
+    /// "adpcm" for compressed wav, empty otherwise
+    format: &'static str,
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn wav_metadata(bytes: &[u8]) -> Result<SoundMetadata, SoundError> {
+    const PCM: u16 = 0x0001;
+    const IEEE_FLOAT: u16 = 0x0003;
+    const IMA_ADPCM: u16 = 0x0011;
+    const EXTENSIBLE: u16 = 0xFFFE;
+
+    if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err(SoundError::Malformed("missing RIFF/WAVE header"));
+    }
+    let mut fmt: Option<(u16, u32, u16, u16)> = None;
+    let mut data_len: Option<u32> = None;
+    let mut fact_sample_count: Option<u32> = None;
+    let mut at = 12;
+    while let (Some(id), Some(len)) = (bytes.get(at..at + 4), u32_le(bytes, at + 4)) {
+        let body = at + 8;
+        match id {
+            b"fmt " => {
+                let truncated = SoundError::Malformed("truncated fmt chunk");
+                let format_code = u16_le(bytes, body).ok_or(truncated.clone())?;
+                let rate = u32_le(bytes, body + 4).ok_or(truncated.clone())?;
+                let block_align = u16_le(bytes, body + 12).ok_or(truncated)?;
+                // Only exists in compressed formats
+                let samples_per_block = u16_le(bytes, body + 18).unwrap_or(0);
+                fmt = Some((format_code, rate, block_align, samples_per_block));
+            }
+            b"fact" => fact_sample_count = u32_le(bytes, body),
+            b"data" => data_len = Some(len),
+            _ => {}
+        }
+        // Chunks are padded to even length
+        at = body + len as usize + (len as usize & 1);
+    }
+    let (format_code, rate, block_align, samples_per_block) =
+        fmt.ok_or(SoundError::Malformed("missing fmt chunk"))?;
+    let data_len = data_len.ok_or(SoundError::Malformed("missing data chunk"))? as u64;
+    if block_align == 0 {
+        return Err(SoundError::Malformed("block align is 0"));
+    }
+    let (sample_count, format) = match format_code {
+        PCM | IEEE_FLOAT | EXTENSIBLE => (data_len / block_align as u64, ""),
+        IMA_ADPCM => {
+            let sample_count = match fact_sample_count {
+                Some(sample_count) => sample_count as u64,
+                None => data_len / block_align as u64 * samples_per_block as u64,
+            };
+            (sample_count, "adpcm")
+        }
+        code => return Err(SoundError::UnsupportedWavEncoding(code)),
+    };
+    Ok(SoundMetadata {
+        rate: rate as u64,
+        sample_count,
+        format,
+    })
+}
+
+fn mp3_metadata(bytes: &[u8]) -> Result<SoundMetadata, SoundError> {
+    #[rustfmt::skip]
+    const BITRATES_V1: [[u64; 15]; 3] = [
+        [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+        [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    ];
+    #[rustfmt::skip]
+    const BITRATES_V2: [[u64; 15]; 3] = [
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+
+    let mut at = 0;
+    // ID3v2 tag size is 4 bytes of 7 bits each
+    if bytes.get(0..3) == Some(b"ID3") {
+        let size = bytes
+            .get(6..10)
+            .ok_or(SoundError::Malformed("truncated ID3 tag"))?
+            .iter()
+            .fold(0usize, |size, byte| (size << 7) | (*byte & 0x7F) as usize);
+        at = 10 + size;
+    }
+    let mut rate: Option<u64> = None;
+    let mut sample_count = 0;
+    while let Some(header) = bytes.get(at..at + 4) {
+        if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+            break;
+        }
+        // 0: MPEG 2.5, 2: MPEG 2, 3: MPEG 1
+        let version = (header[1] >> 3) & 0b11;
+        // 1: Layer III, 2: Layer II, 3: Layer I
+        let layer = (header[1] >> 1) & 0b11;
+        let bitrate_index = (header[2] >> 4) as usize;
+        let rate_index = ((header[2] >> 2) & 0b11) as usize;
+        let padding = ((header[2] >> 1) & 1) as u64;
+        if version == 1
+            || layer == 0
+            || bitrate_index == 0
+            || bitrate_index == 15
+            || rate_index == 3
+        {
+            return Err(SoundError::Malformed("invalid mp3 frame header"));
+        }
+        let frame_rate = [
+            [11025, 12000, 8000],
+            [0, 0, 0],
+            [22050, 24000, 16000],
+            [44100, 48000, 32000],
+        ][version as usize][rate_index];
+        let layer_index = (3 - layer) as usize;
+        let bitrate = match version {
+            3 => BITRATES_V1[layer_index][bitrate_index],
+            _ => BITRATES_V2[layer_index][bitrate_index],
+        } * 1000;
+        let (frame_samples, frame_len) = match (version, layer) {
+            (_, 3) => (384, (12 * bitrate / frame_rate + padding) * 4),
+            (3, _) | (_, 2) => (1152, 144 * bitrate / frame_rate + padding),
+            _ => (576, 72 * bitrate / frame_rate + padding),
+        };
+        rate.get_or_insert(frame_rate);
+        sample_count += frame_samples;
+        at += frame_len as usize;
+    }
+    let rate = rate.ok_or(SoundError::Malformed("no mp3 frame found"))?;
+    Ok(SoundMetadata {
+        rate,
+        sample_count,
+        format: "",
+    })
+}
@@ -0,0 +1,229 @@
+//! Approximate placement of scripts and block comments in the editor,
+//! similar to Scratch's "Clean up".
+//! Sizes are estimated from the blocks and are in the editor's workspace unit.
+
+use crate::opcode;
+use crate::scripting::script_builder::{
+    BlockBuilder, BlockInputBuilder, BlockNormalBuilder, StackBuilder, StackOrValue,
+};
+
+const STACK_GAP: f64 = 48.;
+const MAX_COLUMN_HEIGHT: f64 = 1600.;
+const COMMENT_GAP: f64 = 32.;
+const BLOCK_HEIGHT: f64 = 48.;
+const HAT_HEIGHT: f64 = 72.;
+const C_ARM_HEIGHT: f64 = 32.;
+const EMPTY_SUBSTACK_HEIGHT: f64 = 24.;
+const SUBSTACK_INDENT: f64 = 16.;
+const CHAR_WIDTH: f64 = 8.;
+const INPUT_WIDTH: f64 = 40.;
+const MIN_BLOCK_WIDTH: f64 = 48.;
+const VARLIST_WIDTH: f64 = 80.;
+
+/// Places every stack that doesn't have an explicit position into columns
+/// from top to bottom, then left to right, around the stacks that have one.
+/// Comments without explicit position are placed to the right of their stack.
+pub(crate) fn layout_stacks(stacks: &mut [StackBuilder]) {
+    let reserved: Vec<Rect> = stacks
+        .iter()
+        .filter(|stack| !stack.blocks().is_empty())
+        .filter_map(|stack| {
+            let (x, y) = stack.head_position()?;
+            let (width, height) = stack_size(stack);
+            Some(Rect {
+                x,
+                y,
+                width,
+                height,
+            })
+        })
+        .collect();
+    let mut column_x = 0.;
+    let mut column_width: f64 = 0.;
+    let mut y = 0.;
+    for stack in stacks {
+        if stack.blocks().is_empty() {
+            continue;
+        }
+        let (width, height) = stack_size(stack);
+        let (x, stack_y) = match stack.head_position() {
+            Some(position) => position,
+            None => {
+                loop {
+                    if y > 0. && y + height > MAX_COLUMN_HEIGHT {
+                        column_x += column_width.max(width) + STACK_GAP;
+                        column_width = 0.;
+                        y = 0.;
+                    }
+                    let rect = Rect {
+                        x: column_x,
+                        y,
+                        width,
+                        height,
+                    };
+                    match reserved.iter().find(|reserved| reserved.overlaps(&rect)) {
+                        Some(reserved) => y = reserved.y + reserved.height + STACK_GAP,
+                        None => break,
+                    }
+                }
+                let position = (column_x, y);
+                stack.mut_move_head(column_x, y);
+                column_width = column_width.max(width);
+                y += height + STACK_GAP;
+                position
+            }
+        };
+        place_comments(stack, x + width + COMMENT_GAP, stack_y);
+    }
+}
+
+/// Area a stack takes in the workspace
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Rect {
+    /// Whether the rectangles are closer than [`STACK_GAP`]
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width + STACK_GAP
+            && other.x < self.x + self.width + STACK_GAP
+            && self.y < other.y + other.height + STACK_GAP
+            && other.y < self.y + self.height + STACK_GAP
+    }
+}
+
+/// Approximate width and height of a stack
+fn stack_size(stack: &StackBuilder) -> (f64, f64) {
+    stack.blocks().iter().map(block_size).fold(
+        (0., 0.),
+        |(width, height), (block_width, block_height)| {
+            (width.max(block_width), height + block_height)
+        },
+    )
+}
+
+fn block_size(block: &BlockBuilder) -> (f64, f64) {
+    match block {
+        BlockBuilder::Normal(block) => normal_block_size(block),
+        BlockBuilder::VarList(_) => (VARLIST_WIDTH, BLOCK_HEIGHT),
+    }
+}
+
+fn normal_block_size(block: &BlockNormalBuilder) -> (f64, f64) {
+    let opcode = block.opcode();
+    // Opcode without the category is about as long as the block's label
+    let label = opcode.split_once('_').map_or(opcode, |(_, label)| label);
+    let mut width = label.len() as f64 * CHAR_WIDTH + block.fields().len() as f64 * INPUT_WIDTH;
+    let mut height = if is_hat(opcode) {
+        HAT_HEIGHT
+    } else {
+        BLOCK_HEIGHT
+    };
+    for (key, input) in block.inputs() {
+        if key.starts_with("SUBSTACK") {
+            continue;
+        }
+        width += input_width(input);
+    }
+    for substack_idx in 0..substack_count(block) {
+        let key = match substack_idx {
+            0 => "SUBSTACK".to_owned(),
+            i => format!("SUBSTACK{}", i + 1),
+        };
+        let (substack_width, substack_height) = block
+            .inputs()
+            .get(&key)
+            .and_then(substack_of)
+            .map(stack_size)
+            .unwrap_or((0., 0.));
+        width = width.max(substack_width + SUBSTACK_INDENT);
+        height += substack_height.max(EMPTY_SUBSTACK_HEIGHT) + C_ARM_HEIGHT;
+    }
+    (width.max(MIN_BLOCK_WIDTH), height)
+}
+
+fn input_width(input: &BlockInputBuilder) -> f64 {
+    input
+        .values()
+        .iter()
+        .flatten()
+        .map(|value| match value {
+            StackOrValue::Value(_) => INPUT_WIDTH,
+            StackOrValue::Stack(stack) => stack_size(stack).0,
+        })
+        .fold(INPUT_WIDTH, f64::max)
+}
+
+fn substack_of(input: &BlockInputBuilder) -> Option<&StackBuilder> {
+    input
+        .values()
+        .iter()
+        .flatten()
+        .find_map(|value| match value {
+            StackOrValue::Stack(stack) => Some(stack),
+            StackOrValue::Value(_) => None,
+        })
+}
+
+fn substack_count(block: &BlockNormalBuilder) -> usize {
+    match opcode::substack_count(block.opcode()) {
+        0 => block
+            .inputs()
+            .keys()
+            .filter(|key| key.starts_with("SUBSTACK"))
+            .count(),
+        count => count,
+    }
+}
+
+fn is_hat(opcode: &str) -> bool {
+    opcode.starts_with("event_when")
+        || matches!(opcode, "control_start_as_clone" | "procedures_definition")
+}
+
+/// Places comments of the blocks in this stack and its inputs at `comment_x`,
+/// next to the row of the block it's attached to.
+fn place_comments(stack: &mut StackBuilder, comment_x: f64, y: f64) {
+    let mut y = y;
+    for block in stack.blocks_mut() {
+        let (_, height) = block_size(block);
+        match block {
+            BlockBuilder::Normal(block) => {
+                if let Some(comment) = block.comment_mut() {
+                    if comment.position().is_none() {
+                        comment.mut_pos(comment_x, y);
+                    }
+                }
+                let mut substack_y = y + BLOCK_HEIGHT;
+                let mut substacks: Vec<_> = block.inputs_mut().iter_mut().collect();
+                substacks.sort_by_key(|(name, _)| *name);
+                for (key, input) in substacks {
+                    let is_substack = key.starts_with("SUBSTACK");
+                    for value in input.values_mut().iter_mut().flatten() {
+                        let StackOrValue::Stack(inner) = value else {
+                            continue;
+                        };
+                        if is_substack {
+                            let (_, inner_height) = stack_size(inner);
+                            place_comments(inner, comment_x, substack_y);
+                            substack_y += inner_height.max(EMPTY_SUBSTACK_HEIGHT) + C_ARM_HEIGHT;
+                        } else {
+                            place_comments(inner, comment_x, y);
+                        }
+                    }
+                }
+            }
+            BlockBuilder::VarList(block) => {
+                if let Some(comment) = block.comment_mut() {
+                    if comment.position().is_none() {
+                        comment.mut_pos(comment_x, y);
+                    }
+                }
+            }
+        }
+        y += height;
+    }
+}
@@ -0,0 +1,479 @@
+//! Finds common mistakes in the blocks before building.
+//!
+//! ```ignore
+//! let report = project.lint(&LintConfig::default().severity(Rule::UnreadVariable, Severity::Off));
+//! if report.has_errors() {
+//!     panic!("{report}");
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use rs_sb3::block::{BlockInputValue, BlockMutationEnum, ListOrVariable};
+
+use crate::{
+    scratchblocks::render::is_reporter,
+    scripting::script_builder::{
+        BlockBuilder, BlockNormalBuilder, FieldKind, ReferenceKind, StackBuilder, StackOrValue,
+    },
+};
+
+use super::{monitor::MonitorBuilder, target::TargetBuilder, ProjectBuilder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Blocks after `forever`, `delete this clone` or `stop all/this script` never run
+    BlockAfterCap,
+    /// `stop` needs `has_next` only for "other scripts in sprite/stage"
+    StopHasNext,
+    /// The Stage can't move
+    MotionOnStage,
+    /// Broadcast that no `when I receive` hat receives
+    UnreceivedBroadcast,
+    /// Variable that's set or changed but never reported
+    UnreadVariable,
+    /// Reporter on its own at the top level that doesn't do anything
+    OrphanReporter,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rule = match self {
+            Rule::BlockAfterCap => "block-after-cap",
+            Rule::StopHasNext => "stop-has-next",
+            Rule::MotionOnStage => "motion-on-stage",
+            Rule::UnreceivedBroadcast => "unreceived-broadcast",
+            Rule::UnreadVariable => "unread-variable",
+            Rule::OrphanReporter => "orphan-reporter",
+        };
+        write!(f, "{rule}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Rule isn't checked
+    Off,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self {
+            Severity::Off => "off",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}")
+    }
+}
+
+/// Severity of each rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+    severities: HashMap<Rule, Severity>,
+}
+
+impl LintConfig {
+    /// Every rules are warnings
+    pub fn new() -> LintConfig {
+        LintConfig {
+            severities: HashMap::new(),
+        }
+    }
+
+    pub fn severity(mut self, rule: Rule, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    pub fn severity_of(&self, rule: Rule) -> Severity {
+        self.severities
+            .get(&rule)
+            .copied()
+            .unwrap_or(Severity::Warning)
+    }
+}
+
+impl Default for LintConfig {
+    /// Mistakes that break the script are errors, unused things are warnings
+    fn default() -> Self {
+        LintConfig::new()
+            .severity(Rule::BlockAfterCap, Severity::Error)
+            .severity(Rule::StopHasNext, Severity::Error)
+            .severity(Rule::MotionOnStage, Severity::Error)
+            .severity(Rule::UnreceivedBroadcast, Severity::Warning)
+            .severity(Rule::UnreadVariable, Severity::Warning)
+            .severity(Rule::OrphanReporter, Severity::Warning)
+    }
+}
+
+/// Step to a block from its target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Index in the target's block stacks
+    Stack(usize),
+    /// Body of the procedure with the proccode
+    Procedure(String),
+    /// Index of the block in its stack
+    Block(usize),
+    /// Key of the input the stack is in
+    Input(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockPath(pub Vec<PathSegment>);
+
+impl BlockPath {
+    fn join(&self, segment: PathSegment) -> BlockPath {
+        let mut path = self.clone();
+        path.0.push(segment);
+        path
+    }
+}
+
+impl std::fmt::Display for BlockPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let segments: Vec<String> = self
+            .0
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Stack(idx) => format!("stack {idx}"),
+                PathSegment::Procedure(proccode) => format!("procedure `{proccode}`"),
+                PathSegment::Block(idx) => format!("block {idx}"),
+                PathSegment::Input(key) => format!("input {key}"),
+            })
+            .collect();
+        write!(f, "{}", segments.join(" > "))
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule:     Rule,
+    pub severity: Severity,
+    /// Name of the target the block is in
+    pub target:   String,
+    pub path:     BlockPath,
+    pub message:  String,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} [{}]\n  in target `{}` at {}",
+            self.severity, self.message, self.rule, self.target, self.path
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintReport {
+    pub findings: Vec<Finding>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+    }
+}
+
+impl std::fmt::Display for LintReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{finding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lints every targets together so broadcasts and global variables can be checked across them
+pub fn lint_project(project: &ProjectBuilder, config: &LintConfig) -> LintReport {
+    let targets: Vec<(&TargetBuilder, bool)> =
+        std::iter::once((project.stage_builder.target(), true))
+            .chain(
+                project
+                    .sprite_builders
+                    .iter()
+                    .map(|sprite| (sprite.target(), false)),
+            )
+            .collect();
+    Linter::new(config, &targets).finish(&project.monitors)
+}
+
+/// Lints a target on its own. Broadcasts are only received by this target.
+pub fn lint_target(target: &TargetBuilder, is_stage: bool, config: &LintConfig) -> LintReport {
+    Linter::new(config, &[(target, is_stage)]).finish(&[])
+}
+
+/// Where a block is
+#[rustfmt::skip]
+struct Visit<'a> {
+    target:   &'a str,
+    is_stage: bool,
+    path:     BlockPath,
+}
+
+#[rustfmt::skip]
+struct Linter<'a> {
+    config:     &'a LintConfig,
+    findings:   Vec<Finding>,
+    receivers:  HashSet<String>,
+    /// Broadcast names in lowercase with where it's sent
+    broadcasts: Vec<(String, Visit<'a>)>,
+    /// Sprite name for sprite variables or "" for global ones
+    writes:     Vec<((&'a str, String), Visit<'a>)>,
+    reads:      HashSet<(&'a str, String)>,
+    /// Names read with "of" block, could be any sprite's variable
+    reads_of:   HashSet<String>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(config: &'a LintConfig, targets: &[(&'a TargetBuilder, bool)]) -> Linter<'a> {
+        let mut linter = Linter {
+            config,
+            findings: vec![],
+            receivers: HashSet::new(),
+            broadcasts: vec![],
+            writes: vec![],
+            reads: HashSet::new(),
+            reads_of: HashSet::new(),
+        };
+        for &(target, is_stage) in targets {
+            let procedures = target.procedures().iter().filter_map(|procedure| {
+                let path = BlockPath(vec![PathSegment::Procedure(procedure.proccode())]);
+                procedure.body_ref().map(|body| (path, body, false))
+            });
+            let stacks = target
+                .stacks()
+                .iter()
+                .enumerate()
+                .map(|(idx, stack)| (BlockPath(vec![PathSegment::Stack(idx)]), stack, true));
+            for (path, stack, top_level) in stacks.chain(procedures) {
+                linter.stack(target.name(), is_stage, &path, stack, top_level);
+            }
+        }
+        linter
+    }
+
+    fn report(&mut self, rule: Rule, visit: &Visit, message: String) {
+        let severity = self.config.severity_of(rule);
+        if severity == Severity::Off {
+            return;
+        }
+        self.findings.push(Finding {
+            rule,
+            severity,
+            target: visit.target.to_owned(),
+            path: visit.path.clone(),
+            message,
+        });
+    }
+
+    fn read_variable(&mut self, target: &'a str, kind: ReferenceKind, name: &str) {
+        match kind {
+            ReferenceKind::SpriteVariable => self.reads.insert((target, name.to_owned())),
+            ReferenceKind::GlobalVariable => self.reads.insert(("", name.to_owned())),
+            _ => false,
+        };
+    }
+
+    fn stack(
+        &mut self,
+        target: &'a str,
+        is_stage: bool,
+        path: &BlockPath,
+        stack: &'a StackBuilder,
+        top_level: bool,
+    ) {
+        let blocks = stack.blocks();
+        for (idx, block) in blocks.iter().enumerate() {
+            let visit = Visit {
+                target,
+                is_stage,
+                path: path.join(PathSegment::Block(idx)),
+            };
+            let block = match block {
+                BlockBuilder::Normal(block) => block,
+                BlockBuilder::VarList(varlist) => {
+                    let reference = varlist.reference();
+                    self.read_variable(target, reference.kind, &reference.name);
+                    if top_level && idx == 0 {
+                        let kind = match varlist.kind() {
+                            ListOrVariable::Variable => "variable",
+                            ListOrVariable::List => "list",
+                        };
+                        let message = format!("{kind} `{}` isn't used by anything", varlist.name());
+                        self.report(Rule::OrphanReporter, &visit, message);
+                    }
+                    continue;
+                }
+            };
+            if top_level && idx == 0 && !block.is_shadow() && is_reporter(block.opcode()) {
+                let message = format!("reporter `{}` isn't used by anything", block.opcode());
+                self.report(Rule::OrphanReporter, &visit, message);
+            }
+            if idx + 1 < blocks.len() && is_cap(block) {
+                let message = format!("blocks after `{}` never run", block.opcode());
+                self.report(Rule::BlockAfterCap, &visit, message);
+            }
+            self.block(block, visit);
+        }
+    }
+
+    fn block(&mut self, block: &'a BlockNormalBuilder, visit: Visit<'a>) {
+        let opcode = block.opcode();
+        if visit.is_stage && !block.is_shadow() && opcode.starts_with("motion_") {
+            let message = format!("`{opcode}` doesn't work on the Stage");
+            self.report(Rule::MotionOnStage, &visit, message);
+        }
+        if opcode == "control_stop" {
+            self.stop(block, &visit);
+        }
+        if opcode == "sensing_of" {
+            if let Some(property) = block.fields().get("PROPERTY") {
+                self.reads_of.insert(property.value().to_owned());
+            }
+        }
+        if opcode == "event_whenbroadcastreceived" {
+            if let Some(name) = block_field(block, "BROADCAST_OPTION") {
+                self.receivers.insert(name.to_lowercase());
+            }
+        }
+        for field in block.fields().values() {
+            let scope = match field.kind() {
+                FieldKind::SpriteVariable => visit.target,
+                FieldKind::GlobalVariable => "",
+                _ => continue,
+            };
+            let key = (scope, field.value().to_owned());
+            match opcode {
+                "data_setvariableto" | "data_changevariableby" | "control_for_each" => {}
+                _ => {
+                    self.reads.insert(key);
+                    continue;
+                }
+            }
+            let path = visit.path.clone();
+            let write = Visit { path, ..visit };
+            self.writes.push((key, write));
+        }
+
+        // Nested stacks and reporters, sorted by key so findings are in the same order every time
+        let mut inputs: Vec<_> = block.inputs().iter().collect();
+        inputs.sort_by_key(|(name, _)| *name);
+        for (key, input) in inputs {
+            for value in input.values().iter().flatten() {
+                match value {
+                    StackOrValue::Stack(stack) => {
+                        let path = visit.path.join(PathSegment::Input(key.to_owned()));
+                        self.stack(visit.target, visit.is_stage, &path, stack, false);
+                    }
+                    StackOrValue::Value(BlockInputValue::Broadcast { name, .. })
+                        if is_broadcast(opcode) =>
+                    {
+                        let sent = Visit {
+                            path: visit.path.clone(),
+                            ..visit
+                        };
+                        self.broadcasts.push((name.to_lowercase(), sent));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // Broadcast picked from a menu
+        if is_broadcast(opcode) {
+            let menu = block
+                .inputs()
+                .get("BROADCAST_INPUT")
+                .and_then(|input| input.values().first())
+                .and_then(|value| match value {
+                    Some(StackOrValue::Stack(stack)) => match stack.blocks().first() {
+                        Some(BlockBuilder::Normal(menu)) => block_field(menu, "BROADCAST_OPTION"),
+                        _ => None,
+                    },
+                    _ => None,
+                });
+            if let Some(name) = menu {
+                self.broadcasts.push((name.to_lowercase(), visit));
+            }
+        }
+    }
+
+    fn stop(&mut self, block: &BlockNormalBuilder, visit: &Visit) {
+        let option = block_field(block, "STOP_OPTION").unwrap_or_default();
+        let should_have_next =
+            matches!(option, "other scripts in sprite" | "other scripts in stage");
+        let has_next = match block.mutation_ref().map(|mutation| &mutation.mutation_enum) {
+            Some(BlockMutationEnum::ControlStop { hasnext }) => *hasnext,
+            _ => false,
+        };
+        if has_next != should_have_next {
+            let message = format!("`stop {option}` should have has_next as {should_have_next}");
+            self.report(Rule::StopHasNext, visit, message);
+        }
+    }
+
+    /// Monitored variables count as read
+    fn finish(mut self, monitors: &'a [MonitorBuilder]) -> LintReport {
+        for (name, visit) in std::mem::take(&mut self.broadcasts) {
+            if !self.receivers.contains(&name) {
+                let message = format!("nothing receives broadcast `{name}`");
+                self.report(Rule::UnreceivedBroadcast, &visit, message);
+            }
+        }
+        for monitor in monitors {
+            if let Some((sprite_name, reference)) = monitor.reference() {
+                self.read_variable(
+                    sprite_name.unwrap_or_default(),
+                    reference.kind,
+                    &reference.name,
+                );
+            }
+        }
+        let mut reported = HashSet::new();
+        for (key, visit) in std::mem::take(&mut self.writes) {
+            if self.reads.contains(&key)
+                || self.reads_of.contains(&key.1)
+                || !reported.insert(key.clone())
+            {
+                continue;
+            }
+            let message = format!("variable `{}` is set but never read", key.1);
+            self.report(Rule::UnreadVariable, &visit, message);
+        }
+        LintReport {
+            findings: self.findings,
+        }
+    }
+}
+
+/// Blocks that nothing can be stacked under
+fn is_cap(block: &BlockNormalBuilder) -> bool {
+    match block.opcode() {
+        "control_forever" | "control_delete_this_clone" => true,
+        "control_stop" => matches!(
+            block_field(block, "STOP_OPTION"),
+            Some("all" | "this script")
+        ),
+        _ => false,
+    }
+}
+
+fn is_broadcast(opcode: &str) -> bool {
+    matches!(opcode, "event_broadcast" | "event_broadcastandwait")
+}
+
+fn block_field<'a>(block: &'a BlockNormalBuilder, key: &str) -> Option<&'a str> {
+    block.fields().get(key).map(|field| field.value())
+}
@@ -1,26 +1,47 @@
 use std::collections::HashMap;
+use std::io::{Read, Seek};
 
-use crate::uid::Uid;
+use crate::uid::{Uid, UidGenerator};
+use lint::{lint_project, LintConfig, LintReport};
+use monitor::MonitorBuilder;
 use rs_sb3::{
-    monitor::Monitor,
+    block::Block,
     project::{Meta, Project},
     target::SpriteOrStage,
 };
-use target::{SpriteBuilder, StageBuilder};
+use target::{SpriteBuilder, StageBuilder, TargetBuilder};
+use validation::{
+    declare_missing, duplicate_arguments, duplicate_procedures, out_of_scope, unresolved_names,
+    wrong_argument_counts, BuildError,
+};
 
-use crate::resource::Resource;
+use crate::{
+    import::{read_zip, ImportError},
+    opcode::{extension_of, EXTENSIONS},
+    resource::Resource,
+    typed_scripting::arg::SpriteHandle,
+};
 
 pub mod asset;
+mod layout;
+pub mod lint;
+pub mod monitor;
 pub mod script;
 pub mod target;
+pub mod validation;
 
 #[rustfmt::skip]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProjectBuilder {
     pub stage_builder:   StageBuilder,
     pub sprite_builders: Vec<SpriteBuilder>,
-    pub monitors:        Vec<Monitor>,
+    pub monitors:        Vec<MonitorBuilder>,
     pub meta:            Meta,
+    /// Declares missing broadcasts, variables and lists instead of failing the build.
+    /// Blocks that are out of their target's scope still fail it.
+    pub lenient:         bool,
+    /// Seed for the uids. Same seed gives the same uids.
+    pub uid_seed:        u64,
 }
 
 impl ProjectBuilder {
@@ -33,49 +54,145 @@ impl ProjectBuilder {
         self
     }
 
-    pub fn add_sprite(mut self, sprite_builder: SpriteBuilder) -> Self {
-        self.sprite_builders.push(sprite_builder);
+    pub fn add_sprite<B>(mut self, sprite_builder: SpriteBuilder<B>) -> Self {
+        self.sprite_builders.push(sprite_builder.unbranded());
+        self
+    }
+
+    /// Same as [`ProjectBuilder::add_sprite`] but returns the handle to use in blocks.
+    pub fn declare_sprite<B>(&mut self, sprite_builder: SpriteBuilder<B>) -> SpriteHandle {
+        let handle = SpriteHandle::assume_declared(sprite_builder.target().name());
+        self.sprite_builders.push(sprite_builder.unbranded());
+        handle
+    }
+
+    pub fn add_monitor(mut self, monitor_builder: MonitorBuilder) -> Self {
+        self.monitors.push(monitor_builder);
+        self
+    }
+
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
         self
     }
+
+    pub fn uid_seed(mut self, uid_seed: u64) -> Self {
+        self.uid_seed = uid_seed;
+        self
+    }
+
+    /// Shortcut to [`lint::lint_project`]
+    pub fn lint(&self, config: &LintConfig) -> LintReport {
+        lint_project(self, config)
+    }
+
+    /// Shortcut to [`crate::import::read_zip`]
+    pub fn from_sb3<R: Read + Seek>(reader: R) -> Result<ProjectBuilder, ImportError> {
+        read_zip(reader)
+    }
 }
 
 impl ProjectBuilder {
-    pub fn build(self, res_buf: &mut Vec<Resource>) -> Project {
+    /// Fails when blocks use a name that isn't declared anywhere,
+    /// are in a target they can't be in, when a target has two procedures with the same proccode,
+    /// when a procedure has two arguments with the same name
+    /// when a procedure is called with the wrong number of arguments
+    /// or when a monitor can't be built.
+    pub fn build(self, res_buf: &mut Vec<Resource>) -> Result<Project, Box<BuildError>> {
         let ProjectBuilder {
-            stage_builder,
-            sprite_builders,
+            mut stage_builder,
+            mut sprite_builders,
             monitors,
             meta,
+            lenient,
+            uid_seed,
         } = self;
 
-        let all_broadcasts: HashMap<String, Uid> = stage_builder
-            .target()
-            .broadcasts()
-            .iter()
-            .chain(
-                sprite_builders
-                    .iter()
-                    .flat_map(|sb| sb.target().broadcasts()),
-            )
-            .map(|(name, uid)| (name.clone(), uid.clone()))
-            .collect::<HashMap<_, _>>();
+        if lenient {
+            declare_missing(&mut stage_builder, &mut sprite_builders, &monitors);
+        }
+        let unresolved = unresolved_names(&stage_builder, &sprite_builders, &monitors);
+        let error = BuildError {
+            unresolved,
+            out_of_scope: out_of_scope(&stage_builder, &sprite_builders),
+            duplicate_procedures: duplicate_procedures(&stage_builder, &sprite_builders),
+            duplicate_arguments: duplicate_arguments(&stage_builder, &sprite_builders),
+            wrong_argument_counts: wrong_argument_counts(&stage_builder, &sprite_builders),
+            monitors: vec![],
+        };
+        if !error.is_empty() {
+            return Err(Box::new(error));
+        }
+
+        let mut uid_gen = UidGenerator::new(uid_seed);
+        let target_builders: Vec<&TargetBuilder> = std::iter::once(stage_builder.target())
+            .chain(sprite_builders.iter().map(|sb| sb.target()))
+            .collect();
+        let broadcasts = || target_builders.iter().flat_map(|tb| tb.broadcasts());
+        // Broadcasts that already have uid goes first so they don't get a derived one.
+        let mut all_broadcasts: HashMap<String, Uid> = HashMap::default();
+        for (name, uid) in broadcasts() {
+            if let Some(uid) = uid {
+                uid_gen.reserve(uid);
+                all_broadcasts.insert(name.clone(), uid.clone());
+            }
+        }
+        let mut unassigned: Vec<&String> = broadcasts()
+            .filter(|(name, _)| !all_broadcasts.contains_key(*name))
+            .map(|(name, _)| name)
+            .collect();
+        unassigned.sort();
+        unassigned.dedup();
+        for name in unassigned {
+            let uid = uid_gen.derive(&format!("broadcast/{name}"));
+            all_broadcasts.insert(name.clone(), uid);
+        }
 
         let mut targets = Vec::with_capacity(1 + sprite_builders.len());
-        let (stage, global_varlist_buf) = stage_builder.build(res_buf, &all_broadcasts);
+        let (stage, global_varlist_buf) =
+            stage_builder.build(res_buf, &mut uid_gen, &all_broadcasts);
         targets.push(SpriteOrStage::Stage(stage));
-        targets.extend(sprite_builders.into_iter().map(|sprite_builder| {
-            SpriteOrStage::Sprite(sprite_builder.build(
+        for sprite_builder in sprite_builders {
+            targets.push(SpriteOrStage::Sprite(sprite_builder.build(
                 res_buf,
+                &mut uid_gen,
                 &global_varlist_buf,
                 &all_broadcasts,
-            ))
-        }));
-        Project {
+            )));
+        }
+        let extensions = EXTENSIONS
+            .iter()
+            .filter(|extension| {
+                targets.iter().any(|target| {
+                    let target = match target {
+                        SpriteOrStage::Stage(stage) => &stage.target,
+                        SpriteOrStage::Sprite(sprite) => &sprite.target,
+                    };
+                    target.blocks.0.values().any(|block| match block {
+                        Block::Normal(block) => extension_of(&block.opcode) == Some(**extension),
+                        Block::VarList(_) => false,
+                    })
+                })
+            })
+            .map(|extension| serde_json::value::Value::String((*extension).to_owned()))
+            .collect();
+        let (monitors, monitor_errors): (Vec<_>, Vec<_>) = monitors
+            .into_iter()
+            .map(|monitor_builder| monitor_builder.build(&targets))
+            .partition(Result::is_ok);
+        if !monitor_errors.is_empty() {
+            return Err(Box::new(BuildError {
+                monitors: monitor_errors.into_iter().filter_map(Result::err).collect(),
+                ..BuildError::default()
+            }));
+        }
+        let monitors = monitors.into_iter().filter_map(Result::ok).collect();
+        Ok(Project {
             meta,
-            extensions: serde_json::value::Value::Array(vec![]),
+            extensions: serde_json::value::Value::Array(extensions),
             monitors,
             targets,
-        }
+        })
     }
 }
 
@@ -91,6 +208,8 @@ impl Default for ProjectBuilder {
                 vm:     "0.2.0-prerelease.20220222132735".to_owned(),
                 agent:  "mcscratchy/0.1.0".to_owned(),
             },
+            lenient:         false,
+            uid_seed:        0,
         }
     }
 }
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use rs_sb3::{
+    block::ListOrVariable,
+    monitor::Monitor,
+    target::{SpriteOrStage, Target},
+};
+
+use crate::scripting::script_builder::{NameReference, ReferenceKind, VarListFrom};
+
+/// Reporters that can be monitored but isn't a variable or a list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinReporter {
+    Timer,
+    Answer,
+    Loudness,
+}
+
+impl BuiltinReporter {
+    fn id(&self) -> &'static str {
+        match self {
+            BuiltinReporter::Timer => "timer",
+            BuiltinReporter::Answer => "answer",
+            BuiltinReporter::Loudness => "loudness",
+        }
+    }
+
+    fn opcode(&self) -> &'static str {
+        match self {
+            BuiltinReporter::Timer => "sensing_timer",
+            BuiltinReporter::Answer => "sensing_answer",
+            BuiltinReporter::Loudness => "sensing_loudness",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MonitorKind {
+    VarList {
+        kind: ListOrVariable,
+        from: VarListFrom,
+        /// Name of the sprite when `from` is [`VarListFrom::Sprite`]
+        sprite_name: Option<String>,
+        name: String,
+    },
+    Builtin(BuiltinReporter),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorError {
+    /// Variable or list to show isn't in the target, or the sprite doesn't exist.
+    /// `sprite_name` is `None` for the Stage.
+    Missing {
+        sprite_name: Option<String>,
+        name: String,
+    },
+    /// Built monitor isn't a valid monitor of project.json. Contains the monitor id and the reason
+    Invalid { id: String, reason: String },
+}
+
+impl std::error::Error for MonitorError {}
+
+impl std::fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorError::Missing {
+                sprite_name: Some(sprite_name),
+                name,
+            } => write!(
+                f,
+                "monitor of `{name}` in sprite `{sprite_name}` that doesn't exist"
+            ),
+            MonitorError::Missing {
+                sprite_name: None,
+                name,
+            } => write!(f, "monitor of `{name}` in the Stage that doesn't exist"),
+            MonitorError::Invalid { id, reason } => write!(f, "invalid monitor `{id}`: {reason}"),
+        }
+    }
+}
+
+/// Mode of a variable or a reporter monitor. List monitor is always in list mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MonitorMode {
+    #[default]
+    Normal,
+    Large,
+    Slider {
+        min: f64,
+        max: f64,
+    },
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorBuilder {
+    kind:        MonitorKind,
+    mode:        MonitorMode,
+    is_discrete: bool,
+    x:           f64,
+    y:           f64,
+    visible:     bool,
+    /// Only for list monitor. 0 means default size
+    width:       u64,
+    height:      u64,
+}
+
+impl MonitorBuilder {
+    fn with_kind(kind: MonitorKind) -> MonitorBuilder {
+        MonitorBuilder {
+            kind,
+            mode: MonitorMode::Normal,
+            is_discrete: true,
+            x: 0.,
+            y: 0.,
+            visible: true,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    pub fn global_variable<S: Into<String>>(name: S) -> MonitorBuilder {
+        MonitorBuilder::with_kind(MonitorKind::VarList {
+            kind: ListOrVariable::Variable,
+            from: VarListFrom::Global,
+            sprite_name: None,
+            name: name.into(),
+        })
+    }
+
+    pub fn sprite_variable<S1: Into<String>, S2: Into<String>>(
+        sprite_name: S1,
+        name: S2,
+    ) -> MonitorBuilder {
+        MonitorBuilder::with_kind(MonitorKind::VarList {
+            kind: ListOrVariable::Variable,
+            from: VarListFrom::Sprite,
+            sprite_name: Some(sprite_name.into()),
+            name: name.into(),
+        })
+    }
+
+    pub fn global_list<S: Into<String>>(name: S) -> MonitorBuilder {
+        MonitorBuilder::with_kind(MonitorKind::VarList {
+            kind: ListOrVariable::List,
+            from: VarListFrom::Global,
+            sprite_name: None,
+            name: name.into(),
+        })
+    }
+
+    pub fn sprite_list<S1: Into<String>, S2: Into<String>>(
+        sprite_name: S1,
+        name: S2,
+    ) -> MonitorBuilder {
+        MonitorBuilder::with_kind(MonitorKind::VarList {
+            kind: ListOrVariable::List,
+            from: VarListFrom::Sprite,
+            sprite_name: Some(sprite_name.into()),
+            name: name.into(),
+        })
+    }
+
+    pub fn builtin(reporter: BuiltinReporter) -> MonitorBuilder {
+        MonitorBuilder::with_kind(MonitorKind::Builtin(reporter))
+    }
+
+    /// Ignored for list monitor
+    pub fn mode(mut self, mode: MonitorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Shortcut for `.mode(MonitorMode::Slider { min, max })`
+    pub fn slider(self, min: f64, max: f64) -> Self {
+        self.mode(MonitorMode::Slider { min, max })
+    }
+
+    /// Whether the slider only goes by whole number
+    pub fn discrete(mut self, is_discrete: bool) -> Self {
+        self.is_discrete = is_discrete;
+        self
+    }
+
+    pub fn pos(mut self, x: f64, y: f64) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Only for list monitor
+    pub fn size(mut self, width: u64, height: u64) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// The name this monitor uses and the name of the sprite it's in. `None` sprite is the Stage.
+    pub(crate) fn reference(&self) -> Option<(Option<&str>, NameReference)> {
+        let MonitorKind::VarList {
+            kind,
+            from,
+            sprite_name,
+            name,
+        } = &self.kind
+        else {
+            return None;
+        };
+        let (kind, opcode) = match (kind, from) {
+            (ListOrVariable::Variable, VarListFrom::Global) => {
+                (ReferenceKind::GlobalVariable, "data_variable")
+            }
+            (ListOrVariable::Variable, VarListFrom::Sprite) => {
+                (ReferenceKind::SpriteVariable, "data_variable")
+            }
+            (ListOrVariable::List, VarListFrom::Global) => {
+                (ReferenceKind::GlobalList, "data_listcontents")
+            }
+            (ListOrVariable::List, VarListFrom::Sprite) => {
+                (ReferenceKind::SpriteList, "data_listcontents")
+            }
+        };
+        Some((
+            sprite_name.as_deref(),
+            NameReference {
+                name: name.clone(),
+                kind,
+                opcode: opcode.to_owned(),
+                owner: None,
+            },
+        ))
+    }
+
+    /// Requires:
+    /// - targets: Built targets to find the variable or list id from
+    ///
+    /// Returns:
+    /// - [`MonitorError::Missing`] when the variable or the list doesn't exist
+    pub fn build(self, targets: &[SpriteOrStage]) -> Result<Monitor, MonitorError> {
+        let MonitorBuilder {
+            kind,
+            mode,
+            is_discrete,
+            x,
+            y,
+            visible,
+            width,
+            height,
+        } = self;
+        let (slider_min, slider_max) = match mode {
+            MonitorMode::Slider { min, max } => (min, max),
+            _ => (0., 100.),
+        };
+        let mode = match mode {
+            MonitorMode::Normal => "default",
+            MonitorMode::Large => "large",
+            MonitorMode::Slider { .. } => "slider",
+        };
+        let (id, mode, opcode, params, sprite_name, value) = match kind {
+            MonitorKind::VarList {
+                kind,
+                from,
+                sprite_name,
+                name,
+            } => {
+                let target = targets.iter().find_map(|target| match (target, from) {
+                    (SpriteOrStage::Stage(stage), VarListFrom::Global) => Some(&stage.target),
+                    (SpriteOrStage::Sprite(sprite), VarListFrom::Sprite)
+                        if Some(&sprite.target.name) == sprite_name.as_ref() =>
+                    {
+                        Some(&sprite.target)
+                    }
+                    _ => None,
+                });
+                let Some((id, value)) =
+                    target.and_then(|target| find_varlist(target, &kind, &name))
+                else {
+                    return Err(MonitorError::Missing { sprite_name, name });
+                };
+                let (mode, opcode, param) = match kind {
+                    ListOrVariable::Variable => (mode, "data_variable", "VARIABLE"),
+                    ListOrVariable::List => ("list", "data_listcontents", "LIST"),
+                };
+                let params: HashMap<&str, String> = HashMap::from([(param, name)]);
+                (id, mode, opcode, params, sprite_name, value)
+            }
+            MonitorKind::Builtin(reporter) => (
+                reporter.id().to_owned(),
+                mode,
+                reporter.opcode(),
+                HashMap::default(),
+                None,
+                serde_json::json!(0),
+            ),
+        };
+        // Goes through the json representation which is the same as in project.json
+        let invalid = |reason: serde_json::Error| MonitorError::Invalid {
+            id: id.clone(),
+            reason: reason.to_string(),
+        };
+        let monitor = serde_json::json!({
+            "id": id,
+            "mode": mode,
+            "opcode": opcode,
+            "params": params,
+            "spriteName": sprite_name,
+            "value": value,
+            "width": width,
+            "height": height,
+            "x": x,
+            "y": y,
+            "visible": visible,
+            "sliderMin": slider_min,
+            "sliderMax": slider_max,
+            "isDiscrete": is_discrete,
+        });
+        serde_json::from_value(monitor).map_err(invalid)
+    }
+}
+
+/// Id and the current value of the variable or list
+fn find_varlist(
+    target: &Target,
+    kind: &ListOrVariable,
+    name: &str,
+) -> Option<(String, serde_json::Value)> {
+    match kind {
+        ListOrVariable::Variable => target
+            .variables
+            .0
+            .iter()
+            .find(|(_, var)| var.name == name)
+            .map(|(id, var)| {
+                (
+                    id.clone(),
+                    serde_json::to_value(&var.value).unwrap_or_default(),
+                )
+            }),
+        ListOrVariable::List => target
+            .lists
+            .0
+            .iter()
+            .find(|(_, list)| list.name == name)
+            .map(|(id, list)| {
+                (
+                    id.clone(),
+                    serde_json::to_value(&list.values).unwrap_or_default(),
+                )
+            }),
+    }
+}
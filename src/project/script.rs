@@ -25,18 +25,16 @@ impl VariableBuilder {
         }
     }
 
-    pub fn build(self, name_for_this_var: String) -> (Variable, Uid) {
+    pub fn build(self, name_for_this_var: String) -> Variable {
         let VariableBuilder {
             value,
             is_cloud_variable,
         } = self;
-        let my_uid = Uid::generate();
-        let var = Variable {
+        Variable {
             name: name_for_this_var,
             value,
             is_cloud_variable,
-        };
-        (var, my_uid)
+        }
     }
 }
 
@@ -50,14 +48,12 @@ impl ListBuilder {
         ListBuilder { values }
     }
 
-    pub fn build(self, name_for_this_list: String) -> (List, Uid) {
+    pub fn build(self, name_for_this_list: String) -> List {
         let ListBuilder { values } = self;
-        let my_uid = Uid::generate();
-        let list = List {
+        List {
             name: name_for_this_list,
             values,
-        };
-        (list, my_uid)
+        }
     }
 }
 
@@ -87,6 +83,21 @@ impl CommentBuilder {
         self
     }
 
+    pub fn mut_pos(&mut self, x: f64, y: f64) -> &mut Self {
+        self.x = Some(x);
+        self.y = Some(y);
+        self
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Position set by [`CommentBuilder::pos`]
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
+
     pub fn size(mut self, width: u64, height: u64) -> Self {
         self.width = width;
         self.height = height;
@@ -115,8 +126,8 @@ impl CommentBuilder {
         } = self;
         let comment = Comment {
             block_id: block_uid.map(|u| u.into_inner()),
-            x: x.map(|n| n.into()),
-            y: y.map(|n| n.into()),
+            x: Some(x.unwrap_or(0.).into()),
+            y: Some(y.unwrap_or(0.).into()),
             width: (width as i64).into(),
             height: (height as i64).into(),
             minimized,
@@ -131,8 +142,8 @@ impl Default for CommentBuilder {
     fn default() -> Self {
         CommentBuilder {
             block_uid: None,
-            x:         Some(0.),
-            y:         Some(0.),
+            x:         None,
+            y:         None,
             width:     200,
             height:    200,
             minimized: false,
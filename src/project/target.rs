@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, marker::PhantomData};
 
 use rs_sb3::{
     asset::{Costume, Sound},
@@ -11,10 +11,21 @@ use rs_sb3::{
     variable::Variable,
 };
 
+use super::layout::layout_stacks;
 use crate::{
     resource::Resource,
-    scripting::script_builder::{StackBuilder, TargetContext},
-    uid::Uid,
+    scripting::{
+        procedure::{ProcedureBuilder, ProcedureContext},
+        script_builder::{NameReference, StackBuilder, TargetContext},
+    },
+    typed_scripting::{
+        arg::{
+            BackdropHandle, BroadcastHandle, CostumeHandle, GlobalList, GlobalVar, SoundHandle,
+            SpriteList, SpriteVar,
+        },
+        script_builder::{OwnedBy, SpriteScope, StageScope, TypedStackBuilder},
+    },
+    uid::{Uid, UidGenerator},
 };
 
 use super::{
@@ -33,9 +44,11 @@ pub struct TargetBuilder {
     name:            String,
     variables:       HashMap<String, VariableBuilder>,
     lists:           HashMap<String, ListBuilder>,
-    broadcasts:      HashMap<String, Uid>,
+    /// Broadcast without uid will have its uid derived from the name when building
+    broadcasts:      HashMap<String, Option<Uid>>,
     block_stackes:   Vec<StackBuilder>,
-    comments:        HashMap<Uid, Comment>,
+    procedures:      Vec<ProcedureBuilder>,
+    comments:        Vec<Comment>,
     costumes:        Vec<CostumeBuilder>,
     sounds:          Vec<SoundBuilder>,
     current_costume: u64,
@@ -66,18 +79,36 @@ impl TargetBuilder {
     }
 
     pub fn add_broadcast<S: Into<String>>(mut self, name: S) -> Self {
-        self.broadcasts.insert(name.into(), Uid::generate());
+        self.broadcasts.insert(name.into(), None);
         self
     }
 
+    /// Keeps the broadcast id, uses when the id is already referenced by some blocks.
+    pub(crate) fn add_broadcast_with_uid<S: Into<String>>(mut self, name: S, uid: Uid) -> Self {
+        self.broadcasts.insert(name.into(), Some(uid));
+        self
+    }
+
+    /// Same as [`TargetBuilder::add_broadcast`] but returns the handle to use in blocks.
+    pub fn declare_broadcast<S: Into<String>>(&mut self, name: S) -> BroadcastHandle {
+        let name = name.into();
+        self.broadcasts.entry(name.clone()).or_default();
+        BroadcastHandle::assume_declared(name)
+    }
+
     pub fn add_block_stack(mut self, stack_builder: StackBuilder) -> Self {
         self.block_stackes.push(stack_builder.into());
         self
     }
 
+    /// Blocks that call this procedure is only resolved in this target.
+    pub fn add_procedure(mut self, procedure_builder: ProcedureBuilder) -> Self {
+        self.procedures.push(procedure_builder);
+        self
+    }
+
     pub fn add_comment(mut self, comment_builder: CommentBuilder) -> Self {
-        let comment = comment_builder.build();
-        self.comments.insert(Uid::generate(), comment);
+        self.comments.push(comment_builder.build());
         self
     }
 
@@ -91,6 +122,13 @@ impl TargetBuilder {
         self
     }
 
+    /// Same as [`TargetBuilder::add_sound`] but returns the handle to use in blocks.
+    pub fn declare_sound(&mut self, sound_builder: SoundBuilder) -> SoundHandle {
+        let handle = SoundHandle::assume_declared(sound_builder.name());
+        self.sounds.push(sound_builder);
+        handle
+    }
+
     pub fn current_costume(mut self, index: u64) -> Self {
         self.current_costume = index;
         self
@@ -101,15 +139,66 @@ impl TargetBuilder {
         self
     }
 
-    pub(crate) fn broadcasts(&self) -> &HashMap<String, Uid> {
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub(crate) fn broadcasts(&self) -> &HashMap<String, Option<Uid>> {
         &self.broadcasts
     }
 
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn stacks(&self) -> &[StackBuilder] {
+        &self.block_stackes
+    }
+
+    pub(crate) fn procedures(&self) -> &[ProcedureBuilder] {
+        &self.procedures
+    }
+
+    pub(crate) fn has_variable(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    pub(crate) fn has_list(&self, name: &str) -> bool {
+        self.lists.contains_key(name)
+    }
+
+    pub(crate) fn has_costume(&self, name: &str) -> bool {
+        self.costumes.iter().any(|costume| costume.name() == name)
+    }
+
+    pub(crate) fn has_sound(&self, name: &str) -> bool {
+        self.sounds.iter().any(|sound| sound.name() == name)
+    }
+
+    pub(crate) fn has_procedure(&self, proccode: &str) -> bool {
+        self.procedures
+            .iter()
+            .any(|procedure| procedure.proccode() == proccode)
+    }
+
+    /// Every names used by the blocks in this target
+    pub(crate) fn references(&self) -> Vec<NameReference> {
+        self.procedures
+            .iter()
+            .filter_map(|procedure| procedure.body_ref())
+            .chain(self.block_stackes.iter())
+            .flat_map(|stack| stack.references())
+            .collect()
+    }
+
     /// When global_varlist_buf suppose to be none when the Stage itself is building.
     /// The .1 return value is going to return Some when stage itself is also building.
+    /// `all_broadcasts` have to contains every broadcasts of this target.
     pub fn build(
         self,
         res_buf: &mut Vec<Resource>,
+        uid_gen: &mut UidGenerator,
         global_varlist_ctx: Option<&GlobalVarListContext>,
         all_broadcasts: &HashMap<String, Uid>,
     ) -> (Target, Option<GlobalVarListContext>) {
@@ -119,6 +208,7 @@ impl TargetBuilder {
             lists,
             broadcasts,
             block_stackes,
+            procedures,
             comments,
             costumes,
             sounds,
@@ -129,23 +219,35 @@ impl TargetBuilder {
         let variables: HashMap<String, Variable> = variables
             .into_iter()
             .map(|(var_name, var_builder)| {
-                let (var, uid) = var_builder.build(var_name);
-                (uid.into_inner(), var)
+                let uid = uid_gen.derive(&format!("{name}/variable/{var_name}"));
+                (uid.into_inner(), var_builder.build(var_name))
             })
             .collect();
         let lists: HashMap<String, List> = lists
             .into_iter()
             .map(|(list_name, list_builder)| {
-                let (list, uid) = list_builder.build(list_name);
-                (uid.into_inner(), list)
+                let uid = uid_gen.derive(&format!("{name}/list/{list_name}"));
+                (uid.into_inner(), list_builder.build(list_name))
             })
             .collect();
         let broadcasts: HashMap<String, Broadcast> = broadcasts
-            .into_iter()
-            .map(|(name, uid)| (uid.into_inner(), Broadcast { name }))
+            .into_keys()
+            .map(|broadcast_name| {
+                let uid = all_broadcasts[&broadcast_name].clone();
+                (
+                    uid.into_inner(),
+                    Broadcast {
+                        name: broadcast_name,
+                    },
+                )
+            })
             .collect();
 
-        let mut comments = comments;
+        let mut comments: HashMap<Uid, Comment> = comments
+            .into_iter()
+            .enumerate()
+            .map(|(i, comment)| (uid_gen.derive(&format!("{name}/comment/{i}")), comment))
+            .collect();
         let variable_ctx: HashMap<String, Uid> = variables
             .iter()
             .map(|(uid, var)| (var.name.clone(), Uid::new(uid)))
@@ -154,12 +256,36 @@ impl TargetBuilder {
             .iter()
             .map(|(uid, list)| (list.name.clone(), Uid::new(uid)))
             .collect();
-        let blocks: HashMap<String, Block> = block_stackes
+        let procedure_ctx: HashMap<String, ProcedureContext> = procedures
+            .iter()
+            .map(|procedure| {
+                let proccode = procedure.proccode();
+                let context = procedure.context(uid_gen, &format!("{name}/procedure/{proccode}"));
+                (proccode, context)
+            })
+            .collect();
+        let procedure_stacks: Vec<StackBuilder> = procedures
             .into_iter()
-            .flat_map(|stack_builder| {
+            .map(|procedure| {
+                let context = &procedure_ctx[&procedure.proccode()];
+                procedure.build(context)
+            })
+            .collect();
+        let stack_paths = (0..procedure_stacks.len())
+            .map(|i| format!("{name}/procedure/{i}"))
+            .chain((0..block_stackes.len()).map(|i| format!("{name}/stack/{i}")))
+            .collect::<Vec<_>>();
+        let mut stacks: Vec<StackBuilder> =
+            procedure_stacks.into_iter().chain(block_stackes).collect();
+        layout_stacks(&mut stacks);
+        let blocks: HashMap<String, Block> = stacks
+            .into_iter()
+            .zip(stack_paths)
+            .flat_map(|(stack_builder, path)| {
                 let builded_stack = stack_builder.build(
-                    &Uid::generate(),
+                    &uid_gen.derive(&path),
                     &mut comments,
+                    uid_gen,
                     &match global_varlist_ctx {
                         Some(global_varlist_ctx) => TargetContext {
                             global_vars: &global_varlist_ctx.vars,
@@ -167,6 +293,7 @@ impl TargetBuilder {
                             this_sprite_vars: &variable_ctx,
                             this_sprite_lists: &list_ctx,
                             all_broadcasts,
+                            procedures: &procedure_ctx,
                         },
                         None => TargetContext {
                             global_vars: &variable_ctx,
@@ -174,6 +301,7 @@ impl TargetBuilder {
                             this_sprite_vars: &variable_ctx,
                             this_sprite_lists: &list_ctx,
                             all_broadcasts,
+                            procedures: &procedure_ctx,
                         },
                     },
                 );
@@ -229,7 +357,8 @@ impl Default for TargetBuilder {
             lists:           HashMap::default(),
             broadcasts:      HashMap::default(),
             block_stackes:   Vec::default(),
-            comments:        HashMap::default(),
+            procedures:      Vec::default(),
+            comments:        Vec::default(),
             costumes:        Vec::default(),
             sounds:          Vec::default(),
             current_costume: 0,
@@ -274,13 +403,67 @@ impl StageBuilder {
         self
     }
 
+    /// Same as [`TargetBuilder::add_variable`] but returns the handle to use in blocks.
+    /// A name that's already declared keeps its variable and the handle to it is returned.
+    pub fn declare_global_var<S: Into<String>>(
+        &mut self,
+        name: S,
+        variable_builder: VariableBuilder,
+    ) -> GlobalVar {
+        let name = name.into();
+        self.target
+            .variables
+            .entry(name.clone())
+            .or_insert(variable_builder);
+        GlobalVar::assume_declared(name)
+    }
+
+    /// Same as [`TargetBuilder::add_list`] but returns the handle to use in blocks.
+    /// A name that's already declared keeps its list and the handle to it is returned.
+    pub fn declare_global_list<S: Into<String>>(
+        &mut self,
+        name: S,
+        list_builder: ListBuilder,
+    ) -> GlobalList {
+        let name = name.into();
+        self.target
+            .lists
+            .entry(name.clone())
+            .or_insert(list_builder);
+        GlobalList::assume_declared(name)
+    }
+
+    /// Same as [`TargetBuilder::add_costume`] but returns the handle to use in blocks.
+    pub fn declare_backdrop(&mut self, costume_builder: CostumeBuilder) -> BackdropHandle {
+        let handle = BackdropHandle::assume_declared(costume_builder.name());
+        self.target.costumes.push(costume_builder);
+        handle
+    }
+
+    /// Like [`TargetBuilder::add_block_stack`] but refuses scripts that can only be in a sprite
+    /// ```compile_fail
+    /// # use mcscratchy::project::target::{StageBuilder, TargetBuilder};
+    /// # use mcscratchy::typed_scripting::blocks::*;
+    /// let stage = StageBuilder::new(TargetBuilder::new("Stage"))
+    ///     .add_script(when_flag_clicked().next(move_steps(10)));
+    /// ```
+    pub fn add_script<S, E, C: StageScope>(mut self, script: TypedStackBuilder<S, E, C>) -> Self {
+        self.target.block_stackes.push(script.into_untyped());
+        self
+    }
+
     pub(crate) fn target(&self) -> &TargetBuilder {
         &self.target
     }
 
+    pub(crate) fn target_mut(&mut self) -> &mut TargetBuilder {
+        &mut self.target
+    }
+
     pub fn build(
         self,
         res_buf: &mut Vec<Resource>,
+        uid_gen: &mut UidGenerator,
         all_broadcasts: &HashMap<String, Uid>,
     ) -> (Stage, GlobalVarListContext) {
         let StageBuilder {
@@ -290,7 +473,8 @@ impl StageBuilder {
             video_transparency,
             text_to_speech_language: _,
         } = self;
-        let (target, Some(global_var_list)) = target.build(res_buf, None, all_broadcasts) else {
+        let (target, Some(global_var_list)) = target.build(res_buf, uid_gen, None, all_broadcasts)
+        else {
             panic!("stage suppose to return what global var they had");
         };
         let stage = Stage {
@@ -318,9 +502,32 @@ impl Default for StageBuilder {
     }
 }
 
+/// `B` brands the sprite, the variables and lists it declares can't be used in another sprite.
+/// A brand is any type, ex. `struct Cat;`, and each sprite should have its own.
+/// Sprites in a [`crate::project::ProjectBuilder`] have the `()` brand, their scripts are already added.
+///
+/// A sprite's variable can't be used in another sprite:
+/// ```compile_fail
+/// # use mcscratchy::project::{script::VariableBuilder, target::{SpriteBuilder, TargetBuilder}};
+/// # use mcscratchy::typed_scripting::blocks::*;
+/// struct Cat;
+/// struct Dog;
+/// let mut cat = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+/// let lives = cat.declare_sprite_var("lives", VariableBuilder::new(9.into()));
+/// let dog = SpriteBuilder::<Dog>::new(TargetBuilder::new("Dog"))
+///     .add_script(when_flag_clicked().next(change_var_by(&lives, -1)));
+/// ```
+///
+/// Nor can a sprite go without a brand:
+/// ```compile_fail
+/// # use mcscratchy::project::{target::{SpriteBuilder, TargetBuilder}, ProjectBuilder};
+/// # use mcscratchy::typed_scripting::blocks::*;
+/// let cat = SpriteBuilder::new(TargetBuilder::new("Cat"))
+///     .add_script(when_flag_clicked().next(move_steps(10)));
+/// let project = ProjectBuilder::new().add_sprite(cat);
+/// ```
 #[rustfmt::skip]
-#[derive(Debug, Clone, PartialEq)]
-pub struct SpriteBuilder {
+pub struct SpriteBuilder<B = ()> {
     target:         TargetBuilder,
     visible:        bool,
     x:              f64,
@@ -329,10 +536,12 @@ pub struct SpriteBuilder {
     direction:      f64,
     draggable:      bool,
     rotation_style: RotationStyle,
+    brand:          PhantomData<fn() -> B>,
 }
 
-impl SpriteBuilder {
-    pub fn new(target: TargetBuilder) -> SpriteBuilder {
+impl<B> SpriteBuilder<B> {
+    /// The brand is given as `SpriteBuilder::<Cat>::new(..)`
+    pub fn new(target: TargetBuilder) -> SpriteBuilder<B> {
         SpriteBuilder {
             target,
             ..Default::default()
@@ -370,13 +579,100 @@ impl SpriteBuilder {
         self
     }
 
+    /// Same as [`TargetBuilder::add_variable`] but returns the handle to use in blocks.
+    /// The handle can only be used in this sprite's scripts.
+    /// A name that's already declared keeps its variable and the handle to it is returned.
+    pub fn declare_sprite_var<S: Into<String>>(
+        &mut self,
+        name: S,
+        variable_builder: VariableBuilder,
+    ) -> SpriteVar<OwnedBy<B>> {
+        let name = name.into();
+        self.target
+            .variables
+            .entry(name.clone())
+            .or_insert(variable_builder);
+        SpriteVar::owned(name)
+    }
+
+    /// Same as [`TargetBuilder::add_list`] but returns the handle to use in blocks.
+    /// The handle can only be used in this sprite's scripts.
+    /// A name that's already declared keeps its list and the handle to it is returned.
+    pub fn declare_sprite_list<S: Into<String>>(
+        &mut self,
+        name: S,
+        list_builder: ListBuilder,
+    ) -> SpriteList<OwnedBy<B>> {
+        let name = name.into();
+        self.target
+            .lists
+            .entry(name.clone())
+            .or_insert(list_builder);
+        SpriteList::owned(name)
+    }
+
+    /// Same as [`TargetBuilder::add_costume`] but returns the handle to use in blocks.
+    pub fn declare_costume(&mut self, costume_builder: CostumeBuilder) -> CostumeHandle {
+        let handle = CostumeHandle::assume_declared(costume_builder.name());
+        self.target.costumes.push(costume_builder);
+        handle
+    }
+
+    /// Like [`TargetBuilder::add_block_stack`] but refuses scripts that can only be in the Stage
+    /// or in another sprite.
+    /// ```compile_fail
+    /// # use mcscratchy::project::target::{SpriteBuilder, TargetBuilder};
+    /// # use mcscratchy::typed_scripting::blocks::*;
+    /// struct Cat;
+    /// let sprite = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"))
+    ///     .add_script(when_stage_clicked().next(next_backdrop()));
+    /// ```
+    pub fn add_script<S, E, C: SpriteScope<B>>(
+        mut self,
+        script: TypedStackBuilder<S, E, C>,
+    ) -> Self {
+        self.target.block_stackes.push(script.into_untyped());
+        self
+    }
+
     pub(crate) fn target(&self) -> &TargetBuilder {
         &self.target
     }
 
+    pub(crate) fn target_mut(&mut self) -> &mut TargetBuilder {
+        &mut self.target
+    }
+
+    /// Brands don't matter anymore once the scripts are added
+    pub(crate) fn unbranded(self) -> SpriteBuilder {
+        let SpriteBuilder {
+            target,
+            visible,
+            x,
+            y,
+            size,
+            direction,
+            draggable,
+            rotation_style,
+            brand: _,
+        } = self;
+        SpriteBuilder {
+            target,
+            visible,
+            x,
+            y,
+            size,
+            direction,
+            draggable,
+            rotation_style,
+            brand: PhantomData,
+        }
+    }
+
     pub fn build(
         self,
         res_buf: &mut Vec<Resource>,
+        uid_gen: &mut UidGenerator,
         global_varlist_buf: &GlobalVarListContext,
         all_broadcasts: &HashMap<String, Uid>,
     ) -> Sprite {
@@ -389,10 +685,11 @@ impl SpriteBuilder {
             direction,
             draggable,
             rotation_style,
+            brand: _,
         } = self;
         Sprite {
             target: target
-                .build(res_buf, Some(global_varlist_buf), all_broadcasts)
+                .build(res_buf, uid_gen, Some(global_varlist_buf), all_broadcasts)
                 .0,
             visible,
             x: x.into(),
@@ -406,7 +703,45 @@ impl SpriteBuilder {
     }
 }
 
-impl Default for SpriteBuilder {
+// Brands don't need to implement anything, so these aren't derived
+impl<B> std::fmt::Debug for SpriteBuilder<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpriteBuilder")
+            .field("target", &self.target)
+            .field("visible", &self.visible)
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("size", &self.size)
+            .field("direction", &self.direction)
+            .field("draggable", &self.draggable)
+            .field("rotation_style", &self.rotation_style)
+            .finish()
+    }
+}
+
+impl<B> Clone for SpriteBuilder<B> {
+    fn clone(&self) -> Self {
+        SpriteBuilder {
+            target: self.target.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<B> PartialEq for SpriteBuilder<B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.target == other.target
+            && self.visible == other.visible
+            && self.x == other.x
+            && self.y == other.y
+            && self.size == other.size
+            && self.direction == other.direction
+            && self.draggable == other.draggable
+            && self.rotation_style == other.rotation_style
+    }
+}
+
+impl<B> Default for SpriteBuilder<B> {
     #[rustfmt::skip]
     fn default() -> Self {
         SpriteBuilder {
@@ -418,6 +753,7 @@ impl Default for SpriteBuilder {
             direction:      90.,
             draggable:      false,
             rotation_style: RotationStyle::AllAround,
+            brand:          PhantomData,
         }
     }
 }
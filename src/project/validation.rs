@@ -0,0 +1,531 @@
+use std::collections::HashSet;
+
+use rs_sb3::value::OpCode;
+
+use crate::scripting::{
+    script_builder::{BlockBuilder, NameReference, ReferenceKind, StackBuilder, StackOrValue},
+    spec::spec_of,
+};
+
+use super::{
+    monitor::{MonitorBuilder, MonitorError},
+    script::{ListBuilder, VariableBuilder},
+    target::{SpriteBuilder, StageBuilder, TargetBuilder},
+};
+
+/// Name used by a block that doesn't exist in the project
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedName {
+    pub name:   String,
+    pub kind:   ReferenceKind,
+    /// Name of the target which the block is in
+    pub target: String,
+    pub opcode: OpCode,
+    /// See [`NameReference::owner`]
+    pub owner:  Option<String>,
+}
+
+/// Block in a target it can't be in. Ex. a motion block in the Stage
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfScope {
+    pub opcode: OpCode,
+    /// Name of the target which the block is in
+    pub target: String,
+}
+
+/// Procedure with the same proccode as another procedure in the target
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateProcedure {
+    pub proccode: String,
+    /// Name of the target which the procedures are in
+    pub target:   String,
+}
+
+/// Procedure with two arguments of the same name, calls can't tell them apart
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateArgument {
+    pub proccode: String,
+    pub argument: String,
+    /// Name of the target which the procedure is in
+    pub target:   String,
+}
+
+/// Procedure call that isn't given the number of arguments the procedure has
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrongArgumentCount {
+    pub proccode: String,
+    pub expected: usize,
+    pub given:    usize,
+    /// Name of the target which the call is in
+    pub target:   String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BuildError {
+    pub unresolved: Vec<UnresolvedName>,
+    pub out_of_scope: Vec<OutOfScope>,
+    pub duplicate_procedures: Vec<DuplicateProcedure>,
+    pub duplicate_arguments: Vec<DuplicateArgument>,
+    pub wrong_argument_counts: Vec<WrongArgumentCount>,
+    /// Monitors that failed after the targets are built
+    pub monitors: Vec<MonitorError>,
+}
+
+impl BuildError {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.unresolved.is_empty()
+            && self.out_of_scope.is_empty()
+            && self.duplicate_procedures.is_empty()
+            && self.duplicate_arguments.is_empty()
+            && self.wrong_argument_counts.is_empty()
+            && self.monitors.is_empty()
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} unresolved name(s), {} block(s) out of scope, {} duplicate procedure(s), \
+             {} duplicate argument(s), {} call(s) with the wrong number of arguments, \
+             {} invalid monitor(s)",
+            self.unresolved.len(),
+            self.out_of_scope.len(),
+            self.duplicate_procedures.len(),
+            self.duplicate_arguments.len(),
+            self.wrong_argument_counts.len(),
+            self.monitors.len()
+        )?;
+        for UnresolvedName {
+            name,
+            kind,
+            target,
+            opcode,
+            owner,
+        } in &self.unresolved
+        {
+            write!(f, "\n  {kind} `{name}`")?;
+            if let Some(owner) = owner {
+                write!(f, " of sprite `{owner}`")?;
+            }
+            write!(f, " in target `{target}` used by `{opcode}`")?;
+        }
+        for OutOfScope { opcode, target } in &self.out_of_scope {
+            write!(f, "\n  `{opcode}` can't be in target `{target}`")?;
+        }
+        for DuplicateProcedure { proccode, target } in &self.duplicate_procedures {
+            write!(
+                f,
+                "\n  procedure `{proccode}` is declared more than once in target `{target}`"
+            )?;
+        }
+        for DuplicateArgument {
+            proccode,
+            argument,
+            target,
+        } in &self.duplicate_arguments
+        {
+            write!(
+                f,
+                "\n  procedure `{proccode}` in target `{target}` has more than one argument `{argument}`"
+            )?;
+        }
+        for WrongArgumentCount {
+            proccode,
+            expected,
+            given,
+            target,
+        } in &self.wrong_argument_counts
+        {
+            write!(
+                f,
+                "\n  procedure `{proccode}` takes {expected} argument(s) but is called with {given} in target `{target}`"
+            )?;
+        }
+        for monitor in &self.monitors {
+            write!(f, "\n  {monitor}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the name is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Location {
+    Stage,
+    Sprite(usize),
+    /// Monitor of a sprite that doesn't exist
+    UnknownSprite(String),
+}
+
+fn unresolved(
+    stage: &StageBuilder,
+    sprites: &[SpriteBuilder],
+    monitors: &[MonitorBuilder],
+) -> Vec<(Location, NameReference)> {
+    let stage = stage.target();
+    let broadcasts: HashSet<&String> = stage
+        .broadcasts()
+        .keys()
+        .chain(
+            sprites
+                .iter()
+                .flat_map(|sprite| sprite.target().broadcasts().keys()),
+        )
+        .collect();
+    let sprite_named = |name: &str| {
+        sprites
+            .iter()
+            .map(|sprite| sprite.target())
+            .find(|target| target.name() == name)
+    };
+    let is_resolved = |this: &TargetBuilder, reference: &NameReference| {
+        let this = match &reference.owner {
+            None => this,
+            Some(owner) => match sprite_named(owner) {
+                Some(owner) => owner,
+                // The sprite is unresolved in the menu it's picked from
+                None => return true,
+            },
+        };
+        match reference.kind {
+            ReferenceKind::Broadcast => broadcasts.contains(&reference.name),
+            ReferenceKind::SpriteVariable => this.has_variable(&reference.name),
+            ReferenceKind::GlobalVariable => stage.has_variable(&reference.name),
+            ReferenceKind::SpriteList => this.has_list(&reference.name),
+            ReferenceKind::GlobalList => stage.has_list(&reference.name),
+            ReferenceKind::Procedure => this.has_procedure(&reference.name),
+            ReferenceKind::Costume => this.has_costume(&reference.name),
+            ReferenceKind::Backdrop => stage.has_costume(&reference.name),
+            ReferenceKind::Sound => this.has_sound(&reference.name),
+            ReferenceKind::Sprite => sprite_named(&reference.name).is_some(),
+        }
+    };
+    let block_references = std::iter::once((Location::Stage, stage.references())).chain(
+        sprites
+            .iter()
+            .enumerate()
+            .map(|(i, sprite)| (Location::Sprite(i), sprite.target().references())),
+    );
+    let monitor_references = monitors
+        .iter()
+        .filter_map(|monitor| monitor.reference())
+        .map(|(sprite_name, reference)| {
+            let location = match sprite_name {
+                None => Location::Stage,
+                Some(sprite_name) => sprites
+                    .iter()
+                    .position(|sprite| sprite.target().name() == sprite_name)
+                    .map(Location::Sprite)
+                    .unwrap_or_else(|| Location::UnknownSprite(sprite_name.to_owned())),
+            };
+            (location, vec![reference])
+        });
+    let mut unresolved: Vec<(Location, NameReference)> = vec![];
+    for (location, references) in block_references.chain(monitor_references) {
+        let target = match &location {
+            Location::Stage => Some(stage),
+            Location::Sprite(i) => Some(sprites[*i].target()),
+            Location::UnknownSprite(_) => None,
+        };
+        for reference in references {
+            if target.is_some_and(|target| is_resolved(target, &reference)) {
+                continue;
+            }
+            let entry = (location.clone(), reference);
+            if !unresolved.contains(&entry) {
+                unresolved.push(entry);
+            }
+        }
+    }
+    unresolved
+}
+
+/// Every names that blocks or monitors use but isn't declared
+pub fn unresolved_names(
+    stage: &StageBuilder,
+    sprites: &[SpriteBuilder],
+    monitors: &[MonitorBuilder],
+) -> Vec<UnresolvedName> {
+    unresolved(stage, sprites, monitors)
+        .into_iter()
+        .map(|(location, reference)| {
+            let target = match location {
+                Location::Stage => stage.target().name().to_owned(),
+                Location::Sprite(i) => sprites[i].target().name().to_owned(),
+                Location::UnknownSprite(name) => name,
+            };
+            UnresolvedName {
+                name: reference.name,
+                kind: reference.kind,
+                target,
+                opcode: reference.opcode,
+                owner: reference.owner,
+            }
+        })
+        .collect()
+}
+
+/// Blocks that are in a target they can't be in, see [`crate::scripting::spec::Scope`].
+/// Sprite variables and lists are out of scope in the Stage too.
+pub fn out_of_scope(stage: &StageBuilder, sprites: &[SpriteBuilder]) -> Vec<OutOfScope> {
+    let targets = std::iter::once((stage.target(), true))
+        .chain(sprites.iter().map(|sprite| (sprite.target(), false)));
+    let mut out_of_scope = vec![];
+    for (target, is_stage) in targets {
+        let mut opcodes = vec![];
+        let stacks = target
+            .procedures()
+            .iter()
+            .filter_map(|procedure| procedure.body_ref())
+            .chain(target.stacks());
+        for stack in stacks {
+            collect_out_of_scope(stack, is_stage, &mut opcodes);
+        }
+        if is_stage {
+            opcodes.extend(
+                target
+                    .references()
+                    .into_iter()
+                    .filter(|reference| {
+                        reference.owner.is_none()
+                            && matches!(
+                                reference.kind,
+                                ReferenceKind::SpriteVariable | ReferenceKind::SpriteList
+                            )
+                    })
+                    .map(|reference| reference.opcode),
+            );
+        }
+        for opcode in opcodes {
+            let entry = OutOfScope {
+                opcode,
+                target: target.name().to_owned(),
+            };
+            if !out_of_scope.contains(&entry) {
+                out_of_scope.push(entry);
+            }
+        }
+    }
+    out_of_scope
+}
+
+fn collect_out_of_scope(stack: &StackBuilder, is_stage: bool, opcodes: &mut Vec<OpCode>) {
+    for block in stack.blocks() {
+        let BlockBuilder::Normal(block) = block else {
+            continue;
+        };
+        // Menus are checked with the block they're in
+        if !block.is_shadow() {
+            if let Some(spec) = spec_of(block.opcode()) {
+                let allowed = if is_stage {
+                    spec.scope.allows_stage()
+                } else {
+                    spec.scope.allows_sprite()
+                };
+                if !allowed {
+                    opcodes.push(block.opcode().to_owned());
+                }
+            }
+        }
+        for input in block.inputs().values() {
+            for value in input.values() {
+                if let Some(StackOrValue::Stack(stack)) = value {
+                    collect_out_of_scope(stack, is_stage, opcodes);
+                }
+            }
+        }
+    }
+}
+
+/// Procedures that share their proccode with an earlier procedure in the same target.
+/// Calls can't tell them apart.
+pub fn duplicate_procedures(
+    stage: &StageBuilder,
+    sprites: &[SpriteBuilder],
+) -> Vec<DuplicateProcedure> {
+    let targets =
+        std::iter::once(stage.target()).chain(sprites.iter().map(|sprite| sprite.target()));
+    let mut duplicates = vec![];
+    for target in targets {
+        let mut seen = HashSet::new();
+        for procedure in target.procedures() {
+            let proccode = procedure.proccode();
+            if seen.contains(&proccode) {
+                let entry = DuplicateProcedure {
+                    proccode,
+                    target: target.name().to_owned(),
+                };
+                if !duplicates.contains(&entry) {
+                    duplicates.push(entry);
+                }
+            } else {
+                seen.insert(proccode);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Arguments that share their name with an earlier argument of the same procedure
+pub fn duplicate_arguments(
+    stage: &StageBuilder,
+    sprites: &[SpriteBuilder],
+) -> Vec<DuplicateArgument> {
+    let targets =
+        std::iter::once(stage.target()).chain(sprites.iter().map(|sprite| sprite.target()));
+    let mut duplicates = vec![];
+    for target in targets {
+        for procedure in target.procedures() {
+            let names = procedure.argument_names();
+            for (i, name) in names.iter().enumerate() {
+                let entry = DuplicateArgument {
+                    proccode: procedure.proccode(),
+                    argument: name.clone(),
+                    target: target.name().to_owned(),
+                };
+                if names[..i].contains(name) && !duplicates.contains(&entry) {
+                    duplicates.push(entry);
+                }
+            }
+        }
+    }
+    duplicates
+}
+
+/// Procedure calls that aren't given as many arguments as the procedure in their target has.
+/// Calls to a procedure that isn't declared are unresolved instead.
+pub fn wrong_argument_counts(
+    stage: &StageBuilder,
+    sprites: &[SpriteBuilder],
+) -> Vec<WrongArgumentCount> {
+    let targets =
+        std::iter::once(stage.target()).chain(sprites.iter().map(|sprite| sprite.target()));
+    let mut wrong = vec![];
+    for target in targets {
+        let mut calls = vec![];
+        let stacks = target
+            .procedures()
+            .iter()
+            .filter_map(|procedure| procedure.body_ref())
+            .chain(target.stacks());
+        for stack in stacks {
+            collect_calls(stack, &mut calls);
+        }
+        for (proccode, given) in calls {
+            let Some(procedure) = target
+                .procedures()
+                .iter()
+                .find(|procedure| procedure.proccode() == proccode)
+            else {
+                continue;
+            };
+            let expected = procedure.argument_names().len();
+            let entry = WrongArgumentCount {
+                proccode,
+                expected,
+                given,
+                target: target.name().to_owned(),
+            };
+            if expected != given && !wrong.contains(&entry) {
+                wrong.push(entry);
+            }
+        }
+    }
+    wrong
+}
+
+/// Proccode and argument count of every call made with [`crate::scripting::blocks::call_procedure`]
+fn collect_calls(stack: &StackBuilder, calls: &mut Vec<(String, usize)>) {
+    for block in stack.blocks() {
+        let BlockBuilder::Normal(block) = block else {
+            continue;
+        };
+        if let (Some(proccode), Some(given)) =
+            (block.called_procedure(), block.call_argument_count())
+        {
+            calls.push((proccode.to_owned(), given));
+        }
+        for input in block.inputs().values() {
+            for value in input.values() {
+                if let Some(StackOrValue::Stack(stack)) = value {
+                    collect_calls(stack, calls);
+                }
+            }
+        }
+    }
+}
+
+/// Declares missing broadcasts, variables and lists.
+/// Broadcasts and global variables/lists goes to the Stage.
+/// Variables of another sprite goes to that sprite.
+/// Missing procedures, sprites, costumes, backdrops and sounds can't be declared and will stay unresolved.
+pub fn declare_missing(
+    stage: &mut StageBuilder,
+    sprites: &mut [SpriteBuilder],
+    monitors: &[MonitorBuilder],
+) {
+    for (location, reference) in unresolved(stage, sprites, monitors) {
+        let NameReference {
+            name, kind, owner, ..
+        } = reference;
+        let location = match owner {
+            None => location,
+            Some(owner) => match sprites
+                .iter()
+                .position(|sprite| sprite.target().name() == owner)
+            {
+                Some(i) => Location::Sprite(i),
+                None => continue,
+            },
+        };
+        let target = match (kind, location) {
+            (
+                ReferenceKind::Procedure
+                | ReferenceKind::Costume
+                | ReferenceKind::Backdrop
+                | ReferenceKind::Sound
+                | ReferenceKind::Sprite,
+                _,
+            )
+            | (_, Location::UnknownSprite(_)) => continue,
+            (
+                ReferenceKind::Broadcast
+                | ReferenceKind::GlobalVariable
+                | ReferenceKind::GlobalList,
+                _,
+            )
+            | (_, Location::Stage) => stage.target_mut(),
+            (_, Location::Sprite(i)) => sprites[i].target_mut(),
+        };
+        let declared = match kind {
+            ReferenceKind::Broadcast => target.broadcasts().contains_key(&name),
+            ReferenceKind::SpriteVariable | ReferenceKind::GlobalVariable => {
+                target.has_variable(&name)
+            }
+            ReferenceKind::SpriteList | ReferenceKind::GlobalList => target.has_list(&name),
+            _ => true,
+        };
+        if declared {
+            continue;
+        }
+        let builder = std::mem::take(target);
+        *target = match kind {
+            ReferenceKind::Broadcast => builder.add_broadcast(name),
+            ReferenceKind::SpriteVariable | ReferenceKind::GlobalVariable => {
+                builder.add_variable(name, VariableBuilder::new(0.into()))
+            }
+            ReferenceKind::SpriteList | ReferenceKind::GlobalList => {
+                builder.add_list(name, ListBuilder::new(vec![]))
+            }
+            _ => builder,
+        };
+    }
+}
@@ -0,0 +1,4 @@
+//! [scratchblocks](https://scratchblocks.github.io) text syntax.
+
+pub mod parse;
+pub mod render;
@@ -0,0 +1,1006 @@
+//! Parses scratchblocks text into [`StackBuilder`]s.
+//!
+//! Scripts are separated by a blank line.
+//! C blocks are closed with `end` and `if` can have an `else`.
+//! A `// comment` at the end of a line is attached to the block in that line.
+//! Round reporter that isn't a known block is a variable, `(name :: list)` is a list.
+//! Custom blocks are not supported.
+
+use std::collections::HashSet;
+
+use rs_sb3::block::{BlockInputValue, ShadowInputType};
+
+use crate::{
+    project::script::CommentBuilder,
+    scripting::{
+        blocks,
+        script_builder::{
+            BlockBuilder, BlockFieldBuilder, BlockInputBuilder, FieldKind, StackBuilder,
+            StackOrValue,
+        },
+    },
+};
+
+/// Names of the variables and lists that are in the sprite.
+/// Every other names are global.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Scope {
+    sprite_variables: HashSet<String>,
+    sprite_lists: HashSet<String>,
+}
+
+impl Scope {
+    pub fn new() -> Scope {
+        Scope::default()
+    }
+
+    pub fn sprite_variable<S: Into<String>>(mut self, name: S) -> Self {
+        self.sprite_variables.insert(name.into());
+        self
+    }
+
+    pub fn sprite_list<S: Into<String>>(mut self, name: S) -> Self {
+        self.sprite_lists.insert(name.into());
+        self
+    }
+
+    fn variable_field(&self, name: &str) -> BlockFieldBuilder {
+        let kind = if self.sprite_variables.contains(name) {
+            FieldKind::SpriteVariable
+        } else {
+            FieldKind::GlobalVariable
+        };
+        BlockFieldBuilder::new_with_kind(name.to_owned(), kind)
+    }
+
+    fn list_field(&self, name: &str) -> BlockFieldBuilder {
+        let kind = if self.sprite_lists.contains(name) {
+            FieldKind::SpriteList
+        } else {
+            FieldKind::GlobalList
+        };
+        BlockFieldBuilder::new_with_kind(name.to_owned(), kind)
+    }
+
+    fn variable_reporter(&self, name: &str) -> StackBuilder {
+        if self.sprite_variables.contains(name) {
+            blocks::sprite_var(name)
+        } else {
+            blocks::global_var(name)
+        }
+    }
+
+    fn list_reporter(&self, name: &str) -> StackBuilder {
+        if self.sprite_lists.contains(name) {
+            blocks::sprite_list(name)
+        } else {
+            blocks::global_list(name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Bracket that isn't closed before the end of the line
+    Unclosed(char),
+    /// Closing bracket without the opening one
+    UnexpectedClose(char),
+    /// Text of the block with `_` in place of the arguments
+    UnknownBlock(String),
+    /// `end` or `else` that doesn't close a C block
+    UnexpectedTerminator(String),
+    /// Argument is the wrong kind for the block
+    Expected(&'static str),
+}
+
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Unclosed(bracket) => write!(f, "unclosed `{bracket}`"),
+            ParseErrorKind::UnexpectedClose(bracket) => write!(f, "unexpected `{bracket}`"),
+            ParseErrorKind::UnknownBlock(text) => write!(f, "unknown block `{text}`"),
+            ParseErrorKind::UnexpectedTerminator(word) => {
+                write!(f, "`{word}` without a C block to close")
+            }
+            ParseErrorKind::Expected(what) => write!(f, "expected {what}"),
+        }
+    }
+}
+
+/// `line` and `column` starts at 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+/// Parses every scripts in `source`.
+/// Variables and lists are resolved to the sprite when it's in `scope` or else to global.
+pub fn parse(source: &str, scope: &Scope) -> Result<Vec<StackBuilder>, ParseError> {
+    let mut parser = Parser {
+        lines: source.lines().collect(),
+        next: 0,
+        scope,
+    };
+    let mut scripts = vec![];
+    loop {
+        while parser
+            .lines
+            .get(parser.next)
+            .is_some_and(|line| line.trim().is_empty())
+        {
+            parser.next += 1;
+        }
+        if parser.next >= parser.lines.len() {
+            break;
+        }
+        let (script, terminator) = parser.stack(false)?;
+        if let Some(terminator) = terminator {
+            return Err(terminator.unexpected());
+        }
+        if !script.blocks().is_empty() {
+            scripts.push(script);
+        }
+    }
+    Ok(scripts)
+}
+
+// Lexing ======================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    /// `(10)`
+    Number(String),
+    /// `[text]`
+    Text(String),
+    /// `[#ff0000]`
+    Color(String),
+    /// `[option v]` or `(option v)`
+    Dropdown(String),
+    /// `(block)`
+    Reporter(Vec<Part>),
+    /// `<block>`
+    Boolean(Vec<Part>),
+    /// `()`, `[]` or `<>`
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Arg(Arg),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Part {
+    token: Token,
+    column: usize,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn new(text: &str, line: usize) -> Lexer {
+        Lexer {
+            chars: text.chars().collect(),
+            pos: 0,
+            line,
+        }
+    }
+
+    fn error(&self, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column,
+            kind,
+        }
+    }
+
+    /// Out of bounds counts as a space
+    fn is_space_at(&self, pos: Option<usize>) -> bool {
+        match pos.and_then(|pos| self.chars.get(pos)) {
+            Some(c) => c.is_whitespace(),
+            None => true,
+        }
+    }
+
+    /// `<` or `>` with spaces around is a comparison instead of a bracket.
+    fn is_operator(&self) -> bool {
+        self.is_space_at(self.pos.checked_sub(1)) && self.is_space_at(Some(self.pos + 1))
+    }
+
+    /// Parts until `close` or until the end of the line if `close` is `None`.
+    /// Also returns the `// comment` at the end of the line.
+    fn parts(
+        &mut self,
+        close: Option<char>,
+        open_column: usize,
+    ) -> Result<(Vec<Part>, Option<String>), ParseError> {
+        let mut parts = vec![];
+        loop {
+            while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+                self.pos += 1;
+            }
+            let column = self.pos + 1;
+            let Some(&c) = self.chars.get(self.pos) else {
+                return match close {
+                    Some(_) => Err(self.error(
+                        open_column,
+                        ParseErrorKind::Unclosed(self.chars[open_column - 1]),
+                    )),
+                    None => Ok((parts, None)),
+                };
+            };
+            let arg = match c {
+                _ if Some(c) == close && !(c == '>' && self.is_operator()) => {
+                    self.pos += 1;
+                    return Ok((parts, None));
+                }
+                '/' if close.is_none() && self.chars.get(self.pos + 1) == Some(&'/') => {
+                    let comment: String = self.chars[self.pos + 2..].iter().collect();
+                    self.pos = self.chars.len();
+                    return Ok((parts, Some(comment.trim().to_owned())));
+                }
+                '(' => {
+                    self.pos += 1;
+                    self.round(column)?
+                }
+                '[' => {
+                    self.pos += 1;
+                    self.square(column)?
+                }
+                '<' if !self.is_operator() => {
+                    self.pos += 1;
+                    self.angle(column)?
+                }
+                ')' | ']' => {
+                    return Err(self.error(column, ParseErrorKind::UnexpectedClose(c)));
+                }
+                _ => {
+                    parts.push(Part {
+                        token: Token::Word(self.word(close)),
+                        column,
+                    });
+                    continue;
+                }
+            };
+            parts.push(Part {
+                token: Token::Arg(arg),
+                column,
+            });
+        }
+    }
+
+    fn word(&mut self, close: Option<char>) -> String {
+        let mut word = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            let is_end = c.is_whitespace()
+                || matches!(c, '(' | '[' | ')' | ']')
+                || (c == '>' && close == Some('>'));
+            if is_end && !word.is_empty() {
+                break;
+            }
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    if let Some(&escaped) = self.chars.get(self.pos) {
+                        word.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                c => word.push(c),
+            }
+        }
+        word
+    }
+
+    fn round(&mut self, open_column: usize) -> Result<Arg, ParseError> {
+        let (parts, _) = self.parts(Some(')'), open_column)?;
+        let words: Option<Vec<&str>> = parts
+            .iter()
+            .map(|part| match &part.token {
+                Token::Word(word) => Some(word.as_str()),
+                Token::Arg(_) => None,
+            })
+            .collect();
+        let arg = match words.as_deref() {
+            Some([]) => Arg::Empty,
+            Some([number]) if is_number(number) => Arg::Number(number.to_string()),
+            Some([option @ .., "v"]) if !option.is_empty() => Arg::Dropdown(option.join(" ")),
+            _ => Arg::Reporter(parts),
+        };
+        Ok(arg)
+    }
+
+    fn square(&mut self, open_column: usize) -> Result<Arg, ParseError> {
+        let mut text = String::new();
+        loop {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err(self.error(open_column, ParseErrorKind::Unclosed('[')));
+            };
+            self.pos += 1;
+            match c {
+                ']' => break,
+                '\\' => {
+                    if let Some(&escaped) = self.chars.get(self.pos) {
+                        text.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        let arg = if let Some(option) = text.strip_suffix(" v") {
+            Arg::Dropdown(option.trim().to_owned())
+        } else if text.len() == 7
+            && text.starts_with('#')
+            && text[1..].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            Arg::Color(text)
+        } else if text.is_empty() {
+            Arg::Empty
+        } else {
+            Arg::Text(text)
+        };
+        Ok(arg)
+    }
+
+    fn angle(&mut self, open_column: usize) -> Result<Arg, ParseError> {
+        let (parts, _) = self.parts(Some('>'), open_column)?;
+        if parts.is_empty() {
+            Ok(Arg::Empty)
+        } else {
+            Ok(Arg::Boolean(parts))
+        }
+    }
+}
+
+pub(super) fn is_number(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+        && text.parse::<f64>().is_ok()
+}
+
+// Scripts =====================================================================
+
+struct Terminator {
+    word: String,
+    line: usize,
+    column: usize,
+}
+
+impl Terminator {
+    fn unexpected(self) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind: ParseErrorKind::UnexpectedTerminator(self.word),
+        }
+    }
+}
+
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    next: usize,
+    scope: &'a Scope,
+}
+
+impl<'a> Parser<'a> {
+    /// Blocks until `end`, `else` or the end of the script.
+    /// Blank lines ends the script only when it's not `nested`.
+    fn stack(&mut self, nested: bool) -> Result<(StackBuilder, Option<Terminator>), ParseError> {
+        let mut stack = StackBuilder::new();
+        while let Some(&text) = self.lines.get(self.next) {
+            let line = self.next + 1;
+            if text.trim().is_empty() {
+                if !nested {
+                    break;
+                }
+                self.next += 1;
+                continue;
+            }
+            self.next += 1;
+            let (parts, comment) = Lexer::new(text, line).parts(None, 0)?;
+            if let [Part {
+                token: Token::Word(word),
+                column,
+            }] = parts.as_slice()
+            {
+                if word == "end" || word == "else" {
+                    let terminator = Terminator {
+                        word: word.clone(),
+                        line,
+                        column: *column,
+                    };
+                    return Ok((stack, Some(terminator)));
+                }
+            }
+            let Some(first) = parts.first() else {
+                continue;
+            };
+            let (without_category, _) = category(&parts);
+            let shape = candidates(without_category)
+                .first()
+                .map_or(Shape::Block, |spec| spec.shape);
+            let mut substacks = vec![];
+            if shape != Shape::Block {
+                loop {
+                    let (substack, terminator) = self.stack(true)?;
+                    substacks.push(substack);
+                    match terminator {
+                        Some(terminator) if terminator.word == "else" => {
+                            if shape != Shape::C2 || substacks.len() > 1 {
+                                return Err(terminator.unexpected());
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            let mut block = build_block(&parts, &substacks, line, first.column, self.scope, false)?;
+            if let (Some(comment), Some(BlockBuilder::Normal(head))) =
+                (comment, block.blocks_mut().first_mut())
+            {
+                *head = std::mem::take(head).comment(CommentBuilder::new(comment));
+            }
+            stack = stack.next(block);
+        }
+        Ok((stack, None))
+    }
+}
+
+/// Splits the `:: category` at the end off
+fn category(parts: &[Part]) -> (&[Part], Option<String>) {
+    let Some(idx) = parts
+        .iter()
+        .position(|part| part.token == Token::Word("::".to_owned()))
+    else {
+        return (parts, None);
+    };
+    let category = parts[idx + 1..]
+        .iter()
+        .filter_map(|part| match &part.token {
+            Token::Word(word) => Some(word.to_lowercase()),
+            Token::Arg(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (&parts[..idx], Some(category))
+}
+
+/// Text of the block with `_` in place of the arguments
+fn template_of(parts: &[Part]) -> String {
+    parts
+        .iter()
+        .map(|part| match &part.token {
+            Token::Word(word) => word.as_str(),
+            Token::Arg(_) => "_",
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn build_block(
+    parts: &[Part],
+    substacks: &[StackBuilder],
+    line: usize,
+    column: usize,
+    scope: &Scope,
+    is_reporter: bool,
+) -> Result<StackBuilder, ParseError> {
+    let (parts, category) = category(parts);
+    let words: Option<Vec<&str>> = parts
+        .iter()
+        .map(|part| match &part.token {
+            Token::Word(word) => Some(word.as_str()),
+            Token::Arg(_) => None,
+        })
+        .collect();
+    let name = words.as_ref().map(|words| words.join(" "));
+    match (category.as_deref(), &name) {
+        (Some("list"), Some(name)) => return Ok(scope.list_reporter(name)),
+        (Some("variables"), Some(name)) => return Ok(scope.variable_reporter(name)),
+        _ => {}
+    }
+    let args = Args {
+        args: parts
+            .iter()
+            .filter_map(|part| match &part.token {
+                Token::Arg(arg) => Some((arg, part.column)),
+                Token::Word(_) => None,
+            })
+            .collect(),
+        substacks,
+        line,
+        scope,
+    };
+    let mut first_error = None;
+    for spec in candidates(parts) {
+        match (spec.build)(&args) {
+            Ok(block) => return Ok(block),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    if let Some(error) = first_error {
+        return Err(error);
+    }
+    match (parts, name) {
+        (_, Some(name)) if is_reporter && !name.is_empty() => Ok(scope.variable_reporter(&name)),
+        (
+            [Part {
+                token: Token::Arg(Arg::Reporter(inner) | Arg::Boolean(inner)),
+                column,
+            }],
+            _,
+        ) => build_block(inner, substacks, line, *column, scope, true),
+        _ => Err(ParseError {
+            line,
+            column,
+            kind: ParseErrorKind::UnknownBlock(template_of(parts)),
+        }),
+    }
+}
+
+// Arguments ===================================================================
+
+struct Args<'a> {
+    /// Argument and its column
+    args: Vec<(&'a Arg, usize)>,
+    substacks: &'a [StackBuilder],
+    line: usize,
+    scope: &'a Scope,
+}
+
+impl<'a> Args<'a> {
+    fn expected(&self, i: usize, what: &'static str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.args[i].1,
+            kind: ParseErrorKind::Expected(what),
+        }
+    }
+
+    /// `Some` if the argument is a reporter or a boolean block
+    fn reporter(&self, i: usize) -> Result<Option<BlockInputBuilder>, ParseError> {
+        let (arg, column) = self.args[i];
+        match arg {
+            Arg::Reporter(parts) | Arg::Boolean(parts) => {
+                let block = build_block(parts, &[], self.line, column, self.scope, true)?;
+                Ok(Some(BlockInputBuilder::stack(block)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn number(&self, i: usize) -> Result<BlockInputBuilder, ParseError> {
+        if let Some(reporter) = self.reporter(i)? {
+            return Ok(reporter);
+        }
+        match self.args[i].0 {
+            Arg::Number(text) | Arg::Text(text) | Arg::Dropdown(text) => {
+                let value = if let Ok(number) = text.parse::<i64>() {
+                    number.into()
+                } else if let Ok(number) = text.parse::<f64>() {
+                    number.into()
+                } else {
+                    text.clone().into()
+                };
+                Ok(BlockInputBuilder::value(BlockInputValue::Number { value }))
+            }
+            Arg::Empty => Ok(BlockInputBuilder::value(BlockInputValue::Number {
+                value: String::new().into(),
+            })),
+            _ => Err(self.expected(i, "number")),
+        }
+    }
+
+    fn text(&self, i: usize) -> Result<BlockInputBuilder, ParseError> {
+        if let Some(reporter) = self.reporter(i)? {
+            return Ok(reporter);
+        }
+        match self.args[i].0 {
+            Arg::Number(text) | Arg::Text(text) | Arg::Dropdown(text) | Arg::Color(text) => {
+                Ok(BlockInputBuilder::value(BlockInputValue::String {
+                    value: text.clone().into(),
+                }))
+            }
+            Arg::Empty => Ok(BlockInputBuilder::value(BlockInputValue::String {
+                value: String::new().into(),
+            })),
+            _ => Err(self.expected(i, "text")),
+        }
+    }
+
+    fn boolean(&self, i: usize) -> Result<BlockInputBuilder, ParseError> {
+        match self.args[i].0 {
+            Arg::Boolean(_) | Arg::Reporter(_) => Ok(self.reporter(i)?.unwrap()),
+            Arg::Empty => Ok(BlockInputBuilder::new()),
+            _ => Err(self.expected(i, "boolean")),
+        }
+    }
+
+    fn color(&self, i: usize) -> Result<BlockInputBuilder, ParseError> {
+        if let Some(reporter) = self.reporter(i)? {
+            return Ok(reporter);
+        }
+        match self.args[i].0 {
+            Arg::Color(color) => Ok(BlockInputBuilder::value(BlockInputValue::Color {
+                value: color.clone().into(),
+            })),
+            _ => Err(self.expected(i, "color")),
+        }
+    }
+
+    fn field(&self, i: usize) -> Result<BlockFieldBuilder, ParseError> {
+        match self.args[i].0 {
+            Arg::Dropdown(option) | Arg::Text(option) => Ok(BlockFieldBuilder::new(option.clone())),
+            _ => Err(self.expected(i, "dropdown")),
+        }
+    }
+
+    /// For fields that are in upper case without spaces. Ex. "day of week" is "DAYOFWEEK"
+    fn upper_field(&self, i: usize) -> Result<BlockFieldBuilder, ParseError> {
+        let field = self.field(i)?;
+        Ok(BlockFieldBuilder::new(
+            field.value().replace(' ', "").to_uppercase(),
+        ))
+    }
+
+    /// `options` is a list of shown text and its value
+    fn field_in(
+        &self,
+        i: usize,
+        options: &[(&str, &str)],
+    ) -> Result<BlockFieldBuilder, ParseError> {
+        let field = self.field(i)?;
+        options
+            .iter()
+            .find(|(shown, _)| shown.eq_ignore_ascii_case(field.value()))
+            .map(|(_, value)| BlockFieldBuilder::new(value.to_string()))
+            .ok_or_else(|| self.expected(i, "option of this block"))
+    }
+
+    fn variable(&self, i: usize) -> Result<BlockFieldBuilder, ParseError> {
+        match self.args[i].0 {
+            Arg::Dropdown(name) | Arg::Text(name) => Ok(self.scope.variable_field(name)),
+            _ => Err(self.expected(i, "variable")),
+        }
+    }
+
+    /// Only a dropdown so it's not mistaken for a text input
+    fn list(&self, i: usize) -> Result<BlockFieldBuilder, ParseError> {
+        match self.args[i].0 {
+            Arg::Dropdown(name) => Ok(self.scope.list_field(name)),
+            _ => Err(self.expected(i, "list")),
+        }
+    }
+
+    fn broadcast_field(&self, i: usize) -> Result<BlockFieldBuilder, ParseError> {
+        let field = self.field(i)?;
+        Ok(BlockFieldBuilder::new_with_kind(
+            field.value().to_owned(),
+            FieldKind::Broadcast,
+        ))
+    }
+
+    fn broadcast(&self, i: usize) -> Result<BlockInputBuilder, ParseError> {
+        if let Some(reporter) = self.reporter(i)? {
+            return Ok(reporter);
+        }
+        match self.args[i].0 {
+            Arg::Dropdown(name) | Arg::Text(name) => Ok(BlockInputBuilder::broadcast(name.clone())),
+            _ => Err(self.expected(i, "broadcast")),
+        }
+    }
+
+    fn menu(
+        &self,
+        i: usize,
+        menu: fn(BlockFieldBuilder) -> StackBuilder,
+    ) -> Result<BlockInputBuilder, ParseError> {
+        if let Some(reporter) = self.reporter(i)? {
+            return Ok(reporter);
+        }
+        match self.args[i].0 {
+            Arg::Dropdown(option) | Arg::Text(option) | Arg::Number(option) => {
+                let menu = menu(BlockFieldBuilder::new(menu_value(option)));
+                Ok(BlockInputBuilder::new()
+                    .shadow(ShadowInputType::Shadow)
+                    .input(Some(StackOrValue::Stack(menu))))
+            }
+            _ => Err(self.expected(i, "dropdown")),
+        }
+    }
+
+    /// `None` when the substack is empty
+    fn substack(&self, i: usize) -> Option<BlockInputBuilder> {
+        self.substacks
+            .get(i)
+            .filter(|stack| !stack.blocks().is_empty())
+            .cloned()
+            .map(BlockInputBuilder::stack)
+    }
+}
+
+/// Value of the menu from the text shown in the editor
+fn menu_value(shown: &str) -> String {
+    let value = match shown {
+        "random position" => "_random_",
+        "mouse-pointer" => "_mouse_",
+        "edge" => "_edge_",
+        "myself" => "_myself_",
+        "Stage" => "_stage_",
+        // Numbered menu like "(1) Snare Drum"
+        _ => match shown.strip_prefix('(').and_then(|s| s.split_once(')')) {
+            Some((number, _)) if number.parse::<u32>().is_ok() => number,
+            _ => shown,
+        },
+    };
+    value.to_owned()
+}
+
+// Blocks ======================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Block,
+    /// C block with 1 substack
+    C1,
+    /// C block with 1 or 2 substacks
+    C2,
+}
+
+struct Spec {
+    /// Words of the block with `_` in place of the arguments
+    templates: &'static [&'static str],
+    shape: Shape,
+    build: fn(&Args) -> Result<StackBuilder, ParseError>,
+}
+
+fn matches_template(template: &str, parts: &[Part]) -> bool {
+    let words: Vec<&str> = template.split_whitespace().collect();
+    words.len() == parts.len()
+        && words
+            .iter()
+            .zip(parts)
+            .all(|(word, part)| match &part.token {
+                Token::Arg(_) => *word == "_",
+                Token::Word(text) => *word != "_" && text.eq_ignore_ascii_case(word),
+            })
+}
+
+fn candidates(parts: &[Part]) -> Vec<&'static Spec> {
+    SPECS
+        .iter()
+        .filter(|spec| {
+            spec.templates
+                .iter()
+                .any(|template| matches_template(template, parts))
+        })
+        .collect()
+}
+
+const LOOKS_EFFECTS: &[(&str, &str)] = &[
+    ("color", "COLOR"),
+    ("fisheye", "FISHEYE"),
+    ("whirl", "WHIRL"),
+    ("pixelate", "PIXELATE"),
+    ("mosaic", "MOSAIC"),
+    ("brightness", "BRIGHTNESS"),
+    ("ghost", "GHOST"),
+];
+
+const SOUND_EFFECTS: &[(&str, &str)] = &[("pitch", "PITCH"), ("pan left/right", "PAN")];
+
+const MATH_OPS: &[(&str, &str)] = &[
+    ("abs", "abs"),
+    ("floor", "floor"),
+    ("ceiling", "ceiling"),
+    ("sqrt", "sqrt"),
+    ("sin", "sin"),
+    ("cos", "cos"),
+    ("tan", "tan"),
+    ("asin", "asin"),
+    ("acos", "acos"),
+    ("atan", "atan"),
+    ("ln", "ln"),
+    ("log", "log"),
+    ("e ^", "e ^"),
+    ("10 ^", "10 ^"),
+];
+
+macro_rules! specs {
+    ($($shape:ident [$($template:literal),+] => $build:expr;)*) => {
+        &[$(
+            Spec {
+                templates: &[$($template),+],
+                shape: Shape::$shape,
+                build: $build,
+            },
+        )*]
+    };
+}
+
+// Blocks with the same template are tried in order
+#[rustfmt::skip]
+static SPECS: &[Spec] = specs! {
+    // Control
+    Block ["wait _ seconds", "wait _ secs"] => |a| Ok(blocks::wait(a.number(0)?));
+    C1    ["repeat _"] => |a| Ok(blocks::repeat(a.number(0)?, a.substack(0)));
+    C1    ["forever"] => |a| Ok(blocks::forever(a.substack(0)));
+    C2    ["if _ then", "if _"] => |a| Ok(match a.substacks.len() {
+        2 => blocks::if_else(a.boolean(0)?, a.substack(0), a.substack(1)),
+        _ => blocks::if_(a.boolean(0)?, a.substack(0)),
+    });
+    Block ["wait until _"] => |a| Ok(blocks::wait_until(a.boolean(0)?));
+    C1    ["repeat until _"] => |a| Ok(blocks::repeat_until(a.boolean(0)?, a.substack(0)));
+    Block ["stop _"] => |a| {
+        let option = a.field(0)?;
+        let has_next = option.value() == "other scripts in sprite";
+        Ok(blocks::stop(option, has_next))
+    };
+    Block ["when I start as a clone"] => |_| Ok(blocks::when_i_start_as_a_clone());
+    Block ["create clone of _"] => |a| Ok(blocks::create_clone_of(a.menu(0, blocks::create_clone_of_menu)?));
+    Block ["delete this clone"] => |_| Ok(blocks::delete_this_clone());
+
+    // Event
+    Block ["when flag clicked", "when green flag clicked", "when @greenFlag clicked"] => |_| Ok(blocks::when_flag_clicked());
+    Block ["when _ key pressed"] => |a| Ok(blocks::when_key_pressed(a.field(0)?));
+    Block ["when this sprite clicked"] => |_| Ok(blocks::when_this_sprite_clicked());
+    Block ["when stage clicked"] => |_| Ok(blocks::when_stage_clicked());
+    Block ["when backdrop switches to _"] => |a| Ok(blocks::when_backdrop_switches_to(a.field(0)?));
+    Block ["when _ > _"] => |a| Ok(blocks::when_greater_than(a.upper_field(0)?, a.number(1)?));
+    Block ["when I receive _"] => |a| Ok(blocks::when_broadcast_received(a.broadcast_field(0)?));
+    Block ["broadcast _"] => |a| Ok(blocks::broadcast(a.broadcast(0)?));
+    Block ["broadcast _ and wait"] => |a| Ok(blocks::broadcast_and_wait(a.broadcast(0)?));
+
+    // Looks
+    Block ["think _"] => |a| Ok(blocks::think(a.text(0)?));
+    Block ["think _ for _ seconds", "think _ for _ secs"] => |a| Ok(blocks::think_for_secs(a.text(0)?, a.number(1)?));
+    Block ["say _"] => |a| Ok(blocks::say(a.text(0)?));
+    Block ["say _ for _ seconds", "say _ for _ secs"] => |a| Ok(blocks::say_for_secs(a.text(0)?, a.number(1)?));
+    Block ["switch costume to _"] => |a| Ok(blocks::switch_costume_to(a.menu(0, blocks::costume_menu)?));
+    Block ["next costume"] => |_| Ok(blocks::next_costume());
+    Block ["switch backdrop to _"] => |a| Ok(blocks::switch_backdrop_to(a.menu(0, blocks::backdrop_menu)?));
+    Block ["switch backdrop to _ and wait"] => |a| Ok(blocks::switch_backdrop_to_and_wait(a.menu(0, blocks::backdrop_menu)?));
+    Block ["next backdrop"] => |_| Ok(blocks::next_backdrop());
+    Block ["change size by _"] => |a| Ok(blocks::change_size_by(a.number(0)?));
+    Block ["set size to _ %", "set size to _"] => |a| Ok(blocks::set_size_to(a.number(0)?));
+    Block ["change _ effect by _"] => |a| Ok(blocks::change_looks_effect_by(a.field_in(0, LOOKS_EFFECTS)?, a.number(1)?));
+    Block ["set _ effect to _"] => |a| Ok(blocks::set_looks_effect_to(a.field_in(0, LOOKS_EFFECTS)?, a.number(1)?));
+    Block ["clear graphic effects"] => |_| Ok(blocks::clear_graphic_effects());
+    Block ["show"] => |_| Ok(blocks::show());
+    Block ["hide"] => |_| Ok(blocks::hide());
+    Block ["go to _ layer"] => |a| Ok(blocks::go_to_layer(a.field(0)?));
+    Block ["go _ _ layers"] => |a| Ok(blocks::change_layer(a.field(0)?, a.number(1)?));
+    Block ["costume _"] => |a| Ok(blocks::costume(a.field(0)?));
+    Block ["backdrop _"] => |a| Ok(blocks::backdrop(a.field(0)?));
+    Block ["size"] => |_| Ok(blocks::size());
+
+    // Motion
+    Block ["move _ steps"] => |a| Ok(blocks::move_steps(a.number(0)?));
+    Block ["turn right _ degrees", "turn cw _ degrees", "turn ↻ _ degrees", "turn @turnRight _ degrees"] => |a| Ok(blocks::turn_right(a.number(0)?));
+    Block ["turn left _ degrees", "turn ccw _ degrees", "turn ↺ _ degrees", "turn @turnLeft _ degrees"] => |a| Ok(blocks::turn_left(a.number(0)?));
+    Block ["go to _"] => |a| Ok(blocks::go_to(a.menu(0, blocks::go_to_menu)?));
+    Block ["go to x: _ y: _"] => |a| Ok(blocks::goto_xy(a.number(0)?, a.number(1)?));
+    Block ["glide _ secs to _"] => |a| Ok(blocks::glide_to(a.number(0)?, a.menu(1, blocks::glide_to_menu)?));
+    Block ["glide _ secs to x: _ y: _"] => |a| Ok(blocks::glide_to_xy(a.number(0)?, a.number(1)?, a.number(2)?));
+    Block ["point in direction _"] => |a| Ok(blocks::point_in_direction(a.number(0)?));
+    Block ["point towards _"] => |a| Ok(blocks::point_towards(a.menu(0, blocks::point_towards_menu)?));
+    Block ["set x to _"] => |a| Ok(blocks::set_x(a.number(0)?));
+    Block ["set y to _"] => |a| Ok(blocks::set_y(a.number(0)?));
+    Block ["change x by _"] => |a| Ok(blocks::change_x_by(a.number(0)?));
+    Block ["change y by _"] => |a| Ok(blocks::change_y_by(a.number(0)?));
+    Block ["if on edge, bounce"] => |_| Ok(blocks::if_on_edge_bounce());
+    Block ["set rotation style _"] => |a| Ok(blocks::set_rotation_style(a.field(0)?));
+    Block ["direction"] => |_| Ok(blocks::direction());
+    Block ["x position"] => |_| Ok(blocks::x_position());
+    Block ["y position"] => |_| Ok(blocks::y_position());
+
+    // Operator
+    Block ["_ + _"] => |a| Ok(blocks::add(a.number(0)?, a.number(1)?));
+    Block ["_ - _"] => |a| Ok(blocks::sub(a.number(0)?, a.number(1)?));
+    Block ["_ * _"] => |a| Ok(blocks::mul(a.number(0)?, a.number(1)?));
+    Block ["_ / _"] => |a| Ok(blocks::div(a.number(0)?, a.number(1)?));
+    Block ["pick random _ to _"] => |a| Ok(blocks::random(a.number(0)?, a.number(1)?));
+    Block ["_ < _"] => |a| Ok(blocks::less_than(a.text(0)?, a.text(1)?));
+    Block ["_ > _"] => |a| Ok(blocks::greater_than(a.text(0)?, a.text(1)?));
+    Block ["_ = _"] => |a| Ok(blocks::equals(a.text(0)?, a.text(1)?));
+    Block ["_ and _"] => |a| Ok(blocks::and(a.boolean(0)?, a.boolean(1)?));
+    Block ["_ or _"] => |a| Ok(blocks::or(a.boolean(0)?, a.boolean(1)?));
+    Block ["not _"] => |a| Ok(blocks::not(a.boolean(0)?));
+    Block ["join _ _"] => |a| Ok(blocks::join(a.text(0)?, a.text(1)?));
+    Block ["letter _ of _"] => |a| Ok(blocks::letter_of(a.number(0)?, a.text(1)?));
+    Block ["length of _"] => |a| Ok(blocks::length_of_list(a.list(0)?));
+    Block ["length of _"] => |a| Ok(blocks::length_of(a.text(0)?));
+    Block ["_ contains _ ?"] => |a| Ok(blocks::list_contains(a.list(0)?, a.text(1)?));
+    Block ["_ contains _ ?"] => |a| Ok(blocks::contains(a.text(0)?, a.text(1)?));
+    Block ["_ mod _"] => |a| Ok(blocks::modulo(a.number(0)?, a.number(1)?));
+    Block ["round _"] => |a| Ok(blocks::round(a.number(0)?));
+    Block ["_ of _"] => |a| Ok(blocks::math_op(a.field_in(0, MATH_OPS)?, a.number(1)?));
+
+    // Sensing
+    Block ["_ of _"] => |a| Ok(blocks::var_of(a.field(0)?, a.menu(1, blocks::var_of_object_menu)?));
+    Block ["touching _ ?"] => |a| Ok(blocks::touching(a.menu(0, blocks::touching_menu)?));
+    Block ["touching color _ ?"] => |a| Ok(blocks::touching_color(a.color(0)?));
+    Block ["color _ is touching _ ?"] => |a| Ok(blocks::color_touching_color(a.color(0)?, a.color(1)?));
+    Block ["distance to _"] => |a| Ok(blocks::distance_to(a.menu(0, blocks::distance_to_menu)?));
+    Block ["ask _ and wait"] => |a| Ok(blocks::ask_and_wait(a.text(0)?));
+    Block ["answer"] => |_| Ok(blocks::answer());
+    Block ["key _ pressed?", "key _ pressed ?"] => |a| Ok(blocks::key_pressed(a.menu(0, blocks::key_menu)?));
+    Block ["mouse down?", "mouse down ?"] => |_| Ok(blocks::mouse_down());
+    Block ["mouse x"] => |_| Ok(blocks::mouse_x());
+    Block ["mouse y"] => |_| Ok(blocks::mouse_y());
+    Block ["set drag mode _"] => |a| Ok(blocks::set_drag_mode(a.field(0)?));
+    Block ["loudness"] => |_| Ok(blocks::loudness());
+    Block ["timer"] => |_| Ok(blocks::timer());
+    Block ["reset timer"] => |_| Ok(blocks::reset_timer());
+    Block ["current _"] => |a| Ok(blocks::current_datetime(a.upper_field(0)?));
+    Block ["days since 2000"] => |_| Ok(blocks::days_since_2000());
+    Block ["username"] => |_| Ok(blocks::username());
+
+    // Sound
+    Block ["play sound _ until done"] => |a| Ok(blocks::play_sound_until_done(a.menu(0, blocks::sound_menu)?));
+    Block ["start sound _"] => |a| Ok(blocks::play_sound(a.menu(0, blocks::sound_menu)?));
+    Block ["stop all sounds"] => |_| Ok(blocks::stop_all_sound());
+    Block ["change _ effect by _"] => |a| Ok(blocks::change_sound_effect_by(a.field_in(0, SOUND_EFFECTS)?, a.number(1)?));
+    Block ["set _ effect to _"] => |a| Ok(blocks::set_sound_effect_to(a.field_in(0, SOUND_EFFECTS)?, a.number(1)?));
+    Block ["clear sound effects"] => |_| Ok(blocks::clear_sound_effects());
+    Block ["set volume to _ %", "set volume to _"] => |a| Ok(blocks::set_volume_to(a.number(0)?));
+    Block ["change volume by _"] => |a| Ok(blocks::change_volume_by(a.number(0)?));
+    Block ["volume"] => |_| Ok(blocks::volume());
+
+    // Data
+    Block ["set _ to _"] => |a| Ok(blocks::set_var_to(a.variable(0)?, a.text(1)?));
+    Block ["change _ by _"] => |a| Ok(blocks::change_var_by(a.variable(0)?, a.number(1)?));
+    Block ["show variable _"] => |a| Ok(blocks::show_var(a.variable(0)?));
+    Block ["hide variable _"] => |a| Ok(blocks::hide_var(a.variable(0)?));
+    Block ["add _ to _"] => |a| Ok(blocks::add_to_list(a.list(1)?, a.text(0)?));
+    Block ["delete _ of _"] => |a| Ok(blocks::delete_in_list(a.list(1)?, a.number(0)?));
+    Block ["delete all of _"] => |a| Ok(blocks::delete_all_in_list(a.list(0)?));
+    Block ["insert _ at _ of _"] => |a| Ok(blocks::insert_in_list(a.list(2)?, a.number(1)?, a.text(0)?));
+    Block ["replace item _ of _ with _"] => |a| Ok(blocks::replace_in_list(a.list(1)?, a.number(0)?, a.text(2)?));
+    Block ["item _ of _"] => |a| Ok(blocks::item_in_list(a.list(1)?, a.number(0)?));
+    Block ["item # of _ in _"] => |a| Ok(blocks::count_of_item_in_list(a.list(1)?, a.text(0)?));
+    Block ["show list _"] => |a| Ok(blocks::show_list(a.list(0)?));
+    Block ["hide list _"] => |a| Ok(blocks::hide_list(a.list(0)?));
+
+    // Pen
+    Block ["erase all", "clear"] => |_| Ok(blocks::erase_all());
+    Block ["stamp"] => |_| Ok(blocks::stamp());
+    Block ["pen down"] => |_| Ok(blocks::pen_down());
+    Block ["pen up"] => |_| Ok(blocks::pen_up());
+    Block ["set pen color to _"] => |a| Ok(blocks::set_pen_color_to_color(a.color(0)?));
+    Block ["change pen _ by _"] => |a| Ok(blocks::change_pen_color_param_by(a.menu(0, blocks::pen_color_param_menu)?, a.number(1)?));
+    Block ["set pen _ to _"] => |a| Ok(blocks::set_pen_color_param_to(a.menu(0, blocks::pen_color_param_menu)?, a.number(1)?));
+    Block ["change pen size by _"] => |a| Ok(blocks::change_pen_size_by(a.number(0)?));
+    Block ["set pen size to _"] => |a| Ok(blocks::set_pen_size_to(a.number(0)?));
+    Block ["set pen shade to _"] => |a| Ok(blocks::set_pen_shade_to(a.number(0)?));
+    Block ["change pen shade by _"] => |a| Ok(blocks::change_pen_shade_by(a.number(0)?));
+    Block ["set pen hue to _"] => |a| Ok(blocks::set_pen_hue_to(a.number(0)?));
+    Block ["change pen hue by _"] => |a| Ok(blocks::change_pen_hue_by(a.number(0)?));
+
+    // Music
+    Block ["play drum _ for _ beats"] => |a| Ok(blocks::play_drum_for_beats(a.menu(0, blocks::drum_menu)?, a.number(1)?));
+    Block ["rest for _ beats"] => |a| Ok(blocks::rest_for_beats(a.number(0)?));
+    Block ["play note _ for _ beats"] => |a| Ok(blocks::play_note_for_beats(a.menu(0, blocks::note_menu)?, a.number(1)?));
+    Block ["set instrument to _"] => |a| Ok(blocks::set_instrument_to(a.menu(0, blocks::instrument_menu)?));
+    Block ["set tempo to _"] => |a| Ok(blocks::set_tempo_to(a.number(0)?));
+    Block ["change tempo by _"] => |a| Ok(blocks::change_tempo_by(a.number(0)?));
+    Block ["tempo"] => |_| Ok(blocks::tempo());
+};
@@ -0,0 +1,578 @@
+//! Renders scripts into scratchblocks text.
+//! The text can be parsed back with [`super::parse::parse`] except for custom blocks.
+
+use std::collections::HashMap;
+
+use rs_sb3::{
+    block::{BlockInputValue, BlockMutationEnum, ListOrVariable},
+    target::Target,
+};
+
+use super::parse::is_number;
+use crate::{
+    import::{import_scripts, ImportError},
+    opcode::substack_count,
+    project::target::TargetBuilder,
+    scripting::script_builder::{
+        BlockBuilder, BlockInputBuilder, BlockNormalBuilder, BlockVarListBuilder, StackBuilder,
+        StackOrValue,
+    },
+    uid::UidGenerator,
+};
+
+const INDENT: &str = "    ";
+
+pub fn render_stack(stack: &StackBuilder) -> String {
+    Renderer::default().scripts(std::slice::from_ref(stack))
+}
+
+/// Procedure definitions then every block stacks, separated by a blank line.
+pub fn render_target_builder(target: &TargetBuilder) -> String {
+    let procedures: HashMap<String, Vec<String>> = target
+        .procedures()
+        .iter()
+        .map(|procedure| (procedure.proccode(), procedure.argument_names()))
+        .collect();
+    let mut uid_gen = UidGenerator::new(0);
+    let stacks: Vec<StackBuilder> = target
+        .procedures()
+        .iter()
+        .map(|procedure| {
+            let context = procedure.context(&mut uid_gen, &procedure.proccode());
+            procedure.clone().build(&context)
+        })
+        .chain(target.stacks().iter().cloned())
+        .collect();
+    Renderer { procedures }.scripts(&stacks)
+}
+
+/// Every scripts in a target from a `project.json`, separated by a blank line.
+pub fn render_target(target: &Target) -> Result<String, ImportError> {
+    Ok(Renderer::default().scripts(&import_scripts(target)?))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Stack,
+    Reporter,
+    Boolean,
+    /// Menu that's shown as a dropdown
+    Menu,
+}
+
+#[derive(Default)]
+struct Renderer {
+    /// Argument names of the procedures in order.
+    /// Blocks that call a procedure by proccode have its inputs keyed by argument names.
+    procedures: HashMap<String, Vec<String>>,
+}
+
+impl Renderer {
+    fn scripts(&self, stacks: &[StackBuilder]) -> String {
+        stacks
+            .iter()
+            .map(|stack| {
+                let mut out = String::new();
+                self.stack(stack, 0, &mut out);
+                out
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn stack(&self, stack: &StackBuilder, depth: usize, out: &mut String) {
+        let indent = INDENT.repeat(depth);
+        for block in stack.blocks() {
+            let (text, comment) = match block {
+                BlockBuilder::VarList(varlist) => (varlist_text(varlist), varlist.comment_ref()),
+                BlockBuilder::Normal(block) => {
+                    let (kind, text) = self.block(block);
+                    (wrap(kind, text), block.comment_ref())
+                }
+            };
+            out.push_str(&indent);
+            out.push_str(&text);
+            if let Some(comment) = comment {
+                out.push_str(" // ");
+                out.push_str(&comment.content().replace('\n', " "));
+            }
+            out.push('\n');
+            let BlockBuilder::Normal(block) = block else {
+                continue;
+            };
+            let substacks = substack_count(block.opcode());
+            if substacks == 0 {
+                continue;
+            }
+            for idx in 0..substacks {
+                let key = match idx {
+                    0 => "SUBSTACK".to_owned(),
+                    idx => {
+                        out.push_str(&indent);
+                        out.push_str("else\n");
+                        format!("SUBSTACK{}", idx + 1)
+                    }
+                };
+                if let Some(StackOrValue::Stack(substack)) = block
+                    .inputs()
+                    .get(&key)
+                    .and_then(|input| input.values().first())
+                    .and_then(|value| value.as_ref())
+                {
+                    self.stack(substack, depth + 1, out);
+                }
+            }
+            out.push_str(&indent);
+            out.push_str("end\n");
+        }
+    }
+
+    /// Text of the block without the brackets around it
+    fn block(&self, block: &BlockNormalBuilder) -> (Kind, String) {
+        let opcode = block.opcode();
+        match opcode {
+            "procedures_definition" => (Kind::Stack, self.definition(block)),
+            "procedures_call" => (Kind::Stack, self.call(block)),
+            "argument_reporter_string_number" | "argument_reporter_boolean" => {
+                let name = block
+                    .fields()
+                    .get("VALUE")
+                    .map(|field| escape_round(field.value()))
+                    .unwrap_or_default();
+                let kind = match opcode {
+                    "argument_reporter_boolean" => Kind::Boolean,
+                    _ => Kind::Reporter,
+                };
+                (kind, name)
+            }
+            _ => match template(opcode) {
+                Some((kind, template)) => (kind, self.fill(block, template)),
+                None if block.is_shadow() => (Kind::Menu, menu_text(block)),
+                None => (Kind::Stack, self.unknown(block)),
+            },
+        }
+    }
+
+    fn fill(&self, block: &BlockNormalBuilder, template: &str) -> String {
+        template
+            .split_whitespace()
+            .map(|word| {
+                let slot = |open: char, close: char| {
+                    (word.len() > 2 && word.starts_with(open) && word.ends_with(close))
+                        .then(|| word[1..word.len() - 1].split('|').collect::<Vec<_>>())
+                };
+                if let Some(keys) = slot('(', ')') {
+                    self.input(block, &keys, false)
+                } else if let Some(keys) = slot('<', '>') {
+                    self.input(block, &keys, true)
+                } else if let Some(keys) = slot('[', ']') {
+                    field_text(block, &keys)
+                } else {
+                    word.to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `keys` are the possible keys of this input
+    fn input(&self, block: &BlockNormalBuilder, keys: &[&str], is_boolean: bool) -> String {
+        let value = keys
+            .iter()
+            .find_map(|key| block.inputs().get(*key))
+            .and_then(|input| input.values().first())
+            .and_then(|value| value.as_ref());
+        match value {
+            None if is_boolean => "<>".to_owned(),
+            None => "()".to_owned(),
+            Some(StackOrValue::Value(value)) => value_text(value),
+            Some(StackOrValue::Stack(stack)) => match stack.blocks().first() {
+                None => "()".to_owned(),
+                Some(BlockBuilder::VarList(varlist)) => varlist_text(varlist),
+                Some(BlockBuilder::Normal(block)) => {
+                    let (kind, text) = self.block(block);
+                    match kind {
+                        Kind::Stack => format!("({text})"),
+                        kind => wrap(kind, text),
+                    }
+                }
+            },
+        }
+    }
+
+    fn definition(&self, block: &BlockNormalBuilder) -> String {
+        let mutation = block
+            .inputs()
+            .get("custom_block")
+            .and_then(first_block)
+            .and_then(|prototype| prototype.mutation_ref());
+        let Some(BlockMutationEnum::ProceduresPrototype {
+            proccode,
+            argumentnames,
+            ..
+        }) = mutation.map(|mutation| &mutation.mutation_enum)
+        else {
+            return "define".to_owned();
+        };
+        let mut names = argumentnames.iter();
+        let prototype = fill_proccode(proccode, |is_boolean| {
+            let name = escape_round(names.next().map_or("", |name| name.as_str()));
+            match is_boolean {
+                true => format!("<{name}>"),
+                false => format!("({name})"),
+            }
+        });
+        format!("define {prototype}")
+    }
+
+    fn call(&self, block: &BlockNormalBuilder) -> String {
+        let (proccode, keys) = match block.mutation_ref().map(|mutation| &mutation.mutation_enum) {
+            Some(BlockMutationEnum::ProceduresCall {
+                proccode,
+                argumentids,
+                ..
+            }) => (proccode.as_str(), argumentids.clone()),
+            _ => {
+                let proccode = block.called_procedure().unwrap_or_default();
+                let keys = self.procedures.get(proccode).cloned().unwrap_or_else(|| {
+                    let mut keys: Vec<String> = block.inputs().keys().cloned().collect();
+                    keys.sort();
+                    keys
+                });
+                (proccode, keys)
+            }
+        };
+        let mut keys = keys.iter();
+        fill_proccode(proccode, |is_boolean| match keys.next() {
+            Some(key) => self.input(block, &[key], is_boolean),
+            None if is_boolean => "<>".to_owned(),
+            None => "()".to_owned(),
+        })
+    }
+
+    /// Opcode followed by every inputs and fields
+    fn unknown(&self, block: &BlockNormalBuilder) -> String {
+        let mut input_keys: Vec<&String> = block.inputs().keys().collect();
+        input_keys.sort();
+        let mut field_keys: Vec<&String> = block.fields().keys().collect();
+        field_keys.sort();
+        std::iter::once(block.opcode().to_owned())
+            .chain(
+                input_keys
+                    .into_iter()
+                    .map(|key| self.input(block, &[key], false)),
+            )
+            .chain(field_keys.into_iter().map(|key| field_text(block, &[key])))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn wrap(kind: Kind, text: String) -> String {
+    match kind {
+        Kind::Stack => text,
+        Kind::Reporter => format!("({text})"),
+        Kind::Boolean => format!("<{text}>"),
+        Kind::Menu => format!("({text} v)"),
+    }
+}
+
+fn first_block(input: &BlockInputBuilder) -> Option<&BlockNormalBuilder> {
+    match input.values().first()?.as_ref()? {
+        StackOrValue::Stack(stack) => match stack.blocks().first()? {
+            BlockBuilder::Normal(block) => Some(block),
+            BlockBuilder::VarList(_) => None,
+        },
+        StackOrValue::Value(_) => None,
+    }
+}
+
+/// Replaces `%s` and `%b` in the proccode with `arg(is_boolean)`
+fn fill_proccode<F: FnMut(bool) -> String>(proccode: &str, mut arg: F) -> String {
+    proccode
+        .split_whitespace()
+        .map(|word| match word {
+            "%s" => arg(false),
+            "%b" => arg(true),
+            label => escape_round(label),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn varlist_text(varlist: &BlockVarListBuilder) -> String {
+    let name = escape_round(varlist.name());
+    match varlist.kind() {
+        ListOrVariable::Variable => format!("({name})"),
+        ListOrVariable::List => format!("({name} :: list)"),
+    }
+}
+
+/// Value of the menu's only field or input
+fn menu_text(block: &BlockNormalBuilder) -> String {
+    let field = block
+        .fields()
+        .iter()
+        .next()
+        .map(|(key, field)| shown(key, field.value()));
+    let input = || {
+        let value = block.inputs().values().next()?.values().first()?.as_ref()?;
+        match value {
+            StackOrValue::Value(value) => Some(json_to_string(&value_json(value)[1])),
+            StackOrValue::Stack(_) => None,
+        }
+    };
+    escape_round(&field.or_else(input).unwrap_or_default())
+}
+
+fn field_text(block: &BlockNormalBuilder, keys: &[&str]) -> String {
+    let field = keys
+        .iter()
+        .find_map(|key| block.fields().get(*key).map(|field| (key, field)));
+    match field {
+        Some((key, field)) => format!("[{} v]", escape_square(&shown(key, field.value()))),
+        None => "[ v]".to_owned(),
+    }
+}
+
+/// Text shown in the editor for the value of a field or a menu
+fn shown(key: &str, value: &str) -> String {
+    let shown = match (key, value) {
+        (_, "_random_") => "random position",
+        (_, "_mouse_") => "mouse-pointer",
+        (_, "_edge_") => "edge",
+        (_, "_myself_") => "myself",
+        (_, "_stage_") => "Stage",
+        ("EFFECT", "PAN") => "pan left/right",
+        ("CURRENTMENU", "DAYOFWEEK") => "day of week",
+        ("EFFECT" | "WHENGREATERTHANMENU" | "CURRENTMENU", value) => {
+            return value.to_lowercase();
+        }
+        (_, value) => value,
+    };
+    shown.to_owned()
+}
+
+/// Same as the json in `project.json`. Ex. `[4, "10"]`
+fn value_json(value: &BlockInputValue) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+fn value_text(value: &BlockInputValue) -> String {
+    let json = value_json(value);
+    let text = json_to_string(&json[1]);
+    match json[0].as_u64().unwrap_or_default() {
+        // Numbers
+        4..=8 if text.is_empty() || is_number(&text) => format!("({})", number_text(&json[1])),
+        9 => format!("[{text}]"),
+        11 => format!("({} v)", escape_round(&text)),
+        12 => format!("({})", escape_round(&text)),
+        13 => format!("({} :: list)", escape_round(&text)),
+        _ => format!("[{}]", escape_square(&text)),
+    }
+}
+
+fn json_to_string(json: &serde_json::Value) -> String {
+    match json {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Whole numbers are shown without the decimal point
+fn number_text(json: &serde_json::Value) -> String {
+    match json.as_f64() {
+        Some(number) if number.fract() == 0. && number.abs() < 1e15 => {
+            format!("{}", number as i64)
+        }
+        _ => json_to_string(json),
+    }
+}
+
+fn escape_round(text: &str) -> String {
+    escape(text, &['\\', '(', ')', '[', ']', '<', '>'])
+}
+
+fn escape_square(text: &str) -> String {
+    escape(text, &['\\', ']'])
+}
+
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Whether the block is a round or pointy reporter
+pub(crate) fn is_reporter(opcode: &str) -> bool {
+    match opcode {
+        "argument_reporter_string_number" | "argument_reporter_boolean" => true,
+        opcode => matches!(template(opcode), Some((Kind::Reporter | Kind::Boolean, _))),
+    }
+}
+
+/// Kind of the block and its text where `(KEY)` is an input, `<KEY>` is a boolean input
+/// and `[KEY]` is a field. `KEY|OTHER` tries each keys in order.
+#[rustfmt::skip]
+fn template(opcode: &str) -> Option<(Kind, &'static str)> {
+    use Kind::{Boolean, Reporter, Stack};
+    let template = match opcode {
+        // Control
+        "control_wait" =>                     (Stack, "wait (DURATION) seconds"),
+        "control_repeat" =>                   (Stack, "repeat (TIMES)"),
+        "control_forever" =>                  (Stack, "forever"),
+        "control_if" =>                       (Stack, "if <CONDITION> then"),
+        "control_if_else" =>                  (Stack, "if <CONDITION> then"),
+        "control_wait_until" =>               (Stack, "wait until <CONDITION>"),
+        "control_repeat_until" =>             (Stack, "repeat until <CONDITION>"),
+        "control_stop" =>                     (Stack, "stop [STOP_OPTION]"),
+        "control_start_as_clone" =>           (Stack, "when I start as a clone"),
+        "control_create_clone_of" =>          (Stack, "create clone of (CLONE_OPTION)"),
+        "control_delete_this_clone" =>        (Stack, "delete this clone"),
+        // Event
+        "event_whenflagclicked" =>            (Stack, "when flag clicked"),
+        "event_whenkeypressed" =>             (Stack, "when [KEY_OPTION] key pressed"),
+        "event_whenthisspriteclicked" =>      (Stack, "when this sprite clicked"),
+        "event_whenstageclicked" =>           (Stack, "when stage clicked"),
+        "event_whenbackdropswitchesto" =>     (Stack, "when backdrop switches to [BACKDROP]"),
+        "event_whengreaterthan" =>            (Stack, "when [WHENGREATERTHANMENU] > (VALUE)"),
+        "event_whenbroadcastreceived" =>      (Stack, "when I receive [BROADCAST_OPTION]"),
+        "event_broadcast" =>                  (Stack, "broadcast (BROADCAST_INPUT)"),
+        "event_broadcastandwait" =>           (Stack, "broadcast (BROADCAST_INPUT) and wait"),
+        // Looks
+        "looks_think" =>                      (Stack, "think (MESSAGE)"),
+        "looks_thinkforsecs" =>               (Stack, "think (MESSAGE) for (SECS) seconds"),
+        "looks_say" =>                        (Stack, "say (MESSAGE)"),
+        "looks_sayforsecs" =>                 (Stack, "say (MESSAGE) for (SECS) seconds"),
+        "looks_switchcostumeto" =>            (Stack, "switch costume to (COSTUME)"),
+        "looks_nextcostume" =>                (Stack, "next costume"),
+        "looks_switchbackdropto" =>           (Stack, "switch backdrop to (BACKDROP)"),
+        "looks_switchbackdroptoandwait" =>    (Stack, "switch backdrop to (BACKDROP) and wait"),
+        "looks_nextbackdrop" =>               (Stack, "next backdrop"),
+        "looks_changesizeby" =>               (Stack, "change size by (CHANGE)"),
+        "looks_setsizeto" =>                  (Stack, "set size to (SIZE) %"),
+        "looks_changeeffectby" =>             (Stack, "change [EFFECT] effect by (CHANGE)"),
+        "looks_seteffectto" =>                (Stack, "set [EFFECT] effect to (VALUE)"),
+        "looks_cleargraphiceffects" =>        (Stack, "clear graphic effects"),
+        "looks_show" =>                       (Stack, "show"),
+        "looks_hide" =>                       (Stack, "hide"),
+        "looks_gotofrontback" =>              (Stack, "go to [FRONT_BACK] layer"),
+        "looks_goforwardbackwardlayers" =>    (Stack, "go [FORWARD_BACKWARD|FORWARD_BACKWORD] (NUM) layers"),
+        "looks_costumenumbername" =>          (Reporter, "costume [NUMBER_NAME]"),
+        "looks_backdropnumbername" =>         (Reporter, "backdrop [NUMBER_NAME]"),
+        "looks_size" =>                       (Reporter, "size"),
+        // Motion
+        "motion_movesteps" =>                 (Stack, "move (STEPS) steps"),
+        "motion_turnright" =>                 (Stack, "turn right (DEGREES) degrees"),
+        "motion_turnleft" =>                  (Stack, "turn left (DEGREES) degrees"),
+        "motion_goto" =>                      (Stack, "go to (TO)"),
+        "motion_gotoxy" =>                    (Stack, "go to x: (X) y: (Y)"),
+        "motion_glideto" =>                   (Stack, "glide (SECS) secs to (TO)"),
+        "motion_glidesecstoxy" =>             (Stack, "glide (SECS) secs to x: (X) y: (Y)"),
+        "motion_pointindirection" =>          (Stack, "point in direction (DIRECTION)"),
+        "motion_pointtowards" =>              (Stack, "point towards (TOWARDS)"),
+        "motion_setx" =>                      (Stack, "set x to (X)"),
+        "motion_sety" =>                      (Stack, "set y to (Y)"),
+        "motion_changexby" =>                 (Stack, "change x by (DX)"),
+        "motion_changeyby" =>                 (Stack, "change y by (DY)"),
+        "motion_ifonedgebounce" =>            (Stack, "if on edge, bounce"),
+        "motion_setrotationstyle" =>          (Stack, "set rotation style [STYLE]"),
+        "motion_direction" =>                 (Reporter, "direction"),
+        "motion_xposition" =>                 (Reporter, "x position"),
+        "motion_yposition" =>                 (Reporter, "y position"),
+        // Operator
+        "operator_add" =>                     (Reporter, "(NUM1) + (NUM2)"),
+        "operator_subtract" =>                (Reporter, "(NUM1) - (NUM2)"),
+        "operator_multiply" =>                (Reporter, "(NUM1) * (NUM2)"),
+        "operator_divide" =>                  (Reporter, "(NUM1) / (NUM2)"),
+        "operator_random" =>                  (Reporter, "pick random (FROM) to (TO)"),
+        "operator_lt" =>                      (Boolean, "(OPERAND1) < (OPERAND2)"),
+        "operator_gt" =>                      (Boolean, "(OPERAND1) > (OPERAND2)"),
+        "operator_equals" =>                  (Boolean, "(OPERAND1) = (OPERAND2)"),
+        "operator_and" =>                     (Boolean, "<OPERAND1> and <OPERAND2>"),
+        "operator_or" =>                      (Boolean, "<OPERAND1> or <OPERAND2>"),
+        "operator_not" =>                     (Boolean, "not <OPERAND>"),
+        "operator_join" =>                    (Reporter, "join (STRING1) (STRING2)"),
+        "operator_letter_of" =>               (Reporter, "letter (LETTER) of (STRING)"),
+        "operator_length" =>                  (Reporter, "length of (STRING)"),
+        "operator_contains" =>                (Boolean, "(STRING1) contains (STRING2) ?"),
+        "operator_mod" =>                     (Reporter, "(NUM1) mod (NUM2)"),
+        "operator_round" =>                   (Reporter, "round (NUM)"),
+        "operator_mathop" =>                  (Reporter, "[OPERATOR] of (NUM)"),
+        // Sensing
+        "sensing_touchingobject" =>           (Boolean, "touching (TOUCHINGOBJECTMENU) ?"),
+        "sensing_touchingcolor" =>            (Boolean, "touching color (COLOR) ?"),
+        "sensing_coloristouchingcolor" =>     (Boolean, "color (COLOR) is touching (COLOR2) ?"),
+        "sensing_distanceto" =>               (Reporter, "distance to (DISTANCETOMENU)"),
+        "sensing_askandwait" =>               (Stack, "ask (QUESTION) and wait"),
+        "sensing_answer" =>                   (Reporter, "answer"),
+        "sensing_keypressed" =>               (Boolean, "key (KEY_OPTION) pressed?"),
+        "sensing_mousedown" =>                (Boolean, "mouse down?"),
+        "sensing_mousex" =>                   (Reporter, "mouse x"),
+        "sensing_mousey" =>                   (Reporter, "mouse y"),
+        "sensing_setdragmode" =>              (Stack, "set drag mode [DRAG_MODE]"),
+        "sensing_loudness" =>                 (Reporter, "loudness"),
+        "sensing_timer" =>                    (Reporter, "timer"),
+        "sensing_resettimer" =>               (Stack, "reset timer"),
+        "sensing_of" =>                       (Reporter, "[PROPERTY] of (OBJECT)"),
+        "sensing_current" =>                  (Reporter, "current [CURRENTMENU]"),
+        "sensing_dayssince2000" =>            (Reporter, "days since 2000"),
+        "sensing_username" =>                 (Reporter, "username"),
+        // Sound
+        "sound_playuntildone" =>              (Stack, "play sound (SOUND_MENU) until done"),
+        "sound_play" =>                       (Stack, "start sound (SOUND_MENU)"),
+        "sound_stopallsounds" =>              (Stack, "stop all sounds"),
+        "sound_changeeffectby" =>             (Stack, "change [EFFECT] effect by (VALUE)"),
+        "sound_seteffectto" =>                (Stack, "set [EFFECT] effect to (VALUE)"),
+        "sound_cleareffects" =>               (Stack, "clear sound effects"),
+        "sound_setvolumeto" =>                (Stack, "set volume to (VOLUME) %"),
+        "sound_changevolumeby" =>             (Stack, "change volume by (VOLUME)"),
+        "sound_volume" =>                     (Reporter, "volume"),
+        // Data
+        "data_setvariableto" =>               (Stack, "set [VARIABLE] to (VALUE)"),
+        "data_changevariableby" =>            (Stack, "change [VARIABLE] by (VALUE)"),
+        "data_showvariable" =>                (Stack, "show variable [VARIABLE]"),
+        "data_hidevariable" =>                (Stack, "hide variable [VARIABLE]"),
+        "data_addtolist" =>                   (Stack, "add (ITEM) to [LIST]"),
+        "data_deleteoflist" =>                (Stack, "delete (INDEX) of [LIST]"),
+        "data_deletealloflist" =>             (Stack, "delete all of [LIST]"),
+        "data_insertatlist" =>                (Stack, "insert (ITEM) at (INDEX) of [LIST]"),
+        "data_replaceitemoflist" =>           (Stack, "replace item (INDEX) of [LIST] with (ITEM)"),
+        "data_itemoflist" =>                  (Reporter, "item (INDEX) of [LIST]"),
+        "data_itemnumoflist" =>               (Reporter, "item # of (ITEM) in [LIST]"),
+        "data_lengthoflist" =>                (Reporter, "length of [LIST]"),
+        "data_listcontainsitem" =>            (Boolean, "[LIST] contains (ITEM) ?"),
+        "data_showlist" =>                    (Stack, "show list [LIST]"),
+        "data_hidelist" =>                    (Stack, "hide list [LIST]"),
+        // Pen
+        "pen_clear" =>                        (Stack, "erase all"),
+        "pen_stamp" =>                        (Stack, "stamp"),
+        "pen_penDown" =>                      (Stack, "pen down"),
+        "pen_penUp" =>                        (Stack, "pen up"),
+        "pen_setPenColorToColor" =>           (Stack, "set pen color to (COLOR)"),
+        "pen_changePenColorParamBy" =>        (Stack, "change pen (COLOR_PARAM) by (VALUE)"),
+        "pen_setPenColorParamTo" =>           (Stack, "set pen (COLOR_PARAM) to (VALUE)"),
+        "pen_changePenSizeBy" =>              (Stack, "change pen size by (SIZE)"),
+        "pen_setPenSizeTo" =>                 (Stack, "set pen size to (SIZE)"),
+        "pen_setPenShadeToNumber" =>          (Stack, "set pen shade to (SHADE)"),
+        "pen_changePenShadeBy" =>             (Stack, "change pen shade by (SHADE)"),
+        "pen_setPenHueToNumber" =>            (Stack, "set pen hue to (HUE)"),
+        "pen_changePenHueBy" =>               (Stack, "change pen hue by (HUE)"),
+        // Music
+        "music_playDrumForBeats" =>           (Stack, "play drum (DRUM) for (BEATS) beats"),
+        "music_restForBeats" =>               (Stack, "rest for (BEATS) beats"),
+        "music_playNoteForBeats" =>           (Stack, "play note (NOTE) for (BEATS) beats"),
+        "music_setInstrument" =>              (Stack, "set instrument to (INSTRUMENT)"),
+        "music_setTempo" =>                   (Stack, "set tempo to (TEMPO)"),
+        "music_changeTempo" =>                (Stack, "change tempo by (TEMPO)"),
+        "music_getTempo" =>                   (Reporter, "tempo"),
+        _ => return None,
+    };
+    Some(template)
+}
@@ -7,12 +7,15 @@
 //!  - "_mouse_"
 //!
 
-use super::script_builder::{
-    BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, StackBuilder,
+use super::{
+    procedure::{ProcedureArgumentKind, ProcedureHandle},
+    script_builder::{
+        BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, StackBuilder, StackOrValue,
+    },
 };
-use crate::opcode::PrimaryOpCode;
+use crate::opcode::{MusicExtensionOpCode, PenExtensionOpCode, PrimaryOpCode};
 use crate::scripting::script_builder::BlockVarListBuilder;
-use rs_sb3::block::{BlockMutation, BlockMutationEnum, ListOrVariable};
+use rs_sb3::block::{BlockInputValue, BlockMutation, BlockMutationEnum, ListOrVariable};
 
 // Control
 // Event
@@ -22,6 +25,9 @@ use rs_sb3::block::{BlockMutation, BlockMutationEnum, ListOrVariable};
 // Sensing
 // Sound
 // Data
+// Procedures
+// Pen
+// Music
 
 type BFB = BlockFieldBuilder;
 type BIB = BlockInputBuilder;
@@ -88,7 +94,7 @@ pub fn wait_until(condition: BIB) -> StackBuilder {
 
 pub fn repeat_until(condition: BIB, to_repeat: Option<BIB>) -> StackBuilder {
     StackBuilder::start({
-        let mut b = BlockNormalBuilder::new(PrimaryOpCode::control_if_else)
+        let mut b = BlockNormalBuilder::new(PrimaryOpCode::control_repeat_until)
             .add_input("CONDITION", condition);
         if let Some(to_repeat) = to_repeat {
             b = b.add_input("SUBSTACK", to_repeat);
@@ -133,7 +139,7 @@ pub fn create_clone_of(sprite: BIB) -> StackBuilder {
 ///  - Sprite name
 pub fn create_clone_of_menu(sprite: BFB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::control_create_clone_of)
+        BlockNormalBuilder::new(PrimaryOpCode::control_create_clone_of_menu)
             .add_field("CLONE_OPTION", sprite)
             .shadow(true),
     )
@@ -145,6 +151,62 @@ pub fn delete_this_clone() -> StackBuilder {
     ))
 }
 
+/// Hidden from the palette
+pub fn while_(condition: BIB, to_repeat: Option<BIB>) -> StackBuilder {
+    StackBuilder::start({
+        let mut b =
+            BlockNormalBuilder::new(PrimaryOpCode::control_while).add_input("CONDITION", condition);
+        if let Some(to_repeat) = to_repeat {
+            b = b.add_input("SUBSTACK", to_repeat);
+        }
+        b
+    })
+}
+
+/// Hidden from the palette.
+/// Sets `var` to 1, 2, ... up to `value` and runs `to_repeat` each time.
+pub fn for_each(var: BFB, value: BIB, to_repeat: Option<BIB>) -> StackBuilder {
+    StackBuilder::start({
+        let mut b = BlockNormalBuilder::new(PrimaryOpCode::control_for_each)
+            .add_input("VALUE", value)
+            .add_field("VARIABLE", var);
+        if let Some(to_repeat) = to_repeat {
+            b = b.add_input("SUBSTACK", to_repeat);
+        }
+        b
+    })
+}
+
+/// Hidden from the palette.
+/// The counter is shared by every target in the project.
+pub fn counter() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::control_get_counter))
+}
+
+/// Hidden from the palette
+pub fn incr_counter() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::control_incr_counter))
+}
+
+/// Hidden from the palette
+pub fn clear_counter() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(
+        PrimaryOpCode::control_clear_counter,
+    ))
+}
+
+/// Hidden from the palette.
+/// Scratch 3 runs the substack like any other, it's not warp.
+pub fn all_at_once(to_run: Option<BIB>) -> StackBuilder {
+    StackBuilder::start({
+        let mut b = BlockNormalBuilder::new(PrimaryOpCode::control_all_at_once);
+        if let Some(to_run) = to_run {
+            b = b.add_input("SUBSTACK", to_run);
+        }
+        b
+    })
+}
+
 // Event =======================================================================
 pub fn when_flag_clicked() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(
@@ -173,6 +235,38 @@ pub fn when_this_sprite_clicked() -> StackBuilder {
     ))
 }
 
+/// The Stage's version of [`when_this_sprite_clicked`]
+pub fn when_stage_clicked() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(
+        PrimaryOpCode::event_whenstageclicked,
+    ))
+}
+
+/// Hidden from the palette.
+/// Accepts:
+///  - Sprite name
+///  - "_mouse_"
+///  - "_edge_"
+pub fn when_touching(what: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::event_whentouchingobject)
+            .add_input("TOUCHINGOBJECTMENU", what),
+    )
+}
+
+/// Uses as argument to [`when_touching`]
+/// Accepts:
+///  - Sprite name
+///  - "_mouse_"
+///  - "_edge_"
+pub fn when_touching_menu(what: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::event_touchingobjectmenu)
+            .add_field("TOUCHINGOBJECTMENU", what)
+            .shadow(true),
+    )
+}
+
 /// Accepts:
 ///  - Backdrop name
 pub fn when_backdrop_switches_to(backdrop: BFB) -> StackBuilder {
@@ -214,6 +308,16 @@ pub fn broadcast_and_wait(broadcast: BIB) -> StackBuilder {
     )
 }
 
+/// Uses as argument to [`broadcast`] and [`broadcast_and_wait`].
+/// [`BlockInputBuilder::broadcast`] is the shorter way to write the same input.
+pub fn broadcast_menu(broadcast: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::event_broadcast_menu)
+            .add_field("BROADCAST_OPTION", broadcast)
+            .shadow(true),
+    )
+}
+
 // Looks =======================================================================
 pub fn think(message: BIB) -> StackBuilder {
     StackBuilder::start(
@@ -267,7 +371,7 @@ pub fn next_costume() -> StackBuilder {
 }
 
 /// Accepts:
-///  - Costume name
+///  - Backdrop name
 pub fn switch_backdrop_to(backdrop: BIB) -> StackBuilder {
     StackBuilder::start(
         BlockNormalBuilder::new(PrimaryOpCode::looks_switchbackdropto)
@@ -286,6 +390,16 @@ pub fn backdrop_menu(backdrop: BFB) -> StackBuilder {
     )
 }
 
+/// Only on the Stage.
+/// Accepts:
+///  - Backdrop name
+pub fn switch_backdrop_to_and_wait(backdrop: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::looks_switchbackdroptoandwait)
+            .add_input("BACKDROP", backdrop),
+    )
+}
+
 pub fn next_backdrop() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::looks_nextbackdrop))
 }
@@ -329,7 +443,7 @@ pub fn change_looks_effect_by(effect: BFB, by: BIB) -> StackBuilder {
 pub fn set_looks_effect_to(effect: BFB, to: BIB) -> StackBuilder {
     StackBuilder::start(
         BlockNormalBuilder::new(PrimaryOpCode::looks_seteffectto)
-            .add_input("TO", to)
+            .add_input("VALUE", to)
             .add_field("EFFECT", effect),
     )
 }
@@ -364,7 +478,7 @@ pub fn change_layer(layer: BFB, by: BIB) -> StackBuilder {
     StackBuilder::start(
         BlockNormalBuilder::new(PrimaryOpCode::looks_goforwardbackwardlayers)
             .add_input("NUM", by)
-            .add_field("FORWARD_BACKWORD", layer),
+            .add_field("FORWARD_BACKWARD", layer),
     )
 }
 
@@ -392,6 +506,25 @@ pub fn size() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::looks_size))
 }
 
+/// Hidden from the palette
+pub fn hide_all_sprites() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::looks_hideallsprites))
+}
+
+/// Hidden from the palette
+pub fn change_stretch_by(by: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::looks_changestretchby).add_input("CHANGE", by),
+    )
+}
+
+/// Hidden from the palette
+pub fn set_stretch_to(to: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::looks_setstretchto).add_input("STRETCH", to),
+    )
+}
+
 // Motion ======================================================================
 pub fn move_steps(steps: BIB) -> StackBuilder {
     StackBuilder::start(
@@ -446,7 +579,7 @@ pub fn goto_xy(x: BIB, y: BIB) -> StackBuilder {
 ///  - "_random_" glide to random position
 pub fn glide_to(duration_secs: BIB, to: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::motion_gotoxy)
+        BlockNormalBuilder::new(PrimaryOpCode::motion_glideto)
             .add_input("SECS", duration_secs)
             .add_input("TO", to),
     )
@@ -507,7 +640,7 @@ pub fn set_x(x: BIB) -> StackBuilder {
 }
 
 pub fn set_y(y: BIB) -> StackBuilder {
-    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::motion_setx).add_input("Y", y))
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::motion_sety).add_input("Y", y))
 }
 
 pub fn change_x_by(by: BIB) -> StackBuilder {
@@ -550,6 +683,44 @@ pub fn x_position() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::motion_xposition))
 }
 
+/// Hidden from the palette
+pub fn scroll_right(distance: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::motion_scroll_right).add_input("DISTANCE", distance),
+    )
+}
+
+/// Hidden from the palette
+pub fn scroll_up(distance: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::motion_scroll_up).add_input("DISTANCE", distance),
+    )
+}
+
+/// Hidden from the palette.
+/// Accepts:
+///  - "bottom-left"
+///  - "bottom-right"
+///  - "middle"
+///  - "top-left"
+///  - "top-right"
+pub fn align_scene(alignment: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::motion_align_scene)
+            .add_field("ALIGNMENT", alignment),
+    )
+}
+
+/// Hidden from the palette
+pub fn x_scroll() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::motion_xscroll))
+}
+
+/// Hidden from the palette
+pub fn y_scroll() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::motion_yscroll))
+}
+
 // Operators ===================================================================
 pub fn add(lhs: BIB, rhs: BIB) -> StackBuilder {
     StackBuilder::start(
@@ -633,7 +804,7 @@ pub fn or(a: BIB, b: BIB) -> StackBuilder {
 
 pub fn not(val: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::operator_or).add_input("OPERAND", val),
+        BlockNormalBuilder::new(PrimaryOpCode::operator_not).add_input("OPERAND", val),
     )
 }
 
@@ -749,7 +920,7 @@ pub fn color_touching_color(color_a: BIB, color_b: BIB) -> StackBuilder {
 ///  - "_mouse_"
 pub fn distance_to(what: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::sensing_coloristouchingcolor)
+        BlockNormalBuilder::new(PrimaryOpCode::sensing_distanceto)
             .add_input("DISTANCETOMENU", what),
     )
 }
@@ -760,7 +931,7 @@ pub fn distance_to(what: BIB) -> StackBuilder {
 ///  - "_mouse_"
 pub fn distance_to_menu(what: BFB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::sensing_coloristouchingcolor)
+        BlockNormalBuilder::new(PrimaryOpCode::sensing_distancetomenu)
             .add_field("DISTANCETOMENU", what)
             .shadow(true),
     )
@@ -802,10 +973,10 @@ pub fn key_pressed(key: BIB) -> StackBuilder {
 ///  - "down arrow"
 ///  - Number 0 - 9
 ///  - Letter a - z
-pub fn key_menu(key: BIB) -> StackBuilder {
+pub fn key_menu(key: BFB) -> StackBuilder {
     StackBuilder::start(
         BlockNormalBuilder::new(PrimaryOpCode::sensing_keyoptions)
-            .add_input("KEY_OPTION", key)
+            .add_field("KEY_OPTION", key)
             .shadow(true),
     )
 }
@@ -818,6 +989,10 @@ pub fn mouse_x() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_mousex))
 }
 
+pub fn mouse_y() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_mousey))
+}
+
 /// Accepts:
 ///  - "not draggable"
 ///  - "draggable"
@@ -831,6 +1006,12 @@ pub fn loudness() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_loudness))
 }
 
+/// Hidden from the palette.
+/// `true` when the loudness is above 10.
+pub fn loud() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_loud))
+}
+
 pub fn timer() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_timer))
 }
@@ -904,6 +1085,12 @@ pub fn username() -> StackBuilder {
     StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_username))
 }
 
+/// Hidden from the palette.
+/// Always empty in Scratch 3.
+pub fn user_id() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PrimaryOpCode::sensing_userid))
+}
+
 // Sound =======================================================================
 
 /// Accepts:
@@ -971,7 +1158,7 @@ pub fn set_volume_to(volume: BIB) -> StackBuilder {
 
 pub fn change_volume_by(by: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::sound_changeeffectby).add_input("VOLUME", by),
+        BlockNormalBuilder::new(PrimaryOpCode::sound_changevolumeby).add_input("VOLUME", by),
     )
 }
 
@@ -1024,9 +1211,11 @@ pub fn hide_var(var: BFB) -> StackBuilder {
     )
 }
 
-pub fn add_to_list(item: BIB) -> StackBuilder {
+pub fn add_to_list(list: BFB, item: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::data_addtolist).add_input("ITEM", item),
+        BlockNormalBuilder::new(PrimaryOpCode::data_addtolist)
+            .add_input("ITEM", item)
+            .add_field("LIST", list),
     )
 }
 
@@ -1072,7 +1261,7 @@ pub fn item_in_list(list: BFB, idx: BIB) -> StackBuilder {
 
 pub fn count_of_item_in_list(list: BFB, item: BIB) -> StackBuilder {
     StackBuilder::start(
-        BlockNormalBuilder::new(PrimaryOpCode::data_itemoflist)
+        BlockNormalBuilder::new(PrimaryOpCode::data_itemnumoflist)
             .add_input("ITEM", item)
             .add_field("LIST", list),
     )
@@ -1103,3 +1292,245 @@ pub fn hide_list(list: BFB) -> StackBuilder {
         BlockNormalBuilder::new(PrimaryOpCode::data_hidelist).add_field("LIST", list),
     )
 }
+
+// Procedures ==================================================================
+
+/// Definition hat of a custom block.
+/// `prototype` should be a shadow input of a `procedures_prototype` block.
+/// See [`crate::scripting::procedure::ProcedureBuilder`] for building the whole definition.
+pub fn define_procedure(prototype: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::procedures_definition)
+            .add_input("custom_block", prototype),
+    )
+}
+
+/// Accepts:
+///  - Argument name of the procedure this block is in
+pub fn argument_string_number(name: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::argument_reporter_string_number)
+            .add_field("VALUE", name),
+    )
+}
+
+/// Accepts:
+///  - Argument name of the procedure this block is in
+pub fn argument_boolean(name: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PrimaryOpCode::argument_reporter_boolean).add_field("VALUE", name),
+    )
+}
+
+/// `arguments` is in the same order as the procedure's arguments.
+/// A reporter in a string or number argument covers an empty text like in the editor.
+/// `BlockInputBuilder::new()` leaves a boolean argument empty.
+/// The build fails when the number of `arguments` isn't the number of the procedure's arguments.
+pub fn call_procedure(procedure: &ProcedureHandle, arguments: Vec<BIB>) -> StackBuilder {
+    let mut b = BlockNormalBuilder::new(PrimaryOpCode::procedures_call)
+        .procedure_call(procedure.proccode())
+        .call_arguments(arguments.len());
+    let kinds = procedure.argument_kinds().iter();
+    for ((arg_name, kind), arg) in procedure.argument_names().iter().zip(kinds).zip(arguments) {
+        if arg.values().is_empty() {
+            continue;
+        }
+        let arg = arg.cover(|| {
+            (*kind == ProcedureArgumentKind::StringNumber).then(|| {
+                StackOrValue::Value(BlockInputValue::String {
+                    value: "".to_owned().into(),
+                })
+            })
+        });
+        b = b.add_input(arg_name.clone(), arg);
+    }
+    StackBuilder::start(b)
+}
+
+// Pen =========================================================================
+pub fn erase_all() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PenExtensionOpCode::pen_clear))
+}
+
+pub fn stamp() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PenExtensionOpCode::pen_stamp))
+}
+
+pub fn pen_down() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PenExtensionOpCode::pen_penDown))
+}
+
+pub fn pen_up() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(PenExtensionOpCode::pen_penUp))
+}
+
+pub fn set_pen_color_to_color(color: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_setPenColorToColor)
+            .add_input("COLOR", color),
+    )
+}
+
+/// Accepts:
+///  - "color"
+///  - "saturation"
+///  - "brightness"
+///  - "transparency"
+pub fn change_pen_color_param_by(param: BIB, value: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_changePenColorParamBy)
+            .add_input("COLOR_PARAM", param)
+            .add_input("VALUE", value),
+    )
+}
+
+/// Accepts:
+///  - "color"
+///  - "saturation"
+///  - "brightness"
+///  - "transparency"
+pub fn set_pen_color_param_to(param: BIB, value: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_setPenColorParamTo)
+            .add_input("COLOR_PARAM", param)
+            .add_input("VALUE", value),
+    )
+}
+
+/// Uses as argument to [`change_pen_color_param_by`] and [`set_pen_color_param_to`]
+/// Accepts:
+///  - "color"
+///  - "saturation"
+///  - "brightness"
+///  - "transparency"
+pub fn pen_color_param_menu(param: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_menu_colorParam)
+            .add_field("colorParam", param)
+            .shadow(true),
+    )
+}
+
+pub fn change_pen_size_by(size: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_changePenSizeBy).add_input("SIZE", size),
+    )
+}
+
+pub fn set_pen_size_to(size: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_setPenSizeTo).add_input("SIZE", size),
+    )
+}
+
+/// Legacy block from Scratch 2, hidden from the palette
+pub fn set_pen_shade_to(shade: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_setPenShadeToNumber)
+            .add_input("SHADE", shade),
+    )
+}
+
+/// Legacy block from Scratch 2, hidden from the palette
+pub fn change_pen_shade_by(shade: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_changePenShadeBy).add_input("SHADE", shade),
+    )
+}
+
+/// Legacy block from Scratch 2, hidden from the palette
+pub fn set_pen_hue_to(hue: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_setPenHueToNumber).add_input("HUE", hue),
+    )
+}
+
+/// Legacy block from Scratch 2, hidden from the palette
+pub fn change_pen_hue_by(hue: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(PenExtensionOpCode::pen_changePenHueBy).add_input("HUE", hue),
+    )
+}
+
+// Music =======================================================================
+pub fn play_drum_for_beats(drum: BIB, beats: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_playDrumForBeats)
+            .add_input("DRUM", drum)
+            .add_input("BEATS", beats),
+    )
+}
+
+/// Uses as argument to [`play_drum_for_beats`]
+/// Accepts:
+///  - [`super::menu::Drum`]
+///  - Drum number from "1" to "18"
+pub fn drum_menu(drum: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_menu_DRUM)
+            .add_field("DRUM", drum)
+            .shadow(true),
+    )
+}
+
+pub fn rest_for_beats(beats: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_restForBeats).add_input("BEATS", beats),
+    )
+}
+
+pub fn play_note_for_beats(note: BIB, beats: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_playNoteForBeats)
+            .add_input("NOTE", note)
+            .add_input("BEATS", beats),
+    )
+}
+
+/// Uses as argument to [`play_note_for_beats`]
+/// Accepts:
+///  - MIDI note number from "0" to "130". "60" is middle C
+pub fn note_menu(note: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::note)
+            .add_field("NOTE", note)
+            .shadow(true),
+    )
+}
+
+pub fn set_instrument_to(instrument: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_setInstrument)
+            .add_input("INSTRUMENT", instrument),
+    )
+}
+
+/// Uses as argument to [`set_instrument_to`]
+/// Accepts:
+///  - [`super::menu::Instrument`]
+///  - Instrument number from "1" to "21"
+pub fn instrument_menu(instrument: BFB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_menu_INSTRUMENT)
+            .add_field("INSTRUMENT", instrument)
+            .shadow(true),
+    )
+}
+
+pub fn set_tempo_to(tempo: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_setTempo).add_input("TEMPO", tempo),
+    )
+}
+
+pub fn change_tempo_by(tempo: BIB) -> StackBuilder {
+    StackBuilder::start(
+        BlockNormalBuilder::new(MusicExtensionOpCode::music_changeTempo).add_input("TEMPO", tempo),
+    )
+}
+
+pub fn tempo() -> StackBuilder {
+    StackBuilder::start(BlockNormalBuilder::new(
+        MusicExtensionOpCode::music_getTempo,
+    ))
+}
@@ -0,0 +1,360 @@
+//! Values of the menus that have a fixed set of options.
+//! They convert into [`String`] so they can be used where a field is wanted.
+//! In [`crate::typed_scripting::blocks`] they're the only accepted options,
+//! a [`super::script_builder::BlockFieldBuilder`] can be used for anything else.
+
+/// Drum in [`super::blocks::drum_menu`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drum {
+    SnareDrum = 1,
+    BassDrum,
+    SideStick,
+    CrashCymbal,
+    OpenHiHat,
+    ClosedHiHat,
+    Tambourine,
+    HandClap,
+    Claves,
+    WoodBlock,
+    Cowbell,
+    Triangle,
+    Bongo,
+    Conga,
+    Cabasa,
+    Guiro,
+    Vibraslap,
+    Cuica,
+}
+
+impl Drum {
+    const ALL: [Drum; 18] = [
+        Drum::SnareDrum,
+        Drum::BassDrum,
+        Drum::SideStick,
+        Drum::CrashCymbal,
+        Drum::OpenHiHat,
+        Drum::ClosedHiHat,
+        Drum::Tambourine,
+        Drum::HandClap,
+        Drum::Claves,
+        Drum::WoodBlock,
+        Drum::Cowbell,
+        Drum::Triangle,
+        Drum::Bongo,
+        Drum::Conga,
+        Drum::Cabasa,
+        Drum::Guiro,
+        Drum::Vibraslap,
+        Drum::Cuica,
+    ];
+
+    /// `value` is the number of the drum, starting at 1
+    pub fn from_value(value: &str) -> Option<Drum> {
+        let number: usize = value.parse().ok()?;
+        Drum::ALL.get(number.checked_sub(1)?).copied()
+    }
+}
+
+impl From<Drum> for String {
+    fn from(value: Drum) -> Self {
+        (value as u8).to_string()
+    }
+}
+
+/// Instrument in [`super::blocks::instrument_menu`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument {
+    Piano = 1,
+    ElectricPiano,
+    Organ,
+    Guitar,
+    ElectricGuitar,
+    Bass,
+    Pizzicato,
+    Cello,
+    Trombone,
+    Clarinet,
+    Saxophone,
+    Flute,
+    WoodenFlute,
+    Bassoon,
+    Choir,
+    Vibraphone,
+    MusicBox,
+    SteelDrum,
+    Marimba,
+    SynthLead,
+    SynthPad,
+}
+
+impl Instrument {
+    const ALL: [Instrument; 21] = [
+        Instrument::Piano,
+        Instrument::ElectricPiano,
+        Instrument::Organ,
+        Instrument::Guitar,
+        Instrument::ElectricGuitar,
+        Instrument::Bass,
+        Instrument::Pizzicato,
+        Instrument::Cello,
+        Instrument::Trombone,
+        Instrument::Clarinet,
+        Instrument::Saxophone,
+        Instrument::Flute,
+        Instrument::WoodenFlute,
+        Instrument::Bassoon,
+        Instrument::Choir,
+        Instrument::Vibraphone,
+        Instrument::MusicBox,
+        Instrument::SteelDrum,
+        Instrument::Marimba,
+        Instrument::SynthLead,
+        Instrument::SynthPad,
+    ];
+
+    /// `value` is the number of the instrument, starting at 1
+    pub fn from_value(value: &str) -> Option<Instrument> {
+        let number: usize = value.parse().ok()?;
+        Instrument::ALL.get(number.checked_sub(1)?).copied()
+    }
+}
+
+impl From<Instrument> for String {
+    fn from(value: Instrument) -> Self {
+        (value as u8).to_string()
+    }
+}
+
+macro_rules! menu_def {
+    ($(
+        $(#[$attributes:meta])*
+        $menu:ident {
+            $($variant:ident => $value:literal),* $(,)?
+        }
+    )*) => {
+        $(
+            $(#[$attributes])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum $menu {
+                $($variant),*
+            }
+
+            impl $menu {
+                /// Value of this option in `project.json`
+                pub fn value(self) -> &'static str {
+                    match self {
+                        $($menu::$variant => $value),*
+                    }
+                }
+
+                pub fn from_value(value: &str) -> Option<$menu> {
+                    match value {
+                        $($value => Some($menu::$variant),)*
+                        _ => None,
+                    }
+                }
+            }
+
+            impl From<$menu> for String {
+                fn from(value: $menu) -> Self {
+                    value.value().to_owned()
+                }
+            }
+        )*
+    };
+}
+
+menu_def! {
+    /// Effect in [`super::blocks::change_looks_effect_by`] and [`super::blocks::set_looks_effect_to`]
+    LooksEffect {
+        Color => "COLOR",
+        Fisheye => "FISHEYE",
+        Whirl => "WHIRL",
+        Pixelate => "PIXELATE",
+        Mosaic => "MOSAIC",
+        Brightness => "BRIGHTNESS",
+        Ghost => "GHOST",
+    }
+
+    /// Effect in [`super::blocks::change_sound_effect_by`] and [`super::blocks::set_sound_effect_to`]
+    SoundEffect {
+        Pitch => "PITCH",
+        PanLeftRight => "PAN",
+    }
+
+    /// Operation in [`super::blocks::math_op`]
+    MathOp {
+        Abs => "abs",
+        Floor => "floor",
+        Ceiling => "ceiling",
+        Sqrt => "sqrt",
+        Sin => "sin",
+        Cos => "cos",
+        Tan => "tan",
+        Asin => "asin",
+        Acos => "acos",
+        Atan => "atan",
+        Ln => "ln",
+        Log => "log",
+        EPow => "e ^",
+        TenPow => "10 ^",
+    }
+
+    /// Style in [`super::blocks::set_rotation_style`]
+    RotationStyle {
+        LeftRight => "left-right",
+        DontRotate => "don't rotate",
+        AllAround => "all around",
+    }
+
+    /// Layer in [`super::blocks::go_to_layer`]
+    FrontBack {
+        Front => "front",
+        Back => "back",
+    }
+
+    /// Direction in [`super::blocks::change_layer`]
+    ForwardBackward {
+        Forward => "forward",
+        Backward => "backward",
+    }
+
+    /// Option in [`super::blocks::stop`]
+    StopOption {
+        All => "all",
+        ThisScript => "this script",
+        OtherScriptsInSprite => "other scripts in sprite",
+    }
+
+    /// Value in [`super::blocks::when_greater_than`]
+    WhenGreaterThan {
+        Loudness => "LOUDNESS",
+        Timer => "TIMER",
+    }
+
+    /// Mode in [`super::blocks::set_drag_mode`]
+    DragMode {
+        Draggable => "draggable",
+        NotDraggable => "not draggable",
+    }
+
+    /// Unit in [`super::blocks::current_datetime`]
+    CurrentTime {
+        Year => "YEAR",
+        Month => "MONTH",
+        Date => "DATE",
+        DayOfWeek => "DAYOFWEEK",
+        Hour => "HOUR",
+        Minute => "MINUTE",
+        Second => "SECOND",
+    }
+
+    /// What [`super::blocks::costume`] and [`super::blocks::backdrop`] report
+    NumberName {
+        Number => "number",
+        Name => "name",
+    }
+
+    /// Param in [`super::blocks::pen_color_param_menu`]
+    PenColorParam {
+        Color => "color",
+        Saturation => "saturation",
+        Brightness => "brightness",
+        Transparency => "transparency",
+    }
+
+    /// Option of [`super::blocks::backdrop_menu`] that isn't a backdrop
+    BackdropOption {
+        Next => "next backdrop",
+        Previous => "previous backdrop",
+        Random => "random backdrop",
+    }
+
+    /// Option of [`super::blocks::create_clone_of_menu`] that isn't a sprite
+    CloneTarget {
+        Myself => "_myself_",
+    }
+
+    /// Option of [`super::blocks::go_to_menu`] and [`super::blocks::glide_to_menu`] that isn't a sprite
+    GoToTarget {
+        Mouse => "_mouse_",
+        Random => "_random_",
+    }
+
+    /// Option of [`super::blocks::point_towards_menu`] that isn't a sprite
+    PointTowardsTarget {
+        Mouse => "_mouse_",
+        Random => "_random_",
+    }
+
+    /// Option of [`super::blocks::touching_menu`] and [`super::blocks::when_touching_menu`] that isn't a sprite
+    TouchingTarget {
+        Mouse => "_mouse_",
+        Edge => "_edge_",
+    }
+
+    /// Option of [`super::blocks::distance_to_menu`] that isn't a sprite
+    DistanceToTarget {
+        Mouse => "_mouse_",
+    }
+
+    /// Option of [`super::blocks::var_of_object_menu`] that isn't a sprite
+    ObjectTarget {
+        Stage => "_stage_",
+    }
+}
+
+/// Key in [`super::blocks::when_key_pressed`] and [`super::blocks::key_menu`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Any,
+    Space,
+    LeftArrow,
+    RightArrow,
+    UpArrow,
+    DownArrow,
+    /// Letter from 'a' to 'z'
+    Letter(char),
+    /// Digit from 0 to 9
+    Digit(u8),
+}
+
+impl Key {
+    pub fn from_value(value: &str) -> Option<Key> {
+        let key = match value {
+            "any" => Key::Any,
+            "space" => Key::Space,
+            "left arrow" => Key::LeftArrow,
+            "right arrow" => Key::RightArrow,
+            "up arrow" => Key::UpArrow,
+            "down arrow" => Key::DownArrow,
+            _ => {
+                let mut chars = value.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    return None;
+                };
+                match c {
+                    'a'..='z' => Key::Letter(c),
+                    '0'..='9' => Key::Digit(c as u8 - b'0'),
+                    _ => return None,
+                }
+            }
+        };
+        Some(key)
+    }
+}
+
+impl From<Key> for String {
+    fn from(value: Key) -> Self {
+        match value {
+            Key::Any => "any".to_owned(),
+            Key::Space => "space".to_owned(),
+            Key::LeftArrow => "left arrow".to_owned(),
+            Key::RightArrow => "right arrow".to_owned(),
+            Key::UpArrow => "up arrow".to_owned(),
+            Key::DownArrow => "down arrow".to_owned(),
+            Key::Letter(letter) => letter.to_ascii_lowercase().to_string(),
+            Key::Digit(digit) => digit.to_string(),
+        }
+    }
+}
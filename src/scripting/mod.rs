@@ -1,4 +1,5 @@
 pub mod blocks;
+pub mod menu;
+pub mod procedure;
 pub mod script_builder;
-
-// mod procedural;
+pub mod spec;
@@ -0,0 +1,287 @@
+//! Custom blocks, or "My Blocks" in the editor.
+//!
+//! A procedure is declared with [`ProcedureBuilder`] and added to a target with
+//! [`crate::project::target::TargetBuilder::add_procedure`].
+//! Blocks calling the procedure only need the [`ProcedureHandle`],
+//! the argument ids are resolved when the target is building.
+
+use rs_sb3::{
+    block::{BlockMutation, BlockMutationEnum, ShadowInputType},
+    value::Value,
+};
+
+use crate::{
+    opcode::PrimaryOpCode,
+    uid::{Uid, UidGenerator},
+};
+
+use super::{
+    blocks::define_procedure,
+    script_builder::{
+        BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder, StackBuilder, StackOrValue,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcedureArgumentKind {
+    /// Round argument, shown as `%s` in proccode
+    StringNumber,
+    /// Pointy argument, shown as `%b` in proccode
+    Boolean,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ProcedurePart {
+    Label(String),
+    Argument {
+        name: String,
+        kind: ProcedureArgumentKind,
+    },
+}
+
+/// Declaration and definition of a custom block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcedureBuilder {
+    parts: Vec<ProcedurePart>,
+    warp: bool,
+    body: Option<StackBuilder>,
+}
+
+impl ProcedureBuilder {
+    /// Starts the custom block with a label
+    pub fn new<S: Into<String>>(label: S) -> ProcedureBuilder {
+        ProcedureBuilder {
+            parts: vec![ProcedurePart::Label(label.into())],
+            warp: false,
+            body: None,
+        }
+    }
+
+    /// Custom block of a `procedures_prototype` with this proccode and argument names.
+    /// `None` when the proccode doesn't have a `%s` or `%b` for each name.
+    pub(crate) fn from_proccode(
+        proccode: &str,
+        argument_names: &[String],
+        warp: bool,
+    ) -> Option<ProcedureBuilder> {
+        let mut parts = vec![];
+        let mut names = argument_names.iter();
+        for word in proccode.split(' ') {
+            let kind = match word {
+                "%s" => ProcedureArgumentKind::StringNumber,
+                "%b" => ProcedureArgumentKind::Boolean,
+                _ => {
+                    match parts.last_mut() {
+                        Some(ProcedurePart::Label(label)) => {
+                            label.push(' ');
+                            label.push_str(word);
+                        }
+                        _ => parts.push(ProcedurePart::Label(word.to_owned())),
+                    }
+                    continue;
+                }
+            };
+            parts.push(ProcedurePart::Argument {
+                name: names.next()?.clone(),
+                kind,
+            });
+        }
+        if names.next().is_some() {
+            return None;
+        }
+        Some(ProcedureBuilder {
+            parts,
+            warp,
+            body: None,
+        })
+    }
+
+    pub fn label<S: Into<String>>(mut self, label: S) -> Self {
+        self.parts.push(ProcedurePart::Label(label.into()));
+        self
+    }
+
+    /// Argument name must be unique within this procedure, a duplicate fails the build
+    pub fn string_number_argument<S: Into<String>>(mut self, name: S) -> Self {
+        self.parts.push(ProcedurePart::Argument {
+            name: name.into(),
+            kind: ProcedureArgumentKind::StringNumber,
+        });
+        self
+    }
+
+    /// Argument name must be unique within this procedure, a duplicate fails the build
+    pub fn boolean_argument<S: Into<String>>(mut self, name: S) -> Self {
+        self.parts.push(ProcedurePart::Argument {
+            name: name.into(),
+            kind: ProcedureArgumentKind::Boolean,
+        });
+        self
+    }
+
+    /// "Run without screen refresh"
+    pub fn warp(mut self, warp: bool) -> Self {
+        self.warp = warp;
+        self
+    }
+
+    /// Blocks to run under the definition hat
+    pub fn body(mut self, body: StackBuilder) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    pub(crate) fn body_ref(&self) -> Option<&StackBuilder> {
+        self.body.as_ref()
+    }
+
+    /// Ex. `"jump %s times %b"`
+    pub fn proccode(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                ProcedurePart::Label(label) => label.as_str(),
+                ProcedurePart::Argument {
+                    kind: ProcedureArgumentKind::StringNumber,
+                    ..
+                } => "%s",
+                ProcedurePart::Argument {
+                    kind: ProcedureArgumentKind::Boolean,
+                    ..
+                } => "%b",
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn argument_names(&self) -> Vec<String> {
+        self.arguments().map(|(name, _)| name.to_owned()).collect()
+    }
+
+    fn arguments(&self) -> impl Iterator<Item = (&str, ProcedureArgumentKind)> {
+        self.parts.iter().filter_map(|part| match part {
+            ProcedurePart::Label(_) => None,
+            ProcedurePart::Argument { name, kind } => Some((name.as_str(), *kind)),
+        })
+    }
+
+    /// Handle to use in [`super::blocks::call_procedure`]
+    pub fn handle(&self) -> ProcedureHandle {
+        ProcedureHandle {
+            proccode: self.proccode(),
+            argument_names: self.argument_names(),
+            argument_kinds: self.arguments().map(|(_, kind)| kind).collect(),
+        }
+    }
+
+    /// Generates id for each arguments.
+    /// `path` is where this procedure is at, the ids are derived from it.
+    pub fn context(&self, uid_gen: &mut UidGenerator, path: &str) -> ProcedureContext {
+        ProcedureContext {
+            argument_ids: self
+                .arguments()
+                .map(|(name, _)| uid_gen.derive(&format!("{path}/{name}")))
+                .collect(),
+            argument_names: self.argument_names(),
+            warp: self.warp,
+        }
+    }
+
+    /// Requires:
+    /// - context: Context generated by [`ProcedureBuilder::context`] for this procedure
+    ///
+    /// Returns:
+    /// - [`StackBuilder`]: Definition hat with the prototype and the body
+    pub fn build(self, context: &ProcedureContext) -> StackBuilder {
+        let proccode = self.proccode();
+        let mut prototype = BlockNormalBuilder::new(PrimaryOpCode::procedures_prototype)
+            .shadow(true)
+            .mutation(BlockMutation {
+                tag_name: "mutation".to_owned(),
+                children: vec![],
+                mutation_enum: BlockMutationEnum::ProceduresPrototype {
+                    proccode,
+                    argumentids: context
+                        .argument_ids
+                        .iter()
+                        .map(|uid| uid.clone().into_inner())
+                        .collect(),
+                    argumentnames: context.argument_names.clone(),
+                    argumentdefaults: self
+                        .arguments()
+                        .map(|(_, kind)| match kind {
+                            ProcedureArgumentKind::StringNumber => Value::Text("".to_owned()),
+                            ProcedureArgumentKind::Boolean => Value::Text("false".to_owned()),
+                        })
+                        .collect(),
+                    warp: self.warp,
+                },
+            });
+        for ((name, kind), arg_id) in self.arguments().zip(context.argument_ids.iter()) {
+            let opcode = match kind {
+                ProcedureArgumentKind::StringNumber => {
+                    PrimaryOpCode::argument_reporter_string_number
+                }
+                ProcedureArgumentKind::Boolean => PrimaryOpCode::argument_reporter_boolean,
+            };
+            let reporter = BlockNormalBuilder::new(opcode)
+                .add_field("VALUE", BlockFieldBuilder::new(name.to_owned()))
+                .shadow(true);
+            prototype = prototype.add_input(
+                arg_id.clone().into_inner(),
+                shadow_input(StackBuilder::start(reporter)),
+            );
+        }
+        let definition = define_procedure(shadow_input(StackBuilder::start(prototype)));
+        match self.body {
+            Some(body) => definition.next(body),
+            None => definition,
+        }
+    }
+}
+
+fn shadow_input(stack: StackBuilder) -> BlockInputBuilder {
+    BlockInputBuilder::new()
+        .shadow(ShadowInputType::Shadow)
+        .input(Some(StackOrValue::Stack(stack)))
+}
+
+/// Reference to a procedure for calling it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcedureHandle {
+    proccode: String,
+    argument_names: Vec<String>,
+    argument_kinds: Vec<ProcedureArgumentKind>,
+}
+
+impl ProcedureHandle {
+    pub fn proccode(&self) -> &str {
+        &self.proccode
+    }
+
+    pub fn argument_names(&self) -> &[String] {
+        &self.argument_names
+    }
+
+    /// Kind of each argument, in the same order as [`ProcedureHandle::argument_names`]
+    pub fn argument_kinds(&self) -> &[ProcedureArgumentKind] {
+        &self.argument_kinds
+    }
+}
+
+/// Procedure's information that blocks calling it needs when building
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcedureContext {
+    pub argument_ids: Vec<Uid>,
+    pub argument_names: Vec<String>,
+    pub warp: bool,
+}
+
+impl ProcedureContext {
+    pub fn argument_id(&self, argument_name: &str) -> Option<&Uid> {
+        self.argument_names
+            .iter()
+            .position(|name| name == argument_name)
+            .map(|idx| &self.argument_ids[idx])
+    }
+}
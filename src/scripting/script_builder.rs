@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use crate::{project::script::CommentBuilder, uid::Uid};
+use crate::{
+    project::script::CommentBuilder,
+    scripting::{procedure::ProcedureContext, spec::spec_of},
+    uid::{Uid, UidGenerator},
+};
 use rs_sb3::{
     block::{
-        Block, BlockField, BlockInput, BlockInputValue, BlockMutation, BlockNormal,
-        BlockVarListReporterTop, ListOrVariable, ShadowInputType, UidOrValue,
+        Block, BlockField, BlockInput, BlockInputValue, BlockMutation, BlockMutationEnum,
+        BlockNormal, BlockVarListReporterTop, ListOrVariable, ShadowInputType, UidOrValue,
     },
     comment::Comment,
     string_hashmap::StringHashMap,
@@ -56,12 +60,19 @@ impl BlockInputBuilder {
     /// Shortcut for
     /// ```
     /// BlockInputBuilder::new()
-    ///     .shadow(ShadowInputType::NoShadow)
+    ///     .shadow(ShadowInputType::ShadowObscured)
     ///     .input(Some(StackOrValue::Stack(stack)))
     /// ```
+    /// Like the editor, the reporter covers the input's default shadow which is filled in by
+    /// [`BlockNormalBuilder::add_input`]. It's [`ShadowInputType::NoShadow`] when the input doesn't have one,
+    /// like a boolean slot or a substack, and [`ShadowInputType::Shadow`] if `stack` is a shadow block such as a menu.
     pub fn stack(stack: StackBuilder) -> Self {
+        let shadow = match stack.blocks().first() {
+            Some(BlockBuilder::Normal(block)) if block.is_shadow() => ShadowInputType::Shadow,
+            _ => ShadowInputType::ShadowObscured,
+        };
         BlockInputBuilder::new()
-            .shadow(ShadowInputType::NoShadow)
+            .shadow(shadow)
             .input(Some(StackOrValue::Stack(stack)))
     }
 
@@ -79,21 +90,96 @@ impl BlockInputBuilder {
             .input(Some(StackOrValue::Value(value)))
     }
 
+    /// Broadcast by name. The id is resolved when building.
+    pub fn broadcast<S: Into<String>>(name: S) -> Self {
+        BlockInputBuilder::value(BlockInputValue::Broadcast {
+            name: name.into(),
+            id: String::new(),
+        })
+    }
+
+    /// Puts `shadow` under the reporter of an input made by [`BlockInputBuilder::stack`].
+    /// Without a shadow the reporter isn't covering anything.
+    pub(crate) fn cover(mut self, shadow: impl FnOnce() -> Option<StackOrValue>) -> Self {
+        if self.is_uncovered() {
+            match shadow() {
+                Some(shadow) => self.values.push(Some(shadow)),
+                None => self.shadow = ShadowInputType::NoShadow,
+            }
+        }
+        self
+    }
+
+    fn is_uncovered(&self) -> bool {
+        matches!(self.shadow, ShadowInputType::ShadowObscured) && self.values.len() == 1
+    }
+
+    pub fn values(&self) -> &[Option<StackOrValue>] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [Option<StackOrValue>] {
+        &mut self.values
+    }
+
+    /// `opcode` is of the block that this input is in.
+    fn collect_references(&self, opcode: &OpCode, references: &mut Vec<NameReference>) {
+        for value in &self.values {
+            match value {
+                Some(StackOrValue::Stack(stack)) => stack.collect_references(references),
+                Some(StackOrValue::Value(BlockInputValue::Broadcast { name, id }))
+                    if id.is_empty() =>
+                {
+                    references.push(NameReference {
+                        name: name.clone(),
+                        kind: ReferenceKind::Broadcast,
+                        opcode: opcode.clone(),
+                        owner: None,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `input_key` is the key this input is at in the block.
     pub fn build(
         self,
         this_block_uid: &Uid,
+        input_key: &str,
         comment_buff: &mut HashMap<Uid, Comment>,
         final_stack: &mut HashMap<Uid, Block>,
+        uid_gen: &mut UidGenerator,
         target_context: &TargetContext,
     ) -> BlockInput {
-        let BlockInputBuilder { shadow, values } = self;
+        let shadow = if self.is_uncovered() {
+            ShadowInputType::NoShadow
+        } else {
+            self.shadow
+        };
+        let values = self.values;
         let mut values_b: Vec<Option<UidOrValue>> = vec![];
-        for value in values {
+        for (i, value) in values.into_iter().enumerate() {
             match value {
+                Some(StackOrValue::Value(BlockInputValue::Broadcast { name, id }))
+                    if id.is_empty() =>
+                {
+                    let id = target_context
+                        .all_broadcasts
+                        .get(&name)
+                        .map(|uid| uid.clone().into_inner())
+                        .unwrap_or_else(|| "__unknown__".to_owned());
+                    values_b.push(Some(UidOrValue::Value(BlockInputValue::Broadcast {
+                        name,
+                        id,
+                    })))
+                }
                 Some(StackOrValue::Value(v)) => values_b.push(Some(UidOrValue::Value(v))),
                 Some(StackOrValue::Stack(s)) => {
-                    let first_block_uid = Uid::generate();
-                    let mut s_builded = s.build(&first_block_uid, comment_buff, target_context);
+                    let first_block_uid =
+                        uid_gen.derive(&format!("{}/{input_key}/{i}", this_block_uid.inner()));
+                    let mut s_builded =
+                        s.build(&first_block_uid, comment_buff, uid_gen, target_context);
                     let first_block = s_builded.get_mut(&first_block_uid).unwrap();
                     match first_block {
                         Block::Normal(n) => {
@@ -101,6 +187,8 @@ impl BlockInputBuilder {
                             n.top_level = false;
                             n.x = None;
                             n.y = None;
+                            final_stack.extend(s_builded);
+                            values_b.push(Some(UidOrValue::Uid(first_block_uid.into_inner())))
                         }
                         Block::VarList(_) => {
                             let Block::VarList(vl) = s_builded.remove(&first_block_uid).unwrap() else {
@@ -113,8 +201,6 @@ impl BlockInputBuilder {
                             })))
                         }
                     }
-                    final_stack.extend(s_builded);
-                    values_b.push(Some(UidOrValue::Uid(first_block_uid.into_inner())))
                 }
                 None => values_b.push(None),
             }
@@ -134,6 +220,11 @@ pub struct BlockNormalBuilder {
     inputs: HashMap<String, BlockInputBuilder>,
     fields: HashMap<String, BlockFieldBuilder>,
     mutation: Option<BlockMutation>,
+    /// Proccode of the procedure this block is calling.
+    /// Inputs are keyed by argument name and will be resolved to argument ids when building.
+    procedure_call: Option<String>,
+    /// Number of arguments the call is given, checked against the procedure before building
+    call_arguments: Option<usize>,
     shadow: bool,
     x: Option<f64>,
     y: Option<f64>,
@@ -147,12 +238,19 @@ impl BlockNormalBuilder {
         }
     }
 
+    /// A reporter from [`BlockInputBuilder::stack`] covers the default shadow of this input in the spec.
     pub fn add_input<K: Into<String>>(
         mut self,
         key: K,
         block_input_builder: BlockInputBuilder,
     ) -> Self {
-        self.inputs.insert(key.into(), block_input_builder);
+        let key = key.into();
+        let input = block_input_builder.cover(|| {
+            spec_of(self.opcode.as_str())?
+                .input_spec(&key)?
+                .default_shadow()
+        });
+        self.inputs.insert(key, input);
         self
     }
 
@@ -192,11 +290,137 @@ impl BlockNormalBuilder {
         self
     }
 
+    /// Makes this block a call to the procedure with this `proccode`.
+    /// The mutation and the inputs' key (which should be the argument name) are resolved when building.
+    pub fn procedure_call<S: Into<String>>(mut self, proccode: S) -> Self {
+        self.procedure_call = Some(proccode.into());
+        self
+    }
+
+    /// Number of arguments given to the procedure call.
+    /// The build fails when it isn't the number of arguments the procedure has.
+    pub fn call_arguments(mut self, count: usize) -> Self {
+        self.call_arguments = Some(count);
+        self
+    }
+
+    pub fn opcode(&self) -> &str {
+        &self.opcode
+    }
+
+    pub fn inputs(&self) -> &HashMap<String, BlockInputBuilder> {
+        &self.inputs
+    }
+
+    pub fn inputs_mut(&mut self) -> &mut HashMap<String, BlockInputBuilder> {
+        &mut self.inputs
+    }
+
+    pub fn fields(&self) -> &HashMap<String, BlockFieldBuilder> {
+        &self.fields
+    }
+
+    pub fn comment_ref(&self) -> Option<&CommentBuilder> {
+        self.comment.as_ref()
+    }
+
+    pub fn comment_mut(&mut self) -> Option<&mut CommentBuilder> {
+        self.comment.as_mut()
+    }
+
+    pub fn mutation_ref(&self) -> Option<&BlockMutation> {
+        self.mutation.as_ref()
+    }
+
+    /// Proccode set by [`BlockNormalBuilder::procedure_call`]
+    pub fn called_procedure(&self) -> Option<&str> {
+        self.procedure_call.as_deref()
+    }
+
+    /// Set by [`BlockNormalBuilder::call_arguments`]
+    pub fn call_argument_count(&self) -> Option<usize> {
+        self.call_arguments
+    }
+
+    pub fn is_shadow(&self) -> bool {
+        self.shadow
+    }
+
+    /// Position set by [`BlockNormalBuilder::pos`]
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
+
+    fn collect_references(&self, references: &mut Vec<NameReference>) {
+        if let Some(proccode) = &self.procedure_call {
+            references.push(NameReference {
+                name: proccode.clone(),
+                kind: ReferenceKind::Procedure,
+                opcode: self.opcode.clone(),
+                owner: None,
+            });
+        }
+        for field in self.fields.values() {
+            if let Some(kind) = field.reference_kind() {
+                references.push(NameReference {
+                    name: field.value.clone(),
+                    kind,
+                    opcode: self.opcode.clone(),
+                    owner: None,
+                });
+            }
+        }
+        for input in self.inputs.values() {
+            input.collect_references(&self.opcode, references);
+        }
+        if let Some(reference) = self.property_reference() {
+            references.push(reference);
+        }
+    }
+
+    /// Variable of another sprite that `sensing_of` reports.
+    /// Only known when the sprite in the menu is a [`FieldKind::Sprite`].
+    fn property_reference(&self) -> Option<NameReference> {
+        const BUILTIN: &[&str] = &[
+            "x position",
+            "y position",
+            "direction",
+            "costume #",
+            "costume name",
+            "size",
+            "volume",
+        ];
+        if self.opcode != "sensing_of" {
+            return None;
+        }
+        let property = self.fields.get("PROPERTY")?;
+        if BUILTIN.contains(&property.value()) {
+            return None;
+        }
+        let Some(StackOrValue::Stack(menu)) = self.inputs.get("OBJECT")?.values().first()? else {
+            return None;
+        };
+        let BlockBuilder::Normal(menu) = menu.stack.first()? else {
+            return None;
+        };
+        let sprite = menu
+            .fields
+            .get("OBJECT")
+            .filter(|field| field.kind == FieldKind::Sprite)?;
+        Some(NameReference {
+            name: property.value.clone(),
+            kind: ReferenceKind::SpriteVariable,
+            opcode: self.opcode.clone(),
+            owner: Some(sprite.value.clone()),
+        })
+    }
+
     fn build(
         self,
         my_uid: &Uid,
         comment_buff: &mut HashMap<Uid, Comment>,
         final_stack: &mut HashMap<Uid, Block>,
+        uid_gen: &mut UidGenerator,
         target_context: &TargetContext,
     ) -> BlockNormal {
         let BlockNormalBuilder {
@@ -206,9 +430,45 @@ impl BlockNormalBuilder {
             fields,
             shadow,
             mutation,
+            procedure_call,
+            call_arguments: _,
             x,
             y,
         } = self;
+        let (inputs, mutation) = match procedure_call {
+            Some(proccode) => {
+                let procedure = target_context.procedures.get(&proccode);
+                let inputs: HashMap<String, BlockInputBuilder> = inputs
+                    .into_iter()
+                    .map(|(arg_name, input)| {
+                        let arg_id = procedure
+                            .and_then(|procedure| procedure.argument_id(&arg_name))
+                            .cloned()
+                            .unwrap_or_else(|| Uid::new("__unknown__"));
+                        (arg_id.into_inner(), input)
+                    })
+                    .collect();
+                let mutation = BlockMutation {
+                    tag_name: "mutation".to_owned(),
+                    children: vec![],
+                    mutation_enum: BlockMutationEnum::ProceduresCall {
+                        proccode,
+                        argumentids: procedure
+                            .map(|procedure| {
+                                procedure
+                                    .argument_ids
+                                    .iter()
+                                    .map(|uid| uid.clone().into_inner())
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        warp: procedure.map(|procedure| procedure.warp).unwrap_or(false),
+                    },
+                };
+                (inputs, Some(mutation))
+            }
+            None => (inputs, mutation),
+        };
         // let mut inputs_b: HashMap<String, BlockInput> = HashMap::default();
         // for (key, input) in inputs {
         //     inputs_b.insert(key, input.build(comment_buff, final_stack, &my_uid));
@@ -216,10 +476,15 @@ impl BlockNormalBuilder {
         let inputs: HashMap<String, BlockInput> = inputs
             .into_iter()
             .map(|(key, input)| {
-                (
-                    key,
-                    input.build(my_uid, comment_buff, final_stack, target_context),
-                )
+                let input = input.build(
+                    my_uid,
+                    &key,
+                    comment_buff,
+                    final_stack,
+                    uid_gen,
+                    target_context,
+                );
+                (key, input)
             })
             .collect();
         let fields: HashMap<String, BlockField> = fields
@@ -228,7 +493,7 @@ impl BlockNormalBuilder {
             .collect();
         let comment = match comment {
             Some(comment) => {
-                let comment_uid = Uid::generate();
+                let comment_uid = uid_gen.derive(&format!("{}/comment", my_uid.inner()));
                 let mut comment = comment.build();
                 comment.block_id = Some(my_uid.clone().into_inner());
                 comment_buff.insert(comment_uid.clone(), comment);
@@ -263,6 +528,11 @@ pub enum FieldKind {
     GlobalVariable,
     SpriteList,
     GlobalList,
+    /// Names that are checked when building but don't have an id
+    Costume,
+    Backdrop,
+    Sound,
+    Sprite,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -283,6 +553,29 @@ impl BlockFieldBuilder {
         }
     }
 
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn kind(&self) -> FieldKind {
+        self.kind
+    }
+
+    fn reference_kind(&self) -> Option<ReferenceKind> {
+        match self.kind {
+            FieldKind::NoRef | FieldKind::NoRefMaybe => None,
+            FieldKind::Broadcast => Some(ReferenceKind::Broadcast),
+            FieldKind::SpriteVariable => Some(ReferenceKind::SpriteVariable),
+            FieldKind::GlobalVariable => Some(ReferenceKind::GlobalVariable),
+            FieldKind::SpriteList => Some(ReferenceKind::SpriteList),
+            FieldKind::GlobalList => Some(ReferenceKind::GlobalList),
+            FieldKind::Costume => Some(ReferenceKind::Costume),
+            FieldKind::Backdrop => Some(ReferenceKind::Backdrop),
+            FieldKind::Sound => Some(ReferenceKind::Sound),
+            FieldKind::Sprite => Some(ReferenceKind::Sprite),
+        }
+    }
+
     pub fn build(self, target_context: &TargetContext) -> BlockField {
         let BlockFieldBuilder { value, kind } = self;
         let value = value.into();
@@ -291,7 +584,11 @@ impl BlockFieldBuilder {
         };
         let id = match kind {
             FieldKind::NoRef => return BlockField::NoId { value },
-            FieldKind::NoRefMaybe => return BlockField::WithId { value, id: None },
+            FieldKind::NoRefMaybe
+            | FieldKind::Costume
+            | FieldKind::Backdrop
+            | FieldKind::Sound
+            | FieldKind::Sprite => return BlockField::WithId { value, id: None },
 
             FieldKind::Broadcast => target_context.all_broadcasts,
             FieldKind::SpriteVariable => target_context.this_sprite_vars,
@@ -320,8 +617,8 @@ pub struct BlockVarListBuilder {
     kind: ListOrVariable,
     from: VarListFrom,
     name: String,
-    x: f64,
-    y: f64,
+    x: Option<f64>,
+    y: Option<f64>,
     comment: Option<CommentBuilder>,
 }
 
@@ -334,8 +631,8 @@ impl BlockVarListBuilder {
             kind: var_or_list,
             name: name.into(),
             from: VarListFrom::Global,
-            x: 0.,
-            y: 0.,
+            x: None,
+            y: None,
             comment: None,
         }
     }
@@ -348,8 +645,8 @@ impl BlockVarListBuilder {
             kind: var_or_list,
             from: VarListFrom::Sprite,
             name: name.into(),
-            x: 0.,
-            y: 0.,
+            x: None,
+            y: None,
             comment: None,
         }
     }
@@ -360,21 +657,66 @@ impl BlockVarListBuilder {
     }
 
     pub fn pos(mut self, x: f64, y: f64) -> Self {
-        self.x = x;
-        self.y = y;
+        self.x = Some(x);
+        self.y = Some(y);
         self
     }
 
     pub fn mut_pos(&mut self, x: f64, y: f64) -> &mut Self {
-        self.x = x;
-        self.y = y;
+        self.x = Some(x);
+        self.y = Some(y);
         self
     }
 
+    pub fn kind(&self) -> &ListOrVariable {
+        &self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn comment_ref(&self) -> Option<&CommentBuilder> {
+        self.comment.as_ref()
+    }
+
+    pub fn comment_mut(&mut self) -> Option<&mut CommentBuilder> {
+        self.comment.as_mut()
+    }
+
+    /// Position set by [`BlockVarListBuilder::pos`]
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.x.zip(self.y)
+    }
+
+    pub(crate) fn reference(&self) -> NameReference {
+        let (kind, opcode) = match (&self.kind, self.from) {
+            (ListOrVariable::Variable, VarListFrom::Global) => {
+                (ReferenceKind::GlobalVariable, "data_variable")
+            }
+            (ListOrVariable::Variable, VarListFrom::Sprite) => {
+                (ReferenceKind::SpriteVariable, "data_variable")
+            }
+            (ListOrVariable::List, VarListFrom::Global) => {
+                (ReferenceKind::GlobalList, "data_listcontents")
+            }
+            (ListOrVariable::List, VarListFrom::Sprite) => {
+                (ReferenceKind::SpriteList, "data_listcontents")
+            }
+        };
+        NameReference {
+            name: self.name.clone(),
+            kind,
+            opcode: opcode.to_owned(),
+            owner: None,
+        }
+    }
+
     pub fn build(
         self,
         my_uid: &Uid,
         comment_buff: &mut HashMap<Uid, Comment>,
+        uid_gen: &mut UidGenerator,
         target_context: &TargetContext,
     ) -> BlockVarListReporterTop {
         let BlockVarListBuilder {
@@ -395,7 +737,7 @@ impl BlockVarListBuilder {
         .map(|uid| uid.clone())
         .unwrap_or(Uid::new("__unknown__"));
         if let Some(comment) = comment {
-            let comment_uid = Uid::generate();
+            let comment_uid = uid_gen.derive(&format!("{}/comment", my_uid.inner()));
             let mut comment = comment.build();
             comment.block_id = Some(my_uid.clone().into_inner());
             comment_buff.insert(comment_uid.clone(), comment);
@@ -404,8 +746,8 @@ impl BlockVarListBuilder {
             kind,
             name,
             id: varlist_id.into_inner(),
-            x: x.into(),
-            y: y.into(),
+            x: x.unwrap_or(0.).into(),
+            y: y.unwrap_or(0.).into(),
         };
         block_varlist_b
     }
@@ -417,29 +759,83 @@ pub enum BlockBuilder {
     VarList(BlockVarListBuilder),
 }
 
+/// Kind of name that a block uses and have to be resolved to an id when building.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    Broadcast,
+    SpriteVariable,
+    GlobalVariable,
+    SpriteList,
+    GlobalList,
+    Procedure,
+    Costume,
+    Backdrop,
+    Sound,
+    Sprite,
+}
+
+impl std::fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ReferenceKind::Broadcast => "broadcast",
+            ReferenceKind::SpriteVariable => "sprite variable",
+            ReferenceKind::GlobalVariable => "global variable",
+            ReferenceKind::SpriteList => "sprite list",
+            ReferenceKind::GlobalList => "global list",
+            ReferenceKind::Procedure => "procedure",
+            ReferenceKind::Costume => "costume",
+            ReferenceKind::Backdrop => "backdrop",
+            ReferenceKind::Sound => "sound",
+            ReferenceKind::Sprite => "sprite",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// Name used by a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameReference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    /// Opcode of the block that use this name
+    pub opcode: OpCode,
+    /// Sprite that declares the name when it isn't the target the block is in.
+    /// Ex. the variable of another sprite in `var_of`.
+    pub owner: Option<String>,
+}
+
 pub struct TargetContext<'a> {
     pub global_vars: &'a HashMap<String, Uid>,
     pub global_lists: &'a HashMap<String, Uid>,
     pub this_sprite_vars: &'a HashMap<String, Uid>,
     pub this_sprite_lists: &'a HashMap<String, Uid>,
     pub all_broadcasts: &'a HashMap<String, Uid>,
+    pub procedures: &'a HashMap<String, ProcedureContext>,
 }
 
 impl BlockBuilder {
+    fn collect_references(&self, references: &mut Vec<NameReference>) {
+        match self {
+            BlockBuilder::Normal(n) => n.collect_references(references),
+            BlockBuilder::VarList(vl) => references.push(vl.reference()),
+        }
+    }
+
     pub fn build(
         self,
         my_uid: &Uid,
         comment_buff: &mut HashMap<Uid, Comment>,
         final_stack: &mut HashMap<Uid, Block>,
+        uid_gen: &mut UidGenerator,
         target_context: &TargetContext,
     ) -> Block {
         match self {
             BlockBuilder::Normal(n) => {
-                let b = n.build(my_uid, comment_buff, final_stack, target_context);
+                let b = n.build(my_uid, comment_buff, final_stack, uid_gen, target_context);
                 Block::Normal(b)
             }
             BlockBuilder::VarList(vl) => {
-                let b = vl.build(my_uid, comment_buff, target_context);
+                let b = vl.build(my_uid, comment_buff, uid_gen, target_context);
                 Block::VarList(b)
             }
         }
@@ -504,10 +900,41 @@ impl StackBuilder {
         self
     }
 
+    pub fn blocks(&self) -> &[BlockBuilder] {
+        &self.stack
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut [BlockBuilder] {
+        &mut self.stack
+    }
+
+    /// Position set by [`StackBuilder::move_head`]
+    pub fn head_position(&self) -> Option<(f64, f64)> {
+        match self.stack.first()? {
+            BlockBuilder::Normal(n) => n.position(),
+            BlockBuilder::VarList(vl) => vl.position(),
+        }
+    }
+
+    /// Every names that this stack uses and needs to be resolved when building
+    pub fn references(&self) -> Vec<NameReference> {
+        let mut references = vec![];
+        self.collect_references(&mut references);
+        references
+    }
+
+    fn collect_references(&self, references: &mut Vec<NameReference>) {
+        for block in &self.stack {
+            block.collect_references(references);
+        }
+    }
+
+    /// Uid of the rest of the blocks are derived from `first_block_uid`
     pub fn build(
         self,
         first_block_uid: &Uid,
         comment_buff: &mut HashMap<Uid, Comment>,
+        uid_gen: &mut UidGenerator,
         target_context: &TargetContext,
     ) -> HashMap<Uid, Block> {
         let mut stack_b: HashMap<Uid, Block> = HashMap::default();
@@ -516,20 +943,26 @@ impl StackBuilder {
             &first_block_uid,
             comment_buff,
             &mut stack_b,
+            uid_gen,
             target_context,
         );
 
         match first_block {
             Block::Normal(mut first_block) => {
                 first_block.top_level = true;
-                first_block.x = Some(0.into());
-                first_block.y = Some(0.into());
+                first_block.x = first_block.x.or(Some(0.into()));
+                first_block.y = first_block.y.or(Some(0.into()));
                 let mut previous_block = (first_block, first_block_uid.clone());
                 for block_builder2 in self_stack_iter {
                     let (mut block1, block1_uid) = previous_block;
-                    let block2_uid = Uid::generate();
-                    let Block::Normal(mut block2) =
-                        block_builder2.build(&block2_uid, comment_buff, &mut stack_b, target_context) else {
+                    let block2_uid = uid_gen.derive(&format!("{}/next", block1_uid.inner()));
+                    let Block::Normal(mut block2) = block_builder2.build(
+                        &block2_uid,
+                        comment_buff,
+                        &mut stack_b,
+                        uid_gen,
+                        target_context,
+                    ) else {
                         unreachable!("BlockVarList shouldn't exist here")
                     };
 
@@ -0,0 +1,711 @@
+//! What every block looks like: its shape, inputs, fields and default shadows.
+//! Used to check that a [`BlockNormalBuilder`] is something the editor can load.
+//!
+//! ```ignore
+//! let spec = spec_of(PrimaryOpCode::control_repeat).unwrap();
+//! assert_eq!(spec.input_spec("TIMES").unwrap().default, "10");
+//! validate(&block)?;
+//! ```
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use rs_sb3::{block::BlockInputValue, value::OpCode};
+
+use crate::opcode::{extension_of, MusicExtensionOpCode, PenExtensionOpCode, PrimaryOpCode};
+
+use super::{
+    menu::Key,
+    script_builder::{
+        BlockBuilder, BlockFieldBuilder, BlockNormalBuilder, StackBuilder, StackOrValue,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Starts a script
+    Hat,
+    Stack,
+    /// Nothing can be put after it
+    Cap,
+    Reporter,
+    Boolean,
+    /// Only found in an input of another block, like a dropdown menu
+    Shadow,
+}
+
+/// Targets a block can be in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    AnyTarget,
+    /// Not in the Stage's palette, like motion blocks
+    SpriteOnly,
+    /// Not in a sprite's palette, like `when stage clicked`
+    StageOnly,
+}
+
+impl Scope {
+    pub fn allows_stage(self) -> bool {
+        self != Scope::SpriteOnly
+    }
+
+    pub fn allows_sprite(self) -> bool {
+        self != Scope::StageOnly
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Number,
+    PositiveNumber,
+    /// Positive integer
+    WholeNumber,
+    Integer,
+    Angle,
+    Color,
+    Text,
+    Broadcast,
+    /// Hexagonal slot that's empty by default
+    Boolean,
+    /// Mouth of a C block
+    Substack,
+    /// Slot holding the shadow block with this opcode
+    Shadow(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldMenu {
+    /// One of these values
+    Options(&'static [&'static str]),
+    /// [`Key`] option
+    Key,
+    Variable,
+    List,
+    Broadcast,
+    /// Name of a sprite, costume, etc. or anything else
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSpec {
+    pub name: &'static str,
+    pub kind: InputKind,
+    /// Value of the shadow in the palette.
+    /// For [`InputKind::Shadow`] it's the value of the menu's field.
+    pub default: &'static str,
+}
+
+impl InputSpec {
+    /// Shadow value this input has in the palette.
+    /// `None` when there's no value, like a boolean slot or a menu.
+    pub fn default_value(&self) -> Option<BlockInputValue> {
+        let number = || match self.default.parse::<i64>() {
+            Ok(number) => number.into(),
+            Err(_) => match self.default.parse::<f64>() {
+                Ok(number) => number.into(),
+                Err(_) => self.default.to_owned().into(),
+            },
+        };
+        let value = match self.kind {
+            InputKind::Number => BlockInputValue::Number { value: number() },
+            InputKind::PositiveNumber => BlockInputValue::PositiveNumber { value: number() },
+            InputKind::WholeNumber => BlockInputValue::PositiveInteger { value: number() },
+            InputKind::Integer => BlockInputValue::Integer { value: number() },
+            InputKind::Angle => BlockInputValue::Angle { value: number() },
+            InputKind::Color => BlockInputValue::Color {
+                value: self.default.to_owned().into(),
+            },
+            InputKind::Text => BlockInputValue::String {
+                value: self.default.to_owned().into(),
+            },
+            InputKind::Broadcast
+            | InputKind::Boolean
+            | InputKind::Substack
+            | InputKind::Shadow(_) => return None,
+        };
+        Some(value)
+    }
+
+    /// [`InputSpec::default_value`] or the menu block this input has in the palette
+    pub fn default_shadow(&self) -> Option<StackOrValue> {
+        let InputKind::Shadow(menu) = self.kind else {
+            return self.default_value().map(StackOrValue::Value);
+        };
+        let field = spec_of(menu)?.fields.first()?.name;
+        let menu = BlockNormalBuilder::new(menu)
+            .add_field(field, BlockFieldBuilder::new(self.default.to_owned()))
+            .shadow(true);
+        Some(StackOrValue::Stack(StackBuilder::start(menu)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub menu: FieldMenu,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSpec {
+    pub opcode:        OpCode,
+    pub shape:         Shape,
+    pub inputs:        Vec<InputSpec>,
+    pub fields:        Vec<FieldSpec>,
+    /// Extension id the block is from. `None` for the blocks that's always there.
+    pub extension:     Option<&'static str>,
+    /// Inputs are named by the arguments of a procedure instead
+    pub custom_inputs: bool,
+    /// Shown in the editor's palette. Hidden blocks still run when a project has them.
+    pub palette:       bool,
+    pub scope:         Scope,
+}
+
+impl BlockSpec {
+    fn new<O: Into<OpCode>>(opcode: O, shape: Shape) -> BlockSpec {
+        let opcode = opcode.into();
+        BlockSpec {
+            extension: extension_of(&opcode),
+            opcode,
+            shape,
+            inputs: vec![],
+            fields: vec![],
+            custom_inputs: false,
+            palette: true,
+            scope: Scope::AnyTarget,
+        }
+    }
+
+    fn input(mut self, name: &'static str, kind: InputKind, default: &'static str) -> Self {
+        self.inputs.push(InputSpec {
+            name,
+            kind,
+            default,
+        });
+        self
+    }
+
+    fn field(mut self, name: &'static str, menu: FieldMenu) -> Self {
+        self.fields.push(FieldSpec { name, menu });
+        self
+    }
+
+    fn custom_inputs(mut self) -> Self {
+        self.custom_inputs = true;
+        self
+    }
+
+    fn hidden(mut self) -> Self {
+        self.palette = false;
+        self
+    }
+
+    fn sprite_only(mut self) -> Self {
+        self.scope = Scope::SpriteOnly;
+        self
+    }
+
+    fn stage_only(mut self) -> Self {
+        self.scope = Scope::StageOnly;
+        self
+    }
+
+    pub fn input_spec(&self, name: &str) -> Option<&InputSpec> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    pub fn field_spec(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: HashMap<OpCode, BlockSpec> = registry()
+        .into_iter()
+        .map(|spec| (spec.opcode.clone(), spec))
+        .collect();
+}
+
+/// Spec of the block with this opcode.
+/// `None` for opcodes that aren't a block by themselves, like the `extension_*` palette demos.
+pub fn spec_of<O: Into<OpCode>>(opcode: O) -> Option<&'static BlockSpec> {
+    REGISTRY.get(&opcode.into())
+}
+
+/// Every block in the registry in no particular order
+pub fn specs() -> impl Iterator<Item = &'static BlockSpec> {
+    REGISTRY.values()
+}
+
+// Validation ==================================================================
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    UnknownOpcode,
+    UnknownInput(String),
+    MissingInput(String),
+    /// Input holds something its kind can't, like a value in a substack
+    WrongInput {
+        input: String,
+        expected: InputKind,
+    },
+    UnknownField(String),
+    MissingField(String),
+    /// Field value isn't one of the menu's options
+    InvalidOption {
+        field: String,
+        value: String,
+    },
+    /// Menu or other shadow only block that isn't marked as a shadow
+    NotShadow,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::UnknownOpcode => write!(f, "unknown opcode"),
+            Mismatch::UnknownInput(input) => write!(f, "unknown input `{input}`"),
+            Mismatch::MissingInput(input) => write!(f, "missing input `{input}`"),
+            Mismatch::WrongInput { input, expected } => {
+                write!(f, "input `{input}` doesn't fit {expected:?}")
+            }
+            Mismatch::UnknownField(field) => write!(f, "unknown field `{field}`"),
+            Mismatch::MissingField(field) => write!(f, "missing field `{field}`"),
+            Mismatch::InvalidOption { field, value } => {
+                write!(f, "`{value}` isn't an option of field `{field}`")
+            }
+            Mismatch::NotShadow => write!(f, "should be a shadow"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    pub opcode: OpCode,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl std::error::Error for SpecError {}
+
+impl std::fmt::Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` doesn't match its spec", self.opcode)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the block against its spec. Blocks in its inputs aren't checked, see [`validate_stack`].
+pub fn validate(block: &BlockNormalBuilder) -> Result<(), SpecError> {
+    let mut mismatches = vec![];
+    match spec_of(block.opcode()) {
+        Some(spec) => check(block, spec, &mut mismatches),
+        None => mismatches.push(Mismatch::UnknownOpcode),
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SpecError {
+            opcode: block.opcode().to_owned(),
+            mismatches,
+        })
+    }
+}
+
+/// Checks every block in the stack and in their inputs
+pub fn validate_stack(stack: &StackBuilder) -> Result<(), Vec<SpecError>> {
+    let mut errors = vec![];
+    collect_errors(stack, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn collect_errors(stack: &StackBuilder, errors: &mut Vec<SpecError>) {
+    for block in stack.blocks() {
+        let BlockBuilder::Normal(block) = block else {
+            continue;
+        };
+        if let Err(error) = validate(block) {
+            errors.push(error);
+        }
+        let mut inputs: Vec<_> = block.inputs().iter().collect();
+        inputs.sort_by_key(|(name, _)| *name);
+        for (_, input) in inputs {
+            for value in input.values() {
+                if let Some(StackOrValue::Stack(stack)) = value {
+                    collect_errors(stack, errors);
+                }
+            }
+        }
+    }
+}
+
+fn check(block: &BlockNormalBuilder, spec: &BlockSpec, mismatches: &mut Vec<Mismatch>) {
+    if spec.shape == Shape::Shadow && !block.is_shadow() {
+        mismatches.push(Mismatch::NotShadow);
+    }
+
+    if !spec.custom_inputs {
+        let mut names: Vec<&String> = block.inputs().keys().collect();
+        names.sort();
+        for name in names {
+            let Some(input) = spec.input_spec(name) else {
+                mismatches.push(Mismatch::UnknownInput(name.clone()));
+                continue;
+            };
+            let fits = match block.inputs()[name].values().first() {
+                Some(Some(StackOrValue::Value(_))) => !matches!(
+                    input.kind,
+                    InputKind::Boolean | InputKind::Substack | InputKind::Shadow(_)
+                ),
+                Some(Some(StackOrValue::Stack(stack))) => {
+                    match (input.kind, stack.blocks().first()) {
+                        (InputKind::Shadow(opcode), Some(BlockBuilder::Normal(head)))
+                            if head.is_shadow() =>
+                        {
+                            head.opcode() == opcode
+                        }
+                        _ => true,
+                    }
+                }
+                _ => true,
+            };
+            if !fits {
+                mismatches.push(Mismatch::WrongInput {
+                    input: name.clone(),
+                    expected: input.kind,
+                });
+            }
+        }
+        for input in &spec.inputs {
+            let optional = matches!(input.kind, InputKind::Boolean | InputKind::Substack);
+            if !optional && !block.inputs().contains_key(input.name) {
+                mismatches.push(Mismatch::MissingInput(input.name.to_owned()));
+            }
+        }
+    }
+
+    let mut names: Vec<&String> = block.fields().keys().collect();
+    names.sort();
+    for name in names {
+        let Some(field) = spec.field_spec(name) else {
+            mismatches.push(Mismatch::UnknownField(name.clone()));
+            continue;
+        };
+        let value = block.fields()[name].value();
+        let valid = match field.menu {
+            FieldMenu::Options(options) => options.contains(&value),
+            FieldMenu::Key => Key::from_value(value).is_some(),
+            _ => true,
+        };
+        if !valid {
+            mismatches.push(Mismatch::InvalidOption {
+                field: name.clone(),
+                value: value.to_owned(),
+            });
+        }
+    }
+    for field in &spec.fields {
+        if !block.fields().contains_key(field.name) {
+            mismatches.push(Mismatch::MissingField(field.name.to_owned()));
+        }
+    }
+}
+
+// Registry ====================================================================
+const STOP_OPTIONS: &[&str] = &[
+    "all",
+    "this script",
+    "other scripts in sprite",
+    "other scripts in stage",
+];
+const LOOKS_EFFECTS: &[&str] = &[
+    "COLOR",
+    "FISHEYE",
+    "WHIRL",
+    "PIXELATE",
+    "MOSAIC",
+    "BRIGHTNESS",
+    "GHOST",
+];
+const SOUND_EFFECTS: &[&str] = &["PITCH", "PAN"];
+const NUMBER_NAME: &[&str] = &["number", "name"];
+const DRUMS: &[&str] = &[
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17",
+    "18",
+];
+const INSTRUMENTS: &[&str] = &[
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17",
+    "18", "19", "20", "21",
+];
+
+#[rustfmt::skip]
+fn registry() -> Vec<BlockSpec> {
+    use FieldMenu::{Any, Options};
+    use InputKind::*;
+    use MusicExtensionOpCode as M;
+    use PenExtensionOpCode as Pen;
+    use PrimaryOpCode as P;
+    use Shape::{Cap, Hat, Reporter, Shadow as Menu, Stack};
+    fn block<O: Into<OpCode>>(opcode: O, shape: Shape) -> BlockSpec {
+        BlockSpec::new(opcode, shape)
+    }
+    fn boolean<O: Into<OpCode>>(opcode: O) -> BlockSpec {
+        BlockSpec::new(opcode, Shape::Boolean)
+    }
+    vec![
+        // Control
+        block(P::control_forever, Cap).input("SUBSTACK", Substack, ""),
+        block(P::control_repeat, Stack).input("TIMES", WholeNumber, "10").input("SUBSTACK", Substack, ""),
+        block(P::control_if, Stack).input("CONDITION", Boolean, "").input("SUBSTACK", Substack, ""),
+        block(P::control_if_else, Stack)
+            .input("CONDITION", Boolean, "")
+            .input("SUBSTACK", Substack, "")
+            .input("SUBSTACK2", Substack, ""),
+        // Only a cap when it stops all or this script
+        block(P::control_stop, Cap).field("STOP_OPTION", Options(STOP_OPTIONS)),
+        block(P::control_wait, Stack).input("DURATION", PositiveNumber, "1"),
+        block(P::control_wait_until, Stack).input("CONDITION", Boolean, ""),
+        block(P::control_repeat_until, Stack).input("CONDITION", Boolean, "").input("SUBSTACK", Substack, ""),
+        block(P::control_while, Stack).input("CONDITION", Boolean, "").input("SUBSTACK", Substack, "").hidden(),
+        block(P::control_for_each, Stack)
+            .input("VALUE", Text, "10")
+            .input("SUBSTACK", Substack, "")
+            .field("VARIABLE", FieldMenu::Variable)
+            .hidden(),
+        block(P::control_start_as_clone, Hat).sprite_only(),
+        block(P::control_create_clone_of_menu, Menu).field("CLONE_OPTION", Any),
+        block(P::control_create_clone_of, Stack)
+            .input("CLONE_OPTION", Shadow("control_create_clone_of_menu"), "_myself_"),
+        block(P::control_delete_this_clone, Cap).sprite_only(),
+        block(P::control_get_counter, Reporter).hidden(),
+        block(P::control_incr_counter, Stack).hidden(),
+        block(P::control_clear_counter, Stack).hidden(),
+        block(P::control_all_at_once, Stack).input("SUBSTACK", Substack, "").hidden(),
+        // Data
+        block(P::data_variable, Reporter).field("VARIABLE", FieldMenu::Variable),
+        block(P::data_setvariableto, Stack).input("VALUE", Text, "0").field("VARIABLE", FieldMenu::Variable),
+        block(P::data_changevariableby, Stack).input("VALUE", Number, "1").field("VARIABLE", FieldMenu::Variable),
+        block(P::data_showvariable, Stack).field("VARIABLE", FieldMenu::Variable),
+        block(P::data_hidevariable, Stack).field("VARIABLE", FieldMenu::Variable),
+        block(P::data_listcontents, Reporter).field("LIST", FieldMenu::List),
+        block(P::data_listindexall, Menu).field("INDEX", Any).hidden(),
+        block(P::data_listindexrandom, Menu).field("INDEX", Any).hidden(),
+        block(P::data_addtolist, Stack).input("ITEM", Text, "thing").field("LIST", FieldMenu::List),
+        block(P::data_deleteoflist, Stack).input("INDEX", Integer, "1").field("LIST", FieldMenu::List),
+        block(P::data_deletealloflist, Stack).field("LIST", FieldMenu::List),
+        block(P::data_insertatlist, Stack)
+            .input("ITEM", Text, "thing")
+            .input("INDEX", Integer, "1")
+            .field("LIST", FieldMenu::List),
+        block(P::data_replaceitemoflist, Stack)
+            .input("INDEX", Integer, "1")
+            .input("ITEM", Text, "thing")
+            .field("LIST", FieldMenu::List),
+        block(P::data_itemoflist, Reporter).input("INDEX", Integer, "1").field("LIST", FieldMenu::List),
+        block(P::data_itemnumoflist, Reporter).input("ITEM", Text, "thing").field("LIST", FieldMenu::List),
+        block(P::data_lengthoflist, Reporter).field("LIST", FieldMenu::List),
+        boolean(P::data_listcontainsitem).input("ITEM", Text, "thing").field("LIST", FieldMenu::List),
+        block(P::data_showlist, Stack).field("LIST", FieldMenu::List),
+        block(P::data_hidelist, Stack).field("LIST", FieldMenu::List),
+        // Event
+        block(P::event_whentouchingobject, Hat)
+            .input("TOUCHINGOBJECTMENU", Shadow("event_touchingobjectmenu"), "_mouse_")
+            .hidden()
+            .sprite_only(),
+        block(P::event_touchingobjectmenu, Menu).field("TOUCHINGOBJECTMENU", Any),
+        block(P::event_whenflagclicked, Hat),
+        block(P::event_whenthisspriteclicked, Hat).sprite_only(),
+        block(P::event_whenstageclicked, Hat).stage_only(),
+        block(P::event_whenbroadcastreceived, Hat).field("BROADCAST_OPTION", FieldMenu::Broadcast),
+        block(P::event_whenbackdropswitchesto, Hat).field("BACKDROP", Any),
+        block(P::event_whengreaterthan, Hat)
+            .input("VALUE", Number, "10")
+            .field("WHENGREATERTHANMENU", Options(&["LOUDNESS", "TIMER"])),
+        block(P::event_broadcast_menu, Menu).field("BROADCAST_OPTION", FieldMenu::Broadcast),
+        block(P::event_broadcast, Stack).input("BROADCAST_INPUT", Broadcast, "message1"),
+        block(P::event_broadcastandwait, Stack).input("BROADCAST_INPUT", Broadcast, "message1"),
+        block(P::event_whenkeypressed, Hat).field("KEY_OPTION", FieldMenu::Key),
+        // Looks
+        block(P::looks_sayforsecs, Stack).input("MESSAGE", Text, "Hello!").input("SECS", Number, "2").sprite_only(),
+        block(P::looks_say, Stack).input("MESSAGE", Text, "Hello!").sprite_only(),
+        block(P::looks_thinkforsecs, Stack).input("MESSAGE", Text, "Hmm...").input("SECS", Number, "2").sprite_only(),
+        block(P::looks_think, Stack).input("MESSAGE", Text, "Hmm...").sprite_only(),
+        block(P::looks_show, Stack).sprite_only(),
+        block(P::looks_hide, Stack).sprite_only(),
+        block(P::looks_hideallsprites, Stack).hidden(),
+        block(P::looks_changeeffectby, Stack).input("CHANGE", Number, "25").field("EFFECT", Options(LOOKS_EFFECTS)),
+        block(P::looks_seteffectto, Stack).input("VALUE", Number, "0").field("EFFECT", Options(LOOKS_EFFECTS)),
+        block(P::looks_cleargraphiceffects, Stack),
+        block(P::looks_changesizeby, Stack).input("CHANGE", Number, "10").sprite_only(),
+        block(P::looks_setsizeto, Stack).input("SIZE", Number, "100").sprite_only(),
+        block(P::looks_size, Reporter).sprite_only(),
+        block(P::looks_changestretchby, Stack).input("CHANGE", Number, "10").hidden().sprite_only(),
+        block(P::looks_setstretchto, Stack).input("STRETCH", Number, "100").hidden().sprite_only(),
+        block(P::looks_costume, Menu).field("COSTUME", Any).sprite_only(),
+        block(P::looks_switchcostumeto, Stack).input("COSTUME", Shadow("looks_costume"), "costume1").sprite_only(),
+        block(P::looks_nextcostume, Stack).sprite_only(),
+        block(P::looks_switchbackdropto, Stack).input("BACKDROP", Shadow("looks_backdrops"), "backdrop1"),
+        block(P::looks_backdrops, Menu).field("BACKDROP", Any),
+        block(P::looks_gotofrontback, Stack).field("FRONT_BACK", Options(&["front", "back"])).sprite_only(),
+        block(P::looks_goforwardbackwardlayers, Stack)
+            .input("NUM", Integer, "1")
+            .field("FORWARD_BACKWARD", Options(&["forward", "backward"]))
+            .sprite_only(),
+        block(P::looks_backdropnumbername, Reporter).field("NUMBER_NAME", Options(NUMBER_NAME)),
+        block(P::looks_costumenumbername, Reporter).field("NUMBER_NAME", Options(NUMBER_NAME)).sprite_only(),
+        block(P::looks_switchbackdroptoandwait, Stack).input("BACKDROP", Shadow("looks_backdrops"), "backdrop1").stage_only(),
+        block(P::looks_nextbackdrop, Stack),
+        // Motion
+        block(P::motion_movesteps, Stack).input("STEPS", Number, "10").sprite_only(),
+        block(P::motion_turnright, Stack).input("DEGREES", Number, "15").sprite_only(),
+        block(P::motion_turnleft, Stack).input("DEGREES", Number, "15").sprite_only(),
+        block(P::motion_pointindirection, Stack).input("DIRECTION", Angle, "90").sprite_only(),
+        block(P::motion_pointtowards_menu, Menu).field("TOWARDS", Any).sprite_only(),
+        block(P::motion_pointtowards, Stack).input("TOWARDS", Shadow("motion_pointtowards_menu"), "_mouse_").sprite_only(),
+        block(P::motion_goto_menu, Menu).field("TO", Any).sprite_only(),
+        block(P::motion_gotoxy, Stack).input("X", Number, "0").input("Y", Number, "0").sprite_only(),
+        block(P::motion_goto, Stack).input("TO", Shadow("motion_goto_menu"), "_random_").sprite_only(),
+        block(P::motion_glidesecstoxy, Stack)
+            .input("SECS", Number, "1")
+            .input("X", Number, "0")
+            .input("Y", Number, "0")
+            .sprite_only(),
+        block(P::motion_glideto_menu, Menu).field("TO", Any).sprite_only(),
+        block(P::motion_glideto, Stack)
+            .input("SECS", Number, "1")
+            .input("TO", Shadow("motion_glideto_menu"), "_random_")
+            .sprite_only(),
+        block(P::motion_changexby, Stack).input("DX", Number, "10").sprite_only(),
+        block(P::motion_setx, Stack).input("X", Number, "0").sprite_only(),
+        block(P::motion_changeyby, Stack).input("DY", Number, "10").sprite_only(),
+        block(P::motion_sety, Stack).input("Y", Number, "0").sprite_only(),
+        block(P::motion_ifonedgebounce, Stack).sprite_only(),
+        block(P::motion_setrotationstyle, Stack)
+            .field("STYLE", Options(&["left-right", "don't rotate", "all around"]))
+            .sprite_only(),
+        block(P::motion_xposition, Reporter).sprite_only(),
+        block(P::motion_yposition, Reporter).sprite_only(),
+        block(P::motion_direction, Reporter).sprite_only(),
+        block(P::motion_scroll_right, Stack).input("DISTANCE", Number, "10").hidden(),
+        block(P::motion_scroll_up, Stack).input("DISTANCE", Number, "10").hidden(),
+        block(P::motion_align_scene, Stack)
+            .field("ALIGNMENT", Options(&["bottom-left", "bottom-right", "middle", "top-left", "top-right"]))
+            .hidden(),
+        block(P::motion_xscroll, Reporter).hidden(),
+        block(P::motion_yscroll, Reporter).hidden(),
+        // Operator
+        block(P::operator_add, Reporter).input("NUM1", Number, "").input("NUM2", Number, ""),
+        block(P::operator_subtract, Reporter).input("NUM1", Number, "").input("NUM2", Number, ""),
+        block(P::operator_multiply, Reporter).input("NUM1", Number, "").input("NUM2", Number, ""),
+        block(P::operator_divide, Reporter).input("NUM1", Number, "").input("NUM2", Number, ""),
+        block(P::operator_random, Reporter).input("FROM", Number, "1").input("TO", Number, "10"),
+        boolean(P::operator_lt).input("OPERAND1", Text, "").input("OPERAND2", Text, "50"),
+        boolean(P::operator_equals).input("OPERAND1", Text, "").input("OPERAND2", Text, "50"),
+        boolean(P::operator_gt).input("OPERAND1", Text, "").input("OPERAND2", Text, "50"),
+        boolean(P::operator_and).input("OPERAND1", Boolean, "").input("OPERAND2", Boolean, ""),
+        boolean(P::operator_or).input("OPERAND1", Boolean, "").input("OPERAND2", Boolean, ""),
+        boolean(P::operator_not).input("OPERAND", Boolean, ""),
+        block(P::operator_join, Reporter).input("STRING1", Text, "apple ").input("STRING2", Text, "banana"),
+        block(P::operator_letter_of, Reporter).input("LETTER", WholeNumber, "1").input("STRING", Text, "apple"),
+        block(P::operator_length, Reporter).input("STRING", Text, "apple"),
+        boolean(P::operator_contains).input("STRING1", Text, "apple").input("STRING2", Text, "a"),
+        block(P::operator_mod, Reporter).input("NUM1", Number, "").input("NUM2", Number, ""),
+        block(P::operator_round, Reporter).input("NUM", Number, ""),
+        block(P::operator_mathop, Reporter).input("NUM", Number, "").field("OPERATOR", Options(&[
+            "abs", "floor", "ceiling", "sqrt", "sin", "cos", "tan", "asin", "acos", "atan", "ln", "log", "e ^", "10 ^",
+        ])),
+        // Procedures
+        block(P::procedures_definition, Hat).input("custom_block", Shadow("procedures_prototype"), ""),
+        block(P::procedures_call, Stack).custom_inputs(),
+        block(P::procedures_prototype, Menu).custom_inputs(),
+        block(P::procedures_declaration, Menu).custom_inputs().hidden(),
+        boolean(P::argument_reporter_boolean).field("VALUE", Any),
+        block(P::argument_reporter_string_number, Reporter).field("VALUE", Any),
+        block(P::argument_editor_boolean, Menu).field("TEXT", Any).hidden(),
+        block(P::argument_editor_string_number, Menu).field("TEXT", Any).hidden(),
+        // Sensing
+        boolean(P::sensing_touchingobject)
+            .input("TOUCHINGOBJECTMENU", Shadow("sensing_touchingobjectmenu"), "_mouse_")
+            .sprite_only(),
+        block(P::sensing_touchingobjectmenu, Menu).field("TOUCHINGOBJECTMENU", Any).sprite_only(),
+        boolean(P::sensing_touchingcolor).input("COLOR", Color, "#9966ff").sprite_only(),
+        boolean(P::sensing_coloristouchingcolor).input("COLOR", Color, "#9966ff").input("COLOR2", Color, "#ffab19").sprite_only(),
+        block(P::sensing_distanceto, Reporter).input("DISTANCETOMENU", Shadow("sensing_distancetomenu"), "_mouse_").sprite_only(),
+        block(P::sensing_distancetomenu, Menu).field("DISTANCETOMENU", Any).sprite_only(),
+        block(P::sensing_askandwait, Stack).input("QUESTION", Text, "What's your name?"),
+        block(P::sensing_answer, Reporter),
+        boolean(P::sensing_keypressed).input("KEY_OPTION", Shadow("sensing_keyoptions"), "space"),
+        block(P::sensing_keyoptions, Menu).field("KEY_OPTION", FieldMenu::Key),
+        boolean(P::sensing_mousedown),
+        block(P::sensing_mousex, Reporter),
+        block(P::sensing_mousey, Reporter),
+        block(P::sensing_setdragmode, Stack).field("DRAG_MODE", Options(&["draggable", "not draggable"])).sprite_only(),
+        block(P::sensing_loudness, Reporter),
+        boolean(P::sensing_loud).hidden(),
+        block(P::sensing_timer, Reporter),
+        block(P::sensing_resettimer, Stack),
+        block(P::sensing_of_object_menu, Menu).field("OBJECT", Any),
+        block(P::sensing_of, Reporter)
+            .input("OBJECT", Shadow("sensing_of_object_menu"), "_stage_")
+            .field("PROPERTY", Any),
+        block(P::sensing_current, Reporter).field("CURRENTMENU", Options(&[
+            "YEAR", "MONTH", "DATE", "DAYOFWEEK", "HOUR", "MINUTE", "SECOND",
+        ])),
+        block(P::sensing_dayssince2000, Reporter),
+        block(P::sensing_username, Reporter),
+        block(P::sensing_userid, Reporter).hidden(),
+        // Sound
+        block(P::sound_sounds_menu, Menu).field("SOUND_MENU", Any),
+        block(P::sound_play, Stack).input("SOUND_MENU", Shadow("sound_sounds_menu"), "pop"),
+        block(P::sound_playuntildone, Stack).input("SOUND_MENU", Shadow("sound_sounds_menu"), "pop"),
+        block(P::sound_stopallsounds, Stack),
+        block(P::sound_seteffectto, Stack).input("VALUE", Number, "100").field("EFFECT", Options(SOUND_EFFECTS)),
+        block(P::sound_changeeffectby, Stack).input("VALUE", Number, "10").field("EFFECT", Options(SOUND_EFFECTS)),
+        block(P::sound_cleareffects, Stack),
+        block(P::sound_changevolumeby, Stack).input("VOLUME", Number, "-10"),
+        block(P::sound_setvolumeto, Stack).input("VOLUME", Number, "100"),
+        block(P::sound_volume, Reporter),
+        // Pen
+        block(Pen::pen_clear, Stack),
+        block(Pen::pen_stamp, Stack).sprite_only(),
+        block(Pen::pen_penDown, Stack).sprite_only(),
+        block(Pen::pen_penUp, Stack).sprite_only(),
+        block(Pen::pen_setPenColorToColor, Stack).input("COLOR", Color, "#4c97ff").sprite_only(),
+        block(Pen::pen_changePenColorParamBy, Stack)
+            .input("COLOR_PARAM", Shadow("pen_menu_colorParam"), "color")
+            .input("VALUE", Number, "10")
+            .sprite_only(),
+        block(Pen::pen_setPenColorParamTo, Stack)
+            .input("COLOR_PARAM", Shadow("pen_menu_colorParam"), "color")
+            .input("VALUE", Number, "50")
+            .sprite_only(),
+        block(Pen::pen_changePenSizeBy, Stack).input("SIZE", Number, "1").sprite_only(),
+        block(Pen::pen_setPenSizeTo, Stack).input("SIZE", Number, "1").sprite_only(),
+        block(Pen::pen_setPenShadeToNumber, Stack).input("SHADE", Number, "50").hidden().sprite_only(),
+        block(Pen::pen_changePenShadeBy, Stack).input("SHADE", Number, "10").hidden().sprite_only(),
+        block(Pen::pen_setPenHueToNumber, Stack).input("HUE", Number, "0").hidden().sprite_only(),
+        block(Pen::pen_changePenHueBy, Stack).input("HUE", Number, "10").hidden().sprite_only(),
+        block(Pen::pen_menu_colorParam, Menu)
+            .field("colorParam", Options(&["color", "saturation", "brightness", "transparency"])),
+        // Music
+        block(M::music_playDrumForBeats, Stack)
+            .input("DRUM", Shadow("music_menu_DRUM"), "1")
+            .input("BEATS", Number, "0.25"),
+        block(M::music_restForBeats, Stack).input("BEATS", Number, "0.25"),
+        block(M::music_playNoteForBeats, Stack)
+            .input("NOTE", Shadow("note"), "60")
+            .input("BEATS", Number, "0.25"),
+        block(M::music_setInstrument, Stack).input("INSTRUMENT", Shadow("music_menu_INSTRUMENT"), "1"),
+        block(M::music_setTempo, Stack).input("TEMPO", Number, "60"),
+        block(M::music_changeTempo, Stack).input("TEMPO", Number, "20"),
+        block(M::music_getTempo, Reporter),
+        block(M::music_menu_DRUM, Menu).field("DRUM", Options(DRUMS)),
+        block(M::music_menu_INSTRUMENT, Menu).field("INSTRUMENT", Options(INSTRUMENTS)),
+        block(M::note, Menu).field("NOTE", Any),
+    ]
+}
@@ -1,8 +1,21 @@
+use std::marker::PhantomData;
+
 use rs_sb3::block::BlockInputValue;
 
-use crate::scripting::script_builder::{BlockFieldBuilder, BlockInputBuilder, FieldKind};
+use crate::scripting::{
+    blocks,
+    menu::{
+        BackdropOption, CloneTarget, CurrentTime, DistanceToTarget, DragMode, Drum,
+        ForwardBackward, FrontBack, GoToTarget, Instrument, Key, LooksEffect, MathOp, NumberName,
+        ObjectTarget, PenColorParam, PointTowardsTarget, RotationStyle, SoundEffect, StopOption,
+        TouchingTarget, WhenGreaterThan,
+    },
+    script_builder::{BlockFieldBuilder, BlockInputBuilder, FieldKind},
+};
 
-use super::script_builder::{Reporter, StackableSide, TypedStackBuilder};
+use super::script_builder::{
+    AnyTarget, Reporter, Scoped, SpriteOnly, StackableSide, TypedStackBuilder, UnstackableSide,
+};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Number;
@@ -49,12 +62,42 @@ pub trait IntoInput<T> {
     fn into_input(self) -> BlockInputBuilder;
 }
 
-impl<T, S, E> IntoInput<T> for Reporter<T, S, E> {
+impl<T, S, E, C> IntoInput<T> for Reporter<T, S, E, C> {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::stack(self.0.into_untyped())
     }
 }
 
+macro_rules! reporter_into_impl {
+    ($($arg:ty => $from_arg:ty),*) => {
+        $(
+            impl<S, E, C> IntoInput<$arg> for Reporter<$from_arg, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+        )*
+    }
+}
+
+// Every reporters could report a value, and a value could be used as a number
+reporter_into_impl! {
+    Value => Number,
+    Value => PositiveNumber,
+    Value => PositiveInteger,
+    Value => Integer,
+    Value => Float,
+    Value => Angle,
+    Value => Text,
+    Value => Bool,
+    Number => Value,
+    Number => PositiveNumber,
+    Number => PositiveInteger,
+    Number => Integer,
+    Number => Float,
+    Number => Angle
+}
+
 macro_rules! into_arg_basic_impl {
     ($($arg:ty => $enum:ident => $from_ty:ty),*) => {
         $(
@@ -70,10 +113,19 @@ macro_rules! into_arg_basic_impl {
 into_arg_basic_impl! {
     Number => Number => i64,
     Number => Number => f64,
+    PositiveNumber => PositiveNumber => i64,
+    PositiveNumber => PositiveNumber => f64,
+    PositiveInteger => PositiveInteger => i64,
+    PositiveInteger => PositiveInteger => f64,
+    Integer => Integer => i64,
+    Integer => Integer => f64,
+    Angle => Angle => i64,
+    Angle => Angle => f64,
     Text => String => String,
     Value => String => String,
     Value => Number => i64,
-    Value => Number => f64
+    Value => Number => f64,
+    Color => Color => String
 }
 
 impl IntoInput<Bool> for bool {
@@ -92,6 +144,15 @@ impl IntoInput<Text> for &str {
     }
 }
 
+/// Color in hex. Ex. "#ff0000"
+impl IntoInput<Color> for &str {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::value(BlockInputValue::Color {
+            value: self.to_owned().into(),
+        })
+    }
+}
+
 impl IntoInput<Value> for &str {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::value(BlockInputValue::String {
@@ -100,12 +161,61 @@ impl IntoInput<Value> for &str {
     }
 }
 
-impl<E> IntoInput<Stack> for TypedStackBuilder<StackableSide, E> {
+impl IntoInput<Broadcast> for BroadcastHandle {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::broadcast(self.name)
+    }
+}
+
+impl IntoInput<Broadcast> for &BroadcastHandle {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::broadcast(self.name.clone())
+    }
+}
+
+/// Escape hatch for when the typed API can't express the input.
+impl<T> IntoInput<T> for BlockInputBuilder {
+    fn into_input(self) -> BlockInputBuilder {
+        self
+    }
+}
+
+impl<E, C> IntoInput<Stack> for TypedStackBuilder<StackableSide, E, C> {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::stack(self.into_untyped())
     }
 }
 
+macro_rules! any_target_scoped_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl Scoped for $ty {
+                type Scope = AnyTarget;
+            }
+        )*
+    }
+}
+
+// Values and menu options plugged into an input can be in any target
+any_target_scoped_impl! {
+    i64,
+    f64,
+    bool,
+    String,
+    &str,
+    PenColorParam,
+    Key,
+    Drum,
+    Instrument,
+    BackdropOption,
+    CloneTarget,
+    GoToTarget,
+    PointTowardsTarget,
+    TouchingTarget,
+    DistanceToTarget,
+    ObjectTarget
+}
+
 // Field ====================================================================
 pub trait IntoField<T = NoRefMaybe> {
     fn into_field(self) -> BlockFieldBuilder;
@@ -117,39 +227,364 @@ impl<S: Into<String>> IntoField for S {
     }
 }
 
-impl<S: Into<String>> IntoField<Broadcast> for S {
+/// Escape hatch for when the typed API can't express the field. Ex. a menu option that isn't in the enum
+impl<T> IntoField<T> for BlockFieldBuilder {
     fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.into(), FieldKind::Broadcast)
+        self
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct GlobalVar<S: Into<String>>(pub S);
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct SpriteVar<S: Into<String>>(pub S);
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct GlobalList<S: Into<String>>(pub S);
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct SpriteList<S: Into<String>>(pub S);
+/// The escape hatch isn't checked until building
+impl Scoped for BlockFieldBuilder {
+    type Scope = AnyTarget;
+}
 
-impl<S: Into<String>> IntoField<Variable> for GlobalVar<S> {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::GlobalVariable)
+// Menus ====================================================================
+macro_rules! menu_into_field_impl {
+    ($($menu:ty),*) => {
+        $(
+            impl IntoField<$menu> for $menu {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.into())
+                }
+            }
+        )*
     }
 }
-impl<S: Into<String>> IntoField<Variable> for SpriteVar<S> {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::SpriteVariable)
+
+menu_into_field_impl! {
+    LooksEffect,
+    SoundEffect,
+    MathOp,
+    RotationStyle,
+    FrontBack,
+    ForwardBackward,
+    StopOption,
+    WhenGreaterThan,
+    DragMode,
+    CurrentTime,
+    NumberName,
+    PenColorParam,
+    Key,
+    Drum,
+    Instrument
+}
+
+/// Input that only takes an option of the menu or a reporter, a plain text isn't one of the options
+macro_rules! menu_option_input_impl {
+    ($($menu:ty => $menu_fn:ident),*) => {
+        $(
+            /// Plugs the menu reporter of this option
+            impl IntoInput<$menu> for $menu {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(blocks::$menu_fn(IntoField::<$menu>::into_field(self)))
+                }
+            }
+
+            impl<S, E, C> IntoInput<$menu> for Reporter<Text, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+
+            impl<S, E, C> IntoInput<$menu> for Reporter<Value, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+
+            impl<S, E, C> IntoInput<$menu> for Reporter<Number, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+        )*
     }
 }
 
-impl<S: Into<String>> IntoField<List> for GlobalList<S> {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::GlobalList)
+menu_option_input_impl! {
+    PenColorParam => pen_color_param_menu,
+    Key => key_menu,
+    Drum => drum_menu,
+    Instrument => instrument_menu
+}
+
+// Stop options ==============================================================
+/// Option of [`super::blocks::stop`] as a type, so the option decides the shape of the block
+pub trait StopShape {
+    /// [`StackableSide`] when blocks can go after the stop block
+    type End;
+    const OPTION: StopOption;
+}
+
+macro_rules! stop_shape_def {
+    ($(
+        $(#[$attributes:meta])*
+        $option:ident: $variant:ident => $end:ty
+    ),*) => {
+        $(
+            $(#[$attributes])*
+            #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+            pub struct $option;
+
+            impl StopShape for $option {
+                type End = $end;
+                const OPTION: StopOption = StopOption::$variant;
+            }
+        )*
     }
 }
-impl<S: Into<String>> IntoField<List> for SpriteList<S> {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::SpriteList)
+
+stop_shape_def! {
+    /// Stops every script, nothing can go after it
+    StopAll: All => UnstackableSide,
+    /// Stops the script it's in, nothing can go after it
+    StopThisScript: ThisScript => UnstackableSide,
+    /// Stops the other scripts of the sprite, the script it's in keeps going
+    StopOtherScriptsInSprite: OtherScriptsInSprite => StackableSide
+}
+
+// Handles ==================================================================
+macro_rules! handle_def {
+    ($(
+        $(#[$attributes:meta])*
+        $handle:ident: $arg:ty => $kind:ident in $scope:ty
+    ),*) => {
+        $(
+            $(#[$attributes])*
+            #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+            pub struct $handle {
+                name: String,
+            }
+
+            impl $handle {
+                /// Escape hatch for a name that's declared somewhere this crate can't see.
+                /// Ex. in a project that's imported. The name is only checked when building.
+                pub fn assume_declared<S: Into<String>>(name: S) -> $handle {
+                    $handle { name: name.into() }
+                }
+
+                pub fn name(&self) -> &str {
+                    &self.name
+                }
+            }
+
+            impl IntoField<$arg> for $handle {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new_with_kind(self.name, FieldKind::$kind)
+                }
+            }
+
+            impl IntoField<$arg> for &$handle {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new_with_kind(self.name.clone(), FieldKind::$kind)
+                }
+            }
+
+            impl Scoped for $handle {
+                type Scope = $scope;
+            }
+
+            impl Scoped for &$handle {
+                type Scope = $scope;
+            }
+        )*
+    }
+}
+
+handle_def! {
+    /// Variable of the Stage, from [`crate::project::target::StageBuilder::declare_global_var`]
+    GlobalVar: Variable => GlobalVariable in AnyTarget,
+    /// List of the Stage, from [`crate::project::target::StageBuilder::declare_global_list`]
+    GlobalList: List => GlobalList in AnyTarget,
+    /// From [`crate::project::target::TargetBuilder::declare_broadcast`]
+    BroadcastHandle: Broadcast => Broadcast in AnyTarget,
+    /// Costume of a sprite, from [`crate::project::target::SpriteBuilder::declare_costume`]
+    CostumeHandle: NoRefMaybe => Costume in SpriteOnly,
+    /// Costume of the Stage, from [`crate::project::target::StageBuilder::declare_backdrop`]
+    BackdropHandle: NoRefMaybe => Backdrop in AnyTarget,
+    /// From [`crate::project::target::TargetBuilder::declare_sound`]
+    SoundHandle: NoRefMaybe => Sound in AnyTarget,
+    /// From [`crate::project::ProjectBuilder::declare_sprite`]
+    SpriteHandle: NoRefMaybe => Sprite in AnyTarget
+}
+
+/// Like [`handle_def`] but the handle has the scope of the sprite that declares it,
+/// so it can't be used in another sprite.
+macro_rules! owned_handle_def {
+    ($(
+        $(#[$attributes:meta])*
+        $handle:ident: $arg:ty => $kind:ident
+    ),*) => {
+        $(
+            $(#[$attributes])*
+            pub struct $handle<C = SpriteOnly> {
+                name: String,
+                scope: PhantomData<fn() -> C>,
+            }
+
+            impl $handle {
+                /// Escape hatch for a name that's declared somewhere this crate can't see.
+                /// Ex. in a project that's imported. The name is only checked when building.
+                pub fn assume_declared<S: Into<String>>(name: S) -> $handle {
+                    $handle::owned(name)
+                }
+            }
+
+            impl<C> $handle<C> {
+                pub(crate) fn owned<S: Into<String>>(name: S) -> $handle<C> {
+                    $handle {
+                        name: name.into(),
+                        scope: PhantomData,
+                    }
+                }
+
+                pub fn name(&self) -> &str {
+                    &self.name
+                }
+            }
+
+            impl<C> std::fmt::Debug for $handle<C> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_struct(stringify!($handle))
+                        .field("name", &self.name)
+                        .finish()
+                }
+            }
+
+            impl<C> Clone for $handle<C> {
+                fn clone(&self) -> Self {
+                    $handle::owned(self.name.clone())
+                }
+            }
+
+            impl<C> PartialEq for $handle<C> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.name == other.name
+                }
+            }
+
+            impl<C> Eq for $handle<C> {}
+
+            impl<C> std::hash::Hash for $handle<C> {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    self.name.hash(state)
+                }
+            }
+
+            impl<C> IntoField<$arg> for $handle<C> {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new_with_kind(self.name, FieldKind::$kind)
+                }
+            }
+
+            impl<C> IntoField<$arg> for &$handle<C> {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new_with_kind(self.name.clone(), FieldKind::$kind)
+                }
+            }
+
+            impl<C> Scoped for $handle<C> {
+                type Scope = C;
+            }
+
+            impl<C> Scoped for &$handle<C> {
+                type Scope = C;
+            }
+        )*
     }
 }
+
+owned_handle_def! {
+    /// Variable of a sprite, from [`crate::project::target::SpriteBuilder::declare_sprite_var`]
+    SpriteVar: Variable => SpriteVariable,
+    /// List of a sprite, from [`crate::project::target::SpriteBuilder::declare_sprite_list`]
+    SpriteList: List => SpriteList
+}
+
+// Menu inputs ==============================================================
+/// `$option` is the menu's options that aren't a name, ex. "_mouse_"
+macro_rules! menu_input_def {
+    ($(
+        $(#[$attributes:meta])*
+        $input:ident: $handle:ident $(| $option:ty)? => $menu_fn:ident
+    ),*) => {
+        $(
+            $(#[$attributes])*
+            #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+            pub struct $input;
+
+            /// Plugs the menu with this name, it's checked when building like a handle from `assume_declared`
+            impl IntoInput<$input> for &str {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(blocks::$menu_fn($handle::assume_declared(self).into_field()))
+                }
+            }
+
+            /// Plugs the menu with this handle, the name is checked when building
+            impl IntoInput<$input> for $handle {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(blocks::$menu_fn(self.into_field()))
+                }
+            }
+
+            impl IntoInput<$input> for &$handle {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(blocks::$menu_fn(self.into_field()))
+                }
+            }
+
+            $(
+                /// Plugs the menu with this option
+                impl IntoInput<$input> for $option {
+                    fn into_input(self) -> BlockInputBuilder {
+                        BlockInputBuilder::stack(blocks::$menu_fn(BlockFieldBuilder::new(self.into())))
+                    }
+                }
+            )?
+
+            impl<S, E, C> IntoInput<$input> for Reporter<Text, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+
+            impl<S, E, C> IntoInput<$input> for Reporter<Value, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+
+            impl<S, E, C> IntoInput<$input> for Reporter<Number, S, E, C> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+            }
+        )*
+    }
+}
+
+menu_input_def! {
+    /// Input of [`super::blocks::switch_costume_to`]
+    Costume: CostumeHandle => costume_menu,
+    /// Input of [`super::blocks::switch_backdrop_to`]
+    Backdrop: BackdropHandle | BackdropOption => backdrop_menu,
+    /// Input of [`super::blocks::play_sound`]
+    Sound: SoundHandle => sound_menu,
+    /// Input of [`super::blocks::create_clone_of`]
+    CloneOption: SpriteHandle | CloneTarget => create_clone_of_menu,
+    /// Input of [`super::blocks::go_to`]
+    GoTo: SpriteHandle | GoToTarget => go_to_menu,
+    /// Input of [`super::blocks::glide_to`]
+    GlideTo: SpriteHandle | GoToTarget => glide_to_menu,
+    /// Input of [`super::blocks::point_towards`]
+    PointTowards: SpriteHandle | PointTowardsTarget => point_towards_menu,
+    /// Input of [`super::blocks::touching`]
+    TouchingObject: SpriteHandle | TouchingTarget => touching_menu,
+    /// Input of `when_touching`
+    WhenTouchingObject: SpriteHandle | TouchingTarget => when_touching_menu,
+    /// Input of [`super::blocks::distance_to`]
+    DistanceTo: SpriteHandle | DistanceToTarget => distance_to_menu,
+    /// Input of [`super::blocks::var_of`]
+    Object: SpriteHandle | ObjectTarget => var_of_object_menu
+}
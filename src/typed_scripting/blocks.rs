@@ -8,7 +8,15 @@
 //!
 
 use super::{arg::*, script_builder::*};
-use crate::scripting::blocks;
+use crate::scripting::{
+    blocks,
+    menu::{
+        CurrentTime, DragMode, Drum, ForwardBackward, FrontBack, Instrument, Key, LooksEffect,
+        MathOp, NumberName, PenColorParam, RotationStyle, SoundEffect, StopOption, WhenGreaterThan,
+    },
+    procedure::ProcedureHandle,
+    script_builder::BlockInputBuilder,
+};
 
 // Control
 // Event
@@ -18,7 +26,13 @@ use crate::scripting::blocks;
 // Sensing
 // Sound
 // Data
+// Procedures
+// Pen
+// Music
 
+/// Each input takes a type parameter so the block can join the input's scope into its own.
+/// Ex. `set_x(x_position())` is only in sprites.
+/// A field marked `Scoped` joins its scope too. Ex. a sprite variable can't be set on the Stage.
 macro_rules! simple_typed_block_def {
     ( $(
         $(#[$attributes:meta])*
@@ -27,30 +41,119 @@ macro_rules! simple_typed_block_def {
         ),* ) -> $return_ty:ty
     )* ) => {
         $(
-            $(#[$attributes])*
-            pub fn $fn_name(
-                $($arg_name: impl $($arg_trait)+),*
-            ) -> $return_ty
-            {
-                TypedStackBuilder::assume_typed(
-                    blocks::$fn_name(
-                        $(
-                            simple_typed_block_def!(@arg_thing ($($arg_trait)+) $arg_name)
-                        ),*
-                    )
-                ).into()
+            simple_typed_block_def! {
+                @fn [$(#[$attributes])*] $fn_name;
+                base [$return_ty];
+                generics [];
+                params [];
+                block [$return_ty];
+                bounds [];
+                args [];
+                joined [];
+                names [A B C D];
+                $($arg_name ($($arg_trait)+))*
             }
         )*
     };
 
-    (@arg_thing (IntoInput $($o:tt)*) $arg:expr) => {
-        $arg.into_input()
+    (
+        @fn [$($attributes:tt)*] $fn_name:ident;
+        base [$base:ty];
+        generics [$($generics:tt)*];
+        params [$($params:tt)*];
+        block [$block:ty];
+        bounds [$($bounds:tt)*];
+        args [$($args:tt)*];
+        joined [$($joined:ident)*];
+        names [$($names:ident)*];
+    ) => {
+        $($attributes)*
+        pub fn $fn_name<$($generics)*>($($params)*) -> $block
+        where
+            $($bounds)*
+        {
+            let block: $base = TypedStackBuilder::assume_typed(blocks::$fn_name($($args)*)).into();
+            $(let block = <_ as JoinInput<$joined>>::join_input(block);)*
+            block
+        }
     };
-    (@arg_thing (IntoField $($o:tt)*)$arg:expr) => {
-        $arg.into_field()
+
+    (
+        @fn $attributes:tt $fn_name:ident;
+        base $base:tt;
+        generics [$($generics:tt)*];
+        params [$($params:tt)*];
+        block [$block:ty];
+        bounds [$($bounds:tt)*];
+        args [$($args:tt)*];
+        joined [$($joined:ident)*];
+        names [$name:ident $($names:ident)*];
+        $arg_name:ident (IntoInput $($arg_trait:tt)*) $($rest:tt)*
+    ) => {
+        simple_typed_block_def! {
+            @fn $attributes $fn_name;
+            base $base;
+            generics [$($generics)* $name: IntoInput $($arg_trait)* + Scoped,];
+            params [$($params)* $arg_name: $name,];
+            block [Joined<$block, $name>];
+            bounds [$($bounds)* $block: JoinInput<$name>,];
+            args [$($args)* $arg_name.into_input(),];
+            joined [$($joined)* $name];
+            names [$($names)*];
+            $($rest)*
+        }
+    };
+
+    (
+        @fn $attributes:tt $fn_name:ident;
+        base $base:tt;
+        generics [$($generics:tt)*];
+        params [$($params:tt)*];
+        block [$block:ty];
+        bounds [$($bounds:tt)*];
+        args [$($args:tt)*];
+        joined [$($joined:ident)*];
+        names [$name:ident $($names:ident)*];
+        $arg_name:ident (Scoped IntoField $($arg_trait:tt)*) $($rest:tt)*
+    ) => {
+        simple_typed_block_def! {
+            @fn $attributes $fn_name;
+            base $base;
+            generics [$($generics)* $name: IntoField $($arg_trait)* + Scoped,];
+            params [$($params)* $arg_name: $name,];
+            block [Joined<$block, $name>];
+            bounds [$($bounds)* $block: JoinInput<$name>,];
+            args [$($args)* $arg_name.into_field(),];
+            joined [$($joined)* $name];
+            names [$($names)*];
+            $($rest)*
+        }
     };
-    (@arg_thing (Into $($o:tt)*)$arg:expr) => {
-        $arg
+
+    (
+        @fn $attributes:tt $fn_name:ident;
+        base $base:tt;
+        generics $generics:tt;
+        params [$($params:tt)*];
+        block $block:tt;
+        bounds $bounds:tt;
+        args [$($args:tt)*];
+        joined $joined:tt;
+        names $names:tt;
+        $arg_name:ident (IntoField $($arg_trait:tt)*) $($rest:tt)*
+    ) => {
+        simple_typed_block_def! {
+            @fn $attributes $fn_name;
+            base $base;
+            generics $generics;
+            params [$($params)* $arg_name: impl IntoField $($arg_trait)*,];
+            block $block;
+            bounds $bounds;
+            args [$($args)* $arg_name.into_field(),];
+            joined $joined;
+            names $names;
+            $($rest)*
+        }
     };
 }
 
@@ -59,32 +162,47 @@ simple_typed_block_def! {
     wait(duration: (IntoInput<PositiveNumber>)) -> StackBlock
 }
 
-pub fn repeat(
-    times: impl IntoInput<PositiveInteger>,
-    to_repeat: Option<impl IntoInput<Stack>>,
-) -> StackBlock {
+pub fn repeat<N, B>(times: N, to_repeat: Option<B>) -> StackBlock<Join<B::Scope, N::Scope>>
+where
+    N: IntoInput<PositiveInteger> + Scoped,
+    B: IntoInput<Stack> + Scoped,
+    B::Scope: JoinScope<N::Scope>,
+{
     TypedStackBuilder::assume_typed(blocks::repeat(
         times.into_input(),
         to_repeat.map(IntoInput::into_input),
     ))
 }
 
-pub fn forever(to_repeat: Option<impl IntoInput<Stack>>) -> StackBlock {
+pub fn forever<B: IntoInput<Stack> + Scoped>(to_repeat: Option<B>) -> StackBlock<B::Scope> {
     TypedStackBuilder::assume_typed(blocks::forever(to_repeat.map(IntoInput::into_input)))
 }
 
-pub fn if_(condition: impl IntoInput<Bool>, if_true: Option<impl IntoInput<Stack>>) -> StackBlock {
+pub fn if_<Q, B>(condition: Q, if_true: Option<B>) -> StackBlock<Join<B::Scope, Q::Scope>>
+where
+    Q: IntoInput<Bool> + Scoped,
+    B: IntoInput<Stack> + Scoped,
+    B::Scope: JoinScope<Q::Scope>,
+{
     TypedStackBuilder::assume_typed(blocks::if_(
         condition.into_input(),
         if_true.map(IntoInput::into_input),
     ))
 }
 
-pub fn if_else(
-    condition: impl IntoInput<Bool>,
-    if_true: Option<impl IntoInput<Stack>>,
-    if_false: Option<impl IntoInput<Stack>>,
-) -> StackBlock {
+/// `C` is the scope of both branches put together
+pub fn if_else<Q, T, F, C>(
+    condition: Q,
+    if_true: Option<T>,
+    if_false: Option<F>,
+) -> StackBlock<Join<C, Q::Scope>>
+where
+    Q: IntoInput<Bool> + Scoped,
+    T: IntoInput<Stack> + Scoped,
+    F: IntoInput<Stack> + Scoped,
+    T::Scope: JoinScope<F::Scope, Output = C>,
+    C: JoinScope<Q::Scope>,
+{
     TypedStackBuilder::assume_typed(blocks::if_else(
         condition.into_input(),
         if_true.map(IntoInput::into_input),
@@ -96,181 +214,170 @@ simple_typed_block_def! {
     wait_until(condition: (IntoInput<Bool>)) -> StackBlock
 }
 
-pub fn repeat_until(
-    condition: impl IntoInput<Bool>,
-    to_repeat: Option<impl IntoInput<Stack>>,
-) -> StackBlock {
+pub fn repeat_until<Q, B>(
+    condition: Q,
+    to_repeat: Option<B>,
+) -> StackBlock<Join<B::Scope, Q::Scope>>
+where
+    Q: IntoInput<Bool> + Scoped,
+    B: IntoInput<Stack> + Scoped,
+    B::Scope: JoinScope<Q::Scope>,
+{
     TypedStackBuilder::assume_typed(blocks::repeat_until(
         condition.into_input(),
         to_repeat.map(IntoInput::into_input),
     ))
 }
 
-/// `stop_option` Accepts:
-///  - "this script" and `has_next` should be `false`
-///  - "other scripts in sprite" and `has_next` should be `true`
-///  - "all" and `has_next` should be `false`
-pub fn stop(stop_option: impl IntoField, has_next: bool) -> CapBlock {
-    TypedStackBuilder::assume_typed(blocks::stop(stop_option.into_field(), has_next))
+/// Only [`StopOtherScriptsInSprite`] can have blocks after it:
+/// ```compile_fail
+/// # use mcscratchy::typed_scripting::{arg::StopThisScript, blocks::*};
+/// stop(StopThisScript).next(say("unreachable"));
+/// ```
+pub fn stop<O: StopShape>(_stop_option: O) -> TypedStackBuilder<StackableSide, O::End> {
+    let has_next = O::OPTION == StopOption::OtherScriptsInSprite;
+    TypedStackBuilder::assume_typed(blocks::stop(
+        IntoField::<StopOption>::into_field(O::OPTION),
+        has_next,
+    ))
 }
 
 simple_typed_block_def! {
-    when_i_start_as_a_clone() -> HatBlock
+    when_i_start_as_a_clone() -> HatBlock<SpriteOnly>
 
     /// Accepts:
-    ///  - Sprite name
-    create_clone_of(sprite: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::CloneTarget`]
+    ///  - Reporter
+    create_clone_of(sprite: (IntoInput<CloneOption>)) -> StackBlock
 
     /// Uses as argument to [`create_clone_of`]
     /// Accepts:
     ///  - Sprite name
     create_clone_of_menu(sprite: (IntoField)) -> MenuReporter
 
-    delete_this_clone() -> CapBlock
+    delete_this_clone() -> CapBlock<SpriteOnly>
 }
 
 // Event =======================================================================
 simple_typed_block_def! {
     when_flag_clicked() -> HatBlock
-    /// Accepts:
-    ///  - "any"
-    ///  - "space"
-    ///  - "left arrow"
-    ///  - "right arrow"
-    ///  - "up arrow"
-    ///  - "down arrow"
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    when_key_pressed(key: (IntoField)) -> HatBlock
-    when_this_sprite_clicked() -> HatBlock
+    when_key_pressed(key: (IntoField<Key>)) -> HatBlock
+    when_this_sprite_clicked() -> HatBlock<SpriteOnly>
+    /// The Stage's version of [`when_this_sprite_clicked`]
+    when_stage_clicked() -> HatBlock<StageOnly>
     /// Accepts:
     ///  - Backdrop name
     when_backdrop_switches_to(backdrop: (IntoField)) -> HatBlock
-    /// Accepts:
-    /// - "LOUDNESS"
-    /// - "TIMER"
-    when_greater_than(variable: (IntoField), value: (IntoInput<Number>)) -> HatBlock
+    when_greater_than(variable: (IntoField<WhenGreaterThan>), value: (IntoInput<Number>)) -> HatBlock
     when_broadcast_received(broadcast: (IntoField<Broadcast>)) -> HatBlock
     broadcast(broadcast: (IntoInput<Broadcast>)) -> StackBlock
     broadcast_and_wait(broadcast: (IntoInput<Broadcast>)) -> StackBlock
+    /// Uses as argument to [`broadcast`] and [`broadcast_and_wait`]
+    broadcast_menu(broadcast: (IntoField<Broadcast>)) -> JustReporter<Broadcast>
 }
 
 // Looks =======================================================================
 simple_typed_block_def! {
-    say(message: (IntoInput<Text>)) -> StackBlock
-    say_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock
-    think(message: (IntoInput<Text>)) -> StackBlock
-    think_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock
-    /// Accepts:
-    ///  - Costume name
-    switch_costume_to(costume: (IntoInput<Text>)) -> StackBlock
+    say(message: (IntoInput<Text>)) -> StackBlock<SpriteOnly>
+    say_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    think(message: (IntoInput<Text>)) -> StackBlock<SpriteOnly>
+    think_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`CostumeHandle`]
+    ///  - Costume name, checked when building
+    ///  - Reporter
+    switch_costume_to(costume: (IntoInput<Costume>)) -> StackBlock<SpriteOnly>
     /// Uses as argument to [`switch_costume_to`]
     /// Accepts:
     ///  - Costume name
-    costume_menu(costume: (IntoField)) -> MenuReporter
-    next_costume() -> StackBlock
-    /// Accepts:
-    ///  - Costume name
-    switch_backdrop_to(backdrop: (IntoInput<Text>)) -> StackBlock
+    costume_menu(costume: (IntoField)) -> MenuReporter<SpriteOnly>
+    next_costume() -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`BackdropHandle`]
+    ///  - Backdrop name, checked when building
+    ///  - [`crate::scripting::menu::BackdropOption`]
+    ///  - Reporter
+    switch_backdrop_to(backdrop: (IntoInput<Backdrop>)) -> StackBlock
     /// Uses as argument to [`switch_backdrop_to`]
     /// Accepts:
     ///  - Backdrop name
     backdrop_menu(backdrop: (IntoField)) -> MenuReporter
+    /// Only on the Stage.
+    /// Accepts:
+    ///  - [`BackdropHandle`]
+    ///  - Backdrop name, checked when building
+    ///  - [`crate::scripting::menu::BackdropOption`]
+    ///  - Reporter
+    switch_backdrop_to_and_wait(backdrop: (IntoInput<Backdrop>)) -> StackBlock<StageOnly>
     next_backdrop() -> StackBlock
-    change_size_by(by: (IntoInput<Number>)) -> StackBlock
-    set_size_to(to: (IntoInput<Number>)) -> StackBlock
-    /// Accepts
-    ///  - "COLOR"
-    ///  - "FISHEYE"
-    ///  - "WHIRL"
-    ///  - "PIXELATE"
-    ///  - "MOSAIC"
-    ///  - "BRIGHTNESS"
-    ///  - "GHOST"
-    change_looks_effect_by(effect: (IntoField), by: (IntoInput<Number>)) -> StackBlock
-    /// Accepts
-    ///  - "COLOR"
-    ///  - "FISHEYE"
-    ///  - "WHIRL"
-    ///  - "PIXELATE"
-    ///  - "MOSAIC"
-    ///  - "BRIGHTNESS"
-    ///  - "GHOST"
-    set_looks_effect_to(effect: (IntoField), to: (IntoInput<Number>)) -> StackBlock
+    change_size_by(by: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    set_size_to(to: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    change_looks_effect_by(effect: (IntoField<LooksEffect>), by: (IntoInput<Number>)) -> StackBlock
+    set_looks_effect_to(effect: (IntoField<LooksEffect>), to: (IntoInput<Number>)) -> StackBlock
     clear_graphic_effects() -> StackBlock
-    show() -> StackBlock
-    hide() -> StackBlock
-    /// Accepts:
-    ///  - "front"
-    ///  - "back"
-    go_to_layer(layer: (IntoField)) -> StackBlock
-    /// `layer` Accepts:
-    ///  - "foward"
-    ///  - "backward"
-    change_layer(layer: (IntoField), by: (IntoInput<Integer>)) -> StackBlock
-    /// Accepts:
-    /// - "number"
-    /// - "name"
-    costume(return_type: (IntoField)) -> JustReporter<Value>
-    /// Accepts:
-    /// - "number"
-    /// - "name"
-    backdrop(return_type: (IntoField)) -> JustReporter<Value>
-    size() -> JustReporter<Number>
+    show() -> StackBlock<SpriteOnly>
+    hide() -> StackBlock<SpriteOnly>
+    go_to_layer(layer: (IntoField<FrontBack>)) -> StackBlock<SpriteOnly>
+    change_layer(layer: (IntoField<ForwardBackward>), by: (IntoInput<Integer>)) -> StackBlock<SpriteOnly>
+    costume(return_type: (IntoField<NumberName>)) -> JustReporter<Value, SpriteOnly>
+    backdrop(return_type: (IntoField<NumberName>)) -> JustReporter<Value>
+    size() -> JustReporter<Number, SpriteOnly>
 }
 
 // Motion ======================================================================
 simple_typed_block_def! {
-    move_steps(steps: (IntoInput<Number>)) -> StackBlock
-    turn_right(degress: (IntoInput<Number>)) -> StackBlock
-    turn_left(degress: (IntoInput<Number>)) -> StackBlock
-    /// Accepts:
-    ///  - Sprite name
-    ///  - "_mouse_" go to mouse position
-    ///  - "_random_" go to random position
-    go_to(to: (IntoInput<Text>)) -> StackBlock
+    move_steps(steps: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    turn_right(degress: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    turn_left(degress: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::GoToTarget`]
+    ///  - Reporter
+    go_to(to: (IntoInput<GoTo>)) -> StackBlock<SpriteOnly>
     /// Uses as argument to [`goto`]
     /// Accepts:
     ///  - Sprite name
     ///  - "_mouse_" go to mouse position
     ///  - "_random_" go to random position
-    go_to_menu(to: (IntoField)) -> MenuReporter
-    goto_xy(x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock
-    /// Accepts:
-    ///  - Sprite name
-    ///  - "_mouse_" glide to mouse position
-    ///  - "_random_" glide to random position
-    glide_to(duration_secs: (IntoInput<Number>), to: (IntoInput<Text>)) -> StackBlock
+    go_to_menu(to: (IntoField)) -> MenuReporter<SpriteOnly>
+    goto_xy(x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::GoToTarget`]
+    ///  - Reporter
+    glide_to(duration_secs: (IntoInput<Number>), to: (IntoInput<GlideTo>)) -> StackBlock<SpriteOnly>
     /// Uses as an argument for [`glide_to`] in `to`
     /// Accepts:
     ///  - Sprite name
     ///  - "_mouse_" glide to mouse position
     ///  - "_random_" glide to random position
-    glide_to_menu(to: (IntoField)) -> MenuReporter
-    glide_to_xy(dur: (IntoInput<Number>), x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock
-    point_in_direction(direction: (IntoInput<Angle>)) -> StackBlock
-    /// Accepts:
-    ///  - Sprite name
-    ///  - "_mouse_" glide to mouse position
-    point_towards(towards: (IntoInput<Text>)) -> StackBlock
+    glide_to_menu(to: (IntoField)) -> MenuReporter<SpriteOnly>
+    glide_to_xy(dur: (IntoInput<Number>), x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    point_in_direction(direction: (IntoInput<Angle>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::PointTowardsTarget`]
+    ///  - Reporter
+    point_towards(towards: (IntoInput<PointTowards>)) -> StackBlock<SpriteOnly>
     /// Uses as an argument for [`point_towards`]
     /// Accepts:
     ///  - Sprite name
     ///  - "_mouse_" glide to mouse position
-    point_towards_menu(towards: (IntoField)) -> MenuReporter
-    set_x(x: (IntoInput<Number>)) -> StackBlock
-    change_x_by(by: (IntoInput<Number>)) -> StackBlock
-    set_y(y: (IntoInput<Number>)) -> StackBlock
-    change_y_by(by: (IntoInput<Number>)) -> StackBlock
-    if_on_edge_bounce() -> StackBlock
-    /// Accepts:
-    ///  - "left-right"
-    ///  - "don't rotate"
-    ///  - "all around"
-    set_rotation_style(style: (IntoField)) -> StackBlock
-    direction() -> JustReporter<Number>
-    y_position() -> JustReporter<Number>
-    x_position() -> JustReporter<Number>
+    point_towards_menu(towards: (IntoField)) -> MenuReporter<SpriteOnly>
+    set_x(x: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    change_x_by(by: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    set_y(y: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    change_y_by(by: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    if_on_edge_bounce() -> StackBlock<SpriteOnly>
+    set_rotation_style(style: (IntoField<RotationStyle>)) -> StackBlock<SpriteOnly>
+    direction() -> JustReporter<Number, SpriteOnly>
+    y_position() -> JustReporter<Number, SpriteOnly>
+    x_position() -> JustReporter<Number, SpriteOnly>
 }
 
 // Operators ===================================================================
@@ -279,7 +386,7 @@ simple_typed_block_def! {
     sub(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
     mul(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
     div(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
-    random(from: (IntoInput<Number>), to: (IntoInput<Number>)) -> JustReporter<Bool>
+    random(from: (IntoInput<Number>), to: (IntoInput<Number>)) -> JustReporter<Number>
     less_than(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
     greater_than(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
     equals(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
@@ -292,83 +399,58 @@ simple_typed_block_def! {
     contains(text: (IntoInput<Text>), contains: (IntoInput<Text>)) -> JustReporter<Bool>
     modulo(dividend: (IntoInput<Number>), divisor: (IntoInput<Number>)) -> JustReporter<Number>
     round(val: (IntoInput<Number>)) -> JustReporter<Number>
-    /// `op` Accepts:
-    ///  - "abs"
-    ///  - "floor"
-    ///  - "ceiling"
-    ///  - "sqrt"
-    ///  - "sin"
-    ///  - "cos"
-    ///  - "tan"
-    ///  - "asin"
-    ///  - "acos"
-    ///  - "atan"
-    ///  - "ln"
-    ///  - "log"
-    ///  - "e ^"
-    ///  - "10 ^"
-    math_op(op: (IntoField), val: (IntoInput<Number>)) -> JustReporter<Number>
+    math_op(op: (IntoField<MathOp>), val: (IntoInput<Number>)) -> JustReporter<Number>
 }
 
 // Sensing =====================================================================
 simple_typed_block_def! {
     /// Accepts:
-    ///  - Sprite name
-    ///  - "_mouse_"
-    ///  - "_edge_"
-    touching(what: (IntoInput<Text>)) -> JustReporter<Bool>
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::TouchingTarget`]
+    ///  - Reporter
+    touching(what: (IntoInput<TouchingObject>)) -> JustReporter<Bool, SpriteOnly>
     /// Uses as argument to [`touching`]
     /// Accepts:
     ///  - Sprite name
     ///  - "_mouse_"
     ///  - "_edge_"
-    touching_menu(what: (IntoField)) -> MenuReporter
-    touching_color(color: (IntoInput<Color>)) -> JustReporter<Bool>
-    color_touching_color(color_a: (IntoInput<Color>), color_b: (IntoInput<Color>)) -> JustReporter<Bool>
-    /// Accepts:
-    ///  - Sprite name
-    ///  - "_mouse_"
-    distance_to(what: (IntoInput<Text>)) -> JustReporter<Number>
+    touching_menu(what: (IntoField)) -> MenuReporter<SpriteOnly>
+    touching_color(color: (IntoInput<Color>)) -> JustReporter<Bool, SpriteOnly>
+    color_touching_color(color_a: (IntoInput<Color>), color_b: (IntoInput<Color>)) -> JustReporter<Bool, SpriteOnly>
+    /// Accepts:
+    ///  - [`SpriteHandle`]
+    ///  - Sprite name, checked when building
+    ///  - [`crate::scripting::menu::DistanceToTarget`]
+    ///  - Reporter
+    distance_to(what: (IntoInput<DistanceTo>)) -> JustReporter<Number, SpriteOnly>
     /// Uses as argument to [`distance_to`]
     /// Accepts:
     ///  - Sprite name
     ///  - "_mouse_"
-    distance_to_menu(what: (IntoField)) -> MenuReporter
+    distance_to_menu(what: (IntoField)) -> MenuReporter<SpriteOnly>
     ask_and_wait(prompt_message: (IntoInput<Text>)) -> StackBlock
     answer() -> JustReporter<Text>
     /// Accepts:
-    ///  - "any"
-    ///  - "space"
-    ///  - "left arrow"
-    ///  - "right arrow"
-    ///  - "up arrow"
-    ///  - "down arrow"
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    key_pressed(key: (IntoInput<Text>)) -> JustReporter<Bool>
+    ///  - [`Key`]
+    ///  - Reporter
+    key_pressed(key: (IntoInput<Key>)) -> JustReporter<Bool>
     /// Uses as argument to [`key_pressed`]
-    /// Accepts:
-    ///  - "any"
-    ///  - "space"
-    ///  - "left arrow"
-    ///  - "right arrow"
-    ///  - "up arrow"
-    ///  - "down arrow"
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    key_menu(key: (IntoInput<Text>)) -> MenuReporter
+    key_menu(key: (IntoField<Key>)) -> MenuReporter
     mouse_down() -> JustReporter<Bool>
     mouse_x() -> JustReporter<Number>
-    /// Accepts:
-    ///  - "not draggable"
-    ///  - "draggable"
-    set_drag_mode(mode: (IntoField)) -> StackBlock
+    mouse_y() -> JustReporter<Number>
+    set_drag_mode(mode: (IntoField<DragMode>)) -> StackBlock<SpriteOnly>
     loudness() -> JustReporter<Number>
     timer() -> JustReporter<Number>
     reset_timer() -> StackBlock
     /// `what` Accepts:
-    ///   - Sprite name
-    ///   - "_stage_"
+    ///   - [`SpriteHandle`]
+    ///   - Sprite name, checked when building
+    ///   - [`crate::scripting::menu::ObjectTarget`]
+    ///   - Reporter
+    ///
+    /// When `what` is a [`SpriteHandle`], its custom variable in `var` is checked when building.
     ///
     /// If `what` is "_stage_"
     ///    `var` Accepts:
@@ -387,21 +469,13 @@ simple_typed_block_def! {
     ///      - "costume name"
     ///      - "size"
     ///      - "volume"
-    var_of(var: (IntoField), what: (IntoInput<Text>)) -> JustReporter<Value>
+    var_of(var: (IntoField), what: (IntoInput<Object>)) -> JustReporter<Value>
     /// Uses as argument to [`var_of`]
     /// `what` Accepts:
     ///   - Sprite name
     ///   - "_stage_"
     var_of_object_menu(what: (IntoField)) -> MenuReporter
-    /// Accepts:
-    ///  - "SECOND"
-    ///  - "MINUTE"
-    ///  - "HOUR"
-    ///  - "DAYOFWEEK"
-    ///  - "DATE"
-    ///  - "MONTH"
-    ///  - "YEAR"
-    current_datetime(format: (IntoField)) -> JustReporter<PositiveInteger>
+    current_datetime(format: (IntoField<CurrentTime>)) -> JustReporter<PositiveInteger>
     days_since_2000() -> JustReporter<Number>
     username() -> JustReporter<Text>
 }
@@ -411,24 +485,22 @@ simple_typed_block_def! {
 simple_typed_block_def! {
 
     /// Accepts:
-    ///  - Sound name
-    play_sound_until_done(sound: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SoundHandle`]
+    ///  - Sound name, checked when building
+    ///  - Reporter
+    play_sound_until_done(sound: (IntoInput<Sound>)) -> StackBlock
     /// Accepts:
-    ///  - Sound name
-    play_sound(sound: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SoundHandle`]
+    ///  - Sound name, checked when building
+    ///  - Reporter
+    play_sound(sound: (IntoInput<Sound>)) -> StackBlock
     /// Uses as argument to [`play_sound_until_done`] and [`play_sound`]
     /// Accepts:
     ///  - Sound name
     sound_menu(sound: (IntoField)) -> MenuReporter
     stop_all_sound() -> StackBlock
-    /// Accepts:
-    ///  - "PITCH"
-    ///  - "PAN"
-    change_sound_effect_by(effect: (IntoField), by: (IntoInput<Number>)) -> StackBlock
-    /// Accepts:
-    ///  - "PITCH"
-    ///  - "PAN"
-    set_sound_effect_to(effect: (IntoField), to: (IntoInput<Number>)) -> StackBlock
+    change_sound_effect_by(effect: (IntoField<SoundEffect>), by: (IntoInput<Number>)) -> StackBlock
+    set_sound_effect_to(effect: (IntoField<SoundEffect>), to: (IntoInput<Number>)) -> StackBlock
     clear_sound_effects() -> StackBlock
     set_volume_to(volume: (IntoInput<Number>)) -> StackBlock
     change_volume_by(by: (IntoInput<Number>)) -> StackBlock
@@ -437,24 +509,214 @@ simple_typed_block_def! {
 
 // Data ========================================================================
 
+pub fn sprite_var<C>(var: &SpriteVar<C>) -> JustReporter<Value, C> {
+    TypedStackBuilder::assume_typed(blocks::sprite_var(var.name())).into()
+}
+
+pub fn sprite_list<C>(list: &SpriteList<C>) -> JustReporter<Value, C> {
+    TypedStackBuilder::assume_typed(blocks::sprite_list(list.name())).into()
+}
+
+pub fn global_var(var: &GlobalVar) -> JustReporter<Value> {
+    TypedStackBuilder::assume_typed(blocks::global_var(var.name())).into()
+}
+
+pub fn global_list(list: &GlobalList) -> JustReporter<Value> {
+    TypedStackBuilder::assume_typed(blocks::global_list(list.name())).into()
+}
+
+simple_typed_block_def! {
+    set_var_to(var: (Scoped IntoField<Variable>), to: (IntoInput<Value>)) -> StackBlock
+    change_var_by(var: (Scoped IntoField<Variable>), by: (IntoInput<Value>)) -> StackBlock
+    show_var(var: (Scoped IntoField<Variable>)) -> StackBlock
+    hide_var(var: (Scoped IntoField<Variable>)) -> StackBlock
+    add_to_list(list: (Scoped IntoField<List>), item: (IntoInput<Value>)) -> StackBlock
+    delete_in_list(list: (Scoped IntoField<List>), idx: (IntoInput<Integer>)) -> StackBlock
+    delete_all_in_list(list: (Scoped IntoField<List>)) -> StackBlock
+    insert_in_list(list: (Scoped IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
+    replace_in_list(list: (Scoped IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
+    item_in_list(list: (Scoped IntoField<List>), idx: (IntoInput<Integer>)) -> JustReporter<Value>
+    count_of_item_in_list(list: (Scoped IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Integer>
+    length_of_list(list: (Scoped IntoField<List>)) -> JustReporter<Integer>
+    list_contains(list: (Scoped IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Bool>
+    show_list(list: (Scoped IntoField<List>)) -> StackBlock
+    hide_list(list: (Scoped IntoField<List>)) -> StackBlock
+}
+
+// Procedures ==================================================================
+
+simple_typed_block_def! {
+    /// Accepts:
+    ///  - Argument name of the procedure this block is in
+    argument_string_number(name: (IntoField)) -> JustReporter<Value>
+    /// Accepts:
+    ///  - Argument name of the procedure this block is in
+    argument_boolean(name: (IntoField)) -> JustReporter<Bool>
+}
+
+/// `arguments` is in the same order as the procedure's arguments.
+/// Use [`value_arg`], [`bool_arg`] and [`empty_bool_arg`] to create each argument.
+/// The build fails when the number of `arguments` isn't the number of the procedure's arguments.
+pub fn call_procedure(
+    procedure: &ProcedureHandle,
+    arguments: Vec<BlockInputBuilder>,
+) -> StackBlock {
+    TypedStackBuilder::assume_typed(blocks::call_procedure(procedure, arguments))
+}
+
+/// Uses as argument to [`call_procedure`] for string or number argument
+pub fn value_arg(value: impl IntoInput<Value>) -> BlockInputBuilder {
+    value.into_input()
+}
+
+/// Uses as argument to [`call_procedure`] for boolean argument
+pub fn bool_arg(value: impl IntoInput<Bool>) -> BlockInputBuilder {
+    value.into_input()
+}
+
+/// Uses as argument to [`call_procedure`] for boolean argument that's left empty
+pub fn empty_bool_arg() -> BlockInputBuilder {
+    BlockInputBuilder::new()
+}
+
+// Pen =========================================================================
 simple_typed_block_def! {
-    sprite_var(name: (Into<String>)) -> JustReporter<Value>
-    sprite_list(name: (Into<String>)) -> JustReporter<Value>
-    global_var(name: (Into<String>)) -> JustReporter<Value>
-    global_list(name: (Into<String>)) -> JustReporter<Value>
-    set_var_to(var: (IntoField<Variable>), to: (IntoInput<Value>)) -> StackBlock
-    change_var_by(var: (IntoField<Variable>), by: (IntoInput<Value>)) -> StackBlock
-    show_var(var: (IntoField<Variable>)) -> StackBlock
-    hide_var(var: (IntoField<Variable>)) -> StackBlock
-    add_to_list(item: (IntoInput<Value>)) -> StackBlock
-    delete_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>)) -> StackBlock
-    delete_all_in_list(list: (IntoField<List>)) -> StackBlock
-    insert_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
-    replace_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
-    item_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>)) -> JustReporter<Value>
-    count_of_item_in_list(list: (IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Integer>
-    length_of_list(list: (IntoField<List>)) -> JustReporter<Integer>
-    list_contains(list: (IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Bool>
-    show_list(list: (IntoField<List>)) -> StackBlock
-    hide_list(list: (IntoField<List>)) -> StackBlock
+    erase_all() -> StackBlock
+    stamp() -> StackBlock<SpriteOnly>
+    pen_down() -> StackBlock<SpriteOnly>
+    pen_up() -> StackBlock<SpriteOnly>
+    set_pen_color_to_color(color: (IntoInput<Color>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`PenColorParam`]
+    ///  - Reporter
+    ///
+    /// A text isn't one of the options:
+    /// ```compile_fail
+    /// # use mcscratchy::typed_scripting::blocks::*;
+    /// change_pen_color_param_by("color", 10);
+    /// ```
+    change_pen_color_param_by(param: (IntoInput<PenColorParam>), value: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Accepts:
+    ///  - [`PenColorParam`]
+    ///  - Reporter
+    set_pen_color_param_to(param: (IntoInput<PenColorParam>), value: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Uses as argument to [`change_pen_color_param_by`] and [`set_pen_color_param_to`]
+    pen_color_param_menu(param: (IntoField<PenColorParam>)) -> MenuReporter
+    change_pen_size_by(size: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    set_pen_size_to(size: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Legacy block from Scratch 2, hidden from the palette
+    set_pen_shade_to(shade: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Legacy block from Scratch 2, hidden from the palette
+    change_pen_shade_by(shade: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Legacy block from Scratch 2, hidden from the palette
+    set_pen_hue_to(hue: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+    /// Legacy block from Scratch 2, hidden from the palette
+    change_pen_hue_by(hue: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+}
+
+// Music =======================================================================
+simple_typed_block_def! {
+    /// Accepts:
+    ///  - [`Drum`]
+    ///  - Reporter
+    play_drum_for_beats(drum: (IntoInput<Drum>), beats: (IntoInput<Number>)) -> StackBlock
+    /// Uses as argument to [`play_drum_for_beats`]
+    drum_menu(drum: (IntoField<Drum>)) -> MenuReporter
+    rest_for_beats(beats: (IntoInput<Number>)) -> StackBlock
+    play_note_for_beats(note: (IntoInput<Number>), beats: (IntoInput<Number>)) -> StackBlock
+    /// Uses as argument to [`play_note_for_beats`]
+    /// Accepts:
+    ///  - MIDI note number from "0" to "130". "60" is middle C
+    note_menu(note: (IntoField)) -> JustReporter<Number>
+    /// Accepts:
+    ///  - [`Instrument`]
+    ///  - Reporter
+    set_instrument_to(instrument: (IntoInput<Instrument>)) -> StackBlock
+    /// Uses as argument to [`set_instrument_to`]
+    instrument_menu(instrument: (IntoField<Instrument>)) -> MenuReporter
+    set_tempo_to(tempo: (IntoInput<Number>)) -> StackBlock
+    change_tempo_by(tempo: (IntoInput<Number>)) -> StackBlock
+    tempo() -> JustReporter<Number>
+}
+
+// Hidden ======================================================================
+#[cfg(feature = "hidden-blocks")]
+pub use hidden::*;
+
+/// Blocks hidden from the palette that Scratch 3 still runs.
+/// Enabled by the `hidden-blocks` feature.
+#[cfg(feature = "hidden-blocks")]
+mod hidden {
+    use super::*;
+
+    pub fn while_<Q, B>(condition: Q, to_repeat: Option<B>) -> StackBlock<Join<B::Scope, Q::Scope>>
+    where
+        Q: IntoInput<Bool> + Scoped,
+        B: IntoInput<Stack> + Scoped,
+        B::Scope: JoinScope<Q::Scope>,
+    {
+        TypedStackBuilder::assume_typed(blocks::while_(
+            condition.into_input(),
+            to_repeat.map(IntoInput::into_input),
+        ))
+    }
+
+    /// Sets `var` to 1, 2, ... up to `value` and runs `to_repeat` each time.
+    /// `C` is the scope of `var` and `to_repeat` put together.
+    pub fn for_each<V, N, B, C>(
+        var: V,
+        value: N,
+        to_repeat: Option<B>,
+    ) -> StackBlock<Join<C, N::Scope>>
+    where
+        V: IntoField<Variable> + Scoped,
+        N: IntoInput<PositiveInteger> + Scoped,
+        B: IntoInput<Stack> + Scoped,
+        V::Scope: JoinScope<B::Scope, Output = C>,
+        C: JoinScope<N::Scope>,
+    {
+        TypedStackBuilder::assume_typed(blocks::for_each(
+            var.into_field(),
+            value.into_input(),
+            to_repeat.map(IntoInput::into_input),
+        ))
+    }
+
+    pub fn all_at_once<B: IntoInput<Stack> + Scoped>(to_run: Option<B>) -> StackBlock<B::Scope> {
+        TypedStackBuilder::assume_typed(blocks::all_at_once(to_run.map(IntoInput::into_input)))
+    }
+
+    simple_typed_block_def! {
+        /// Accepts:
+        ///  - [`SpriteHandle`]
+        ///  - Sprite name, checked when building
+        ///  - [`crate::scripting::menu::TouchingTarget`]
+        ///  - Reporter
+        when_touching(what: (IntoInput<WhenTouchingObject>)) -> HatBlock<SpriteOnly>
+        /// Uses as argument to [`when_touching`]
+        /// Accepts:
+        ///  - Sprite name
+        ///  - "_mouse_"
+        ///  - "_edge_"
+        when_touching_menu(what: (IntoField)) -> MenuReporter<SpriteOnly>
+        counter() -> JustReporter<PositiveInteger>
+        incr_counter() -> StackBlock
+        clear_counter() -> StackBlock
+        hide_all_sprites() -> StackBlock
+        change_stretch_by(by: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+        set_stretch_to(to: (IntoInput<Number>)) -> StackBlock<SpriteOnly>
+        scroll_right(distance: (IntoInput<Number>)) -> StackBlock
+        scroll_up(distance: (IntoInput<Number>)) -> StackBlock
+        /// Accepts:
+        ///  - "bottom-left"
+        ///  - "bottom-right"
+        ///  - "middle"
+        ///  - "top-left"
+        ///  - "top-right"
+        align_scene(alignment: (IntoField)) -> StackBlock
+        x_scroll() -> JustReporter<Number>
+        y_scroll() -> JustReporter<Number>
+        loud() -> JustReporter<Bool>
+        user_id() -> JustReporter<Text>
+    }
 }
@@ -10,68 +10,94 @@ pub struct Building;
 pub struct End;
 
 /// Shortcut to `IfElseChainBuilder::if_`
-pub fn if_(
-    cond: impl IntoInput<Bool>,
-    then: Option<impl IntoInput<Stack>>,
-) -> IfElseChainBuilder<Building> {
+pub fn if_<Q, B>(cond: Q, then: Option<B>) -> IfElseChainBuilder<Building, Join<B::Scope, Q::Scope>>
+where
+    Q: IntoInput<Bool> + Scoped,
+    B: IntoInput<Stack> + Scoped,
+    B::Scope: JoinScope<Q::Scope>,
+{
     IfElseChainBuilder::<Building>::if_(cond, then)
 }
 
+/// `C` is the scope of every branches and conditions put together
 #[derive(Debug, Clone, PartialEq)]
-pub struct IfElseChainBuilder<S> {
+pub struct IfElseChainBuilder<S, C = AnyTarget> {
     if_: (BlockInputBuilder, Option<BlockInputBuilder>),
     else_ifs: Vec<(BlockInputBuilder, Option<BlockInputBuilder>)>,
     else_: Option<Option<BlockInputBuilder>>,
     marker: PhantomData<S>,
+    scope: PhantomData<C>,
 }
 
-impl IfElseChainBuilder<Building> {
-    pub fn else_if(
-        mut self,
-        cond: impl IntoInput<Bool>,
-        then: Option<impl IntoInput<Stack>>,
-    ) -> IfElseChainBuilder<Building> {
-        self.else_ifs
-            .push((cond.into_input(), then.map(IntoInput::<Stack>::into_input)));
-        self
-    }
-
-    pub fn else_(mut self, else_: Option<impl IntoInput<Stack>>) -> IfElseChainBuilder<End> {
-        self.else_ = Some(else_.map(IntoInput::<Stack>::into_input));
+impl<C> IfElseChainBuilder<Building, C> {
+    pub fn else_if<Q, B>(
+        self,
+        cond: Q,
+        then: Option<B>,
+    ) -> IfElseChainBuilder<Building, Join<C::Output, Q::Scope>>
+    where
+        Q: IntoInput<Bool> + Scoped,
+        B: IntoInput<Stack> + Scoped,
+        C: JoinScope<B::Scope>,
+        C::Output: JoinScope<Q::Scope>,
+    {
         let IfElseChainBuilder {
             if_,
-            else_ifs,
+            mut else_ifs,
             else_,
-            marker: _,
+            ..
         } = self;
+        else_ifs.push((cond.into_input(), then.map(IntoInput::<Stack>::into_input)));
         IfElseChainBuilder {
             if_,
             else_ifs,
             else_,
             marker: PhantomData,
+            scope: PhantomData,
+        }
+    }
+
+    pub fn else_<B>(self, else_: Option<B>) -> IfElseChainBuilder<End, C::Output>
+    where
+        B: IntoInput<Stack> + Scoped,
+        C: JoinScope<B::Scope>,
+    {
+        let IfElseChainBuilder { if_, else_ifs, .. } = self;
+        IfElseChainBuilder {
+            if_,
+            else_ifs,
+            else_: Some(else_.map(IntoInput::<Stack>::into_input)),
+            marker: PhantomData,
+            scope: PhantomData,
         }
     }
 }
 
-impl<S> IfElseChainBuilder<S> {
-    pub fn if_(
-        cond: impl IntoInput<Bool>,
-        then: Option<impl IntoInput<Stack>>,
-    ) -> IfElseChainBuilder<Building> {
+impl<S, C> IfElseChainBuilder<S, C> {
+    pub fn if_<Q, B>(
+        cond: Q,
+        then: Option<B>,
+    ) -> IfElseChainBuilder<Building, Join<B::Scope, Q::Scope>>
+    where
+        Q: IntoInput<Bool> + Scoped,
+        B: IntoInput<Stack> + Scoped,
+        B::Scope: JoinScope<Q::Scope>,
+    {
         IfElseChainBuilder {
             if_: (cond.into_input(), then.map(|s| s.into_input())),
             else_ifs: vec![],
             else_: None,
             marker: PhantomData,
+            scope: PhantomData,
         }
     }
 
-    pub fn end(self) -> StackBlock {
+    pub fn end(self) -> StackBlock<C> {
         let IfElseChainBuilder {
             if_,
             else_ifs,
             else_,
-            marker: _,
+            ..
         } = self;
         // not very readable - fix later
         let b = match (else_ifs.len(), else_) {
@@ -1,4 +1,5 @@
 pub mod arg;
 pub mod blocks;
 pub mod if_else_chain_builder;
+mod ops;
 pub mod script_builder;
@@ -0,0 +1,146 @@
+//! Operators on typed reporters. Ex. `x_position() * 2 + 1`, `!(mouse_down() & touching(TouchingTarget::Edge))`
+//!
+//! Number and value reporters lower to the arithmetic operator blocks,
+//! bool reporters lower to `and`, `or` and `not`.
+//! The result has the scope of both sides put together.
+
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Neg, Not, Rem, Sub};
+
+use super::{
+    arg::{Bool, IntoInput, Number, Value},
+    blocks,
+    script_builder::{JoinScope, JustReporter, Reporter, Scoped},
+};
+
+macro_rules! number_ops_impl {
+    (@op $arg:ty, $trait:ident, $method:ident, $block:ident) => {
+        impl<S, E, C, Rhs> $trait<Rhs> for Reporter<$arg, S, E, C>
+        where
+            Rhs: IntoInput<Number> + Scoped,
+            C: JoinScope<Rhs::Scope>,
+        {
+            type Output = JustReporter<Number, C::Output>;
+
+            fn $method(self, rhs: Rhs) -> Self::Output {
+                blocks::$block(self, rhs)
+            }
+        }
+
+        impl<S, E, C> $trait<Reporter<$arg, S, E, C>> for i64 {
+            type Output = JustReporter<Number, C>;
+
+            fn $method(self, rhs: Reporter<$arg, S, E, C>) -> Self::Output {
+                blocks::$block(self, rhs)
+            }
+        }
+
+        impl<S, E, C> $trait<Reporter<$arg, S, E, C>> for f64 {
+            type Output = JustReporter<Number, C>;
+
+            fn $method(self, rhs: Reporter<$arg, S, E, C>) -> Self::Output {
+                blocks::$block(self, rhs)
+            }
+        }
+    };
+
+    ($($arg:ty),*) => {
+        $(
+            number_ops_impl!(@op $arg, Add, add, add);
+            number_ops_impl!(@op $arg, Sub, sub, sub);
+            number_ops_impl!(@op $arg, Mul, mul, mul);
+            number_ops_impl!(@op $arg, Div, div, div);
+            number_ops_impl!(@op $arg, Rem, rem, modulo);
+
+            /// `0 - self`
+            impl<S, E, C> Neg for Reporter<$arg, S, E, C> {
+                type Output = JustReporter<Number, C>;
+
+                fn neg(self) -> Self::Output {
+                    blocks::sub(0, self)
+                }
+            }
+        )*
+    };
+}
+
+number_ops_impl! { Number, Value }
+
+impl<T, S, E, C> Reporter<T, S, E, C> {
+    /// `self < rhs`
+    pub fn lt<Rhs>(self, rhs: Rhs) -> JustReporter<Bool, C::Output>
+    where
+        Self: IntoInput<Value>,
+        Rhs: IntoInput<Value> + Scoped,
+        C: JoinScope<Rhs::Scope>,
+    {
+        blocks::less_than(self, rhs)
+    }
+
+    /// `self > rhs`
+    pub fn gt<Rhs>(self, rhs: Rhs) -> JustReporter<Bool, C::Output>
+    where
+        Self: IntoInput<Value>,
+        Rhs: IntoInput<Value> + Scoped,
+        C: JoinScope<Rhs::Scope>,
+    {
+        blocks::greater_than(self, rhs)
+    }
+
+    /// `self = rhs`. Named `eq_` to not be confused with [`PartialEq::eq`]
+    pub fn eq_<Rhs>(self, rhs: Rhs) -> JustReporter<Bool, C::Output>
+    where
+        Self: IntoInput<Value>,
+        Rhs: IntoInput<Value> + Scoped,
+        C: JoinScope<Rhs::Scope>,
+    {
+        blocks::equals(self, rhs)
+    }
+}
+
+impl<S, E, C, Rhs> BitAnd<Rhs> for Reporter<Bool, S, E, C>
+where
+    Rhs: IntoInput<Bool> + Scoped,
+    C: JoinScope<Rhs::Scope>,
+{
+    type Output = JustReporter<Bool, C::Output>;
+
+    fn bitand(self, rhs: Rhs) -> Self::Output {
+        blocks::and(self, rhs)
+    }
+}
+
+impl<S, E, C> BitAnd<Reporter<Bool, S, E, C>> for bool {
+    type Output = JustReporter<Bool, C>;
+
+    fn bitand(self, rhs: Reporter<Bool, S, E, C>) -> Self::Output {
+        blocks::and(self, rhs)
+    }
+}
+
+impl<S, E, C, Rhs> BitOr<Rhs> for Reporter<Bool, S, E, C>
+where
+    Rhs: IntoInput<Bool> + Scoped,
+    C: JoinScope<Rhs::Scope>,
+{
+    type Output = JustReporter<Bool, C::Output>;
+
+    fn bitor(self, rhs: Rhs) -> Self::Output {
+        blocks::or(self, rhs)
+    }
+}
+
+impl<S, E, C> BitOr<Reporter<Bool, S, E, C>> for bool {
+    type Output = JustReporter<Bool, C>;
+
+    fn bitor(self, rhs: Reporter<Bool, S, E, C>) -> Self::Output {
+        blocks::or(self, rhs)
+    }
+}
+
+impl<S, E, C> Not for Reporter<Bool, S, E, C> {
+    type Output = JustReporter<Bool, C>;
+
+    fn not(self) -> Self::Output {
+        blocks::not(self)
+    }
+}
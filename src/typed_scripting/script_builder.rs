@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::scripting::script_builder::{
-    BlockBuilder, BlockNormalBuilder, BlockVarListBuilder, StackBuilder,
+    BlockBuilder, BlockInputBuilder, BlockNormalBuilder, BlockVarListBuilder, StackBuilder,
 };
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -9,49 +9,186 @@ pub struct StackableSide;
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct UnstackableSide;
 
+// Scope =======================================================================
+// Which targets a stack can be in. Mirrors [`crate::scripting::spec::Scope`].
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AnyTarget;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteOnly;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StageOnly;
+/// Only in the sprite branded `B`, see [`crate::project::target::SpriteBuilder`].
+pub struct OwnedBy<B>(PhantomData<fn() -> B>);
+
+/// Scope of two stacks put together.
+/// There's no `SpriteOnly` with `StageOnly`, such a stack can't be in any target.
+/// Neither is there `OwnedBy` two different sprites.
+pub trait JoinScope<Other> {
+    type Output;
+}
+
+/// Scope of `A` and `B` put together
+pub type Join<A, B> = <A as JoinScope<B>>::Output;
+
+impl<C> JoinScope<C> for AnyTarget {
+    type Output = C;
+}
+
+impl JoinScope<AnyTarget> for SpriteOnly {
+    type Output = SpriteOnly;
+}
+
+impl JoinScope<SpriteOnly> for SpriteOnly {
+    type Output = SpriteOnly;
+}
+
+impl JoinScope<AnyTarget> for StageOnly {
+    type Output = StageOnly;
+}
+
+impl JoinScope<StageOnly> for StageOnly {
+    type Output = StageOnly;
+}
+
+impl<B> JoinScope<OwnedBy<B>> for SpriteOnly {
+    type Output = OwnedBy<B>;
+}
+
+impl<B> JoinScope<AnyTarget> for OwnedBy<B> {
+    type Output = OwnedBy<B>;
+}
+
+impl<B> JoinScope<SpriteOnly> for OwnedBy<B> {
+    type Output = OwnedBy<B>;
+}
+
+impl<B> JoinScope<OwnedBy<B>> for OwnedBy<B> {
+    type Output = OwnedBy<B>;
+}
+
+/// Scopes a script of the sprite branded `B` can have
+pub trait SpriteScope<B> {}
+impl<B> SpriteScope<B> for AnyTarget {}
+impl<B> SpriteScope<B> for SpriteOnly {}
+impl<B> SpriteScope<B> for OwnedBy<B> {}
+
+/// Scopes a Stage's script can have
+pub trait StageScope {}
+impl StageScope for AnyTarget {}
+impl StageScope for StageOnly {}
+
+/// Things that carries a scope when plugged into a block. Ex. the substack of a C block.
+pub trait Scoped {
+    type Scope;
+}
+
+impl<S, E, C> Scoped for TypedStackBuilder<S, E, C> {
+    type Scope = C;
+}
+
+/// The escape hatch isn't checked until building
+impl Scoped for BlockInputBuilder {
+    type Scope = AnyTarget;
+}
+
+impl<T, S, E, C> Scoped for Reporter<T, S, E, C> {
+    type Scope = C;
+}
+
+/// Block that takes the scope of the input `I` plugged into it. Ex. `say(x_position())` is only in sprites.
+pub trait JoinInput<I> {
+    type Output;
+
+    fn join_input(self) -> Self::Output;
+}
+
+impl<S, E, C, I> JoinInput<I> for TypedStackBuilder<S, E, C>
+where
+    I: Scoped,
+    C: JoinScope<I::Scope>,
+{
+    type Output = TypedStackBuilder<S, E, C::Output>;
+
+    fn join_input(self) -> Self::Output {
+        TypedStackBuilder::assume_typed(self.into_untyped())
+    }
+}
+
+impl<T, S, E, C, I> JoinInput<I> for Reporter<T, S, E, C>
+where
+    I: Scoped,
+    C: JoinScope<I::Scope>,
+{
+    type Output = Reporter<T, S, E, C::Output>;
+
+    fn join_input(self) -> Self::Output {
+        Reporter::new(JoinInput::<I>::join_input(self.0))
+    }
+}
+
+/// `Block` with the scope of the input `I` joined into its scope
+pub type Joined<Block, I> = <Block as JoinInput<I>>::Output;
+
+// Stack =======================================================================
+
+/// The scope of a reporter is carried over to the block it's plugged in,
+/// so a sprite's reporter can't end up in the Stage's script:
+/// ```compile_fail
+/// # use mcscratchy::project::{script::VariableBuilder, target::{StageBuilder, TargetBuilder}};
+/// # use mcscratchy::typed_scripting::blocks::*;
+/// let mut stage = StageBuilder::new(TargetBuilder::new("Stage"));
+/// let global = stage.declare_global_var("global", VariableBuilder::new(0.into()));
+/// let stage = stage.add_script(when_flag_clicked().next(set_var_to(&global, x_position())));
+/// ```
+///
+/// Neither can a reporter of a sprite's variable:
+/// ```compile_fail
+/// # use mcscratchy::project::{script::VariableBuilder, target::{SpriteBuilder, StageBuilder, TargetBuilder}};
+/// # use mcscratchy::typed_scripting::blocks::*;
+/// struct Cat;
+/// let mut cat = SpriteBuilder::<Cat>::new(TargetBuilder::new("Cat"));
+/// let lives = cat.declare_sprite_var("lives", VariableBuilder::new(9.into()));
+/// let mut stage = StageBuilder::new(TargetBuilder::new("Stage"));
+/// let global = stage.declare_global_var("global", VariableBuilder::new(0.into()));
+/// let stage = stage.add_script(when_flag_clicked().next(set_var_to(&global, sprite_var(&lives))));
+/// ```
 #[derive(Debug, Clone, PartialEq)]
-pub struct Reporter<T, S, E>(pub TypedStackBuilder<S, E>, pub PhantomData<T>);
+pub struct Reporter<T, S, E, C = AnyTarget>(pub TypedStackBuilder<S, E, C>, pub PhantomData<T>);
 
-impl<T, S, E> Reporter<T, S, E> {
-    pub fn new(typed_stack_builder: TypedStackBuilder<S, E>) -> Reporter<T, S, E> {
+impl<T, S, E, C> Reporter<T, S, E, C> {
+    pub fn new(typed_stack_builder: TypedStackBuilder<S, E, C>) -> Reporter<T, S, E, C> {
         Reporter(typed_stack_builder, PhantomData)
     }
 }
 
-impl<T, S, E> From<TypedStackBuilder<S, E>> for Reporter<T, S, E> {
-    fn from(stb: TypedStackBuilder<S, E>) -> Self {
+impl<T, S, E, C> From<TypedStackBuilder<S, E, C>> for Reporter<T, S, E, C> {
+    fn from(stb: TypedStackBuilder<S, E, C>) -> Self {
         Reporter::new(stb)
     }
 }
 
-pub type JustReporter<T> = Reporter<T, UnstackableSide, UnstackableSide>;
-pub type HatBlock = TypedStackBuilder<UnstackableSide, StackableSide>;
-pub type CapBlock = TypedStackBuilder<StackableSide, UnstackableSide>;
-pub type StackBlock = TypedStackBuilder<StackableSide, StackableSide>;
-pub type MenuReporter = JustReporter<super::arg::Text>;
+pub type JustReporter<T, C = AnyTarget> = Reporter<T, UnstackableSide, UnstackableSide, C>;
+pub type HatBlock<C = AnyTarget> = TypedStackBuilder<UnstackableSide, StackableSide, C>;
+pub type CapBlock<C = AnyTarget> = TypedStackBuilder<StackableSide, UnstackableSide, C>;
+pub type StackBlock<C = AnyTarget> = TypedStackBuilder<StackableSide, StackableSide, C>;
+pub type MenuReporter<C = AnyTarget> = JustReporter<super::arg::Text, C>;
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct TypedStackBuilder<S, E> {
+pub struct TypedStackBuilder<S, E, C = AnyTarget> {
     stack_builder: StackBuilder,
     start: PhantomData<S>,
     end: PhantomData<E>,
+    scope: PhantomData<C>,
 }
 
-impl<S, E> TypedStackBuilder<S, E> {
-    pub fn start(block_builder: BlockNormalBuilder) -> TypedStackBuilder<S, E> {
-        TypedStackBuilder {
-            stack_builder: StackBuilder::start(block_builder),
-            start: PhantomData,
-            end: PhantomData,
-        }
+impl<S, E, C> TypedStackBuilder<S, E, C> {
+    pub fn start(block_builder: BlockNormalBuilder) -> TypedStackBuilder<S, E, C> {
+        TypedStackBuilder::assume_typed(StackBuilder::start(block_builder))
     }
 
-    pub fn start_varlist(block_builder: BlockVarListBuilder) -> TypedStackBuilder<S, E> {
-        TypedStackBuilder {
-            stack_builder: StackBuilder::start_varlist(block_builder),
-            start: PhantomData,
-            end: PhantomData,
-        }
+    pub fn start_varlist(block_builder: BlockVarListBuilder) -> TypedStackBuilder<S, E, C> {
+        TypedStackBuilder::assume_typed(StackBuilder::start_varlist(block_builder))
     }
 
     pub fn into_untyped(self) -> StackBuilder {
@@ -61,12 +198,8 @@ impl<S, E> TypedStackBuilder<S, E> {
     pub fn start_with_capacity(
         capacity: usize,
         block_builder: BlockBuilder,
-    ) -> TypedStackBuilder<S, E> {
-        TypedStackBuilder {
-            stack_builder: StackBuilder::start_with_capacity(capacity, block_builder),
-            start: PhantomData,
-            end: PhantomData,
-        }
+    ) -> TypedStackBuilder<S, E, C> {
+        TypedStackBuilder::assume_typed(StackBuilder::start_with_capacity(capacity, block_builder))
     }
 
     pub fn move_head(mut self, x: f64, y: f64) -> Self {
@@ -74,26 +207,27 @@ impl<S, E> TypedStackBuilder<S, E> {
         self
     }
 
-    pub fn assume_typed(stack_builder: StackBuilder) -> TypedStackBuilder<S, E> {
+    pub fn assume_typed(stack_builder: StackBuilder) -> TypedStackBuilder<S, E, C> {
         TypedStackBuilder {
             stack_builder,
             start: PhantomData,
             end: PhantomData,
+            scope: PhantomData,
         }
     }
 }
 
-impl<S> TypedStackBuilder<S, StackableSide> {
-    pub fn next<NE>(
+impl<S, C> TypedStackBuilder<S, StackableSide, C> {
+    /// The stack is only in the targets that both stacks can be in
+    pub fn next<NE, NC>(
         self,
-        next_stack: TypedStackBuilder<StackableSide, NE>,
-    ) -> TypedStackBuilder<S, NE> {
+        next_stack: TypedStackBuilder<StackableSide, NE, NC>,
+    ) -> TypedStackBuilder<S, NE, C::Output>
+    where
+        C: JoinScope<NC>,
+    {
         let stack = self.into_untyped();
         let next_stack = next_stack.into_untyped();
-        TypedStackBuilder {
-            stack_builder: stack.next(next_stack),
-            start: PhantomData,
-            end: PhantomData,
-        }
+        TypedStackBuilder::assume_typed(stack.next(next_stack))
     }
 }
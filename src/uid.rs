@@ -1,6 +1,6 @@
-use rand::prelude::*;
+use std::collections::HashSet;
 
-// TODO: no collision
+use rand::prelude::*;
 
 const SOUP: &str =
     "!#%()*+,-./:;=?@[]^_`{|}~ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
@@ -10,6 +10,7 @@ const UID_LEN: usize = 20;
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Uid(String);
 
+/// Random uid. Use [`UidGenerator`] when the uid have to be unique or reproducible.
 pub fn uid() -> String {
     let mut rng = thread_rng();
     let uid = (0..UID_LEN)
@@ -28,6 +29,20 @@ impl Uid {
         Uid(uid.into())
     }
 
+    /// Uids ordered by length then by `SOUP`, starting from index 0. Ex. `"!"`, `"#"`, ..., `"!!"`
+    pub fn nth_shortest(mut index: usize) -> Uid {
+        let mut uid = vec![SOUP.as_bytes()[index % SOUP_LEN]];
+        index /= SOUP_LEN;
+        while index > 0 {
+            index -= 1;
+            uid.push(SOUP.as_bytes()[index % SOUP_LEN]);
+            index /= SOUP_LEN;
+        }
+        uid.reverse();
+        // SAFETY: We're taking from 'SOUP' const and they're all valid utf8 characters
+        Uid(unsafe { String::from_utf8_unchecked(uid) })
+    }
+
     pub fn inner(&self) -> &str {
         &self.0
     }
@@ -36,3 +51,53 @@ impl Uid {
         self.0
     }
 }
+
+/// Gives out uids that are unique within a project.
+/// The uid is derived from the seed and a path that describe where the uid is used
+/// so building the same project twice gives the same uids.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UidGenerator {
+    seed: u64,
+    used: HashSet<Uid>,
+}
+
+impl UidGenerator {
+    pub fn new(seed: u64) -> UidGenerator {
+        UidGenerator {
+            seed,
+            used: HashSet::default(),
+        }
+    }
+
+    /// Same seed and path always gives the same uid unless it was already given out.
+    /// Ex. path: `"Cat/stack/0"`
+    pub fn derive(&mut self, path: &str) -> Uid {
+        let mut attempt: u64 = 0;
+        loop {
+            let input = match attempt {
+                0 => format!("{}:{path}", self.seed),
+                _ => format!("{}:{path}#{attempt}", self.seed),
+            };
+            let first = md5::compute(input.as_bytes());
+            let second = md5::compute(first.0);
+            let uid = first
+                .0
+                .iter()
+                .chain(second.0.iter())
+                .take(UID_LEN)
+                .map(|byte| SOUP.as_bytes()[*byte as usize % SOUP_LEN])
+                .collect();
+            // SAFETY: We're taking from 'SOUP' const and they're all valid utf8 characters
+            let uid = Uid(unsafe { String::from_utf8_unchecked(uid) });
+            if self.used.insert(uid.clone()) {
+                return uid;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Marks the uid as used, for uid that is already decided. Ex. Broadcast from an imported project.
+    pub fn reserve(&mut self, uid: &Uid) {
+        self.used.insert(uid.clone());
+    }
+}